@@ -1,5 +1,16 @@
-use meta::executor::Executor;
+use meta::{
+    executor::Executor,
+    log::{self, Level},
+    parser::ParserOptions,
+};
 
 fn main() {
-    Executor::run("Script.mt");
+    log::set_level(Some(Level::Info));
+
+    let options = ParserOptions::new().dump_ast("ast.dat").cache_ast(true);
+    let result = Executor::run_with_options("Script.mt", options);
+
+    for diagnostic in result.diagnostics.iter() {
+        println!("{diagnostic}");
+    }
 }
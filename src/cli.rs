@@ -0,0 +1,867 @@
+use std::{
+    cell::RefCell,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    rc::Rc,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use meta::{
+    analyzer::Analyzer,
+    ast_printer::AstPrinter,
+    codegen::{
+        c::{self, CBackend},
+        rust::RustBackend,
+    },
+    debugger::{Breakpoint, DebugAction, DebugHook, DebugState, PauseReason},
+    diagnostics::{self, OutputFormat, Renderer},
+    error::RuntimeErrorKind,
+    executor::{Environment, Executor, EVAL_FILENAME},
+    formatter::Formatter,
+    fuzz::{self, FuzzOptions},
+    golden::{self, GoldenOptions},
+    hooks::Tracer,
+    log::{self, Level},
+    lsp::index::{self, SymbolIndex, SymbolKind},
+    parser::{AstFormat, Parser, ParserOptions},
+    pretty::PrettyPrinter,
+    timer::Profiler,
+    Engine,
+};
+
+const USAGE: &str = "usage: meta run [--no-color] [--json] [--release] [--strict] [--trace] [--profile] [--emit-ast=<tree|dot|debug>] <file>
+       meta run [--no-color] [--json] [--release] [--strict] -e <expr>
+       meta debug [--no-color] [--break <file>:<line>]... <file>
+       meta fmt [--no-color] [--json] [--check] <file>
+       meta fix <file>
+       meta build [--no-color] [--json] [--release] [--strict] --emit=<rust|c> <file> [-o <path>]
+       meta test [--bless] <dir|file>
+       meta refs <name|Type::name> [<dir|file>...]
+       meta symbols [--filter <pattern>] [<dir|file>...]
+       meta fuzz [--no-color] [--json] <file> --target <proc> [--runs <n>] [--seed <n>]
+       meta fuzz --parser [--runs <n>] [--seed <n>] [<file>...]
+
+every command accepts --log=<error|warn|info|trace> to print internal log messages";
+
+const INTERRUPTED_EXIT_CODE: u8 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+const DEBUG_HELP: &str = "commands:
+  s, step               run until the next statement
+  n, next               run until the next statement in this procedure or its caller
+  f, finish             run until the current procedure returns
+  c, continue           run until the next breakpoint
+  b, break [<line>]     set a breakpoint or list breakpoints
+  d, delete <line>      remove a breakpoint
+  p, print <name>       print a variable
+  locals                print the variables in scope
+  bt, backtrace         print the call stack
+  l, list               print the source around the current statement
+  q, quit               abort execution";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut renderer = Renderer::new()
+        .color(std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none());
+
+    let mut positional = Vec::new();
+    let mut target = None;
+    let mut fuzz_parser = false;
+    let mut runs = None;
+    let mut seed = None;
+    let mut release = false;
+    let mut strict = false;
+    let mut check = false;
+    let mut bless = false;
+    let mut trace = false;
+    let mut profile = false;
+    let mut json = false;
+    let mut emit_ast = None;
+    let mut emit = None;
+    let mut output = None;
+    let mut filter = None;
+    let mut breakpoints = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--no-color" => renderer = renderer.color(false),
+            "--json" => {
+                json = true;
+                renderer = renderer.color(false).format(OutputFormat::Json);
+            }
+            "--release" => release = true,
+            "--strict" => strict = true,
+            "--check" => check = true,
+            "--bless" => bless = true,
+            "--trace" => trace = true,
+            "--profile" => profile = true,
+            "--target" => target = args.next(),
+            "--parser" => fuzz_parser = true,
+            "--runs" => runs = args.next().map(|n| n.parse::<usize>()),
+            "--seed" => seed = args.next().map(|n| n.parse::<u64>()),
+            "-o" => output = args.next(),
+            "--filter" => filter = args.next(),
+            "--break" => breakpoints.extend(args.next()),
+            arg => {
+                if let Some(level) = arg.strip_prefix("--log=") {
+                    match level.parse::<Level>() {
+                        Ok(level) => log::set_level(Some(level)),
+                        Err(err) => {
+                            eprintln!("error: {err}");
+                            return ExitCode::FAILURE;
+                        }
+                    }
+                } else if let Some(format) = arg.strip_prefix("--emit-ast=") {
+                    emit_ast = Some(format);
+                } else if let Some(target) = arg.strip_prefix("--emit=") {
+                    emit = Some(target);
+                } else {
+                    positional.push(arg);
+                }
+            }
+        }
+    }
+
+    let result = match positional.as_slice() {
+        ["run", "-e", snippet] => {
+            renderer.add_source(String::from(EVAL_FILENAME), String::from(*snippet));
+
+            let options = ParserOptions::new().release(release).strict(strict);
+            let result = Executor::eval(snippet, options);
+            if !result.value.is_none() {
+                println!("{}", PrettyPrinter::new().print(&result.value));
+            }
+
+            result
+        }
+        ["debug", path] => {
+            let source = match std::fs::read_to_string(path) {
+                Ok(source) => source,
+                Err(err) => {
+                    eprintln!("error: failed to read '{path}': {err}");
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            let filename = Path::new(path)
+                .file_name()
+                .map_or_else(|| String::from(*path), |f| f.to_string_lossy().into_owned());
+
+            let mut prompt = DebugPrompt::new(filename.clone(), &source);
+            for spec in breakpoints.iter() {
+                match Breakpoint::parse(spec, &filename) {
+                    Some(breakpoint) => prompt.breakpoints.push(breakpoint),
+                    None => {
+                        eprintln!("{USAGE}");
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+
+            renderer.add_source(filename, source);
+
+            let options = ParserOptions::new().release(release).strict(strict);
+            Executor::debug(path, options, &mut prompt)
+        }
+        ["fmt", path] => return format_file(path, check, renderer),
+        ["refs", name, paths @ ..] => return find_references(name, paths),
+        ["symbols", paths @ ..] => {
+            return list_symbols(filter.map_or("", String::as_str), paths);
+        }
+        ["fix", path] => return fix_file(path),
+        ["test", path] => {
+            let options = GoldenOptions::new().bless(bless);
+            return match golden::run(Path::new(path), options) {
+                Ok(report) => {
+                    println!("{report}");
+
+                    if report.passed() {
+                        ExitCode::SUCCESS
+                    } else {
+                        ExitCode::FAILURE
+                    }
+                }
+                Err(err) => {
+                    eprintln!("error: failed to run tests in '{path}': {err}");
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        ["build", path] => {
+            let target = match emit {
+                Some("rust") => BuildTarget::Rust,
+                Some("c") => BuildTarget::C,
+                _ => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+            };
+
+            let options = ParserOptions::new().release(release).strict(strict);
+            return build(path, target, output.map(String::as_str), options, renderer);
+        }
+        ["fuzz", paths @ ..] => {
+            let mut options = FuzzOptions::new();
+
+            match runs {
+                Some(Ok(runs)) => options = options.runs(runs),
+                Some(Err(..)) => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+                None => {}
+            }
+
+            match seed {
+                Some(Ok(seed)) => options = options.seed(seed),
+                Some(Err(..)) => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::FAILURE;
+                }
+                None => {
+                    let nanos = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |d| d.as_nanos() as u64);
+                    options = options.seed(nanos);
+                }
+            }
+
+            if fuzz_parser {
+                return run_parser_fuzzer(paths, options);
+            }
+
+            if let ([path], Some(target)) = (paths, target) {
+                return run_fuzzer(path, target, options, renderer);
+            }
+
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+        ["run", path] => {
+            let mut env = Environment {
+                interrupt: Some(install_interrupt_handler()),
+                ..Default::default()
+            };
+
+            if let (Some(filename), Ok(source)) =
+                (Path::new(path).file_name(), std::fs::read_to_string(path))
+            {
+                if trace {
+                    env.hooks.push(Rc::new(RefCell::new(Tracer::new(&source))));
+                }
+
+                renderer.add_source(filename.to_string_lossy().into_owned(), source);
+            }
+
+            let options = ParserOptions::new().release(release).strict(strict);
+
+            if let Some(format) = emit_ast {
+                let format = match format {
+                    "tree" => AstFormat::Tree,
+                    "dot" => AstFormat::Dot,
+                    "debug" => AstFormat::Debug,
+                    _ => {
+                        eprintln!("{USAGE}");
+                        return ExitCode::FAILURE;
+                    }
+                };
+
+                return print_ast(path, format, options, renderer);
+            }
+
+            let profiler = Rc::new(RefCell::new(Profiler::new()));
+            if profile {
+                env.hooks.push(profiler.clone());
+            }
+
+            let result = Executor::run_with_environment(path, options, env);
+            profiler.borrow_mut().finish();
+
+            if profile && json {
+                println!("{}", profiler.borrow().to_json());
+            } else if profile {
+                print!("{}", profiler.borrow().report());
+            }
+
+            result
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for diagnostic in result.diagnostics.iter() {
+        eprintln!("{}", renderer.render(diagnostic));
+    }
+
+    if let Some(RuntimeErrorKind::Interrupted { .. }) = result.error.as_ref().map(|e| &e.kind) {
+        return ExitCode::from(INTERRUPTED_EXIT_CODE);
+    }
+
+    if result.has_errors() {
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn print_ast(
+    path: &str,
+    format: AstFormat,
+    options: ParserOptions,
+    renderer: Renderer,
+) -> ExitCode {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("error: failed to read '{path}': {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut parser = Parser::from_file(path, &source).with_options(options);
+    let Ok(program) = parser.parse_program();
+
+    for diagnostic in parser.diagnostics().iter() {
+        eprintln!("{}", renderer.render(diagnostic));
+    }
+
+    let printer = AstPrinter::new();
+    match format {
+        AstFormat::Tree => print!("{}", printer.print(&program)),
+        AstFormat::Dot => print!("{}", printer.print_dot(&program)),
+        AstFormat::Debug => println!("{program:#?}"),
+    }
+
+    if parser.diagnostics().iter().any(|d| d.is_error()) {
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[derive(Clone, Copy)]
+enum BuildTarget {
+    Rust,
+    C,
+}
+
+fn build(
+    path: &str,
+    target: BuildTarget,
+    output: Option<&str>,
+    options: ParserOptions,
+    mut renderer: Renderer,
+) -> ExitCode {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("error: failed to read '{path}': {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some(filename) = Path::new(path).file_name() {
+        renderer.add_source(filename.to_string_lossy().into_owned(), source.clone());
+    }
+
+    let mut parser = Parser::from_file(path, &source).with_options(options);
+
+    let Ok(program) = parser.parse_program();
+
+    let mut diagnostics = parser.diagnostics().to_vec();
+    if !diagnostics.iter().any(|d| d.is_error()) {
+        diagnostics.extend(Analyzer::check(&program).into_iter().map(Into::into));
+    }
+
+    for diagnostic in diagnostics.iter() {
+        eprintln!("{}", renderer.render(diagnostic));
+    }
+
+    if diagnostics.iter().any(|d| d.is_error()) {
+        return ExitCode::FAILURE;
+    }
+
+    let emitted = match target {
+        BuildTarget::Rust => RustBackend::new().emit(&program),
+        BuildTarget::C => CBackend::new()
+            .inline_runtime(output.is_none())
+            .emit(&program),
+    };
+
+    let source = match emitted {
+        Ok(source) => source,
+        Err(diagnostics) => {
+            for diagnostic in diagnostics.iter() {
+                eprintln!("{}", renderer.render(diagnostic));
+            }
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match output {
+        Some(output) => {
+            if let Err(err) = std::fs::write(output, source) {
+                eprintln!("error: failed to write '{output}': {err}");
+                return ExitCode::FAILURE;
+            }
+
+            if let BuildTarget::C = target {
+                let header = Path::new(output).with_file_name(c::RUNTIME_HEADER);
+                if let Err(err) = std::fs::write(&header, CBackend::runtime()) {
+                    eprintln!("error: failed to write '{}': {err}", header.display());
+                    return ExitCode::FAILURE;
+                }
+            }
+        }
+        None => print!("{source}"),
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn format_file(path: &str, check: bool, mut renderer: Renderer) -> ExitCode {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("error: failed to read '{path}': {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let filename = Path::new(path)
+        .file_name()
+        .map_or_else(|| String::from(path), |f| f.to_string_lossy().into_owned());
+
+    renderer.add_source(filename.clone(), source.clone());
+
+    let formatted = match Formatter::new().format(&source, &filename) {
+        Ok(formatted) => formatted,
+        Err(diagnostics) => {
+            for diagnostic in diagnostics.iter() {
+                eprintln!("{}", renderer.render(diagnostic));
+            }
+
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if formatted == source {
+        return ExitCode::SUCCESS;
+    }
+
+    if check {
+        eprintln!("{path} is not formatted");
+        return ExitCode::FAILURE;
+    }
+
+    if let Err(err) = std::fs::write(path, formatted) {
+        eprintln!("error: failed to write '{path}': {err}");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn fix_file(path: &str) -> ExitCode {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("error: failed to read '{path}': {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut parser = Parser::from_file(path, &source);
+
+    let Ok(_program) = parser.parse_program();
+
+    let fixes: Vec<_> = parser
+        .diagnostics()
+        .iter()
+        .filter_map(|d| d.fix.as_deref())
+        .collect();
+
+    if fixes.is_empty() {
+        eprintln!("no fixes to apply in {path}");
+        return ExitCode::SUCCESS;
+    }
+
+    let edits: Vec<_> = fixes.iter().flat_map(|f| f.edits.iter().cloned()).collect();
+
+    if let Err(err) = std::fs::write(path, diagnostics::apply_edits(&source, &edits)) {
+        eprintln!("error: failed to write '{path}': {err}");
+        return ExitCode::FAILURE;
+    }
+
+    for fix in fixes.iter() {
+        eprintln!("fixed: {}", fix.message);
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn run_fuzzer(path: &str, target: &str, options: FuzzOptions, mut renderer: Renderer) -> ExitCode {
+    if let (Some(filename), Ok(source)) =
+        (Path::new(path).file_name(), std::fs::read_to_string(path))
+    {
+        renderer.add_source(filename.to_string_lossy().into_owned(), source);
+    }
+
+    let mut engine = Engine::new();
+    engine.set_log_level(log::level());
+    if let Err(err) = engine.load_file(path) {
+        eprintln!("error: {err}");
+        return ExitCode::FAILURE;
+    }
+
+    for diagnostic in engine.diagnostics().iter() {
+        eprintln!("{}", renderer.render(diagnostic));
+    }
+
+    if engine.diagnostics().iter().any(|d| d.is_error()) {
+        return ExitCode::FAILURE;
+    }
+
+    match fuzz::fuzz(&mut engine, target, options) {
+        Ok(report) => {
+            println!("{report}");
+
+            if let Some(failure) = report.failure {
+                eprintln!("{}", renderer.render(&failure.error.into()));
+                return ExitCode::FAILURE;
+            }
+
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn discover_sources(paths: &[&str]) -> Option<Vec<PathBuf>> {
+    let roots = if paths.is_empty() { &["."][..] } else { paths };
+
+    let mut files = Vec::new();
+    for root in roots.iter() {
+        match golden::discover(Path::new(root)) {
+            Ok(found) => files.extend(found),
+            Err(err) => {
+                eprintln!("error: failed to read '{root}': {err}");
+                return None;
+            }
+        }
+    }
+
+    Some(files)
+}
+
+fn list_symbols(filter: &str, paths: &[&str]) -> ExitCode {
+    let Some(files) = discover_sources(paths) else {
+        return ExitCode::FAILURE;
+    };
+
+    let mut found = Vec::new();
+    for file in files.iter() {
+        let source = match std::fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("error: failed to read '{}': {err}", file.display());
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let index = SymbolIndex::new(&source, &file.to_string_lossy());
+        for (score, symbol) in index.search(filter) {
+            let name = match &symbol.container {
+                Some(container) => format!("{container}::{}", symbol.name),
+                None => symbol.name.clone(),
+            };
+
+            found.push((
+                score,
+                format!(
+                    "{}:{}:{}-{}:{}: {} {name}",
+                    file.display(),
+                    symbol.start.row + 1,
+                    symbol.start.column + 1,
+                    symbol.end.row + 1,
+                    symbol.end.column + 1,
+                    symbol.kind
+                ),
+            ));
+        }
+    }
+
+    if found.is_empty() {
+        eprintln!("no symbols match '{filter}'");
+        return ExitCode::FAILURE;
+    }
+
+    found.sort_by(|(a, _), (b, _)| b.cmp(a));
+    for (_, line) in found {
+        println!("{line}");
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn find_references(name: &str, paths: &[&str]) -> ExitCode {
+    let (container, name) = match name.rsplit_once("::") {
+        Some((container, name)) => (Some(container), name),
+        None => (None, name),
+    };
+
+    let Some(files) = discover_sources(paths) else {
+        return ExitCode::FAILURE;
+    };
+
+    let mut found = false;
+    for file in files.iter() {
+        let source = match std::fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("error: failed to read '{}': {err}", file.display());
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let index = SymbolIndex::new(&source, &file.to_string_lossy());
+        let lines: Vec<&str> = source.lines().collect();
+
+        let mut references: Vec<_> = index
+            .symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| {
+                symbol.name == name
+                    && symbol.kind != SymbolKind::Impl
+                    && container.is_none_or(|c| symbol.container.as_deref() == Some(c))
+            })
+            .flat_map(|(s, _)| index.references(s))
+            .collect();
+        references.sort_by_key(|reference| reference.token);
+
+        for reference in references {
+            found = true;
+
+            let position = &reference.position;
+            let line = lines.get(position.row as usize).map_or("", |l| l.trim());
+            let within = index
+                .enclosing(position)
+                .filter(|&s| index::key(&index.symbols[s].position) != index::key(position))
+                .map(|s| format!(" in {}", index.symbols[s].name))
+                .unwrap_or_default();
+
+            println!(
+                "{}:{}:{}: {}{within}: {line}",
+                file.display(),
+                position.row + 1,
+                position.column + 1,
+                reference.kind
+            );
+        }
+    }
+
+    if !found {
+        eprintln!("no references to '{name}' found");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+#[cfg(unix)]
+fn install_interrupt_handler() -> &'static AtomicBool {
+    const SIGINT: i32 = 2;
+    const SIG_DFL: usize = 0;
+
+    extern "C" {
+        fn signal(signum: i32, handler: usize) -> usize;
+    }
+
+    extern "C" fn on_interrupt(_: i32) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+
+        // a second Ctrl-C kills the process if the script never reaches another statement
+        unsafe {
+            signal(SIGINT, SIG_DFL);
+        }
+    }
+
+    unsafe {
+        signal(SIGINT, on_interrupt as extern "C" fn(i32) as usize);
+    }
+
+    &INTERRUPTED
+}
+
+#[cfg(not(unix))]
+fn install_interrupt_handler() -> &'static AtomicBool {
+    &INTERRUPTED
+}
+
+fn run_parser_fuzzer(paths: &[&str], options: FuzzOptions) -> ExitCode {
+    let mut corpus = Vec::new();
+    for path in paths.iter() {
+        match std::fs::read_to_string(path) {
+            Ok(source) => corpus.push(source),
+            Err(err) => {
+                eprintln!("error: failed to read '{path}': {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let report = fuzz::fuzz_parser(&corpus, options);
+    println!("{report}");
+
+    if report.failure.is_some() {
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+struct DebugPrompt {
+    filename: String,
+    lines: Vec<String>,
+    breakpoints: Vec<Breakpoint>,
+    last: String,
+}
+
+impl DebugPrompt {
+    fn new(filename: String, source: &str) -> Self {
+        Self {
+            filename,
+            lines: source.lines().map(String::from).collect(),
+            breakpoints: Vec::new(),
+            last: String::new(),
+        }
+    }
+
+    fn list(&self, row: usize, context: usize) {
+        let start = row.saturating_sub(context);
+        let end = (row + context).min(self.lines.len().saturating_sub(1));
+
+        for (i, line) in self.lines.iter().enumerate().take(end + 1).skip(start) {
+            let marker = if i == row { '>' } else { ' ' };
+            println!("{marker}{:>4} | {line}", i + 1);
+        }
+    }
+
+    fn read_command(&mut self) -> Option<String> {
+        print!("(meta) ");
+        let _ = std::io::stdout().flush();
+
+        let mut line = String::new();
+        match std::io::stdin().read_line(&mut line) {
+            Ok(0) | Err(..) => return None,
+            Ok(..) => {}
+        }
+
+        if !line.trim().is_empty() {
+            self.last = String::from(line.trim());
+        }
+
+        Some(self.last.clone())
+    }
+}
+
+impl DebugHook for DebugPrompt {
+    fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    fn pause(&mut self, state: &DebugState) -> DebugAction {
+        if state.reason == PauseReason::Entry && !self.breakpoints.is_empty() {
+            return DebugAction::Continue;
+        }
+
+        let procedure = state.call_stack.last().map_or("", String::as_str);
+        match state.reason {
+            PauseReason::Breakpoint => {
+                println!("breakpoint hit at {} in '{procedure}'", state.position)
+            }
+            PauseReason::Entry | PauseReason::Step => {
+                println!("{} in '{procedure}'", state.position)
+            }
+        }
+
+        let row = state.position.row as usize;
+        self.list(row, 0);
+
+        let printer = PrettyPrinter::new();
+
+        while let Some(line) = self.read_command() {
+            let (command, arg) = line.split_once(' ').unwrap_or((&line, ""));
+            let arg = arg.trim();
+
+            match command {
+                "s" | "step" => return DebugAction::Step,
+                "n" | "next" => return DebugAction::Next,
+                "f" | "finish" => return DebugAction::Finish,
+                "c" | "continue" => return DebugAction::Continue,
+                "q" | "quit" => return DebugAction::Abort,
+                "b" | "break" if arg.is_empty() => {
+                    if self.breakpoints.is_empty() {
+                        println!("no breakpoints");
+                    }
+
+                    for breakpoint in self.breakpoints.iter() {
+                        println!("{breakpoint}");
+                    }
+                }
+                "b" | "break" => match Breakpoint::parse(arg, &self.filename) {
+                    Some(breakpoint) => {
+                        println!("breakpoint set at {breakpoint}");
+                        if !self.breakpoints.contains(&breakpoint) {
+                            self.breakpoints.push(breakpoint);
+                        }
+                    }
+                    None => println!("invalid breakpoint '{arg}'"),
+                },
+                "d" | "delete" => match Breakpoint::parse(arg, &self.filename) {
+                    Some(breakpoint) if self.breakpoints.contains(&breakpoint) => {
+                        println!("breakpoint removed at {breakpoint}");
+                        self.breakpoints.retain(|b| *b != breakpoint);
+                    }
+                    _ => println!("no breakpoint at '{arg}'"),
+                },
+                "p" | "print" => match state.lookup(arg) {
+                    Some(value) => println!("{arg} = {}", printer.print(value)),
+                    None => println!("no variable named '{arg}' in scope"),
+                },
+                "locals" => {
+                    let locals = state.locals();
+                    if locals.is_empty() {
+                        println!("no variables in scope");
+                    }
+
+                    for (name, value) in locals {
+                        println!("{name} = {}", printer.print(value));
+                    }
+                }
+                "bt" | "backtrace" => {
+                    for (i, name) in state.call_stack.iter().rev().enumerate() {
+                        println!("#{i} {name}");
+                    }
+                }
+                "l" | "list" => self.list(row, 3),
+                "h" | "help" => println!("{DEBUG_HELP}"),
+                _ => println!("unknown command '{command}', type 'help' for a list of commands"),
+            }
+        }
+
+        DebugAction::Continue
+    }
+}
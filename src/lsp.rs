@@ -0,0 +1,14 @@
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+
+    match meta::lsp::serve(stdin.lock(), stdout.lock()) {
+        Ok(code) => ExitCode::from(code as u8),
+        Err(err) => {
+            eprintln!("{}: {err}", meta::lsp::SERVER_NAME);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use std::fmt::Display;
+
+use crate::{
+    expression::Expression,
+    nodes::BinaryOp,
+    parser::Program,
+    token::{LiteralType, Position, Token},
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LintCode {
+    DeadStore,
+    ConstantCondition,
+}
+
+impl LintCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LintCode::DeadStore => "dead-store",
+            LintCode::ConstantCondition => "constant-condition",
+        }
+    }
+
+    pub fn rationale(&self) -> &'static str {
+        match self {
+            LintCode::DeadStore => {
+                "the value is overwritten before it is ever read, so the earlier assignment has no effect"
+            }
+            LintCode::ConstantCondition => {
+                "the condition can be decided without running the program, so the branch is either always or never taken"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Lint {
+    pub code: LintCode,
+    pub position: Position,
+    pub message: String,
+    pub rationale: &'static str,
+}
+
+impl Lint {
+    pub fn new(code: LintCode, position: Position, message: String) -> Self {
+        Self {
+            code,
+            position,
+            message,
+            rationale: code.rationale(),
+        }
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "<{}> Warning: {} [{}]\n\tnote: {}",
+            self.position,
+            self.message,
+            self.code.as_str(),
+            self.rationale
+        ))
+    }
+}
+
+struct Store {
+    name: String,
+    position: Position,
+}
+
+pub struct Analyzer {
+    lints: Vec<Lint>,
+}
+
+impl Analyzer {
+    pub fn check(program: &Program) -> Vec<Lint> {
+        let mut analyzer = Self { lints: Vec::new() };
+
+        analyzer.visit_block(program);
+
+        analyzer.lints
+    }
+
+    fn visit_block(&mut self, statements: &[Expression]) {
+        let mut stores = Vec::new();
+
+        for statement in statements.iter() {
+            self.visit_statement(statement, &mut stores);
+        }
+    }
+
+    fn visit_statement(&mut self, statement: &Expression, stores: &mut Vec<Store>) {
+        match statement {
+            Expression::ProcDef(proc_def_node) if proc_def_node.is_instance() => {}
+            Expression::ProcDef(proc_def_node) => self.visit_block(&proc_def_node.statements),
+            Expression::ImplStatement(impl_node) => self.visit_block(&impl_node.procedures),
+            Expression::IfStatement(if_node) => {
+                self.check_condition(&if_node.value, &if_node.position, false);
+                self.read(statement, stores);
+                self.visit_block(&if_node.statements);
+                if let Some(else_node) = &if_node.else_branch {
+                    self.visit_block(&else_node.statements);
+                }
+            }
+            Expression::WhileStatement(while_node) => {
+                self.check_condition(&while_node.value, &while_node.position, true);
+                self.read(statement, stores);
+                self.visit_block(&while_node.statements);
+            }
+            Expression::ForLoop(for_node) => {
+                self.read(statement, stores);
+                self.visit_block(&for_node.statements);
+            }
+            Expression::MatchStatement(match_node) => {
+                self.read(statement, stores);
+                for arm in match_node.arms.iter() {
+                    self.visit_block(&arm.statements);
+                }
+            }
+            Expression::LetStatement(let_node) => {
+                self.read(&let_node.value, stores);
+
+                stores.retain(|s| s.name != let_node.name);
+                stores.push(Store {
+                    name: let_node.name.clone(),
+                    position: let_node.position.clone(),
+                });
+            }
+            Expression::AssignStatement(assign_node) => {
+                self.read(&assign_node.new_value, stores);
+
+                let name = assign_node.value.metadata.name.clone();
+                if let Some(index) = stores.iter().position(|s| s.name == name) {
+                    let store = stores.remove(index);
+
+                    self.lints.push(Lint::new(
+                        LintCode::DeadStore,
+                        store.position,
+                        format!("value assigned to '{name}' is never read before it is reassigned"),
+                    ));
+                }
+
+                stores.push(Store {
+                    name,
+                    position: assign_node.position.clone(),
+                });
+            }
+            Expression::Error(..) => stores.clear(),
+            _ => self.read(statement, stores),
+        }
+    }
+
+    fn read(&self, expr: &Expression, stores: &mut Vec<Store>) {
+        let mut names = Vec::new();
+        collect_reads(expr, &mut names);
+
+        stores.retain(|s| !names.contains(&s.name));
+    }
+
+    fn check_condition(&mut self, condition: &Expression, position: &Position, is_loop: bool) {
+        if is_loop {
+            if let Expression::Literal(_, LiteralType::Bool) = condition {
+                return;
+            }
+        }
+
+        if let Some(value) = constant_condition(condition) {
+            self.lints.push(Lint::new(
+                LintCode::ConstantCondition,
+                position.clone(),
+                format!("condition is always {value}"),
+            ));
+        }
+    }
+}
+
+fn collect_reads(expr: &Expression, names: &mut Vec<String>) {
+    match expr {
+        Expression::IfStatement(if_node) => {
+            collect_reads(&if_node.value, names);
+            for statement in if_node.statements.iter() {
+                collect_reads(statement, names);
+            }
+            if let Some(else_node) = &if_node.else_branch {
+                for statement in else_node.statements.iter() {
+                    collect_reads(statement, names);
+                }
+            }
+        }
+        Expression::WhileStatement(while_node) => {
+            collect_reads(&while_node.value, names);
+            for statement in while_node.statements.iter() {
+                collect_reads(statement, names);
+            }
+        }
+        Expression::ForLoop(for_node) => {
+            collect_reads(&for_node.range, names);
+            for statement in for_node.statements.iter() {
+                collect_reads(statement, names);
+            }
+        }
+        Expression::MatchStatement(match_node) => {
+            collect_reads(&match_node.value, names);
+            for arm in match_node.arms.iter() {
+                if let Some(guard) = &arm.guard {
+                    collect_reads(guard, names);
+                }
+                for statement in arm.statements.iter() {
+                    collect_reads(statement, names);
+                }
+            }
+        }
+        Expression::RangeStatement(range_node) => {
+            collect_reads(&range_node.start, names);
+            collect_reads(&range_node.end, names);
+        }
+        Expression::LetStatement(let_node) => collect_reads(&let_node.value, names),
+        Expression::AssignStatement(assign_node) => collect_reads(&assign_node.new_value, names),
+        Expression::ReturnStatement(return_node) => collect_reads(&return_node.value, names),
+        Expression::Variable(variable_node) => names.push(variable_node.metadata.name.clone()),
+        Expression::FunCall(fun_call_node) => {
+            for arg in fun_call_node.args.iter() {
+                collect_reads(&arg.value, names);
+            }
+        }
+        Expression::Closure(closure_node) => {
+            for capture in closure_node.captures.iter() {
+                names.push(capture.name.clone());
+            }
+        }
+        Expression::ClosureCall(closure_call_node) => {
+            collect_reads(&closure_call_node.callee, names);
+            for arg in closure_call_node.args.iter() {
+                collect_reads(arg, names);
+            }
+        }
+        Expression::ImplFunCall(impl_fun_call_node) => {
+            collect_reads(&impl_fun_call_node.fun_call_node, names)
+        }
+        Expression::DynCall(dyn_call_node) => {
+            for arg in dyn_call_node.fun_call_node.args.iter() {
+                collect_reads(&arg.value, names);
+            }
+        }
+        Expression::Pipe(pipe_node) => collect_reads(&pipe_node.call, names),
+        Expression::Coalesce(coalesce_node) => {
+            collect_reads(&coalesce_node.value, names);
+            collect_reads(&coalesce_node.fallback, names);
+        }
+        Expression::CompareChain(compare_chain_node) => {
+            for operand in compare_chain_node.operands.iter() {
+                collect_reads(operand, names);
+            }
+        }
+        Expression::MapCall(map_call_node) => {
+            collect_reads(&map_call_node.map, names);
+            for arg in map_call_node.args.iter() {
+                collect_reads(arg, names);
+            }
+        }
+        Expression::VecCall(vec_call_node) => {
+            collect_reads(&vec_call_node.vec, names);
+            for arg in vec_call_node.args.iter() {
+                collect_reads(arg, names);
+            }
+        }
+        Expression::StructInstance(struct_instance_node) => {
+            for field in struct_instance_node.fields.iter() {
+                collect_reads(&field.value, names);
+            }
+        }
+        Expression::EnumInstance(enum_instance_node) => {
+            for field in enum_instance_node.fields.iter() {
+                collect_reads(&field.value, names);
+            }
+        }
+        Expression::EnumFrom(enum_from_node) => collect_reads(&enum_from_node.value, names),
+        Expression::EnumParse(enum_parse_node) => collect_reads(&enum_parse_node.value, names),
+        Expression::VariantName(variant_name_node) => {
+            collect_reads(&variant_name_node.value, names)
+        }
+        Expression::Cast(cast_node) => collect_reads(&cast_node.value, names),
+        Expression::Contains(contains_node) => {
+            collect_reads(&contains_node.value, names);
+            collect_reads(&contains_node.flag, names);
+        }
+        Expression::StructFieldAssign(field_assign_node) => {
+            names.push(field_assign_node.struct_instance.metadata.name.clone());
+            collect_reads(&field_assign_node.new_value, names);
+        }
+        Expression::StructFieldAccess(field_access_node) => {
+            collect_reads(&field_access_node.base, names)
+        }
+        Expression::BinaryOp(binary_op_node) => {
+            collect_reads(&binary_op_node.lhs, names);
+            collect_reads(&binary_op_node.rhs, names);
+        }
+        Expression::ProcDef(..)
+        | Expression::StructDef(..)
+        | Expression::EnumDef(..)
+        | Expression::ImplStatement(..)
+        | Expression::TraitDef(..)
+        | Expression::MapNew(..)
+        | Expression::VecNew(..)
+        | Expression::BreakStatement(..)
+        | Expression::ContinueStatement(..)
+        | Expression::Literal(..)
+        | Expression::Error(..) => {}
+    }
+}
+
+fn constant_condition(expr: &Expression) -> Option<bool> {
+    match expr {
+        Expression::Literal(token, LiteralType::Bool) => Some(token.value == "true"),
+        Expression::BinaryOp(binary_op_node) => {
+            match (binary_op_node.lhs.as_ref(), binary_op_node.rhs.as_ref()) {
+                (Expression::Literal(lhs, lt), Expression::Literal(rhs, rt)) if lt == rt => {
+                    compare_literals(lhs, &binary_op_node.op, rhs, *lt)
+                }
+                (Expression::Variable(lhs), Expression::Variable(rhs))
+                    if lhs.metadata.name == rhs.metadata.name =>
+                {
+                    match binary_op_node.op {
+                        BinaryOp::Eq | BinaryOp::Lte | BinaryOp::Gte => Some(true),
+                        BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Gt => Some(false),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        Expression::CompareChain(compare_chain_node) => {
+            let operands = &compare_chain_node.operands;
+            for (op, pair) in compare_chain_node.ops.iter().zip(operands.windows(2)) {
+                if let (Expression::Literal(lhs, lt), Expression::Literal(rhs, rt)) =
+                    (&pair[0], &pair[1])
+                {
+                    if lt == rt && compare_literals(lhs, op, rhs, *lt) == Some(false) {
+                        return Some(false);
+                    }
+                }
+            }
+
+            // the bounds of a chain decide it even when the operands between them are unknown
+            let first = operands.first()?;
+            let last = operands.last()?;
+            let strict = compare_chain_node
+                .ops
+                .iter()
+                .any(|op| matches!(op, BinaryOp::Lt | BinaryOp::Gt));
+            let op = match (compare_chain_node.ops.first()?.is_ascending(), strict) {
+                (true, true) => BinaryOp::Lt,
+                (true, false) => BinaryOp::Lte,
+                (false, true) => BinaryOp::Gt,
+                (false, false) => BinaryOp::Gte,
+            };
+
+            match (first, last) {
+                (Expression::Literal(lhs, lt), Expression::Literal(rhs, rt)) if lt == rt => {
+                    compare_literals(lhs, &op, rhs, *lt).filter(|holds| !holds)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn compare_literals(lhs: &Token, op: &BinaryOp, rhs: &Token, kind: LiteralType) -> Option<bool> {
+    let ordering = match kind {
+        LiteralType::Number => {
+            let lhs = lhs.value.parse::<i64>().ok()?;
+            let rhs = rhs.value.parse::<i64>().ok()?;
+            lhs.partial_cmp(&rhs)?
+        }
+        LiteralType::Float => {
+            let lhs = lhs.value.parse::<f64>().ok()?;
+            let rhs = rhs.value.parse::<f64>().ok()?;
+            lhs.partial_cmp(&rhs)?
+        }
+        _ => {
+            return match op {
+                BinaryOp::Eq => Some(lhs.value == rhs.value),
+                BinaryOp::Ne => Some(lhs.value != rhs.value),
+                _ => None,
+            }
+        }
+    };
+
+    match op {
+        BinaryOp::Eq => Some(ordering.is_eq()),
+        BinaryOp::Ne => Some(ordering.is_ne()),
+        BinaryOp::Lt => Some(ordering.is_lt()),
+        BinaryOp::Lte => Some(ordering.is_le()),
+        BinaryOp::Gt => Some(ordering.is_gt()),
+        BinaryOp::Gte => Some(ordering.is_ge()),
+        _ => None,
+    }
+}
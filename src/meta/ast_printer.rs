@@ -0,0 +1,656 @@
+use crate::{
+    expression::Expression,
+    nodes::{ClosureSyntax, ContractKind, ProcType, VariableNode},
+    token::Position,
+};
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum AstStyle {
+    #[default]
+    Multiline,
+    Compact,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AstPrinter {
+    indent: usize,
+    style: AstStyle,
+    positions: bool,
+}
+
+impl Default for AstPrinter {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            style: AstStyle::Multiline,
+            positions: false,
+        }
+    }
+}
+
+struct Tree {
+    label: String,
+    position: Option<Position>,
+    group: bool,
+    edge: Option<&'static str>,
+    children: Vec<Tree>,
+}
+
+impl Tree {
+    fn leaf(label: String) -> Self {
+        Self {
+            label,
+            position: None,
+            group: false,
+            edge: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn node(label: String, position: &Position, children: Vec<Tree>) -> Self {
+        Self {
+            label,
+            position: Some(position.clone()),
+            group: false,
+            edge: None,
+            children,
+        }
+    }
+
+    fn edge(mut self, edge: &'static str) -> Self {
+        self.edge = Some(edge);
+        self
+    }
+
+    fn group(label: &str, children: Vec<Tree>) -> Self {
+        Self {
+            label: String::from(label),
+            position: None,
+            group: true,
+            edge: None,
+            children,
+        }
+    }
+}
+
+impl AstPrinter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn style(mut self, style: AstStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    pub fn positions(mut self, enabled: bool) -> Self {
+        self.positions = enabled;
+        self
+    }
+
+    pub fn print(&self, program: &[Expression]) -> String {
+        let mut out = String::new();
+        for expr in program.iter() {
+            out.push_str(&self.print_expr(expr));
+            out.push('\n');
+        }
+
+        out
+    }
+
+    pub fn print_expr(&self, expr: &Expression) -> String {
+        let tree = build(expr);
+
+        let mut out = String::new();
+        match self.style {
+            AstStyle::Multiline => self.write_multiline(&mut out, &tree, 0),
+            AstStyle::Compact => self.write_compact(&mut out, &tree),
+        }
+
+        out
+    }
+
+    pub fn print_dot(&self, program: &[Expression]) -> String {
+        let mut out = String::from("digraph ast {\n");
+        out.push_str(&format!(
+            "{}node [shape=box, fontname=\"monospace\"];\n",
+            " ".repeat(self.indent)
+        ));
+
+        let mut next_id = 0;
+        for expr in program.iter() {
+            self.write_dot(&mut out, &build(expr), &mut next_id);
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    fn write_dot(&self, out: &mut String, tree: &Tree, next_id: &mut usize) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+
+        let mut label = tree.label.clone();
+        self.write_position(&mut label, tree);
+
+        let indent = " ".repeat(self.indent);
+        out.push_str(&format!(
+            "{indent}n{id} [label=\"{}\"];\n",
+            escape_dot(&label)
+        ));
+
+        for child in tree.children.iter() {
+            let (edge, grandchildren) = if child.group {
+                (Some(child.label.as_str()), child.children.iter().collect())
+            } else {
+                (child.edge, vec![child])
+            };
+
+            for node in grandchildren {
+                let child_id = self.write_dot(out, node, next_id);
+                match edge {
+                    Some(edge) => out.push_str(&format!(
+                        "{indent}n{id} -> n{child_id} [label=\"{}\"];\n",
+                        escape_dot(edge)
+                    )),
+                    None => out.push_str(&format!("{indent}n{id} -> n{child_id};\n")),
+                }
+            }
+        }
+
+        id
+    }
+
+    fn write_multiline(&self, out: &mut String, tree: &Tree, depth: usize) {
+        if depth > 0 {
+            out.push('\n');
+        }
+
+        out.push_str(&" ".repeat(self.indent * depth));
+        out.push_str(&tree.label);
+
+        if tree.group {
+            out.push(':');
+        }
+
+        self.write_position(out, tree);
+
+        for child in tree.children.iter() {
+            self.write_multiline(out, child, depth + 1);
+        }
+    }
+
+    fn write_compact(&self, out: &mut String, tree: &Tree) {
+        out.push_str(&tree.label);
+        self.write_position(out, tree);
+
+        if tree.group {
+            out.push_str(": [");
+        } else if !tree.children.is_empty() {
+            out.push('(');
+        } else {
+            return;
+        }
+
+        for (i, child) in tree.children.iter().enumerate() {
+            if i > 0 {
+                out.push_str(", ");
+            }
+
+            self.write_compact(out, child);
+        }
+
+        out.push(if tree.group { ']' } else { ')' });
+    }
+
+    fn write_position(&self, out: &mut String, tree: &Tree) {
+        if !self.positions {
+            return;
+        }
+
+        if let Some(position) = tree.position.as_ref().filter(|p| !p.filename.is_empty()) {
+            out.push_str(&format!(" @ {}:{}", position.row + 1, position.column + 1));
+        }
+    }
+}
+
+fn escape_dot(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn generic_name(name: &str, generics: &[String]) -> String {
+    if generics.is_empty() {
+        return String::from(name);
+    }
+
+    format!("{name}<{}>", generics.join(", "))
+}
+
+fn build_all(exprs: &[Expression]) -> Vec<Tree> {
+    exprs.iter().map(build).collect()
+}
+
+fn build_fields(fields: &[VariableNode]) -> Vec<Tree> {
+    fields
+        .iter()
+        .map(|field| Tree::group(&field.metadata.name, vec![build(&field.value)]))
+        .collect()
+}
+
+fn build(expr: &Expression) -> Tree {
+    match expr {
+        Expression::IfStatement(if_node) => {
+            let mut children = vec![
+                build(&if_node.value).edge("condition"),
+                Tree::group("then", build_all(&if_node.statements)),
+            ];
+            if let Some(else_node) = &if_node.else_branch {
+                children.push(Tree::group("else", build_all(&else_node.statements)));
+            }
+
+            Tree::node(String::from("If"), &if_node.position, children)
+        }
+        Expression::WhileStatement(while_node) => Tree::node(
+            String::from("While"),
+            &while_node.position,
+            vec![
+                build(&while_node.value).edge("condition"),
+                Tree::group("body", build_all(&while_node.statements)),
+            ],
+        ),
+        Expression::ForLoop(for_node) => Tree::node(
+            format!("For '{}'", for_node.counter.metadata.name),
+            &for_node.position,
+            vec![
+                build(&for_node.range).edge("range"),
+                Tree::group("body", build_all(&for_node.statements)),
+            ],
+        ),
+        Expression::MatchStatement(match_node) => {
+            let arms = match_node
+                .arms
+                .iter()
+                .map(|arm| {
+                    let mut children = Vec::new();
+                    if let Some(guard) = &arm.guard {
+                        children.push(Tree::group("guard", vec![build(guard)]));
+                    }
+                    children.push(Tree::group("body", build_all(&arm.statements)));
+
+                    Tree {
+                        label: format!("Arm {}", arm.pattern),
+                        position: None,
+                        group: false,
+                        edge: None,
+                        children,
+                    }
+                })
+                .collect();
+
+            Tree::node(
+                String::from("Match"),
+                &match_node.position,
+                vec![
+                    build(&match_node.value).edge("value"),
+                    Tree::group("arms", arms),
+                ],
+            )
+        }
+        Expression::RangeStatement(range_node) => Tree {
+            label: if range_node.inclusive {
+                String::from("Range (inclusive)")
+            } else {
+                String::from("Range")
+            },
+            position: None,
+            group: false,
+            edge: None,
+            children: vec![
+                build(&range_node.start).edge("start"),
+                build(&range_node.end).edge("end"),
+            ],
+        },
+        Expression::LetStatement(let_node) => {
+            let mutability = if let_node.mutable { "mut " } else { "" };
+            Tree::node(
+                format!(
+                    "Let {mutability}'{}': {}",
+                    let_node.name, let_node.type_name
+                ),
+                &let_node.position,
+                vec![build(&let_node.value).edge("value")],
+            )
+        }
+        Expression::AssignStatement(assign_node) => Tree::node(
+            format!("Assign '{}'", assign_node.value.metadata.name),
+            &assign_node.position,
+            vec![build(&assign_node.new_value).edge("value")],
+        ),
+        Expression::ReturnStatement(return_node) => Tree::node(
+            if return_node.implicit {
+                String::from("Return (implicit)")
+            } else {
+                String::from("Return")
+            },
+            &return_node.position,
+            vec![build(&return_node.value).edge("value")],
+        ),
+        Expression::BreakStatement(..) => Tree::leaf(String::from("Break")),
+        Expression::ContinueStatement(..) => Tree::leaf(String::from("Continue")),
+        Expression::Variable(variable_node) => Tree::leaf(format!(
+            "Variable '{}': {}",
+            variable_node.metadata.name, variable_node.metadata.type_name
+        )),
+        Expression::ProcDef(proc_def) => {
+            let args = proc_def
+                .args
+                .iter()
+                .map(|arg| format!("{}: {}", arg.name, arg.type_name))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let return_type = proc_def.return_type.as_deref().unwrap_or("None");
+
+            let mut children = Vec::new();
+            if !proc_def.contracts.is_empty() {
+                let contracts = proc_def
+                    .contracts
+                    .iter()
+                    .map(|contract| {
+                        let kind = match contract.kind {
+                            ContractKind::Requires => "Requires",
+                            ContractKind::Ensures => "Ensures",
+                        };
+
+                        Tree::node(
+                            format!("{kind} '{}'", contract.source),
+                            &contract.position,
+                            Vec::new(),
+                        )
+                    })
+                    .collect();
+
+                children.push(Tree::group("contracts", contracts));
+            }
+            children.push(Tree::group("body", build_all(&proc_def.statements)));
+
+            Tree::node(
+                format!(
+                    "ProcDef '{}'({args}): {return_type}",
+                    generic_name(&proc_def.name, &proc_def.generics)
+                ),
+                &proc_def.position,
+                children,
+            )
+        }
+        Expression::FunCall(fun_call_node) => Tree::node(
+            format!("Call '{}'", fun_call_node.proc_def.name),
+            &fun_call_node.position,
+            build_fields(&fun_call_node.args),
+        ),
+        Expression::Closure(closure_node) if closure_node.syntax == ClosureSyntax::Reference => {
+            Tree::node(
+                format!("ProcRef '{}'", closure_node.proc_def.name),
+                &closure_node.proc_def.position,
+                Vec::new(),
+            )
+        }
+        Expression::Closure(closure_node) => {
+            let proc_def = &closure_node.proc_def;
+            let args = proc_def
+                .args
+                .iter()
+                .map(|arg| format!("{}: {}", arg.name, arg.type_name))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            let return_type = proc_def.return_type.as_deref().unwrap_or("None");
+
+            let mut children = Vec::new();
+            if !closure_node.captures.is_empty() {
+                let captures = closure_node
+                    .captures
+                    .iter()
+                    .map(|capture| Tree::leaf(format!("{}: {}", capture.name, capture.type_name)))
+                    .collect();
+
+                children.push(Tree::group("captures", captures));
+            }
+            children.push(Tree::group("body", build_all(&proc_def.statements)));
+
+            Tree::node(
+                format!("Closure '{}'({args}): {return_type}", proc_def.name),
+                &proc_def.position,
+                children,
+            )
+        }
+        Expression::ClosureCall(closure_call_node) => {
+            let mut children = vec![build(&closure_call_node.callee).edge("callee")];
+            children.extend(build_all(&closure_call_node.args));
+
+            Tree::node(
+                String::from("ClosureCall"),
+                &closure_call_node.position,
+                children,
+            )
+        }
+        Expression::StructDef(struct_def) => Tree::node(
+            if struct_def.derives.is_empty() {
+                format!(
+                    "StructDef '{}'",
+                    generic_name(&struct_def.type_name, &struct_def.generics)
+                )
+            } else {
+                format!(
+                    "StructDef '{}' @derive({})",
+                    struct_def.type_name,
+                    struct_def.derives.join(", ")
+                )
+            },
+            &struct_def.position,
+            struct_def
+                .fields
+                .iter()
+                .map(|field| Tree::leaf(format!("{}: {}", field.name, field.type_name)))
+                .collect(),
+        ),
+        Expression::EnumDef(enum_def) => Tree::node(
+            if enum_def.flags {
+                format!("EnumDef '{}' @flags", enum_def.type_name)
+            } else {
+                format!("EnumDef '{}'", enum_def.type_name)
+            },
+            &enum_def.position,
+            enum_def
+                .variants
+                .iter()
+                .map(|variant| {
+                    let fields = variant
+                        .fields
+                        .iter()
+                        .map(|field| format!("{}: {}", field.name, field.type_name))
+                        .collect::<Vec<_>>();
+
+                    match variant.discriminant {
+                        Some(discriminant) => {
+                            Tree::leaf(format!("{} = {discriminant}", variant.name))
+                        }
+                        None if fields.is_empty() => Tree::leaf(variant.name.clone()),
+                        None => Tree::leaf(format!("{}({})", variant.name, fields.join(", "))),
+                    }
+                })
+                .collect(),
+        ),
+        Expression::EnumInstance(enum_instance_node) => Tree::node(
+            format!(
+                "Enum '{}::{}'",
+                enum_instance_node.enum_def.type_name, enum_instance_node.variant
+            ),
+            &enum_instance_node.position,
+            build_fields(&enum_instance_node.fields),
+        ),
+        Expression::EnumFrom(enum_from_node) => Tree::node(
+            format!("EnumFrom '{}'", enum_from_node.enum_def.type_name),
+            &enum_from_node.position,
+            vec![build(&enum_from_node.value).edge("value")],
+        ),
+        Expression::EnumParse(enum_parse_node) => Tree::node(
+            format!("EnumParse '{}'", enum_parse_node.enum_def.type_name),
+            &enum_parse_node.position,
+            vec![build(&enum_parse_node.value).edge("value")],
+        ),
+        Expression::VariantName(variant_name_node) => Tree::node(
+            String::from("VariantName"),
+            &variant_name_node.position,
+            vec![build(&variant_name_node.value).edge("value")],
+        ),
+        Expression::Cast(cast_node) => Tree::node(
+            format!("Cast as '{}'", cast_node.type_name),
+            &cast_node.position,
+            vec![build(&cast_node.value).edge("value")],
+        ),
+        Expression::Contains(contains_node) => Tree::node(
+            String::from("Contains"),
+            &contains_node.position,
+            vec![
+                build(&contains_node.value).edge("value"),
+                build(&contains_node.flag).edge("flag"),
+            ],
+        ),
+        Expression::ImplStatement(impl_node) => Tree::node(
+            if impl_node.derived {
+                format!("Impl '{}' (derived)", impl_node.struct_def.type_name)
+            } else if let Some(trait_name) = &impl_node.trait_name {
+                format!(
+                    "Impl '{trait_name}' for '{}'",
+                    impl_node.struct_def.type_name
+                )
+            } else {
+                format!("Impl '{}'", impl_node.struct_def.type_name)
+            },
+            &impl_node.position,
+            build_all(&impl_node.procedures),
+        ),
+        Expression::ImplFunCall(impl_fun_call_node) => Tree {
+            label: format!(
+                "ImplCall '{}'",
+                impl_fun_call_node.impl_node.struct_def.type_name
+            ),
+            position: None,
+            group: false,
+            edge: None,
+            children: vec![build(&impl_fun_call_node.fun_call_node).edge("call")],
+        },
+        Expression::TraitDef(trait_def) => Tree::node(
+            format!("TraitDef '{}'", trait_def.name),
+            &trait_def.position,
+            trait_def
+                .methods
+                .iter()
+                .map(|method| Tree::leaf(format!("{}: {}", method.name, ProcType::of(method))))
+                .collect(),
+        ),
+        Expression::DynCall(dyn_call_node) => Tree::node(
+            format!(
+                "DynCall '{}::{}'",
+                dyn_call_node.trait_name, dyn_call_node.fun_call_node.proc_def.name
+            ),
+            &dyn_call_node.fun_call_node.position,
+            build_fields(&dyn_call_node.fun_call_node.args),
+        ),
+        Expression::Coalesce(coalesce_node) => Tree::node(
+            String::from("Coalesce"),
+            &coalesce_node.position,
+            vec![
+                build(&coalesce_node.value).edge("value"),
+                build(&coalesce_node.fallback).edge("fallback"),
+            ],
+        ),
+        Expression::CompareChain(compare_chain_node) => Tree::node(
+            format!(
+                "CompareChain {}",
+                compare_chain_node
+                    .ops
+                    .iter()
+                    .map(|op| format!("'{op}'"))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+            &compare_chain_node.position,
+            build_all(&compare_chain_node.operands),
+        ),
+        Expression::Pipe(pipe_node) => Tree::node(
+            String::from("Pipe"),
+            &pipe_node.position,
+            vec![build(&pipe_node.call).edge("call")],
+        ),
+        Expression::MapNew(map_new_node) => Tree::node(
+            format!("MapNew '{}'", map_new_node.type_name),
+            &map_new_node.position,
+            Vec::new(),
+        ),
+        Expression::MapCall(map_call_node) => Tree::node(
+            format!("MapCall '{}'", map_call_node.method),
+            &map_call_node.position,
+            std::iter::once(build(&map_call_node.map).edge("map"))
+                .chain(build_all(&map_call_node.args))
+                .collect(),
+        ),
+        Expression::VecNew(vec_new_node) => Tree::node(
+            format!("VecNew '{}'", vec_new_node.type_name),
+            &vec_new_node.position,
+            Vec::new(),
+        ),
+        Expression::VecCall(vec_call_node) => Tree::node(
+            format!("VecCall '{}'", vec_call_node.method),
+            &vec_call_node.position,
+            std::iter::once(build(&vec_call_node.vec).edge("vec"))
+                .chain(build_all(&vec_call_node.args))
+                .collect(),
+        ),
+        Expression::StructInstance(struct_instance_node) => Tree::node(
+            format!("Struct '{}'", struct_instance_node.struct_def.type_name),
+            &struct_instance_node.position,
+            build_fields(&struct_instance_node.fields),
+        ),
+        Expression::StructFieldAssign(field_assign_node) => {
+            let mut path = vec![field_assign_node.struct_instance.metadata.name.as_str()];
+            path.extend(
+                field_assign_node
+                    .fields
+                    .iter()
+                    .map(|f| f.metadata.name.as_str()),
+            );
+
+            Tree::node(
+                format!("FieldAssign '{}'", path.join(".")),
+                &field_assign_node.position,
+                vec![build(&field_assign_node.new_value).edge("value")],
+            )
+        }
+        Expression::StructFieldAccess(field_access_node) => Tree::node(
+            format!("FieldAccess '{}'", field_access_node.field.metadata.name),
+            &field_access_node.position,
+            vec![build(&field_access_node.base).edge("base")],
+        ),
+        Expression::BinaryOp(binary_op_node) => Tree::node(
+            format!("BinaryOp '{}'", binary_op_node.op),
+            &binary_op_node.position,
+            vec![
+                build(&binary_op_node.lhs).edge("lhs"),
+                build(&binary_op_node.rhs).edge("rhs"),
+            ],
+        ),
+        Expression::Literal(token, lt) => Tree::node(
+            format!("Literal {:?}: {lt:?}", token.value),
+            &token.position,
+            Vec::new(),
+        ),
+        Expression::Error(position) => Tree::node(String::from("Error"), position, Vec::new()),
+    }
+}
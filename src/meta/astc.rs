@@ -0,0 +1,1464 @@
+use std::collections::HashMap;
+
+use crate::{
+    diagnostics::{Diagnostic, Edit, Fix, Severity},
+    expression::Expression,
+    nodes::{
+        AssignNode, BinaryOp, BinaryOpNode, CastNode, ClosureCallNode, ClosureNode, ClosureSyntax,
+        CoalesceNode, CompareChainNode, ContainsNode, ContractKind, ContractNode, DispatchTable,
+        DynCallNode, ElseNode, EnumDefNode, EnumFromNode, EnumInstanceNode, EnumParseNode,
+        EnumVariantNode, FieldAccessNode, FieldAssignNode, ForNode, FunCallNode, IfNode,
+        ImplFunCallNode, ImplNode, LetNode, MapCallNode, MapMethod, MapNewNode, MatchArmNode,
+        MatchNode, PatternNode, PipeNode, ProcDefNode, RangeNode, ReturnNode, StructDefNode,
+        StructInstanceNode, TraitDefNode, VarMetadataNode, VariableNode, VariantNameNode,
+        VecCallNode, VecMethod, VecNewNode, WhileNode,
+    },
+    parser::Program,
+    token::{LiteralType, Location, Position, Span, Token, TokenType},
+};
+
+const MAGIC: &[u8; 4] = b"ASTC";
+pub const VERSION: u32 = 32;
+
+const HASH_SEED: u64 = 0xcbf29ce484222325;
+
+pub struct Cached {
+    pub program: Program,
+    pub diagnostics: Vec<Diagnostic>,
+    pub comments: Vec<(Position, String)>,
+}
+
+pub fn source_hash(source: &str) -> u64 {
+    mix(HASH_SEED, source.as_bytes())
+}
+
+pub fn mix(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes.iter() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+pub fn encode(
+    program: &[Expression],
+    diagnostics: &[Diagnostic],
+    comments: &[(Position, String)],
+    source_hash: u64,
+) -> Vec<u8> {
+    let mut encoder = Encoder::new();
+
+    encoder.varint(program.len() as u64);
+    for expr in program.iter() {
+        encoder.expr(expr);
+    }
+
+    encoder.varint(diagnostics.len() as u64);
+    for diagnostic in diagnostics.iter() {
+        encoder.diagnostic(diagnostic);
+    }
+
+    encoder.varint(comments.len() as u64);
+    for (position, text) in comments.iter() {
+        encoder.position(position);
+        encoder.string(text);
+    }
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&source_hash.to_le_bytes());
+
+    let mut table = Encoder::new();
+    table.varint(encoder.strings.len() as u64);
+    for s in encoder.strings.iter() {
+        table.varint(s.len() as u64);
+        table.body.extend_from_slice(s.as_bytes());
+    }
+
+    bytes.extend_from_slice(&table.body);
+    bytes.extend_from_slice(&encoder.body);
+
+    bytes
+}
+
+pub fn decode(bytes: &[u8], source_hash: u64) -> Option<Cached> {
+    if bytes.len() < 16 || &bytes[0..4] != MAGIC {
+        return None;
+    }
+
+    let version = u32::from_le_bytes(bytes[4..8].try_into().ok()?);
+    let hash = u64::from_le_bytes(bytes[8..16].try_into().ok()?);
+    if version != VERSION || hash != source_hash {
+        return None;
+    }
+
+    let mut decoder = Decoder {
+        bytes,
+        cursor: 16,
+        strings: Vec::new(),
+        tables: Vec::new(),
+    };
+
+    let count = decoder.varint()?;
+    for _ in 0..count {
+        let len = decoder.varint()? as usize;
+        let end = decoder.cursor.checked_add(len)?;
+        let s = std::str::from_utf8(bytes.get(decoder.cursor..end)?).ok()?;
+        decoder.strings.push(String::from(s));
+        decoder.cursor = end;
+    }
+
+    let len = decoder.varint()?;
+    let mut program = Program::new();
+    for _ in 0..len {
+        program.push(decoder.expr()?);
+    }
+
+    let len = decoder.varint()?;
+    let mut diagnostics = Vec::new();
+    for _ in 0..len {
+        diagnostics.push(decoder.diagnostic()?);
+    }
+
+    let len = decoder.varint()?;
+    let mut comments = Vec::new();
+    for _ in 0..len {
+        comments.push((decoder.position()?, decoder.string()?));
+    }
+
+    if decoder.cursor != bytes.len() {
+        return None;
+    }
+
+    Some(Cached {
+        program,
+        diagnostics,
+        comments,
+    })
+}
+
+struct Encoder {
+    body: Vec<u8>,
+    strings: Vec<String>,
+    string_ids: HashMap<String, u64>,
+    tables: Vec<DispatchTable>,
+}
+
+impl Encoder {
+    fn new() -> Self {
+        Self {
+            body: Vec::new(),
+            strings: Vec::new(),
+            string_ids: HashMap::new(),
+            tables: Vec::new(),
+        }
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.body.push(value);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(u8::from(value));
+    }
+
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.body.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+
+        self.body.push(value as u8);
+    }
+
+    fn string(&mut self, value: &str) {
+        let id = if let Some(&id) = self.string_ids.get(value) {
+            id
+        } else {
+            let id = self.strings.len() as u64;
+            self.strings.push(String::from(value));
+            self.string_ids.insert(String::from(value), id);
+            id
+        };
+
+        self.varint(id);
+    }
+
+    fn string_list(&mut self, values: &[String]) {
+        self.varint(values.len() as u64);
+        for value in values.iter() {
+            self.string(value);
+        }
+    }
+
+    fn option_string(&mut self, value: &Option<String>) {
+        if let Some(s) = value {
+            self.u8(1);
+            self.string(s);
+        } else {
+            self.u8(0);
+        }
+    }
+
+    fn position(&mut self, position: &Position) {
+        self.string(&position.filename);
+        self.varint(position.row as u64);
+        self.varint(position.column as u64);
+    }
+
+    fn span(&mut self, span: Span) {
+        self.varint(span.start.row as u64);
+        self.varint(span.start.column as u64);
+        self.varint(span.end.row as u64);
+        self.varint(span.end.column as u64);
+    }
+
+    fn token(&mut self, token: &Token) {
+        self.string(&token.value);
+        self.position(&token.position);
+        self.span(token.span);
+    }
+
+    // parser diagnostics never carry a lint code, so it is not stored
+    fn diagnostic(&mut self, diagnostic: &Diagnostic) {
+        self.bool(diagnostic.is_error());
+        match &diagnostic.position {
+            Some(position) => {
+                self.u8(1);
+                self.position(position);
+            }
+            None => self.u8(0),
+        }
+        self.string(&diagnostic.message);
+        self.option_string(&diagnostic.note);
+
+        match &diagnostic.fix {
+            Some(fix) => {
+                self.u8(1);
+                self.string(&fix.message);
+                self.varint(fix.edits.len() as u64);
+                for edit in fix.edits.iter() {
+                    self.position(&edit.position);
+                    self.string(&edit.text);
+                }
+            }
+            None => self.u8(0),
+        }
+
+        match diagnostic.span {
+            Some(span) => {
+                self.u8(1);
+                self.span(span);
+            }
+            None => self.u8(0),
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[Expression]) {
+        self.varint(exprs.len() as u64);
+        for expr in exprs.iter() {
+            self.expr(expr);
+        }
+    }
+
+    fn metadata(&mut self, metadata: &VarMetadataNode) {
+        self.string(&metadata.name);
+        self.string(&metadata.type_name);
+        self.bool(metadata.mutable);
+    }
+
+    fn variable(&mut self, variable: &VariableNode) {
+        self.metadata(&variable.metadata);
+        self.expr(&variable.value);
+        self.span(variable.span);
+    }
+
+    fn variables(&mut self, variables: &[VariableNode]) {
+        self.varint(variables.len() as u64);
+        for variable in variables.iter() {
+            self.variable(variable);
+        }
+    }
+
+    fn proc_def(&mut self, proc_def: &ProcDefNode) {
+        self.string(&proc_def.name);
+        self.option_string(&proc_def.return_type);
+        self.varint(proc_def.args.len() as u64);
+        for arg in proc_def.args.iter() {
+            self.metadata(arg);
+        }
+        self.exprs(&proc_def.statements);
+        self.varint(proc_def.contracts.len() as u64);
+        for contract in proc_def.contracts.iter() {
+            self.bool(contract.kind == ContractKind::Ensures);
+            self.expr(&contract.condition);
+            self.string(&contract.source);
+            self.position(&contract.position);
+        }
+        self.u8(proc_def.native as u8);
+        self.string_list(&proc_def.generics);
+        self.option_string(&proc_def.template);
+        self.option_string(&proc_def.docs);
+        self.position(&proc_def.position);
+        self.span(proc_def.span);
+    }
+
+    fn struct_def(&mut self, struct_def: &StructDefNode) {
+        self.string(&struct_def.type_name);
+        self.varint(struct_def.fields.len() as u64);
+        for field in struct_def.fields.iter() {
+            self.metadata(field);
+        }
+        self.varint(struct_def.derives.len() as u64);
+        for derive in struct_def.derives.iter() {
+            self.string(derive);
+        }
+        self.string_list(&struct_def.generics);
+        self.option_string(&struct_def.docs);
+        self.position(&struct_def.position);
+        self.span(struct_def.span);
+    }
+
+    fn enum_def(&mut self, enum_def: &EnumDefNode) {
+        self.string(&enum_def.type_name);
+        self.varint(enum_def.variants.len() as u64);
+        for variant in enum_def.variants.iter() {
+            self.string(&variant.name);
+            self.varint(variant.fields.len() as u64);
+            for field in variant.fields.iter() {
+                self.metadata(field);
+            }
+            self.bool(variant.discriminant.is_some());
+            if let Some(discriminant) = variant.discriminant {
+                self.varint(discriminant as u32 as u64);
+            }
+        }
+        self.bool(enum_def.flags);
+        self.option_string(&enum_def.docs);
+        self.position(&enum_def.position);
+        self.span(enum_def.span);
+    }
+
+    fn pattern(&mut self, pattern: &PatternNode) {
+        match pattern {
+            PatternNode::Wildcard => self.u8(0),
+            PatternNode::Literal(token, lt) => {
+                self.u8(1);
+                self.u8(literal_type_to_u8(*lt));
+                self.token(token);
+            }
+            PatternNode::Binding(binding) => {
+                self.u8(2);
+                self.metadata(binding);
+            }
+            PatternNode::Variant {
+                type_name,
+                variant,
+                bindings,
+            } => {
+                self.u8(3);
+                self.string(type_name);
+                self.string(variant);
+                self.varint(bindings.len() as u64);
+                for binding in bindings.iter() {
+                    self.metadata(binding);
+                }
+            }
+            PatternNode::Range { start, end, kind } => {
+                self.u8(4);
+                self.u8(literal_type_to_u8(*kind));
+                self.token(start);
+                self.token(end);
+            }
+            PatternNode::Bind { binding, pattern } => {
+                self.u8(5);
+                self.metadata(binding);
+                self.pattern(pattern);
+            }
+            PatternNode::Or(alternatives) => {
+                self.u8(6);
+                self.varint(alternatives.len() as u64);
+                for alternative in alternatives.iter() {
+                    self.pattern(alternative);
+                }
+            }
+        }
+    }
+
+    // tables are shared and may be reached again from inside their own
+    // entries, so each one is written out only at its first occurrence
+    fn dispatch(&mut self, table: &DispatchTable) {
+        if let Some(id) = self.tables.iter().position(|t| t.same(table)) {
+            self.varint(id as u64);
+            return;
+        }
+
+        self.varint(self.tables.len() as u64);
+        self.tables.push(table.clone());
+
+        let entries = table.entries();
+        self.varint(entries.len() as u64);
+        for (type_name, proc_def) in entries.iter() {
+            self.string(type_name);
+            self.proc_def(proc_def);
+        }
+    }
+
+    fn impl_node(&mut self, impl_node: &ImplNode) {
+        self.exprs(&impl_node.procedures);
+        self.struct_def(&impl_node.struct_def);
+        self.option_string(&impl_node.trait_name);
+        self.bool(impl_node.derived);
+        self.position(&impl_node.position);
+        self.span(impl_node.span);
+    }
+
+    fn expr(&mut self, expr: &Expression) {
+        self.span(expr.span().unwrap_or_default());
+        match expr {
+            Expression::IfStatement(if_node) => {
+                self.u8(0);
+                self.expr(&if_node.value);
+                self.exprs(&if_node.statements);
+                match &if_node.else_branch {
+                    Some(else_node) => {
+                        self.u8(1);
+                        self.exprs(&else_node.statements);
+                        self.position(&else_node.position);
+                    }
+                    None => self.u8(0),
+                }
+                self.position(&if_node.position);
+            }
+            Expression::WhileStatement(while_node) => {
+                self.u8(1);
+                self.expr(&while_node.value);
+                self.exprs(&while_node.statements);
+                self.position(&while_node.position);
+            }
+            Expression::ForLoop(for_node) => {
+                self.u8(2);
+                self.variable(&for_node.counter);
+                self.expr(&for_node.range);
+                self.exprs(&for_node.statements);
+                self.position(&for_node.position);
+            }
+            Expression::RangeStatement(range_node) => {
+                self.u8(3);
+                self.expr(&range_node.start);
+                self.expr(&range_node.end);
+                self.bool(range_node.inclusive);
+            }
+            Expression::LetStatement(let_node) => {
+                self.u8(4);
+                self.string(&let_node.name);
+                self.string(&let_node.type_name);
+                self.bool(let_node.mutable);
+                self.bool(let_node.annotated);
+                self.expr(&let_node.value);
+                self.position(&let_node.position);
+            }
+            Expression::AssignStatement(assign_node) => {
+                self.u8(5);
+                self.variable(&assign_node.value);
+                self.expr(&assign_node.new_value);
+                self.position(&assign_node.position);
+            }
+            Expression::ReturnStatement(return_node) => {
+                self.u8(6);
+                self.expr(&return_node.value);
+                self.bool(return_node.implicit);
+                self.position(&return_node.position);
+            }
+            Expression::BreakStatement(..) => self.u8(21),
+            Expression::ContinueStatement(..) => self.u8(22),
+            Expression::Variable(variable_node) => {
+                self.u8(7);
+                self.variable(variable_node);
+            }
+            Expression::ProcDef(proc_def_node) => {
+                self.u8(8);
+                self.proc_def(proc_def_node);
+            }
+            Expression::FunCall(fun_call_node) => {
+                self.u8(9);
+                self.proc_def(&fun_call_node.proc_def);
+                self.variables(&fun_call_node.args);
+                self.string_list(&fun_call_node.type_args);
+                self.position(&fun_call_node.position);
+            }
+            Expression::StructDef(struct_def_node) => {
+                self.u8(10);
+                self.struct_def(struct_def_node);
+            }
+            Expression::ImplStatement(impl_node) => {
+                self.u8(11);
+                self.impl_node(impl_node);
+            }
+            Expression::ImplFunCall(impl_fun_call_node) => {
+                self.u8(12);
+                self.impl_node(&impl_fun_call_node.impl_node);
+                self.expr(&impl_fun_call_node.fun_call_node);
+                self.bool(impl_fun_call_node.method);
+            }
+            Expression::TraitDef(trait_def) => {
+                self.u8(31);
+                self.string(&trait_def.name);
+                self.varint(trait_def.methods.len() as u64);
+                for method in trait_def.methods.iter() {
+                    self.proc_def(method);
+                }
+                self.dispatch(&trait_def.dispatch);
+                self.option_string(&trait_def.docs);
+                self.position(&trait_def.position);
+            }
+            Expression::DynCall(dyn_call_node) => {
+                self.u8(32);
+                self.string(&dyn_call_node.trait_name);
+                self.proc_def(&dyn_call_node.fun_call_node.proc_def);
+                self.variables(&dyn_call_node.fun_call_node.args);
+                self.position(&dyn_call_node.fun_call_node.position);
+                self.dispatch(&dyn_call_node.dispatch);
+            }
+            Expression::Coalesce(coalesce_node) => {
+                self.u8(39);
+                self.expr(&coalesce_node.value);
+                self.expr(&coalesce_node.fallback);
+                self.position(&coalesce_node.position);
+            }
+            Expression::CompareChain(compare_chain_node) => {
+                self.u8(36);
+                self.exprs(&compare_chain_node.operands);
+                self.varint(compare_chain_node.ops.len() as u64);
+                for op in compare_chain_node.ops.iter() {
+                    self.u8(binary_op_to_u8(op));
+                }
+                self.position(&compare_chain_node.position);
+            }
+            Expression::Pipe(pipe_node) => {
+                self.u8(35);
+                self.expr(&pipe_node.call);
+                self.bool(pipe_node.bare);
+                self.position(&pipe_node.position);
+            }
+            Expression::MapNew(map_new_node) => {
+                self.u8(33);
+                self.string(&map_new_node.type_name);
+                self.bool(map_new_node.explicit);
+                self.position(&map_new_node.position);
+            }
+            Expression::MapCall(map_call_node) => {
+                self.u8(34);
+                self.expr(&map_call_node.map);
+                self.string(&map_call_node.method.to_string());
+                self.exprs(&map_call_node.args);
+                self.string(&map_call_node.type_name);
+                self.position(&map_call_node.position);
+            }
+            Expression::VecNew(vec_new_node) => {
+                self.u8(37);
+                self.string(&vec_new_node.type_name);
+                self.bool(vec_new_node.explicit);
+                self.position(&vec_new_node.position);
+            }
+            Expression::VecCall(vec_call_node) => {
+                self.u8(38);
+                self.expr(&vec_call_node.vec);
+                self.string(&vec_call_node.method.to_string());
+                self.exprs(&vec_call_node.args);
+                self.bool(vec_call_node.indexed);
+                self.string(&vec_call_node.type_name);
+                self.position(&vec_call_node.position);
+            }
+            Expression::StructInstance(struct_instance_node) => {
+                self.u8(13);
+                self.struct_def(&struct_instance_node.struct_def);
+                self.variables(&struct_instance_node.fields);
+                self.position(&struct_instance_node.position);
+            }
+            Expression::StructFieldAssign(field_assign_node) => {
+                self.u8(14);
+                self.variable(&field_assign_node.struct_instance);
+                self.variables(&field_assign_node.fields);
+                self.expr(&field_assign_node.new_value);
+                self.position(&field_assign_node.position);
+            }
+            Expression::StructFieldAccess(field_access_node) => {
+                self.u8(15);
+                self.expr(&field_access_node.base);
+                self.variable(&field_access_node.field);
+                self.position(&field_access_node.position);
+            }
+            Expression::BinaryOp(binary_op_node) => {
+                self.u8(16);
+                self.expr(&binary_op_node.lhs);
+                self.u8(binary_op_to_u8(&binary_op_node.op));
+                self.expr(&binary_op_node.rhs);
+                self.position(&binary_op_node.position);
+            }
+            Expression::Literal(token, lt) => {
+                self.u8(17);
+                self.u8(literal_type_to_u8(*lt));
+                self.string(&token.value);
+                self.position(&token.position);
+            }
+            Expression::MatchStatement(match_node) => {
+                self.u8(20);
+                self.expr(&match_node.value);
+                self.varint(match_node.arms.len() as u64);
+                for arm in match_node.arms.iter() {
+                    self.pattern(&arm.pattern);
+                    match &arm.guard {
+                        Some(guard) => {
+                            self.u8(1);
+                            self.expr(guard);
+                        }
+                        None => self.u8(0),
+                    }
+                    self.exprs(&arm.statements);
+                }
+                self.position(&match_node.position);
+            }
+            Expression::EnumDef(enum_def_node) => {
+                self.u8(18);
+                self.enum_def(enum_def_node);
+            }
+            Expression::EnumInstance(enum_instance_node) => {
+                self.u8(19);
+                self.enum_def(&enum_instance_node.enum_def);
+                self.string(&enum_instance_node.variant);
+                self.variables(&enum_instance_node.fields);
+                self.position(&enum_instance_node.position);
+            }
+            Expression::EnumFrom(enum_from_node) => {
+                self.u8(23);
+                self.enum_def(&enum_from_node.enum_def);
+                self.expr(&enum_from_node.value);
+                self.position(&enum_from_node.position);
+            }
+            Expression::Cast(cast_node) => {
+                self.u8(24);
+                self.expr(&cast_node.value);
+                self.string(&cast_node.type_name);
+                self.enum_def(&cast_node.enum_def);
+                self.position(&cast_node.position);
+            }
+            Expression::Contains(contains_node) => {
+                self.u8(25);
+                self.expr(&contains_node.value);
+                self.expr(&contains_node.flag);
+                self.position(&contains_node.position);
+            }
+            Expression::EnumParse(enum_parse_node) => {
+                self.u8(26);
+                self.enum_def(&enum_parse_node.enum_def);
+                self.expr(&enum_parse_node.value);
+                self.position(&enum_parse_node.position);
+            }
+            Expression::VariantName(variant_name_node) => {
+                self.u8(27);
+                self.expr(&variant_name_node.value);
+                self.position(&variant_name_node.position);
+            }
+            Expression::Error(position) => {
+                self.u8(28);
+                self.position(position);
+            }
+            Expression::Closure(closure_node) => {
+                self.u8(29);
+                self.proc_def(&closure_node.proc_def);
+                self.varint(closure_node.captures.len() as u64);
+                for capture in closure_node.captures.iter() {
+                    self.metadata(capture);
+                }
+                self.u8(match closure_node.syntax {
+                    ClosureSyntax::Pipe => 0,
+                    ClosureSyntax::Proc => 1,
+                    ClosureSyntax::Named => 2,
+                    ClosureSyntax::Reference => 3,
+                });
+            }
+            Expression::ClosureCall(closure_call_node) => {
+                self.u8(30);
+                self.expr(&closure_call_node.callee);
+                self.exprs(&closure_call_node.args);
+                self.option_string(&closure_call_node.return_type);
+                self.position(&closure_call_node.position);
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    cursor: usize,
+    strings: Vec<String>,
+    tables: Vec<DispatchTable>,
+}
+
+impl<'a> Decoder<'a> {
+    fn u8(&mut self) -> Option<u8> {
+        let value = *self.bytes.get(self.cursor)?;
+        self.cursor += 1;
+        Some(value)
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        match self.u8()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.u8()?;
+            if shift >= 64 {
+                return None;
+            }
+
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+
+            shift += 7;
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let id = self.varint()? as usize;
+        self.strings.get(id).cloned()
+    }
+
+    fn string_list(&mut self) -> Option<Vec<String>> {
+        let len = self.varint()?;
+        let mut values = Vec::new();
+        for _ in 0..len {
+            values.push(self.string()?);
+        }
+
+        Some(values)
+    }
+
+    fn option_string(&mut self) -> Option<Option<String>> {
+        match self.u8()? {
+            0 => Some(None),
+            1 => Some(Some(self.string()?)),
+            _ => None,
+        }
+    }
+
+    fn position(&mut self) -> Option<Position> {
+        let filename = self.string()?;
+        let row = self.varint()? as u32;
+        let column = self.varint()? as u32;
+
+        Some(Position::from(filename, row, column))
+    }
+
+    fn location(&mut self) -> Option<Location> {
+        Some(Location {
+            row: self.varint()? as u32,
+            column: self.varint()? as u32,
+        })
+    }
+
+    fn span(&mut self) -> Option<Span> {
+        Some(Span::new(self.location()?, self.location()?))
+    }
+
+    fn token(&mut self, kind: TokenType) -> Option<Token<'static>> {
+        let value = self.string()?;
+        let mut token = Token::from(kind, value, self.position()?);
+        token.span = self.span()?;
+        Some(token)
+    }
+
+    fn diagnostic(&mut self) -> Option<Diagnostic> {
+        let severity = if self.bool()? {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+
+        let position = match self.u8()? {
+            0 => None,
+            1 => Some(self.position()?),
+            _ => return None,
+        };
+
+        let message = self.string()?;
+        let note = self.option_string()?;
+
+        let fix = match self.u8()? {
+            0 => None,
+            1 => {
+                let message = self.string()?;
+                let len = self.varint()?;
+                let mut edits = Vec::new();
+                for _ in 0..len {
+                    edits.push(Edit {
+                        position: self.position()?,
+                        text: self.string()?,
+                    });
+                }
+
+                Some(Box::new(Fix { message, edits }))
+            }
+            _ => return None,
+        };
+
+        let span = match self.u8()? {
+            0 => None,
+            1 => Some(self.span()?),
+            _ => return None,
+        };
+
+        Some(Diagnostic {
+            severity,
+            position,
+            message,
+            code: None,
+            note,
+            fix,
+            span,
+        })
+    }
+
+    fn boxed(&mut self) -> Option<Box<Expression>> {
+        Some(Box::new(self.expr()?))
+    }
+
+    fn exprs(&mut self) -> Option<Vec<Expression>> {
+        let len = self.varint()?;
+        let mut exprs = Vec::new();
+        for _ in 0..len {
+            exprs.push(self.expr()?);
+        }
+
+        Some(exprs)
+    }
+
+    fn metadata(&mut self) -> Option<VarMetadataNode> {
+        Some(VarMetadataNode {
+            name: self.string()?,
+            type_name: self.string()?,
+            mutable: self.bool()?,
+        })
+    }
+
+    fn variable(&mut self) -> Option<VariableNode> {
+        Some(VariableNode {
+            metadata: self.metadata()?,
+            value: self.boxed()?,
+            span: self.span()?,
+        })
+    }
+
+    fn variables(&mut self) -> Option<Vec<VariableNode>> {
+        let len = self.varint()?;
+        let mut variables = Vec::new();
+        for _ in 0..len {
+            variables.push(self.variable()?);
+        }
+
+        Some(variables)
+    }
+
+    fn proc_def(&mut self) -> Option<ProcDefNode> {
+        let name = self.string()?;
+        let return_type = self.option_string()?;
+
+        let len = self.varint()?;
+        let mut args = Vec::new();
+        for _ in 0..len {
+            args.push(self.metadata()?);
+        }
+
+        let statements = self.exprs()?;
+        let contracts = self.contracts()?;
+        let native = self.u8()? != 0;
+        let generics = self.string_list()?;
+
+        Some(ProcDefNode {
+            name,
+            return_type,
+            args,
+            statements,
+            contracts,
+            native,
+            generics,
+            template: self.option_string()?,
+            docs: self.option_string()?,
+            position: self.position()?,
+            span: self.span()?,
+        })
+    }
+
+    fn contracts(&mut self) -> Option<Vec<ContractNode>> {
+        let len = self.varint()?;
+        let mut contracts = Vec::new();
+        for _ in 0..len {
+            let kind = if self.bool()? {
+                ContractKind::Ensures
+            } else {
+                ContractKind::Requires
+            };
+
+            contracts.push(ContractNode {
+                kind,
+                condition: self.expr()?,
+                source: self.string()?,
+                position: self.position()?,
+            });
+        }
+
+        Some(contracts)
+    }
+
+    fn struct_def(&mut self) -> Option<StructDefNode> {
+        let type_name = self.string()?;
+
+        let len = self.varint()?;
+        let mut fields = Vec::new();
+        for _ in 0..len {
+            fields.push(self.metadata()?);
+        }
+
+        let len = self.varint()?;
+        let mut derives = Vec::new();
+        for _ in 0..len {
+            derives.push(self.string()?);
+        }
+
+        let generics = self.string_list()?;
+
+        Some(StructDefNode {
+            type_name,
+            fields,
+            derives,
+            generics,
+            docs: self.option_string()?,
+            position: self.position()?,
+            span: self.span()?,
+        })
+    }
+
+    fn enum_def(&mut self) -> Option<EnumDefNode> {
+        let type_name = self.string()?;
+
+        let len = self.varint()?;
+        let mut variants = Vec::new();
+        for _ in 0..len {
+            let name = self.string()?;
+
+            let field_len = self.varint()?;
+            let mut fields = Vec::new();
+            for _ in 0..field_len {
+                fields.push(self.metadata()?);
+            }
+
+            let discriminant = if self.bool()? {
+                Some(self.varint()? as u32 as i32)
+            } else {
+                None
+            };
+
+            variants.push(EnumVariantNode {
+                name,
+                fields,
+                discriminant,
+            });
+        }
+
+        Some(EnumDefNode {
+            type_name,
+            variants,
+            flags: self.bool()?,
+            docs: self.option_string()?,
+            position: self.position()?,
+            span: self.span()?,
+        })
+    }
+
+    fn pattern(&mut self) -> Option<PatternNode> {
+        let pattern = match self.u8()? {
+            0 => PatternNode::Wildcard,
+            1 => {
+                let lt = literal_type_from_u8(self.u8()?)?;
+                PatternNode::Literal(self.token(TokenType::Literal(lt))?, lt)
+            }
+            2 => PatternNode::Binding(self.metadata()?),
+            3 => {
+                let type_name = self.string()?;
+                let variant = self.string()?;
+
+                let len = self.varint()?;
+                let mut bindings = Vec::new();
+                for _ in 0..len {
+                    bindings.push(self.metadata()?);
+                }
+
+                PatternNode::Variant {
+                    type_name,
+                    variant,
+                    bindings,
+                }
+            }
+            4 => {
+                let kind = literal_type_from_u8(self.u8()?)?;
+
+                PatternNode::Range {
+                    start: self.token(TokenType::Literal(kind))?,
+                    end: self.token(TokenType::Literal(kind))?,
+                    kind,
+                }
+            }
+            5 => {
+                let binding = self.metadata()?;
+                let pattern = Box::new(self.pattern()?);
+
+                PatternNode::Bind { binding, pattern }
+            }
+            6 => {
+                let len = self.varint()?;
+                let mut alternatives = Vec::new();
+                for _ in 0..len {
+                    alternatives.push(self.pattern()?);
+                }
+
+                PatternNode::Or(alternatives)
+            }
+            _ => return None,
+        };
+
+        Some(pattern)
+    }
+
+    fn match_node(&mut self) -> Option<MatchNode> {
+        let value = self.boxed()?;
+
+        let len = self.varint()?;
+        let mut arms = Vec::new();
+        for _ in 0..len {
+            let pattern = self.pattern()?;
+            let guard = match self.u8()? {
+                0 => None,
+                1 => Some(self.boxed()?),
+                _ => return None,
+            };
+
+            arms.push(MatchArmNode {
+                pattern,
+                guard,
+                statements: self.exprs()?,
+            });
+        }
+
+        Some(MatchNode {
+            value,
+            arms,
+            position: self.position()?,
+            span: Span::default(),
+        })
+    }
+
+    fn dispatch(&mut self) -> Option<DispatchTable> {
+        let id = self.varint()? as usize;
+        if let Some(table) = self.tables.get(id) {
+            return Some(table.clone());
+        }
+
+        if id != self.tables.len() {
+            return None;
+        }
+
+        let table = DispatchTable::default();
+        self.tables.push(table.clone());
+
+        let len = self.varint()?;
+        for _ in 0..len {
+            let type_name = self.string()?;
+            table.insert(&type_name, self.proc_def()?);
+        }
+
+        Some(table)
+    }
+
+    fn impl_node(&mut self) -> Option<ImplNode> {
+        Some(ImplNode {
+            procedures: self.exprs()?,
+            struct_def: self.struct_def()?,
+            trait_name: self.option_string()?,
+            derived: self.bool()?,
+            position: self.position()?,
+            span: self.span()?,
+        })
+    }
+
+    fn expr(&mut self) -> Option<Expression> {
+        let span = self.span()?;
+        let mut expr = match self.u8()? {
+            0 => Expression::IfStatement(IfNode {
+                value: self.boxed()?,
+                statements: self.exprs()?,
+                else_branch: match self.u8()? {
+                    0 => None,
+                    1 => Some(ElseNode {
+                        statements: self.exprs()?,
+                        position: self.position()?,
+                    }),
+                    _ => return None,
+                },
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            1 => Expression::WhileStatement(WhileNode {
+                value: self.boxed()?,
+                statements: self.exprs()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            2 => Expression::ForLoop(ForNode {
+                counter: self.variable()?,
+                range: self.boxed()?,
+                statements: self.exprs()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            3 => Expression::RangeStatement(RangeNode {
+                start: self.boxed()?,
+                end: self.boxed()?,
+                inclusive: self.bool()?,
+                span: Span::default(),
+            }),
+            4 => Expression::LetStatement(LetNode {
+                name: self.string()?,
+                type_name: self.string()?,
+                mutable: self.bool()?,
+                annotated: self.bool()?,
+                value: self.boxed()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            5 => Expression::AssignStatement(AssignNode {
+                value: self.variable()?,
+                new_value: self.boxed()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            6 => Expression::ReturnStatement(ReturnNode {
+                value: self.boxed()?,
+                implicit: self.bool()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            7 => Expression::Variable(self.variable()?),
+            8 => Expression::ProcDef(self.proc_def()?),
+            9 => Expression::FunCall(FunCallNode {
+                proc_def: self.proc_def()?,
+                args: self.variables()?,
+                type_args: self.string_list()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            10 => Expression::StructDef(self.struct_def()?),
+            11 => Expression::ImplStatement(self.impl_node()?),
+            12 => Expression::ImplFunCall(ImplFunCallNode {
+                impl_node: self.impl_node()?,
+                fun_call_node: self.boxed()?,
+                method: self.bool()?,
+            }),
+            13 => Expression::StructInstance(StructInstanceNode {
+                struct_def: self.struct_def()?,
+                fields: self.variables()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            14 => Expression::StructFieldAssign(FieldAssignNode {
+                struct_instance: self.variable()?,
+                fields: self.variables()?,
+                new_value: self.boxed()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            15 => Expression::StructFieldAccess(FieldAccessNode {
+                base: self.boxed()?,
+                field: self.variable()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            16 => Expression::BinaryOp(BinaryOpNode {
+                lhs: self.boxed()?,
+                op: binary_op_from_u8(self.u8()?)?,
+                rhs: self.boxed()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            17 => {
+                let lt = literal_type_from_u8(self.u8()?)?;
+                let value = self.string()?;
+                let position = self.position()?;
+
+                Expression::Literal(Token::from(TokenType::Literal(lt), value, position), lt)
+            }
+            18 => Expression::EnumDef(self.enum_def()?),
+            20 => Expression::MatchStatement(self.match_node()?),
+            21 => Expression::BreakStatement(Span::default()),
+            22 => Expression::ContinueStatement(Span::default()),
+            19 => Expression::EnumInstance(EnumInstanceNode {
+                enum_def: self.enum_def()?,
+                variant: self.string()?,
+                fields: self.variables()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            23 => Expression::EnumFrom(EnumFromNode {
+                enum_def: self.enum_def()?,
+                value: self.boxed()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            24 => Expression::Cast(CastNode {
+                value: self.boxed()?,
+                type_name: self.string()?,
+                enum_def: self.enum_def()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            25 => Expression::Contains(ContainsNode {
+                value: self.boxed()?,
+                flag: self.boxed()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            26 => Expression::EnumParse(EnumParseNode {
+                enum_def: self.enum_def()?,
+                value: self.boxed()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            27 => Expression::VariantName(VariantNameNode {
+                value: self.boxed()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            28 => Expression::Error(self.position()?),
+            29 => {
+                let proc_def = self.proc_def()?;
+
+                let len = self.varint()?;
+                let mut captures = Vec::new();
+                for _ in 0..len {
+                    captures.push(self.metadata()?);
+                }
+
+                let syntax = match self.u8()? {
+                    0 => ClosureSyntax::Pipe,
+                    1 => ClosureSyntax::Proc,
+                    2 => ClosureSyntax::Named,
+                    3 => ClosureSyntax::Reference,
+                    _ => return None,
+                };
+
+                Expression::Closure(ClosureNode {
+                    proc_def,
+                    captures,
+                    syntax,
+                })
+            }
+            30 => Expression::ClosureCall(ClosureCallNode {
+                callee: self.boxed()?,
+                args: self.exprs()?,
+                return_type: self.option_string()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            31 => {
+                let name = self.string()?;
+
+                let len = self.varint()?;
+                let mut methods = Vec::new();
+                for _ in 0..len {
+                    methods.push(self.proc_def()?);
+                }
+
+                Expression::TraitDef(TraitDefNode {
+                    name,
+                    methods,
+                    dispatch: self.dispatch()?,
+                    docs: self.option_string()?,
+                    position: self.position()?,
+                    span: Span::default(),
+                })
+            }
+            32 => {
+                let trait_name = self.string()?;
+                let fun_call_node = FunCallNode {
+                    proc_def: self.proc_def()?,
+                    args: self.variables()?,
+                    type_args: Vec::new(),
+                    position: self.position()?,
+                    span: Span::default(),
+                };
+
+                Expression::DynCall(DynCallNode {
+                    trait_name,
+                    fun_call_node,
+                    dispatch: self.dispatch()?,
+                })
+            }
+            33 => Expression::MapNew(MapNewNode {
+                type_name: self.string()?,
+                explicit: self.bool()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            34 => Expression::MapCall(MapCallNode {
+                map: self.boxed()?,
+                method: MapMethod::parse(&self.string()?)?,
+                args: self.exprs()?,
+                type_name: self.string()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            39 => Expression::Coalesce(CoalesceNode {
+                value: self.boxed()?,
+                fallback: self.boxed()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            36 => {
+                let operands = self.exprs()?;
+
+                let len = self.varint()?;
+                let mut ops = Vec::new();
+                for _ in 0..len {
+                    ops.push(binary_op_from_u8(self.u8()?)?);
+                }
+
+                Expression::CompareChain(CompareChainNode {
+                    operands,
+                    ops,
+                    position: self.position()?,
+                    span: Span::default(),
+                })
+            }
+            37 => Expression::VecNew(VecNewNode {
+                type_name: self.string()?,
+                explicit: self.bool()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            38 => Expression::VecCall(VecCallNode {
+                vec: self.boxed()?,
+                method: VecMethod::parse(&self.string()?)?,
+                args: self.exprs()?,
+                indexed: self.bool()?,
+                type_name: self.string()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            35 => Expression::Pipe(PipeNode {
+                call: self.boxed()?,
+                bare: self.bool()?,
+                position: self.position()?,
+                span: Span::default(),
+            }),
+            _ => return None,
+        };
+
+        if let Some(expr_span) = expr.span_mut() {
+            *expr_span = span;
+        }
+
+        Some(expr)
+    }
+}
+
+fn literal_type_to_u8(kind: LiteralType) -> u8 {
+    match kind {
+        LiteralType::None => 0,
+        LiteralType::Char => 1,
+        LiteralType::Bool => 2,
+        LiteralType::Number => 3,
+        LiteralType::Float => 4,
+        LiteralType::String => 5,
+    }
+}
+
+fn literal_type_from_u8(tag: u8) -> Option<LiteralType> {
+    let kind = match tag {
+        0 => LiteralType::None,
+        1 => LiteralType::Char,
+        2 => LiteralType::Bool,
+        3 => LiteralType::Number,
+        4 => LiteralType::Float,
+        5 => LiteralType::String,
+        _ => return None,
+    };
+
+    Some(kind)
+}
+
+fn binary_op_to_u8(op: &BinaryOp) -> u8 {
+    match op {
+        BinaryOp::None => 0,
+        BinaryOp::Inc => 1,
+        BinaryOp::Dec => 2,
+        BinaryOp::Add => 3,
+        BinaryOp::AddAssign => 4,
+        BinaryOp::Sub => 5,
+        BinaryOp::SubAssign => 6,
+        BinaryOp::Mul => 7,
+        BinaryOp::MulAssign => 8,
+        BinaryOp::Div => 9,
+        BinaryOp::DivAssign => 10,
+        BinaryOp::Eq => 11,
+        BinaryOp::Ne => 12,
+        BinaryOp::Lt => 13,
+        BinaryOp::Lte => 14,
+        BinaryOp::Gt => 15,
+        BinaryOp::Gte => 16,
+        BinaryOp::Neg => 17,
+        BinaryOp::BitOr => 18,
+        BinaryOp::BitAnd => 19,
+        BinaryOp::BitXor => 20,
+    }
+}
+
+fn binary_op_from_u8(tag: u8) -> Option<BinaryOp> {
+    let op = match tag {
+        0 => BinaryOp::None,
+        1 => BinaryOp::Inc,
+        2 => BinaryOp::Dec,
+        3 => BinaryOp::Add,
+        4 => BinaryOp::AddAssign,
+        5 => BinaryOp::Sub,
+        6 => BinaryOp::SubAssign,
+        7 => BinaryOp::Mul,
+        8 => BinaryOp::MulAssign,
+        9 => BinaryOp::Div,
+        10 => BinaryOp::DivAssign,
+        11 => BinaryOp::Eq,
+        12 => BinaryOp::Ne,
+        13 => BinaryOp::Lt,
+        14 => BinaryOp::Lte,
+        15 => BinaryOp::Gt,
+        16 => BinaryOp::Gte,
+        17 => BinaryOp::Neg,
+        18 => BinaryOp::BitOr,
+        19 => BinaryOp::BitAnd,
+        20 => BinaryOp::BitXor,
+        _ => return None,
+    };
+
+    Some(op)
+}
@@ -0,0 +1,88 @@
+use crate::{
+    native::NativeFn,
+    value::{LinesValue, Value},
+};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub const FEATURES: &[&str] = &[
+    "closures",
+    "contracts",
+    "enums",
+    "files",
+    "impls",
+    "maps",
+    "vecs",
+    "patterns",
+    "ranges",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    pub backend: String,
+    pub features: Vec<String>,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::new("interpreter")
+    }
+}
+
+impl Capabilities {
+    pub fn new(backend: &str) -> Self {
+        Self {
+            backend: String::from(backend),
+            features: FEATURES.iter().map(|f| String::from(*f)).collect(),
+        }
+    }
+
+    pub fn without(mut self, feature: &str) -> Self {
+        self.set_feature(feature, false);
+        self
+    }
+
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.features.iter().any(|f| f == name)
+    }
+
+    pub fn set_feature(&mut self, name: &str, enabled: bool) {
+        self.features.retain(|f| f != name);
+
+        if enabled {
+            self.features.push(String::from(name));
+        }
+    }
+
+    pub fn natives(&self) -> Vec<NativeFn> {
+        let backend = self.backend.clone();
+        let features = self.features.clone();
+
+        vec![
+            NativeFn::new("version", &[], Some("String"), |_| {
+                Value::String(String::from(VERSION))
+            }),
+            NativeFn::new(
+                "has_feature",
+                &["String"],
+                Some("bool"),
+                move |args| match args {
+                    [Value::String(name)] => Value::Bool(features.iter().any(|f| f == name)),
+                    _ => Value::Bool(false),
+                },
+            ),
+            NativeFn::new("backend", &[], Some("String"), move |_| {
+                Value::String(backend.clone())
+            }),
+            NativeFn::new(
+                "read_lines",
+                &["String"],
+                Some("Lines"),
+                |args| match args {
+                    [Value::String(path)] => Value::Lines(LinesValue { path: path.clone() }),
+                    _ => Value::None,
+                },
+            ),
+        ]
+    }
+}
@@ -0,0 +1,1932 @@
+use crate::{
+    builtins::{Capabilities, VERSION},
+    codegen::{is_generic, mangle},
+    diagnostics::Diagnostic,
+    executor::ENTRY_POINT,
+    expression::Expression,
+    nodes::{
+        map_types, vec_type, BinaryOp, BinaryOpNode, ContractKind, EnumDefNode, FunCallNode,
+        IfNode, MatchNode, PatternNode, ProcDefNode, StructDefNode,
+    },
+    token::{LiteralType, Position, Span, Token},
+};
+
+pub const RUNTIME_HEADER: &str = "meta_runtime.h";
+
+const RUNTIME: &str = include_str!("meta_runtime.h");
+
+const C_RESERVED: &[&str] = &[
+    "auto", "bool", "case", "char", "const", "default", "do", "double", "else", "extern", "false",
+    "float", "goto", "inline", "int", "long", "register", "restrict", "short", "signed", "sizeof",
+    "static", "switch", "true", "typedef", "union", "unsigned", "void", "volatile", "NULL", "abs",
+    "abort", "exit", "free", "isinf", "isnan", "malloc", "memcpy", "printf", "strcat", "strcmp",
+    "strlen", "strtod",
+];
+
+const ENTRY_FN: &str = "meta_main";
+
+#[derive(Debug, Default, Clone)]
+pub struct CBackend {
+    natives: Vec<(String, String)>,
+    inline_runtime: bool,
+}
+
+impl CBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn native(mut self, name: &str, function: &str) -> Self {
+        self.natives
+            .push((String::from(name), String::from(function)));
+        self
+    }
+
+    pub fn inline_runtime(mut self, enabled: bool) -> Self {
+        self.inline_runtime = enabled;
+        self
+    }
+
+    pub fn runtime() -> &'static str {
+        RUNTIME
+    }
+
+    pub fn emit(&self, program: &[Expression]) -> Result<String, Vec<Diagnostic>> {
+        let mut items = Items::default();
+        for expr in program.iter().filter(|expr| !is_generic(expr)) {
+            items.collect(expr, None);
+        }
+
+        let mut emitter = Emitter {
+            backend: self,
+            enums: items.enums.iter().map(|e| (*e).clone()).collect(),
+            errors: Vec::new(),
+            scopes: Vec::new(),
+            temps: 0,
+            returns: false,
+        };
+
+        for expr in program.iter() {
+            if let Expression::TraitDef(trait_def) = expr {
+                emitter.error(
+                    Some(&trait_def.position),
+                    String::from("traits cannot be emitted as C"),
+                );
+            }
+        }
+
+        let mut out = if self.inline_runtime {
+            format!("{RUNTIME}\n")
+        } else {
+            format!("#include \"{RUNTIME_HEADER}\"\n\n")
+        };
+
+        let types = items.ordered_types();
+
+        for ty in types.iter() {
+            let name = ty.name();
+            out.push_str(&format!("typedef struct {name} {name};\n"));
+        }
+        if !types.is_empty() {
+            out.push('\n');
+        }
+
+        for ty in types.iter() {
+            match ty {
+                TypeDef::Struct(struct_def) => out.push_str(&emitter.struct_def(struct_def)),
+                TypeDef::Enum(enum_def) => out.push_str(&emitter.enum_def(enum_def)),
+            }
+            out.push('\n');
+        }
+
+        let mut prototypes = String::new();
+        let mut helpers = String::new();
+        for ty in types.iter() {
+            let (prototype, helper) = match ty {
+                TypeDef::Struct(struct_def) => emitter.struct_helpers(struct_def),
+                TypeDef::Enum(enum_def) => emitter.enum_helpers(enum_def),
+            };
+
+            prototypes.push_str(&prototype);
+            helpers.push_str(&helper);
+        }
+
+        let mut procedures = String::new();
+        for (owner, proc_def) in items.procs.iter() {
+            if proc_def.native {
+                continue;
+            }
+
+            let (prototype, definition) = emitter.proc_def(owner.as_deref(), proc_def);
+            prototypes.push_str(&prototype);
+            procedures.push('\n');
+            procedures.push_str(&definition);
+        }
+
+        if !prototypes.is_empty() {
+            out.push_str(&prototypes);
+        }
+        out.push_str(&helpers);
+        out.push_str(&procedures);
+
+        match items
+            .procs
+            .iter()
+            .find(|(owner, p)| owner.is_none() && p.name == ENTRY_POINT)
+        {
+            Some(..) => out.push_str(&format!(
+                "\nint main(void) {{\n    {ENTRY_FN}();\n    return 0;\n}}\n"
+            )),
+            None => emitter.errors.push(Diagnostic::error(
+                None,
+                format!("cannot emit C without a '{ENTRY_POINT}' procedure"),
+            )),
+        }
+
+        if !emitter.errors.is_empty() {
+            return Err(emitter.errors);
+        }
+
+        Ok(out)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum TypeDef<'a> {
+    Struct(&'a StructDefNode),
+    Enum(&'a EnumDefNode),
+}
+
+impl TypeDef<'_> {
+    fn name(&self) -> String {
+        match self {
+            TypeDef::Struct(struct_def) => ident(&struct_def.type_name),
+            TypeDef::Enum(enum_def) => ident(&enum_def.type_name),
+        }
+    }
+
+    fn field_types(&self) -> Vec<&str> {
+        match self {
+            TypeDef::Struct(struct_def) => struct_def
+                .fields
+                .iter()
+                .map(|f| f.type_name.as_str())
+                .collect(),
+            TypeDef::Enum(enum_def) => enum_def
+                .variants
+                .iter()
+                .flat_map(|v| v.fields.iter().map(|f| f.type_name.as_str()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Items<'a> {
+    structs: Vec<&'a StructDefNode>,
+    enums: Vec<&'a EnumDefNode>,
+    procs: Vec<(Option<String>, &'a ProcDefNode)>,
+}
+
+impl<'a> Items<'a> {
+    fn collect(&mut self, expr: &'a Expression, owner: Option<&str>) {
+        match expr {
+            Expression::StructDef(struct_def) => self.structs.push(struct_def),
+            Expression::EnumDef(enum_def) => self.enums.push(enum_def),
+            Expression::ImplStatement(impl_node) => {
+                for procedure in impl_node.procedures.iter() {
+                    self.collect(procedure, Some(&impl_node.struct_def.type_name));
+                }
+            }
+            Expression::ProcDef(proc_def) => {
+                self.procs.push((owner.map(String::from), proc_def));
+                self.collect_nested(&proc_def.statements);
+            }
+            _ => {}
+        }
+    }
+
+    fn collect_nested(&mut self, statements: &'a [Expression]) {
+        for statement in statements.iter() {
+            match statement {
+                Expression::IfStatement(if_node) => {
+                    self.collect_nested(&if_node.statements);
+                    if let Some(else_node) = &if_node.else_branch {
+                        self.collect_nested(&else_node.statements);
+                    }
+                }
+                Expression::WhileStatement(while_node) => {
+                    self.collect_nested(&while_node.statements)
+                }
+                Expression::ForLoop(for_node) => self.collect_nested(&for_node.statements),
+                Expression::MatchStatement(match_node) => {
+                    for arm in match_node.arms.iter() {
+                        self.collect_nested(&arm.statements);
+                    }
+                }
+                statement => self.collect(statement, None),
+            }
+        }
+    }
+
+    fn ordered_types(&self) -> Vec<TypeDef<'a>> {
+        let mut types: Vec<TypeDef> = self.structs.iter().map(|s| TypeDef::Struct(s)).collect();
+        types.extend(self.enums.iter().map(|e| TypeDef::Enum(e)));
+
+        let mut ordered = Vec::new();
+        let mut visited = Vec::new();
+        for ty in types.iter() {
+            visit_type(*ty, &types, &mut visited, &mut ordered);
+        }
+
+        ordered
+    }
+}
+
+fn visit_type<'a>(
+    ty: TypeDef<'a>,
+    types: &[TypeDef<'a>],
+    visited: &mut Vec<String>,
+    ordered: &mut Vec<TypeDef<'a>>,
+) {
+    let name = ty.name();
+    if visited.contains(&name) {
+        return;
+    }
+    visited.push(name);
+
+    for field_type in ty.field_types() {
+        if let Some(dependency) = types.iter().find(|t| t.name() == ident(field_type)) {
+            visit_type(*dependency, types, visited, ordered);
+        }
+    }
+
+    ordered.push(ty);
+}
+
+fn ident(name: &str) -> String {
+    if name.contains('<') {
+        return mangle(name);
+    }
+
+    if C_RESERVED.contains(&name) || name.starts_with("meta_") || name == "main" {
+        return format!("{name}_");
+    }
+
+    String::from(name)
+}
+
+fn pad(depth: usize) -> String {
+    "    ".repeat(depth)
+}
+
+fn is_branching(expr: &Expression) -> bool {
+    match expr {
+        Expression::MatchStatement(..) => true,
+        Expression::IfStatement(if_node) => if_node.else_branch.is_some(),
+        _ => false,
+    }
+}
+
+fn string_literal(value: &str) -> String {
+    let mut out = String::from("\"");
+    for byte in value.bytes() {
+        match byte {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\n' => out.push_str("\\n"),
+            b'\t' => out.push_str("\\t"),
+            b'\r' => out.push_str("\\r"),
+            b'?' => out.push_str("\\?"),
+            0x20..=0x7e => out.push(byte as char),
+            byte => out.push_str(&format!("\\{byte:03o}")),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn unknown_variant(enum_def: &EnumDefNode) -> String {
+    let variants = enum_def
+        .variants
+        .iter()
+        .map(|v| v.name.as_str())
+        .collect::<Vec<_>>();
+
+    string_literal(&format!(
+        "' is not a variant of '{}', expected one of: {}",
+        enum_def.type_name,
+        variants.join(", ")
+    ))
+}
+
+fn to_string(type_name: &str, value: &str) -> String {
+    match type_name {
+        "i32" => format!("meta_i32_to_string({value})"),
+        "f32" => format!("meta_f32_to_string({value})"),
+        "bool" => format!("meta_bool_to_string({value})"),
+        "char" => format!("meta_char_to_string({value})"),
+        "String" => String::from(value),
+        "None" => String::from("\"None\""),
+        type_name => format!("{}__to_string({value})", ident(type_name)),
+    }
+}
+
+fn equals(type_name: &str, lhs: &str, rhs: &str) -> String {
+    match type_name {
+        "i32" | "f32" | "bool" | "char" => format!("{lhs} == {rhs}"),
+        "String" => format!("meta_string_eq({lhs}, {rhs})"),
+        type_name => format!("{}__eq({lhs}, {rhs})", ident(type_name)),
+    }
+}
+
+struct Emitter<'a> {
+    backend: &'a CBackend,
+    enums: Vec<EnumDefNode>,
+    errors: Vec<Diagnostic>,
+    scopes: Vec<Vec<(String, String)>>,
+    temps: usize,
+    returns: bool,
+}
+
+impl Emitter<'_> {
+    fn error(&mut self, position: Option<&Position>, message: String) {
+        let position = position.filter(|p| !p.filename.is_empty()).cloned();
+        self.errors.push(Diagnostic::error(position, message));
+    }
+
+    fn c_type(&mut self, type_name: &str, position: Option<&Position>) -> String {
+        match type_name {
+            "i32" => String::from("int32_t"),
+            "f32" => String::from("float"),
+            "bool" => String::from("bool"),
+            "char" => String::from("meta_char"),
+            "String" => String::from("meta_string"),
+            "None" => String::from("void"),
+            "any" => {
+                self.error(
+                    position,
+                    String::from("values of type 'any' cannot be emitted as C"),
+                );
+                String::from("void")
+            }
+            type_name if type_name.starts_with('{') => {
+                self.error(
+                    position,
+                    String::from("cannot emit a value whose type is unknown"),
+                );
+                String::from("void")
+            }
+            type_name
+                if type_name.starts_with("proc(")
+                    || map_types(type_name).is_some()
+                    || vec_type(type_name).is_some() =>
+            {
+                self.error(
+                    position,
+                    format!("values of type '{type_name}' cannot be emitted as C"),
+                );
+                String::from("void")
+            }
+            type_name => ident(type_name),
+        }
+    }
+
+    fn temp(&mut self, prefix: &str) -> String {
+        self.temps += 1;
+        format!("meta_{prefix}{}", self.temps)
+    }
+
+    fn declare(&mut self, name: &str) -> String {
+        let taken = |c_name: &str, scopes: &[Vec<(String, String)>]| {
+            scopes.iter().flatten().any(|(_, c)| c == c_name)
+        };
+
+        let mut c_name = ident(name);
+        let mut i = 1;
+        while taken(&c_name, &self.scopes) {
+            c_name = format!("{}_{i}", ident(name));
+            i += 1;
+        }
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((String::from(name), c_name.clone()));
+        }
+
+        c_name
+    }
+
+    fn lookup(&self, name: &str) -> String {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(n, _)| n == name)
+            .map_or_else(|| ident(name), |(_, c)| c.clone())
+    }
+
+    fn struct_def(&mut self, struct_def: &StructDefNode) -> String {
+        let mut out = format!("struct {} {{\n", ident(&struct_def.type_name));
+
+        for field in struct_def.fields.iter() {
+            let type_name = self.c_type(&field.type_name, Some(&struct_def.position));
+            out.push_str(&format!("    {type_name} {};\n", ident(&field.name)));
+        }
+
+        if struct_def.fields.is_empty() {
+            out.push_str("    char unused;\n");
+        }
+
+        out.push_str("};\n");
+        out
+    }
+
+    fn enum_def(&mut self, enum_def: &EnumDefNode) -> String {
+        let name = ident(&enum_def.type_name);
+
+        let tags = enum_def
+            .discriminants()
+            .iter()
+            .map(|(variant, discriminant)| format!("{name}__{}__tag = {discriminant}", variant))
+            .collect::<Vec<_>>();
+
+        let mut out = String::new();
+        if !tags.is_empty() {
+            out.push_str(&format!("enum {{ {} }};\n\n", tags.join(", ")));
+        }
+
+        out.push_str(&format!("struct {name} {{\n    int32_t tag;\n"));
+
+        if !enum_def.is_fieldless() {
+            out.push_str("    union {\n");
+
+            for variant in enum_def.variants.iter().filter(|v| !v.fields.is_empty()) {
+                let fields = variant
+                    .fields
+                    .iter()
+                    .map(|f| {
+                        let type_name = self.c_type(&f.type_name, Some(&enum_def.position));
+                        format!("{type_name} {};", ident(&f.name))
+                    })
+                    .collect::<Vec<_>>();
+
+                out.push_str(&format!(
+                    "        struct {{ {} }} {};\n",
+                    fields.join(" "),
+                    ident(&variant.name)
+                ));
+            }
+
+            out.push_str("    } as;\n");
+        }
+
+        out.push_str("};\n");
+        out
+    }
+
+    fn struct_helpers(&mut self, struct_def: &StructDefNode) -> (String, String) {
+        let name = ident(&struct_def.type_name);
+
+        let mut prototypes =
+            format!("static inline meta_string {name}__to_string({name} value);\n");
+        prototypes.push_str(&format!(
+            "static inline bool {name}__eq({name} a, {name} b);\n"
+        ));
+
+        let mut out = format!("\nstatic inline meta_string {name}__to_string({name} value) {{\n");
+        out.push_str(&format!(
+            "    meta_string s = {};\n",
+            string_literal(&format!("{} {{", struct_def.type_name))
+        ));
+
+        for (i, field) in struct_def.fields.iter().enumerate() {
+            let separator = if i > 0 { "," } else { "" };
+            let label = string_literal(&format!("{separator} {}: ", field.name));
+            let value = to_string(&field.type_name, &format!("value.{}", ident(&field.name)));
+            out.push_str(&format!("    s = meta_concat(s, {label});\n"));
+            out.push_str(&format!("    s = meta_concat(s, {value});\n"));
+        }
+
+        out.push_str("    return meta_concat(s, \" }\");\n}\n");
+
+        let checks = struct_def
+            .fields
+            .iter()
+            .map(|field| {
+                let name = ident(&field.name);
+                let check = equals(&field.type_name, &format!("a.{name}"), &format!("b.{name}"));
+                format!("({check})")
+            })
+            .collect::<Vec<_>>();
+
+        out.push_str(&format!(
+            "\nstatic inline bool {name}__eq({name} a, {name} b) {{\n"
+        ));
+        if checks.is_empty() {
+            out.push_str("    (void)a;\n    (void)b;\n    return true;\n}\n");
+        } else {
+            out.push_str(&format!("    return {};\n}}\n", checks.join(" && ")));
+        }
+
+        (prototypes, out)
+    }
+
+    fn enum_helpers(&mut self, enum_def: &EnumDefNode) -> (String, String) {
+        if enum_def.flags {
+            return self.flags_helpers(enum_def);
+        }
+
+        let name = ident(&enum_def.type_name);
+
+        let mut prototypes =
+            format!("static inline meta_string {name}__to_string({name} value);\n");
+        prototypes.push_str(&format!(
+            "static inline bool {name}__eq({name} a, {name} b);\n"
+        ));
+
+        let mut out = String::new();
+
+        for variant in enum_def.variants.iter() {
+            let variant_name = ident(&variant.name);
+
+            let params = variant
+                .fields
+                .iter()
+                .map(|f| {
+                    let type_name = self.c_type(&f.type_name, Some(&enum_def.position));
+                    format!("{type_name} {}", ident(&f.name))
+                })
+                .collect::<Vec<_>>();
+            let params = if params.is_empty() {
+                String::from("void")
+            } else {
+                params.join(", ")
+            };
+
+            prototypes.push_str(&format!(
+                "static inline {name} {name}__{variant_name}({params});\n"
+            ));
+
+            out.push_str(&format!(
+                "\nstatic inline {name} {name}__{variant_name}({params}) {{\n"
+            ));
+            out.push_str(&format!("    {name} value;\n"));
+            out.push_str(&format!("    value.tag = {name}__{}__tag;\n", variant.name));
+            for field in variant.fields.iter() {
+                let field = ident(&field.name);
+                out.push_str(&format!("    value.as.{variant_name}.{field} = {field};\n"));
+            }
+            out.push_str("    return value;\n}\n");
+        }
+
+        out.push_str(&format!(
+            "\nstatic inline meta_string {name}__to_string({name} value) {{\n    switch (value.tag) {{\n"
+        ));
+
+        for variant in enum_def.variants.iter() {
+            let label = format!("{}::{}", enum_def.type_name, variant.name);
+            out.push_str(&format!("    case {name}__{}__tag:", variant.name));
+
+            if variant.fields.is_empty() {
+                out.push_str(&format!(" return {};\n", string_literal(&label)));
+                continue;
+            }
+
+            out.push_str(" {\n");
+            out.push_str(&format!(
+                "        meta_string s = {};\n",
+                string_literal(&format!("{label}("))
+            ));
+
+            for (i, field) in variant.fields.iter().enumerate() {
+                if i > 0 {
+                    out.push_str("        s = meta_concat(s, \", \");\n");
+                }
+
+                let value = to_string(
+                    &field.type_name,
+                    &format!("value.as.{}.{}", ident(&variant.name), ident(&field.name)),
+                );
+                out.push_str(&format!("        s = meta_concat(s, {value});\n"));
+            }
+
+            out.push_str("        return meta_concat(s, \")\");\n    }\n");
+        }
+
+        out.push_str(&format!(
+            "    }}\n    meta_panic({});\n    return \"\";\n}}\n",
+            string_literal(&format!("invalid '{}' value", enum_def.type_name))
+        ));
+
+        out.push_str(&format!(
+            "\nstatic inline bool {name}__eq({name} a, {name} b) {{\n    if (a.tag != b.tag) {{\n        return false;\n    }}\n"
+        ));
+
+        let with_fields: Vec<_> = enum_def
+            .variants
+            .iter()
+            .filter(|v| !v.fields.is_empty())
+            .collect();
+
+        if !with_fields.is_empty() {
+            out.push_str("    switch (a.tag) {\n");
+
+            for variant in with_fields {
+                let variant_name = ident(&variant.name);
+                let checks = variant
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let path = format!("as.{variant_name}.{}", ident(&field.name));
+                        format!(
+                            "({})",
+                            equals(&field.type_name, &format!("a.{path}"), &format!("b.{path}"))
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                out.push_str(&format!(
+                    "    case {name}__{}__tag: return {};\n",
+                    variant.name,
+                    checks.join(" && ")
+                ));
+            }
+
+            out.push_str("    }\n");
+        }
+
+        out.push_str("    return true;\n}\n");
+
+        prototypes.push_str(&format!(
+            "static inline meta_string {name}__variant_name({name} value);\n"
+        ));
+        out.push_str(&format!(
+            "\nstatic inline meta_string {name}__variant_name({name} value) {{\n    switch (value.tag) {{\n"
+        ));
+        for variant in enum_def.variants.iter() {
+            out.push_str(&format!(
+                "    case {name}__{}__tag: return {};\n",
+                variant.name,
+                string_literal(&variant.name)
+            ));
+        }
+        out.push_str(&format!(
+            "    }}\n    meta_panic({});\n    return \"\";\n}}\n",
+            string_literal(&format!("invalid '{}' value", enum_def.type_name))
+        ));
+
+        if enum_def.is_fieldless() && !enum_def.variants.is_empty() {
+            prototypes.push_str(&format!(
+                "static inline {name} {name}__parse(meta_string value);\n"
+            ));
+            out.push_str(&format!(
+                "\nstatic inline {name} {name}__parse(meta_string value) {{\n"
+            ));
+            for variant in enum_def.variants.iter() {
+                out.push_str(&format!(
+                    "    if (meta_string_eq(value, {})) {{\n        return {name}__{}();\n    }}\n",
+                    string_literal(&variant.name),
+                    ident(&variant.name)
+                ));
+            }
+            out.push_str(&format!(
+                "    meta_panic(meta_unknown_variant(value, strlen(value), {}));\n    return {name}__{}();\n}}\n",
+                unknown_variant(enum_def),
+                ident(&enum_def.variants[0].name)
+            ));
+        }
+
+        if enum_def.is_fieldless() && !enum_def.variants.is_empty() {
+            prototypes.push_str(&format!(
+                "static inline {name} {name}__from_i32(int32_t value);\n"
+            ));
+
+            out.push_str(&format!(
+                "\nstatic inline {name} {name}__from_i32(int32_t value) {{\n    {name} result;\n    result.tag = value;\n    switch (value) {{\n"
+            ));
+
+            for variant in enum_def.variants.iter() {
+                out.push_str(&format!("    case {name}__{}__tag:\n", variant.name));
+            }
+
+            out.push_str("        return result;\n    }\n");
+            out.push_str(&format!(
+                "    meta_panic(meta_concat({}, meta_i32_to_string(value)));\n    return result;\n}}\n",
+                string_literal(&format!(
+                    "'{}' has no variant with discriminant ",
+                    enum_def.type_name
+                ))
+            ));
+        }
+
+        (prototypes, out)
+    }
+
+    fn flags_helpers(&mut self, enum_def: &EnumDefNode) -> (String, String) {
+        let name = ident(&enum_def.type_name);
+        let discriminants = enum_def.discriminants();
+
+        let mut prototypes = String::new();
+        let mut out = String::new();
+
+        prototypes.push_str(&format!(
+            "static inline {name} {name}__from_bits(int32_t bits);\n"
+        ));
+        out.push_str(&format!(
+            "\nstatic inline {name} {name}__from_bits(int32_t bits) {{\n    {name} value;\n    value.tag = bits;\n    return value;\n}}\n"
+        ));
+
+        for (variant, _) in discriminants.iter() {
+            let variant_name = ident(variant);
+            prototypes.push_str(&format!(
+                "static inline {name} {name}__{variant_name}(void);\n"
+            ));
+            out.push_str(&format!(
+                "\nstatic inline {name} {name}__{variant_name}(void) {{\n    return {name}__from_bits({name}__{variant}__tag);\n}}\n"
+            ));
+        }
+
+        prototypes.push_str(&format!(
+            "static inline meta_string {name}__to_string({name} value);\n"
+        ));
+        out.push_str(&format!(
+            "\nstatic inline meta_string {name}__to_string({name} value) {{\n"
+        ));
+
+        let unnamed = format!(
+            "meta_concat(meta_concat({}, meta_i32_to_string(remaining)), \")\")",
+            string_literal(&format!("{}(", enum_def.type_name))
+        );
+
+        if discriminants.is_empty() {
+            out.push_str(&format!(
+                "    int32_t remaining = value.tag;\n    return {unnamed};\n}}\n"
+            ));
+        } else {
+            let names = discriminants
+                .iter()
+                .map(|(variant, _)| string_literal(&format!("{}::{variant}", enum_def.type_name)))
+                .collect::<Vec<_>>();
+            let bits = discriminants
+                .iter()
+                .map(|(_, d)| format!("(int32_t)({d})"))
+                .collect::<Vec<_>>();
+
+            out.push_str(&format!(
+                "    static const char *const names[] = {{ {} }};\n",
+                names.join(", ")
+            ));
+            out.push_str(&format!(
+                "    static const int32_t bits[] = {{ {} }};\n",
+                bits.join(", ")
+            ));
+            out.push_str("    meta_string s = \"\";\n    int32_t remaining = value.tag;\n");
+            out.push_str(&format!(
+                "    for (size_t i = 0; i < {}; i++) {{\n",
+                discriminants.len()
+            ));
+            out.push_str("        if (bits[i] == value.tag) {\n            return names[i];\n        }\n    }\n");
+            out.push_str(&format!(
+                "    for (size_t i = 0; i < {}; i++) {{\n",
+                discriminants.len()
+            ));
+            out.push_str("        if (bits[i] != 0 && (value.tag & bits[i]) == bits[i] && (remaining & bits[i]) != 0) {\n");
+            out.push_str("            s = s[0] == '\\0' ? names[i] : meta_concat(meta_concat(s, \" | \"), names[i]);\n");
+            out.push_str("            remaining &= ~bits[i];\n        }\n    }\n");
+            out.push_str("    if (remaining != 0 || s[0] == '\\0') {\n");
+            out.push_str(&format!(
+                "        s = s[0] == '\\0' ? {unnamed} : meta_concat(meta_concat(s, \" | \"), {unnamed});\n"
+            ));
+            out.push_str("    }\n    return s;\n}\n");
+        }
+
+        prototypes.push_str(&format!(
+            "static inline bool {name}__eq({name} a, {name} b);\n"
+        ));
+        out.push_str(&format!(
+            "\nstatic inline bool {name}__eq({name} a, {name} b) {{\n    return a.tag == b.tag;\n}}\n"
+        ));
+
+        prototypes.push_str(&format!(
+            "static inline bool {name}__contains({name} a, {name} b);\n"
+        ));
+        out.push_str(&format!(
+            "\nstatic inline bool {name}__contains({name} a, {name} b) {{\n    return (a.tag & b.tag) == b.tag;\n}}\n"
+        ));
+
+        let (names, bits) = if discriminants.is_empty() {
+            (String::from("NULL"), String::from("NULL"))
+        } else {
+            let names = discriminants
+                .iter()
+                .map(|(variant, _)| string_literal(variant))
+                .collect::<Vec<_>>();
+            let bits = discriminants
+                .iter()
+                .map(|(_, d)| format!("(int32_t)({d})"))
+                .collect::<Vec<_>>();
+
+            out.push_str(&format!(
+                "\nstatic const char *const {name}__variant_names[] = {{ {} }};\n",
+                names.join(", ")
+            ));
+            out.push_str(&format!(
+                "static const int32_t {name}__variant_bits[] = {{ {} }};\n",
+                bits.join(", ")
+            ));
+
+            (
+                format!("{name}__variant_names"),
+                format!("{name}__variant_bits"),
+            )
+        };
+
+        prototypes.push_str(&format!(
+            "static inline meta_string {name}__variant_name({name} value);\n"
+        ));
+        out.push_str(&format!(
+            "\nstatic inline meta_string {name}__variant_name({name} value) {{\n    return meta_flags_names(value.tag, {names}, {bits}, {});\n}}\n",
+            discriminants.len()
+        ));
+
+        prototypes.push_str(&format!(
+            "static inline {name} {name}__parse(meta_string value);\n"
+        ));
+        out.push_str(&format!(
+            "\nstatic inline {name} {name}__parse(meta_string value) {{\n    return {name}__from_bits(meta_flags_parse(value, {names}, {bits}, {}, {}));\n}}\n",
+            discriminants.len(),
+            unknown_variant(enum_def)
+        ));
+
+        prototypes.push_str(&format!(
+            "static inline {name} {name}__from_i32(int32_t value);\n"
+        ));
+        out.push_str(&format!(
+            "\nstatic inline {name} {name}__from_i32(int32_t value) {{\n    if ((value & ~(int32_t)({})) != 0) {{\n",
+            enum_def.all_flags()
+        ));
+        out.push_str(&format!(
+            "        meta_panic(meta_concat(meta_i32_to_string(value), {}));\n    }}\n    return {name}__from_bits(value);\n}}\n",
+            string_literal(&format!(
+                " sets bits that are not flags of '{}'",
+                enum_def.type_name
+            ))
+        ));
+
+        (prototypes, out)
+    }
+
+    fn proc_name(owner: Option<&str>, proc_def: &ProcDefNode) -> String {
+        match owner {
+            Some(owner) => format!("{}_{}", ident(owner), proc_def.name),
+            None if proc_def.name == ENTRY_POINT => String::from(ENTRY_FN),
+            None => ident(&proc_def.name),
+        }
+    }
+
+    fn proc_def(&mut self, owner: Option<&str>, proc_def: &ProcDefNode) -> (String, String) {
+        let name = Emitter::proc_name(owner, proc_def);
+
+        let return_type = proc_def.return_type.as_deref().map_or_else(
+            || String::from("void"),
+            |t| self.c_type(t, Some(&proc_def.position)),
+        );
+        let returns = return_type != "void";
+
+        self.scopes.push(Vec::new());
+
+        let mut params = Vec::new();
+        let mut args = Vec::new();
+        for arg in proc_def.args.iter() {
+            let type_name = self.c_type(&arg.type_name, Some(&proc_def.position));
+            let c_name = self.declare(&arg.name);
+
+            if arg.name == "self" && arg.mutable {
+                if let Some((_, place)) = self.scopes.last_mut().and_then(|s| s.last_mut()) {
+                    *place = format!("(*{c_name})");
+                }
+                params.push(format!("{type_name} *{c_name}"));
+            } else {
+                params.push(format!("{type_name} {c_name}"));
+            }
+            args.push(c_name);
+        }
+
+        let params = if params.is_empty() {
+            String::from("void")
+        } else {
+            params.join(", ")
+        };
+
+        let requires: Vec<_> = proc_def
+            .contracts
+            .iter()
+            .filter(|c| c.kind == ContractKind::Requires)
+            .collect();
+        let ensures: Vec<_> = proc_def
+            .contracts
+            .iter()
+            .filter(|c| c.kind == ContractKind::Ensures)
+            .collect();
+
+        let mut prototypes = format!("static {return_type} {name}({params});\n");
+        let mut out = String::new();
+
+        let mut contracts = String::new();
+        for contract in requires.iter() {
+            let condition = self.value(&contract.condition);
+            contracts.push_str(&format!(
+                "    meta_assert({condition}, {});\n",
+                string_literal(&format!(
+                    "{} '{}' does not hold",
+                    contract.kind, contract.source
+                ))
+            ));
+        }
+
+        let body_name = if ensures.is_empty() {
+            name.clone()
+        } else {
+            format!("{name}__body")
+        };
+
+        if !ensures.is_empty() {
+            prototypes.push_str(&format!("static {return_type} {body_name}({params});\n"));
+
+            out.push_str(&format!("static {return_type} {name}({params}) {{\n"));
+            out.push_str(&contracts);
+            contracts.clear();
+
+            self.scopes.push(Vec::new());
+            let call = format!("{body_name}({})", args.join(", "));
+            if returns {
+                let result = self.declare("result");
+                out.push_str(&format!("    {return_type} {result} = {call};\n"));
+            } else {
+                out.push_str(&format!("    {call};\n"));
+            }
+
+            for contract in ensures.iter() {
+                let condition = self.value(&contract.condition);
+                out.push_str(&format!(
+                    "    meta_assert({condition}, {});\n",
+                    string_literal(&format!(
+                        "{} '{}' does not hold",
+                        contract.kind, contract.source
+                    ))
+                ));
+            }
+
+            if returns {
+                out.push_str(&format!("    return {};\n", self.lookup("result")));
+            }
+            out.push_str("}\n\n");
+            self.scopes.pop();
+        }
+
+        out.push_str(&format!("static {return_type} {body_name}({params}) {{\n"));
+        out.push_str(&contracts);
+
+        let previous = std::mem::replace(&mut self.returns, returns);
+        self.statements(&proc_def.statements, 1, &mut out);
+        self.returns = previous;
+
+        if returns
+            && !matches!(
+                proc_def.statements.last(),
+                Some(Expression::ReturnStatement(..))
+            )
+        {
+            out.push_str(&format!(
+                "    meta_panic({});\n",
+                string_literal(&format!("'{}' did not return a value", proc_def.name))
+            ));
+        }
+
+        out.push_str("}\n");
+        self.scopes.pop();
+
+        (prototypes, out)
+    }
+
+    fn statements(&mut self, statements: &[Expression], depth: usize, out: &mut String) {
+        for statement in statements.iter() {
+            self.statement(statement, depth, out);
+        }
+    }
+
+    fn block(&mut self, statements: &[Expression], depth: usize, out: &mut String) {
+        self.scopes.push(Vec::new());
+        self.statements(statements, depth + 1, out);
+        self.scopes.pop();
+        out.push_str(&format!("{}}}\n", pad(depth)));
+    }
+
+    fn statement(&mut self, expr: &Expression, depth: usize, out: &mut String) {
+        let indent = pad(depth);
+
+        match expr {
+            Expression::ProcDef(..)
+            | Expression::StructDef(..)
+            | Expression::EnumDef(..)
+            | Expression::ImplStatement(..) => {}
+            Expression::TraitDef(trait_def) => self.error(
+                Some(&trait_def.position),
+                String::from("traits cannot be emitted as C"),
+            ),
+            Expression::IfStatement(if_node) => self.if_statement(if_node, depth, out, None),
+            Expression::WhileStatement(while_node) => {
+                let condition = self.value(&while_node.value);
+                out.push_str(&format!("{indent}while ({condition}) {{\n"));
+                self.block(&while_node.statements, depth, out);
+            }
+            Expression::ForLoop(for_node) if for_node.range.type_name() == "String" => {
+                let value = self.value(&for_node.range);
+
+                self.scopes.push(Vec::new());
+                let cursor = self.temp("cursor");
+                let counter = self.declare(&for_node.counter.metadata.name);
+
+                out.push_str(&format!(
+                    "{indent}for (meta_string {cursor} = {value}; *{cursor} != '\\0';) {{\n"
+                ));
+                out.push_str(&format!(
+                    "{}meta_char {counter} = meta_utf8_next(&{cursor});\n",
+                    pad(depth + 1)
+                ));
+                self.block(&for_node.statements, depth, out);
+                self.scopes.pop();
+            }
+            Expression::ForLoop(for_node) => {
+                let Expression::RangeStatement(range_node) = for_node.range.as_ref() else {
+                    self.error(
+                        Some(&for_node.position),
+                        String::from("only range and string loops can be emitted as C"),
+                    );
+                    return;
+                };
+
+                let start = self.value(&range_node.start);
+                let end = self.value(&range_node.end);
+
+                self.scopes.push(Vec::new());
+                let counter = self.declare(&for_node.counter.metadata.name);
+                let end_name = self.temp("end");
+                let op = if range_node.inclusive { "<=" } else { "<" };
+
+                out.push_str(&format!(
+                    "{indent}for (int32_t {counter} = {start}, {end_name} = {end}; {counter} {op} {end_name}; {counter}++) {{\n"
+                ));
+                self.block(&for_node.statements, depth, out);
+                self.scopes.pop();
+            }
+            Expression::MatchStatement(match_node) => {
+                self.match_statement(match_node, depth, out, None)
+            }
+            Expression::LetStatement(let_node) => {
+                let type_name = self.c_type(&let_node.type_name, Some(&let_node.position));
+
+                let value = if is_branching(&let_node.value) {
+                    let target = self.temp("value");
+                    out.push_str(&format!("{indent}{type_name} {target};\n"));
+                    self.branch_into(&let_node.value, depth, out, &target);
+                    target
+                } else {
+                    self.value(&let_node.value)
+                };
+
+                let name = self.declare(&let_node.name);
+                out.push_str(&format!("{indent}{type_name} {name} = {value};\n"));
+            }
+            Expression::AssignStatement(assign_node) => {
+                let name = self.lookup(&assign_node.value.metadata.name);
+
+                if is_branching(&assign_node.new_value) {
+                    self.branch_into(&assign_node.new_value, depth, out, &name);
+                    return;
+                }
+
+                let value = self.value(&assign_node.new_value);
+                out.push_str(&format!("{indent}{name} = {value};\n"));
+            }
+            Expression::StructFieldAssign(field_assign_node) => {
+                let mut target = self.lookup(&field_assign_node.struct_instance.metadata.name);
+                for field in field_assign_node.fields.iter() {
+                    target.push('.');
+                    target.push_str(&ident(&field.metadata.name));
+                }
+
+                let value = self.value(&field_assign_node.new_value);
+                out.push_str(&format!("{indent}{target} = {value};\n"));
+            }
+            Expression::ReturnStatement(return_node) => {
+                if !self.returns {
+                    if !matches!(return_node.value.as_ref(), Expression::Literal(..)) {
+                        self.statement(&return_node.value, depth, out);
+                    }
+
+                    out.push_str(&format!("{indent}return;\n"));
+                    return;
+                }
+
+                if is_branching(&return_node.value) {
+                    let type_name = self.c_type(&return_node.value.type_name(), None);
+                    let target = self.temp("value");
+                    out.push_str(&format!("{indent}{type_name} {target};\n"));
+                    self.branch_into(&return_node.value, depth, out, &target);
+                    out.push_str(&format!("{indent}return {target};\n"));
+                    return;
+                }
+
+                let value = self.value(&return_node.value);
+                out.push_str(&format!("{indent}return {value};\n"));
+            }
+            Expression::BreakStatement(..) => out.push_str(&format!("{indent}break;\n")),
+            Expression::ContinueStatement(..) => out.push_str(&format!("{indent}continue;\n")),
+            Expression::Error(position) => self.error(
+                Some(position),
+                String::from("code that failed to parse cannot be emitted as C"),
+            ),
+            expr => {
+                let value = self.value(expr);
+                out.push_str(&format!("{indent}{value};\n"));
+            }
+        }
+    }
+
+    fn pattern_test(
+        &mut self,
+        pattern: &PatternNode,
+        value_type: &str,
+        type_name: &str,
+        scrutinee: &str,
+    ) -> (Option<String>, Vec<(String, String, String)>) {
+        match pattern {
+            PatternNode::Wildcard => (None, Vec::new()),
+            PatternNode::Binding(binding) => (
+                None,
+                vec![(
+                    binding.name.clone(),
+                    String::from(type_name),
+                    String::from(scrutinee),
+                )],
+            ),
+            PatternNode::Literal(token, lt) => {
+                let literal = self.literal(token, *lt);
+                (Some(equals(value_type, scrutinee, &literal)), Vec::new())
+            }
+            PatternNode::Range { start, end, kind } => {
+                let start = self.literal(start, *kind);
+                let end = self.literal(end, *kind);
+                (
+                    Some(format!("({scrutinee} >= {start} && {scrutinee} < {end})")),
+                    Vec::new(),
+                )
+            }
+            PatternNode::Bind { binding, pattern } => {
+                let (condition, inner) =
+                    self.pattern_test(pattern, value_type, type_name, scrutinee);
+
+                let mut bindings = vec![(
+                    binding.name.clone(),
+                    String::from(type_name),
+                    String::from(scrutinee),
+                )];
+                bindings.extend(inner);
+                (condition, bindings)
+            }
+            PatternNode::Variant {
+                type_name: enum_name,
+                variant,
+                bindings: names,
+            } => {
+                let fields = self
+                    .enums
+                    .iter()
+                    .find(|e| e.type_name == *enum_name)
+                    .and_then(|e| e.variants.iter().find(|v| v.name == *variant))
+                    .map(|v| v.fields.clone())
+                    .unwrap_or_default();
+
+                let mut bindings = Vec::new();
+                for (binding, field) in names.iter().zip(fields.iter()) {
+                    if binding.name == "_" {
+                        continue;
+                    }
+
+                    bindings.push((
+                        binding.name.clone(),
+                        self.c_type(&field.type_name, None),
+                        format!("{scrutinee}.as.{}.{}", ident(variant), ident(&field.name)),
+                    ));
+                }
+
+                (
+                    Some(format!(
+                        "{scrutinee}.tag == {}__{variant}__tag",
+                        ident(enum_name)
+                    )),
+                    bindings,
+                )
+            }
+            PatternNode::Or(alternatives) => {
+                let tests: Vec<_> = alternatives
+                    .iter()
+                    .map(|alternative| {
+                        self.pattern_test(alternative, value_type, type_name, scrutinee)
+                    })
+                    .collect();
+
+                let condition = tests
+                    .iter()
+                    .map(|(condition, _)| condition.as_ref().map(|c| format!("({c})")))
+                    .collect::<Option<Vec<_>>>()
+                    .map(|conditions| format!("({})", conditions.join(" || ")));
+
+                let names = tests
+                    .first()
+                    .map(|(_, bindings)| bindings.clone())
+                    .unwrap_or_default();
+
+                let mut bindings = Vec::new();
+                for (name, binding_type, _) in names {
+                    let mut value = String::new();
+
+                    for (i, (condition, alternative)) in tests.iter().enumerate() {
+                        let alternative_value = alternative
+                            .iter()
+                            .find(|(n, _, _)| *n == name)
+                            .map(|(_, _, v)| v.clone())
+                            .unwrap_or_default();
+
+                        match condition {
+                            Some(condition) if i + 1 < tests.len() => {
+                                value.push_str(&format!("({condition}) ? {alternative_value} : "))
+                            }
+                            _ => {
+                                value.push_str(&alternative_value);
+                                break;
+                            }
+                        }
+                    }
+
+                    bindings.push((name, binding_type, value));
+                }
+
+                (condition, bindings)
+            }
+        }
+    }
+
+    fn match_statement(
+        &mut self,
+        match_node: &MatchNode,
+        depth: usize,
+        out: &mut String,
+        target: Option<&str>,
+    ) {
+        let indent = pad(depth);
+        let inner = pad(depth + 1);
+
+        let value_type = match_node.value.type_name();
+        let type_name = self.c_type(&value_type, Some(&match_node.position));
+        let value = self.value(&match_node.value);
+
+        let scrutinee = self.temp("match");
+        let done = format!("{scrutinee}_done");
+
+        out.push_str(&format!("{indent}{{\n"));
+        out.push_str(&format!("{inner}{type_name} {scrutinee} = {value};\n"));
+        out.push_str(&format!("{inner}bool {done} = false;\n"));
+
+        let mut catch_all = false;
+        for arm in match_node.arms.iter() {
+            self.scopes.push(Vec::new());
+
+            let (condition, values) =
+                self.pattern_test(&arm.pattern, &value_type, &type_name, &scrutinee);
+
+            let mut bindings = Vec::new();
+            for (binding, binding_type, value) in values {
+                let name = self.declare(&binding);
+                bindings.push((format!("{binding_type} {name} = {value};"), name));
+            }
+
+            if condition.is_none() && arm.guard.is_none() {
+                catch_all = true;
+            }
+
+            match condition {
+                Some(condition) => {
+                    out.push_str(&format!("{inner}if (!{done} && {condition}) {{\n"))
+                }
+                None => out.push_str(&format!("{inner}if (!{done}) {{\n")),
+            }
+
+            let mut arm_depth = depth + 2;
+            for (binding, name) in bindings.iter() {
+                out.push_str(&format!("{}{binding}\n", pad(arm_depth)));
+                out.push_str(&format!("{}(void){name};\n", pad(arm_depth)));
+            }
+
+            if let Some(guard) = &arm.guard {
+                let guard = self.value(guard);
+                out.push_str(&format!("{}if ({guard}) {{\n", pad(arm_depth)));
+                arm_depth += 1;
+            }
+
+            out.push_str(&format!("{}{done} = true;\n", pad(arm_depth)));
+            self.arm_body(
+                &arm.statements,
+                arm_depth,
+                out,
+                target,
+                &match_node.position,
+                "match arm",
+            );
+
+            if arm.guard.is_some() {
+                out.push_str(&format!("{}}}\n", pad(depth + 2)));
+            }
+
+            out.push_str(&format!("{inner}}}\n"));
+            self.scopes.pop();
+        }
+
+        if !catch_all {
+            out.push_str(&format!(
+                "{inner}if (!{done}) {{\n{}meta_panic(\"no match arm matched\");\n{inner}}}\n",
+                pad(depth + 2)
+            ));
+        }
+
+        out.push_str(&format!("{indent}}}\n"));
+    }
+
+    fn if_statement(
+        &mut self,
+        if_node: &IfNode,
+        depth: usize,
+        out: &mut String,
+        target: Option<&str>,
+    ) {
+        let indent = pad(depth);
+
+        let condition = self.value(&if_node.value);
+        out.push_str(&format!("{indent}if ({condition}) {{\n"));
+        self.scopes.push(Vec::new());
+        self.arm_body(
+            &if_node.statements,
+            depth + 1,
+            out,
+            target,
+            &if_node.position,
+            "if branch",
+        );
+        self.scopes.pop();
+
+        if let Some(else_node) = &if_node.else_branch {
+            out.push_str(&format!("{indent}}} else {{\n"));
+            self.scopes.push(Vec::new());
+            match else_node.statements.as_slice() {
+                [Expression::IfStatement(nested)] => {
+                    self.if_statement(nested, depth + 1, out, target)
+                }
+                statements => self.arm_body(
+                    statements,
+                    depth + 1,
+                    out,
+                    target,
+                    &else_node.position,
+                    "if branch",
+                ),
+            }
+            self.scopes.pop();
+        }
+
+        out.push_str(&format!("{indent}}}\n"));
+    }
+
+    fn branch_into(&mut self, expr: &Expression, depth: usize, out: &mut String, target: &str) {
+        match expr {
+            Expression::MatchStatement(match_node) => {
+                self.match_statement(match_node, depth, out, Some(target))
+            }
+            Expression::IfStatement(if_node) => {
+                self.if_statement(if_node, depth, out, Some(target))
+            }
+            expr => {
+                let value = self.value(expr);
+                out.push_str(&format!("{}{target} = {value};\n", pad(depth)));
+            }
+        }
+    }
+
+    fn arm_body(
+        &mut self,
+        statements: &[Expression],
+        depth: usize,
+        out: &mut String,
+        target: Option<&str>,
+        position: &Position,
+        kind: &str,
+    ) {
+        let Some(target) = target else {
+            self.statements(statements, depth, out);
+            return;
+        };
+
+        let Some((last, rest)) = statements.split_last() else {
+            out.push_str(&format!(
+                "{}meta_panic(\"{kind} produced no value\");\n",
+                pad(depth)
+            ));
+            return;
+        };
+
+        self.statements(rest, depth, out);
+
+        match last {
+            last if is_branching(last) => self.branch_into(last, depth, out, target),
+            Expression::ReturnStatement(..)
+            | Expression::BreakStatement(..)
+            | Expression::ContinueStatement(..)
+            | Expression::LetStatement(..)
+            | Expression::AssignStatement(..)
+            | Expression::StructFieldAssign(..)
+            | Expression::IfStatement(..)
+            | Expression::WhileStatement(..)
+            | Expression::ForLoop(..) => {
+                self.statement(last, depth, out);
+                if !matches!(
+                    last,
+                    Expression::ReturnStatement(..)
+                        | Expression::BreakStatement(..)
+                        | Expression::ContinueStatement(..)
+                ) {
+                    self.error(Some(position), format!("{kind} produced no value"));
+                }
+            }
+            last => {
+                let value = self.value(last);
+                out.push_str(&format!("{}{target} = {value};\n", pad(depth)));
+            }
+        }
+    }
+
+    fn literal(&mut self, token: &Token, lt: LiteralType) -> String {
+        match lt {
+            LiteralType::String => string_literal(&token.value),
+            LiteralType::Char => match token.value.chars().next() {
+                Some(c) if c.is_ascii_graphic() && c != '\'' && c != '\\' => format!("'{c}'"),
+                Some(c) => format!("0x{:x}", c as u32),
+                None => String::from("0"),
+            },
+            LiteralType::Float => {
+                if token.value.contains(['.', 'e', 'E']) {
+                    format!("{}f", token.value)
+                } else {
+                    format!("{}.0f", token.value)
+                }
+            }
+            LiteralType::None => String::from("((void)0)"),
+            LiteralType::Number | LiteralType::Bool => token.value.to_string(),
+        }
+    }
+
+    fn is_mapped(&self, name: &str) -> bool {
+        self.backend
+            .natives
+            .iter()
+            .any(|(native, _)| native == name)
+    }
+
+    fn builtin(&mut self, fun_call_node: &FunCallNode) -> Option<String> {
+        let capabilities = Capabilities::new("c")
+            .without("closures")
+            .without("files")
+            .without("maps")
+            .without("vecs");
+
+        match fun_call_node.proc_def.name.as_str() {
+            "version" => Some(string_literal(VERSION)),
+            "backend" => Some(string_literal(&capabilities.backend)),
+            "read_lines" => {
+                self.error(
+                    Some(&fun_call_node.position),
+                    String::from("'read_lines' cannot be emitted as C"),
+                );
+                Some(String::from("0"))
+            }
+            "has_feature" => {
+                let arg = fun_call_node.args.first()?;
+                if let Expression::Literal(token, LiteralType::String) = arg.value.as_ref() {
+                    return Some(capabilities.has_feature(&token.value).to_string());
+                }
+
+                self.error(
+                    Some(&fun_call_node.position),
+                    String::from("'has_feature' needs a string literal argument when emitted as C"),
+                );
+                Some(String::from("false"))
+            }
+            _ => None,
+        }
+    }
+
+    fn place(&mut self, expr: &Expression) -> String {
+        match expr {
+            Expression::Variable(variable_node) => self.lookup(&variable_node.metadata.name),
+            Expression::StructFieldAccess(field_access_node) => {
+                let base = self.place(&field_access_node.base);
+                format!("{base}.{}", ident(&field_access_node.field.metadata.name))
+            }
+            expr => self.operand(expr),
+        }
+    }
+
+    fn operand(&mut self, expr: &Expression) -> String {
+        let value = self.value(expr);
+
+        if let Expression::BinaryOp(..) = expr {
+            return format!("({value})");
+        }
+
+        value
+    }
+
+    fn value(&mut self, expr: &Expression) -> String {
+        match expr {
+            Expression::Variable(..) | Expression::StructFieldAccess(..) => self.place(expr),
+            Expression::Literal(token, lt) => self.literal(token, *lt),
+            Expression::Pipe(pipe_node) => self.value(&pipe_node.call),
+            Expression::Coalesce(coalesce_node) => {
+                let value = self.value(&coalesce_node.value);
+                if coalesce_node.value.type_name() != "None" {
+                    return value;
+                }
+
+                let fallback = self.value(&coalesce_node.fallback);
+                format!("((void){value}, {fallback})")
+            }
+            Expression::CompareChain(compare_chain_node) => {
+                let operands = &compare_chain_node.operands;
+
+                let inner = operands
+                    .get(1..operands.len().saturating_sub(1))
+                    .unwrap_or_default();
+                if let Some(operand) = inner.iter().find(|operand| {
+                    !matches!(
+                        operand,
+                        Expression::Variable(..)
+                            | Expression::Literal(..)
+                            | Expression::StructFieldAccess(..)
+                    )
+                }) {
+                    self.error(
+                        operand.position().or(Some(&compare_chain_node.position)),
+                        String::from(
+                            "comparison chain operand cannot be emitted as C, bind it to a variable first",
+                        ),
+                    );
+                    return String::new();
+                }
+
+                let links = compare_chain_node
+                    .ops
+                    .iter()
+                    .zip(operands.windows(2))
+                    .map(|(op, pair)| {
+                        let link = self.binary_op(&BinaryOpNode {
+                            lhs: Box::new(pair[0].clone()),
+                            op: op.clone(),
+                            rhs: Box::new(pair[1].clone()),
+                            position: compare_chain_node.position.clone(),
+                            span: Span::default(),
+                        });
+                        format!("({link})")
+                    })
+                    .collect::<Vec<_>>();
+
+                format!("({})", links.join(" && "))
+            }
+            Expression::FunCall(fun_call_node) => {
+                let proc_def = &fun_call_node.proc_def;
+
+                if proc_def.native && !self.is_mapped(&proc_def.name) {
+                    if let Some(value) = self.builtin(fun_call_node) {
+                        return value;
+                    }
+                }
+
+                let name = if proc_def.native {
+                    let mapped = self
+                        .backend
+                        .natives
+                        .iter()
+                        .find(|(name, _)| *name == proc_def.name)
+                        .map(|(_, function)| function.clone());
+
+                    match mapped {
+                        Some(function) => function,
+                        None => {
+                            self.error(
+                                Some(&fun_call_node.position),
+                                format!("native procedure '{}' has no C mapping", proc_def.name),
+                            );
+                            ident(&proc_def.name)
+                        }
+                    }
+                } else {
+                    Emitter::proc_name(None, proc_def)
+                };
+
+                let args = fun_call_node
+                    .args
+                    .iter()
+                    .map(|arg| self.value(&arg.value))
+                    .collect::<Vec<_>>();
+
+                format!("{name}({})", args.join(", "))
+            }
+            Expression::ImplFunCall(impl_fun_call_node) => {
+                let Expression::FunCall(fun_call_node) = impl_fun_call_node.fun_call_node.as_ref()
+                else {
+                    return String::new();
+                };
+
+                let mutates = fun_call_node
+                    .proc_def
+                    .args
+                    .first()
+                    .is_some_and(|a| a.mutable);
+                let args = fun_call_node
+                    .args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| match (i, arg.value.as_ref()) {
+                        (
+                            0,
+                            value @ (Expression::Variable(..) | Expression::StructFieldAccess(..)),
+                        ) if impl_fun_call_node.method && mutates => {
+                            format!("&{}", self.place(value))
+                        }
+                        (0, value) if impl_fun_call_node.method && mutates => {
+                            let type_name = self.c_type(&value.type_name(), None);
+                            format!("({type_name}[]){{{}}}", self.value(value))
+                        }
+                        (_, value) => self.value(value),
+                    })
+                    .collect::<Vec<_>>();
+
+                let name = Emitter::proc_name(
+                    Some(&impl_fun_call_node.impl_node.struct_def.type_name),
+                    &fun_call_node.proc_def,
+                );
+
+                format!("{name}({})", args.join(", "))
+            }
+            Expression::StructInstance(struct_instance_node) => {
+                let name = ident(&struct_instance_node.struct_def.type_name);
+
+                let fields = struct_instance_node
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let value = self.value(&field.value);
+                        format!(".{} = {value}", ident(&field.metadata.name))
+                    })
+                    .collect::<Vec<_>>();
+
+                if fields.is_empty() {
+                    return format!("(({name}){{0}})");
+                }
+
+                format!("(({name}){{ {} }})", fields.join(", "))
+            }
+            Expression::EnumInstance(enum_instance_node) => {
+                let fields = enum_instance_node
+                    .fields
+                    .iter()
+                    .map(|field| self.value(&field.value))
+                    .collect::<Vec<_>>();
+
+                format!(
+                    "{}__{}({})",
+                    ident(&enum_instance_node.enum_def.type_name),
+                    ident(&enum_instance_node.variant),
+                    fields.join(", ")
+                )
+            }
+            Expression::EnumFrom(enum_from_node) => {
+                let value = self.value(&enum_from_node.value);
+                format!(
+                    "{}__from_i32({value})",
+                    ident(&enum_from_node.enum_def.type_name)
+                )
+            }
+            Expression::EnumParse(enum_parse_node) => {
+                let value = self.value(&enum_parse_node.value);
+                format!(
+                    "{}__parse({value})",
+                    ident(&enum_parse_node.enum_def.type_name)
+                )
+            }
+            Expression::VariantName(variant_name_node) => {
+                let value = self.value(&variant_name_node.value);
+                format!(
+                    "{}__variant_name({value})",
+                    ident(&variant_name_node.value.type_name())
+                )
+            }
+            Expression::Cast(cast_node) => {
+                let value = self.operand(&cast_node.value);
+                format!("{value}.tag")
+            }
+            Expression::Contains(contains_node) => {
+                let value = self.value(&contains_node.value);
+                let flag = self.value(&contains_node.flag);
+                format!(
+                    "{}__contains({value}, {flag})",
+                    ident(&contains_node.value.type_name())
+                )
+            }
+            Expression::BinaryOp(binary_op_node) => self.binary_op(binary_op_node),
+            Expression::MatchStatement(match_node) => {
+                self.error(
+                    Some(&match_node.position),
+                    String::from(
+                        "match expressions can only be emitted as C when bound, assigned or returned",
+                    ),
+                );
+                String::new()
+            }
+            Expression::IfStatement(if_node) => {
+                self.error(
+                    Some(&if_node.position),
+                    String::from(
+                        "if expressions can only be emitted as C when bound, assigned or returned",
+                    ),
+                );
+                String::new()
+            }
+            Expression::Closure(closure_node) => {
+                self.error(
+                    Some(&closure_node.proc_def.position),
+                    String::from("closures cannot be emitted as C"),
+                );
+                String::new()
+            }
+            Expression::ClosureCall(closure_call_node) => {
+                self.error(
+                    Some(&closure_call_node.position),
+                    String::from("closures cannot be emitted as C"),
+                );
+                String::new()
+            }
+            Expression::DynCall(dyn_call_node) => {
+                self.error(
+                    Some(&dyn_call_node.fun_call_node.position),
+                    String::from("trait method calls cannot be emitted as C"),
+                );
+                String::new()
+            }
+            Expression::MapNew(map_new_node) => {
+                self.error(
+                    Some(&map_new_node.position),
+                    String::from("maps cannot be emitted as C"),
+                );
+                String::new()
+            }
+            Expression::MapCall(map_call_node) => {
+                self.error(
+                    Some(&map_call_node.position),
+                    String::from("maps cannot be emitted as C"),
+                );
+                String::new()
+            }
+            Expression::VecNew(vec_new_node) => {
+                self.error(
+                    Some(&vec_new_node.position),
+                    String::from("vecs cannot be emitted as C"),
+                );
+                String::new()
+            }
+            Expression::VecCall(vec_call_node) => {
+                self.error(
+                    Some(&vec_call_node.position),
+                    String::from("vecs cannot be emitted as C"),
+                );
+                String::new()
+            }
+            Expression::Error(position) => {
+                self.error(
+                    Some(position),
+                    String::from("code that failed to parse cannot be emitted as C"),
+                );
+                String::new()
+            }
+            _ => {
+                self.error(
+                    None,
+                    String::from("statement used as a value cannot be emitted as C"),
+                );
+                String::new()
+            }
+        }
+    }
+
+    fn binary_op(&mut self, binary_op_node: &BinaryOpNode) -> String {
+        let lhs_type = binary_op_node.lhs.type_name();
+        let rhs_type = binary_op_node.rhs.type_name();
+
+        let op = &binary_op_node.op;
+
+        if let BinaryOp::Inc | BinaryOp::Dec = op {
+            let target = self.place(&binary_op_node.lhs);
+
+            if lhs_type == "f32" {
+                let symbol = if *op == BinaryOp::Inc { "+" } else { "-" };
+                return format!("{target} {symbol}= 1.0f");
+            }
+
+            let helper = if *op == BinaryOp::Inc {
+                "meta_add_i32"
+            } else {
+                "meta_sub_i32"
+            };
+
+            return format!("{target} = {helper}({target}, 1)");
+        }
+
+        let compound = match op {
+            BinaryOp::AddAssign => Some(BinaryOp::Add),
+            BinaryOp::SubAssign => Some(BinaryOp::Sub),
+            BinaryOp::MulAssign => Some(BinaryOp::Mul),
+            BinaryOp::DivAssign => Some(BinaryOp::Div),
+            _ => None,
+        };
+
+        if let Some(arithmetic) = compound {
+            let target = self.place(&binary_op_node.lhs);
+            let rhs = self.operand(&binary_op_node.rhs);
+            let value = self.arithmetic(&arithmetic, &target, &lhs_type, &rhs, &rhs_type);
+            return format!("{target} = {value}");
+        }
+
+        let lhs = self.operand(&binary_op_node.lhs);
+        let rhs = self.operand(&binary_op_node.rhs);
+
+        match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+                self.arithmetic(op, &lhs, &lhs_type, &rhs, &rhs_type)
+            }
+            BinaryOp::BitOr | BinaryOp::BitAnd | BinaryOp::BitXor if lhs_type != "i32" => {
+                format!("{}__from_bits({lhs}.tag {op} {rhs}.tag)", ident(&lhs_type))
+            }
+            op => match (lhs_type.as_str(), rhs_type.as_str()) {
+                ("i32", "f32") => format!("(float){lhs} {op} {rhs}"),
+                ("f32", "i32") => format!("{lhs} {op} (float){rhs}"),
+                ("String", "String") => format!("meta_string_cmp({lhs}, {rhs}) {op} 0"),
+                (lhs_type, _) if !matches!(lhs_type, "i32" | "f32" | "bool" | "char") => {
+                    let check = equals(lhs_type, &lhs, &rhs);
+                    if *op == BinaryOp::Ne {
+                        format!("!{check}")
+                    } else {
+                        check
+                    }
+                }
+                _ => format!("{lhs} {op} {rhs}"),
+            },
+        }
+    }
+
+    fn arithmetic(
+        &mut self,
+        op: &BinaryOp,
+        lhs: &str,
+        lhs_type: &str,
+        rhs: &str,
+        rhs_type: &str,
+    ) -> String {
+        if lhs_type == "String" && *op == BinaryOp::Add {
+            return format!("meta_concat({lhs}, {})", to_string(rhs_type, rhs));
+        }
+
+        match (lhs_type, rhs_type) {
+            ("i32", "i32") => {
+                let helper = match op {
+                    BinaryOp::Add => "meta_add_i32",
+                    BinaryOp::Sub => "meta_sub_i32",
+                    BinaryOp::Mul => "meta_mul_i32",
+                    _ => "meta_div_i32",
+                };
+
+                format!("{helper}({lhs}, {rhs})")
+            }
+            ("i32", "f32") => format!("(float){lhs} {op} {rhs}"),
+            ("f32", "i32") => format!("{lhs} {op} (float){rhs}"),
+            _ => format!("{lhs} {op} {rhs}"),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+#ifndef META_RUNTIME_H
+#define META_RUNTIME_H
+
+#include <ctype.h>
+#include <math.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef const char *meta_string;
+typedef uint32_t meta_char;
+
+#if defined(__GNUC__)
+#define META_NORETURN __attribute__((noreturn))
+#else
+#define META_NORETURN
+#endif
+
+META_NORETURN static inline void meta_panic(const char *message) {
+    fprintf(stderr, "error: %s\n", message);
+    exit(1);
+}
+
+static inline void meta_assert(bool condition, const char *message) {
+    if (!condition) {
+        meta_panic(message);
+    }
+}
+
+static inline char *meta_alloc(size_t size) {
+    char *data = malloc(size);
+    if (data == NULL) {
+        meta_panic("out of memory");
+    }
+    return data;
+}
+
+static inline meta_string meta_concat(meta_string a, meta_string b) {
+    size_t a_len = strlen(a);
+    size_t b_len = strlen(b);
+    char *out = meta_alloc(a_len + b_len + 1);
+    memcpy(out, a, a_len);
+    memcpy(out + a_len, b, b_len + 1);
+    return out;
+}
+
+static inline bool meta_string_eq(meta_string a, meta_string b) {
+    return strcmp(a, b) == 0;
+}
+
+static inline int meta_string_cmp(meta_string a, meta_string b) {
+    return strcmp(a, b);
+}
+
+static inline meta_string meta_i32_to_string(int32_t value) {
+    char buffer[16];
+    snprintf(buffer, sizeof buffer, "%ld", (long)value);
+    return meta_concat(buffer, "");
+}
+
+static inline meta_string meta_f32_to_string(float value) {
+    char buffer[64];
+
+    if (isnan(value)) {
+        return "NaN";
+    }
+    if (isinf(value)) {
+        return value < 0 ? "-inf" : "inf";
+    }
+
+    for (int precision = 1; precision <= 9; precision++) {
+        snprintf(buffer, sizeof buffer, "%.*g", precision, (double)value);
+        if ((float)strtod(buffer, NULL) == value) {
+            break;
+        }
+    }
+
+    if (strpbrk(buffer, ".e") == NULL) {
+        strcat(buffer, ".0");
+    }
+    return meta_concat(buffer, "");
+}
+
+static inline meta_string meta_bool_to_string(bool value) {
+    return value ? "true" : "false";
+}
+
+static inline meta_string meta_char_to_string(meta_char c) {
+    char *out = meta_alloc(5);
+    if (c < 0x80) {
+        out[0] = (char)c;
+        out[1] = '\0';
+    } else if (c < 0x800) {
+        out[0] = (char)(0xC0 | (c >> 6));
+        out[1] = (char)(0x80 | (c & 0x3F));
+        out[2] = '\0';
+    } else if (c < 0x10000) {
+        out[0] = (char)(0xE0 | (c >> 12));
+        out[1] = (char)(0x80 | ((c >> 6) & 0x3F));
+        out[2] = (char)(0x80 | (c & 0x3F));
+        out[3] = '\0';
+    } else {
+        out[0] = (char)(0xF0 | (c >> 18));
+        out[1] = (char)(0x80 | ((c >> 12) & 0x3F));
+        out[2] = (char)(0x80 | ((c >> 6) & 0x3F));
+        out[3] = (char)(0x80 | (c & 0x3F));
+        out[4] = '\0';
+    }
+    return out;
+}
+
+static inline meta_char meta_utf8_next(meta_string *cursor) {
+    const unsigned char *s = (const unsigned char *)*cursor;
+    meta_char c = s[0];
+    int length = 1;
+    if (c >= 0xF0) {
+        c &= 0x07;
+        length = 4;
+    } else if (c >= 0xE0) {
+        c &= 0x0F;
+        length = 3;
+    } else if (c >= 0xC0) {
+        c &= 0x1F;
+        length = 2;
+    }
+
+    int i = 1;
+    while (i < length && (s[i] & 0xC0) == 0x80) {
+        c = (c << 6) | (s[i] & 0x3F);
+        i++;
+    }
+    *cursor += i;
+    return c;
+}
+
+static inline meta_string meta_unknown_variant(const char *name, size_t length,
+                                               meta_string expected) {
+    char *out = meta_alloc(length + 1);
+    memcpy(out, name, length);
+    out[length] = '\0';
+    return meta_concat(meta_concat("'", out), expected);
+}
+
+static inline int32_t meta_flags_parse(meta_string value, const char *const names[],
+                                       const int32_t bits[], size_t count,
+                                       meta_string expected) {
+    int32_t result = 0;
+    const char *start = value;
+
+    while (isspace((unsigned char)*start)) {
+        start++;
+    }
+    if (*start == '\0') {
+        return 0;
+    }
+
+    start = value;
+    for (;;) {
+        const char *end = strchr(start, '|');
+        if (end == NULL) {
+            end = start + strlen(start);
+        }
+
+        const char *first = start;
+        const char *last = end;
+        while (first < last && isspace((unsigned char)*first)) {
+            first++;
+        }
+        while (last > first && isspace((unsigned char)last[-1])) {
+            last--;
+        }
+
+        size_t length = (size_t)(last - first);
+        size_t i = 0;
+        while (i < count && (strlen(names[i]) != length || strncmp(names[i], first, length) != 0)) {
+            i++;
+        }
+        if (i == count) {
+            meta_panic(meta_unknown_variant(first, length, expected));
+        }
+
+        result |= bits[i];
+        if (*end == '\0') {
+            return result;
+        }
+        start = end + 1;
+    }
+}
+
+static inline meta_string meta_flags_names(int32_t value, const char *const names[],
+                                           const int32_t bits[], size_t count) {
+    meta_string s = "";
+    int32_t remaining = value;
+
+    for (size_t i = 0; i < count; i++) {
+        if (bits[i] == value) {
+            return names[i];
+        }
+    }
+    for (size_t i = 0; i < count; i++) {
+        if (bits[i] != 0 && (value & bits[i]) == bits[i] && (remaining & bits[i]) != 0) {
+            s = s[0] == '\0' ? names[i] : meta_concat(meta_concat(s, " | "), names[i]);
+            remaining &= ~bits[i];
+        }
+    }
+    return s;
+}
+
+static inline int32_t meta_add_i32(int32_t a, int32_t b) {
+    return (int32_t)((uint32_t)a + (uint32_t)b);
+}
+
+static inline int32_t meta_sub_i32(int32_t a, int32_t b) {
+    return (int32_t)((uint32_t)a - (uint32_t)b);
+}
+
+static inline int32_t meta_mul_i32(int32_t a, int32_t b) {
+    return (int32_t)((uint32_t)a * (uint32_t)b);
+}
+
+static inline int32_t meta_div_i32(int32_t a, int32_t b) {
+    if (b == 0) {
+        meta_panic("division by zero");
+    }
+    if (a == INT32_MIN && b == -1) {
+        return INT32_MIN;
+    }
+    return a / b;
+}
+
+#endif
@@ -0,0 +1,25 @@
+pub mod c;
+pub mod rust;
+
+use crate::expression::Expression;
+
+pub fn is_generic(expr: &Expression) -> bool {
+    match expr {
+        Expression::ProcDef(proc_def) => !proc_def.generics.is_empty(),
+        Expression::StructDef(struct_def) => !struct_def.generics.is_empty(),
+        _ => false,
+    }
+}
+
+pub fn mangle(name: &str) -> String {
+    let mut out = String::new();
+    for c in name.chars() {
+        match c {
+            '<' | ',' => out.push_str("__"),
+            c if c.is_alphanumeric() || c == '_' => out.push(c),
+            _ => {}
+        }
+    }
+
+    out
+}
@@ -0,0 +1,1826 @@
+use crate::{
+    builtins::{Capabilities, VERSION},
+    codegen::{is_generic, mangle},
+    diagnostics::Diagnostic,
+    executor::ENTRY_POINT,
+    expression::Expression,
+    nodes::{
+        map_types, vec_type, BinaryOp, BinaryOpNode, ClosureNode, ClosureSyntax, CoalesceNode,
+        CompareChainNode, ContractKind, EnumDefNode, FunCallNode, IfNode, MapCallNode, MatchNode,
+        PatternNode, ProcDefNode, ProcType, StructDefNode, TraitDefNode, VariableNode, VecCallNode,
+        VecMethod,
+    },
+    parser::UNKNOWN_TYPE,
+    token::{LiteralType, Position, Token},
+};
+
+const PRELUDE: &str = "#![allow(dead_code, unused_variables, unused_mut, unused_parens)]
+#![allow(unreachable_code, unreachable_patterns, unused_assignments)]
+#![allow(non_upper_case_globals, non_camel_case_types, non_snake_case)]
+";
+
+const LINES_HELPER: &str = "#[derive(Debug, Clone, PartialEq)]
+struct MetaLines(String);
+
+impl IntoIterator for MetaLines {
+    type Item = String;
+    type IntoIter = Box<dyn Iterator<Item = String>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match std::fs::File::open(&self.0) {
+            Ok(file) => Box::new(std::io::BufRead::lines(std::io::BufReader::new(file)).map_while(Result::ok)),
+            Err(err) => panic!(\"cannot read '{}': {err}\", self.0),
+        }
+    }
+}
+";
+
+const MAP_HELPER: &str = "#[derive(Debug, Clone, PartialEq)]
+struct MetaMap<K, V>(Vec<(K, V)>);
+
+impl<K: PartialEq + std::fmt::Display, V: Clone> MetaMap<K, V> {
+    fn new() -> Self {
+        MetaMap(Vec::new())
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((key, value)),
+        }
+    }
+
+    fn get(&self, key: K) -> V {
+        match self.0.iter().find(|(k, _)| *k == key) {
+            Some((_, v)) => v.clone(),
+            None => panic!(\"key '{}' is not in the map\", key),
+        }
+    }
+
+    fn try_get(&self, key: K) -> Option<V> {
+        self.0.iter().find(|(k, _)| *k == key).map(|(_, v)| v.clone())
+    }
+
+    fn remove(&mut self, key: K) -> V {
+        match self.0.iter().position(|(k, _)| *k == key) {
+            Some(slot) => self.0.remove(slot).1,
+            None => panic!(\"key '{}' is not in the map\", key),
+        }
+    }
+
+    fn try_remove(&mut self, key: K) -> Option<V> {
+        let slot = self.0.iter().position(|(k, _)| *k == key)?;
+        Some(self.0.remove(slot).1)
+    }
+
+    fn contains_key(&self, key: K) -> bool {
+        self.0.iter().any(|(k, _)| *k == key)
+    }
+
+    fn len(&self) -> i32 {
+        self.0.len() as i32
+    }
+}
+
+impl<K: 'static, V: 'static> IntoIterator for MetaMap<K, V> {
+    type Item = K;
+    type IntoIter = Box<dyn Iterator<Item = K>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.0.into_iter().map(|(k, _)| k))
+    }
+}
+
+impl<K: std::fmt::Display, V: std::fmt::Display> std::fmt::Display for MetaMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(\"{\")?;
+        for (i, (key, value)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(\", \")?;
+            }
+            write!(f, \"{key}: {value}\")?;
+        }
+        f.write_str(\"}\")
+    }
+}
+";
+
+const VEC_HELPER: &str = "#[derive(Debug, Clone, PartialEq)]
+struct MetaVec<T>(Vec<T>);
+
+impl<T: Clone> MetaVec<T> {
+    fn new() -> Self {
+        MetaVec(Vec::new())
+    }
+
+    fn slot(&self, index: i32) -> usize {
+        if index < 0 || index as usize >= self.0.len() {
+            panic!(
+                \"index {} is out of bounds for a Vec of length {}\",
+                index,
+                self.0.len()
+            );
+        }
+        index as usize
+    }
+
+    fn push(&mut self, item: T) {
+        self.0.push(item);
+    }
+
+    fn pop(&mut self) -> T {
+        match self.0.pop() {
+            Some(item) => item,
+            None => panic!(\"cannot pop from an empty Vec\"),
+        }
+    }
+
+    fn try_pop(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    fn get(&self, index: i32) -> T {
+        self.0[self.slot(index)].clone()
+    }
+
+    fn try_get(&self, index: i32) -> Option<T> {
+        if index < 0 {
+            return None;
+        }
+        self.0.get(index as usize).cloned()
+    }
+
+    fn set(&mut self, index: i32, item: T) {
+        let slot = self.slot(index);
+        self.0[slot] = item;
+    }
+
+    fn len(&self) -> i32 {
+        self.0.len() as i32
+    }
+}
+
+impl<T> IntoIterator for MetaVec<T> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for MetaVec<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(\"[\")?;
+        for (i, item) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(\", \")?;
+            }
+            write!(f, \"{item}\")?;
+        }
+        f.write_str(\"]\")
+    }
+}
+";
+
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "const", "do", "dyn", "extern", "final",
+    "fn", "gen", "loop", "macro", "mod", "move", "override", "priv", "pub", "ref", "static",
+    "trait", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "yield",
+];
+
+const ENTRY_FN: &str = "meta_main";
+
+#[derive(Debug, Default, Clone)]
+pub struct RustBackend {
+    natives: Vec<(String, String)>,
+}
+
+impl RustBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn native(mut self, name: &str, path: &str) -> Self {
+        self.natives.push((String::from(name), String::from(path)));
+        self
+    }
+
+    pub fn emit(&self, program: &[Expression]) -> Result<String, Vec<Diagnostic>> {
+        let mut emitter = Emitter {
+            backend: self,
+            errors: Vec::new(),
+            returns: None,
+            uses_lines: false,
+            uses_maps: false,
+            uses_vecs: false,
+            traits: program
+                .iter()
+                .filter_map(|expr| match expr {
+                    Expression::TraitDef(trait_def) => Some(trait_def.clone()),
+                    _ => None,
+                })
+                .collect(),
+        };
+
+        let mut out = String::from(PRELUDE);
+
+        let mut entry_returns = None;
+        for expr in program.iter().filter(|expr| !is_generic(expr)) {
+            if let Expression::ProcDef(proc_def) = expr {
+                if proc_def.name == ENTRY_POINT {
+                    entry_returns = Some(proc_def.return_type.is_some());
+                }
+            }
+
+            out.push('\n');
+            out.push_str(&emitter.item(expr, 0));
+        }
+
+        if emitter.uses_lines {
+            out.push('\n');
+            out.push_str(LINES_HELPER);
+        }
+
+        if emitter.uses_maps {
+            out.push('\n');
+            out.push_str(MAP_HELPER);
+        }
+
+        if emitter.uses_vecs {
+            out.push('\n');
+            out.push_str(VEC_HELPER);
+        }
+
+        match entry_returns {
+            Some(true) => out.push_str(&format!("\nfn main() {{\n    let _ = {ENTRY_FN}();\n}}\n")),
+            Some(false) => out.push_str(&format!("\nfn main() {{\n    {ENTRY_FN}();\n}}\n")),
+            None => emitter.errors.push(Diagnostic::error(
+                None,
+                format!("cannot emit Rust without a '{ENTRY_POINT}' procedure"),
+            )),
+        }
+
+        if !emitter.errors.is_empty() {
+            return Err(emitter.errors);
+        }
+
+        Ok(out)
+    }
+}
+
+fn ident(name: &str) -> String {
+    match name {
+        "crate" | "super" | "Self" | "clone" => format!("{name}_"),
+        name if name.contains('<') => mangle(name),
+        name if RUST_KEYWORDS.contains(&name) => format!("r#{name}"),
+        name => String::from(name),
+    }
+}
+
+fn is_copy(type_name: &str) -> bool {
+    matches!(type_name, "i32" | "f32" | "bool" | "char" | "None")
+}
+
+fn format_spec(type_name: &str) -> &'static str {
+    if type_name == "f32" {
+        return "{:?}";
+    }
+
+    "{}"
+}
+
+fn pad(depth: usize) -> String {
+    "    ".repeat(depth)
+}
+
+fn string_check(pattern: &PatternNode, subject: &str) -> String {
+    match pattern {
+        PatternNode::Literal(token, _) => format!("{subject} == {:?}", token.value),
+        PatternNode::Bind { pattern, .. } => string_check(pattern, subject),
+        PatternNode::Or(alternatives) => alternatives
+            .iter()
+            .map(|alternative| string_check(alternative, subject))
+            .collect::<Vec<_>>()
+            .join(" || "),
+        _ => String::from("true"),
+    }
+}
+
+fn unknown_variant(enum_def: &EnumDefNode) -> String {
+    let variants = enum_def
+        .variants
+        .iter()
+        .map(|v| v.name.as_str())
+        .collect::<Vec<_>>();
+
+    format!(
+        "'{{}}' is not a variant of '{}', expected one of: {}",
+        enum_def.type_name,
+        variants.join(", ")
+    )
+}
+
+struct Emitter<'a> {
+    backend: &'a RustBackend,
+    errors: Vec<Diagnostic>,
+    returns: Option<String>,
+    uses_lines: bool,
+    uses_maps: bool,
+    uses_vecs: bool,
+    traits: Vec<TraitDefNode>,
+}
+
+impl Emitter<'_> {
+    fn error(&mut self, position: Option<&Position>, message: String) {
+        let position = position.filter(|p| !p.filename.is_empty()).cloned();
+        self.errors.push(Diagnostic::error(position, message));
+    }
+
+    fn rust_type(&mut self, type_name: &str, position: Option<&Position>) -> String {
+        if let Some((key, value)) = map_types(type_name) {
+            self.uses_maps = true;
+            return format!(
+                "MetaMap<{}, {}>",
+                self.rust_type(&key, position),
+                self.rust_type(&value, position)
+            );
+        }
+
+        if let Some(element) = vec_type(type_name) {
+            self.uses_vecs = true;
+            return format!("MetaVec<{}>", self.rust_type(&element, position));
+        }
+
+        match type_name {
+            "i32" | "f32" | "bool" | "char" | "String" => String::from(type_name),
+            "None" => String::from("()"),
+            "Lines" => {
+                self.uses_lines = true;
+                String::from("MetaLines")
+            }
+            "any" => {
+                self.error(
+                    position,
+                    String::from("values of type 'any' cannot be emitted as Rust"),
+                );
+                String::from("()")
+            }
+            type_name if type_name.starts_with('{') => {
+                self.error(
+                    position,
+                    String::from("cannot emit a value whose type is unknown"),
+                );
+                String::from("()")
+            }
+            type_name if type_name.starts_with("proc(") => {
+                let Some(proc_type) = ProcType::parse(type_name) else {
+                    self.error(position, format!("malformed procedure type '{type_name}'"));
+                    return String::from("()");
+                };
+
+                let args = proc_type
+                    .args
+                    .iter()
+                    .map(|arg| self.rust_type(arg, position))
+                    .collect::<Vec<_>>();
+
+                let mut out = format!("std::rc::Rc<dyn Fn({})", args.join(", "));
+                if let Some(return_type) = proc_type.return_type.filter(|t| t != "None") {
+                    out.push_str(&format!(" -> {}", self.rust_type(&return_type, position)));
+                }
+                out.push('>');
+                out
+            }
+            type_name => ident(type_name),
+        }
+    }
+
+    fn item(&mut self, expr: &Expression, depth: usize) -> String {
+        match expr {
+            Expression::StructDef(struct_def) => self.struct_def(struct_def),
+            Expression::EnumDef(enum_def) => self.enum_def(enum_def),
+            Expression::TraitDef(trait_def) => self.trait_def(trait_def),
+            Expression::ImplStatement(impl_node) => {
+                let mut out = format!("impl {} {{\n", ident(&impl_node.struct_def.type_name));
+
+                for (i, procedure) in impl_node.procedures.iter().enumerate() {
+                    if i > 0 {
+                        out.push('\n');
+                    }
+
+                    if let Expression::ProcDef(proc_def) = procedure {
+                        out.push_str(&self.proc_def(proc_def, depth + 1));
+                    }
+                }
+
+                out.push_str("}\n");
+                out
+            }
+            Expression::ProcDef(proc_def) => self.proc_def(proc_def, depth),
+            expr => {
+                let statement = self.statement(expr, depth);
+                format!("{}{statement}\n", pad(depth))
+            }
+        }
+    }
+
+    // a trait becomes an enum over its implementors whose methods forward
+    // to the concrete impl, boxed since an implementor may hold the trait
+    fn trait_def(&mut self, trait_def: &TraitDefNode) -> String {
+        let name = ident(&trait_def.name);
+
+        let mut implementors = Vec::new();
+        for (type_name, _) in trait_def.dispatch.entries() {
+            if !implementors.contains(&type_name) {
+                implementors.push(type_name);
+            }
+        }
+
+        let mut out = String::from("#[derive(Debug, Clone, PartialEq)]\n");
+        out.push_str(&format!("enum {name} {{\n"));
+        for type_name in implementors.iter() {
+            let variant = ident(type_name);
+            out.push_str(&format!("    {variant}(Box<{variant}>),\n"));
+        }
+        out.push_str("}\n\n");
+
+        out.push_str(&format!("impl {name} {{\n"));
+        for (i, method) in trait_def.methods.iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+
+            let mut args = Vec::new();
+            let mut params = Vec::new();
+            for arg in method.args.iter() {
+                if arg.name == "self" {
+                    let receiver = if arg.mutable { "&mut self" } else { "self" };
+                    params.push(String::from(receiver));
+                } else {
+                    let type_name = self.rust_type(&arg.type_name, Some(&trait_def.position));
+                    params.push(format!("{}: {type_name}", ident(&arg.name)));
+                    args.push(ident(&arg.name));
+                }
+            }
+
+            out.push_str(&format!(
+                "    fn {}({})",
+                ident(&method.name),
+                params.join(", ")
+            ));
+            if let Some(return_type) = method.return_type.as_deref().filter(|t| *t != "None") {
+                let return_type = self.rust_type(return_type, Some(&trait_def.position));
+                out.push_str(&format!(" -> {return_type}"));
+            }
+            // an empty enum can only be matched on directly, not through a borrow
+            let mutable = method.args.first().is_some_and(|a| a.mutable);
+            if implementors.is_empty() && mutable {
+                out.push_str(" {\n        match *self {\n");
+            } else {
+                out.push_str(" {\n        match self {\n");
+            }
+
+            for type_name in implementors.iter() {
+                out.push_str(&format!(
+                    "            {name}::{}(value) => value.{}({}),\n",
+                    ident(type_name),
+                    ident(&method.name),
+                    args.join(", ")
+                ));
+            }
+
+            out.push_str("        }\n    }\n");
+        }
+        out.push_str("}\n\n");
+
+        out.push_str(&format!("impl std::fmt::Display for {name} {{\n"));
+        out.push_str("    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n");
+        out.push_str("        match self {\n");
+        for type_name in implementors.iter() {
+            out.push_str(&format!(
+                "            {name}::{}(value) => write!(f, \"{{}}\", value),\n",
+                ident(type_name)
+            ));
+        }
+        out.push_str("        }\n    }\n}\n");
+        out
+    }
+
+    fn struct_def(&mut self, struct_def: &StructDefNode) -> String {
+        let name = ident(&struct_def.type_name);
+
+        let mut out = String::from("#[derive(Debug, Clone, PartialEq)]\n");
+        out.push_str(&format!("struct {name} {{\n"));
+
+        for field in struct_def.fields.iter() {
+            if field.type_name.starts_with("proc(") {
+                self.error(
+                    Some(&struct_def.position),
+                    format!(
+                        "field '{}' of '{}' has type '{}', which cannot be emitted as Rust",
+                        field.name, struct_def.type_name, field.type_name
+                    ),
+                );
+            }
+
+            let type_name = self.rust_type(&field.type_name, Some(&struct_def.position));
+            out.push_str(&format!("    {}: {type_name},\n", ident(&field.name)));
+        }
+
+        out.push_str("}\n\n");
+
+        out.push_str(&format!("impl std::fmt::Display for {name} {{\n"));
+        out.push_str("    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n");
+        out.push_str(&format!(
+            "        write!(f, \"{} {{{{\")?;\n",
+            struct_def.type_name
+        ));
+
+        for (i, field) in struct_def.fields.iter().enumerate() {
+            let separator = if i > 0 { "," } else { "" };
+            out.push_str(&format!(
+                "        write!(f, \"{separator} {}: {}\", self.{})?;\n",
+                field.name,
+                format_spec(&field.type_name),
+                ident(&field.name)
+            ));
+        }
+
+        out.push_str("        write!(f, \" }}\")\n");
+        out.push_str("    }\n}\n");
+        out
+    }
+
+    fn enum_def(&mut self, enum_def: &EnumDefNode) -> String {
+        if enum_def.flags {
+            return self.flags_def(enum_def);
+        }
+
+        let name = ident(&enum_def.type_name);
+
+        let discriminants = if enum_def.is_fieldless() && !enum_def.variants.is_empty() {
+            enum_def.discriminants()
+        } else {
+            Vec::new()
+        };
+
+        let mut out = String::from("#[derive(Debug, Clone, PartialEq)]\n");
+        if !discriminants.is_empty() {
+            out.push_str("#[repr(i32)]\n");
+        }
+        out.push_str(&format!("enum {name} {{\n"));
+
+        for (variant, discriminant) in discriminants.iter() {
+            out.push_str(&format!("    {} = {discriminant},\n", ident(variant)));
+        }
+
+        for variant in enum_def
+            .variants
+            .iter()
+            .filter(|_| discriminants.is_empty())
+        {
+            if variant.fields.is_empty() {
+                out.push_str(&format!("    {},\n", ident(&variant.name)));
+                continue;
+            }
+
+            let fields = variant
+                .fields
+                .iter()
+                .map(|f| self.rust_type(&f.type_name, Some(&enum_def.position)))
+                .collect::<Vec<_>>();
+
+            out.push_str(&format!(
+                "    {}({}),\n",
+                ident(&variant.name),
+                fields.join(", ")
+            ));
+        }
+
+        out.push_str("}\n\n");
+
+        out.push_str(&format!("impl std::fmt::Display for {name} {{\n"));
+        out.push_str("    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n");
+        out.push_str("        match self {\n");
+
+        for variant in enum_def.variants.iter() {
+            let path = format!("{name}::{}", ident(&variant.name));
+            let label = format!("{}::{}", enum_def.type_name, variant.name);
+
+            if variant.fields.is_empty() {
+                out.push_str(&format!("            {path} => write!(f, \"{label}\"),\n"));
+                continue;
+            }
+
+            let bindings = (0..variant.fields.len())
+                .map(|i| format!("v{i}"))
+                .collect::<Vec<_>>();
+            let specs = variant
+                .fields
+                .iter()
+                .map(|f| format_spec(&f.type_name))
+                .collect::<Vec<_>>();
+
+            out.push_str(&format!(
+                "            {path}({}) => write!(f, \"{label}({})\", {}),\n",
+                bindings.join(", "),
+                specs.join(", "),
+                bindings.join(", ")
+            ));
+        }
+
+        out.push_str("        }\n    }\n}\n");
+
+        out.push_str(&format!("\nimpl {name} {{\n"));
+        out.push_str("    fn variant_name(&self) -> String {\n");
+        out.push_str("        String::from(match *self {\n");
+
+        for variant in enum_def.variants.iter() {
+            let pattern = if variant.fields.is_empty() || !discriminants.is_empty() {
+                ""
+            } else {
+                "(..)"
+            };
+
+            out.push_str(&format!(
+                "            {name}::{}{pattern} => \"{}\",\n",
+                ident(&variant.name),
+                variant.name
+            ));
+        }
+
+        out.push_str("        })\n    }\n");
+
+        if !discriminants.is_empty() {
+            out.push_str("\n    fn parse(value: String) -> Self {\n");
+            out.push_str("        match value.as_str() {\n");
+
+            for (variant, _) in discriminants.iter() {
+                out.push_str(&format!(
+                    "            \"{variant}\" => {name}::{},\n",
+                    ident(variant)
+                ));
+            }
+
+            out.push_str(&format!(
+                "            value => panic!(\"{}\", value),\n",
+                unknown_variant(enum_def)
+            ));
+            out.push_str("        }\n    }\n");
+
+            out.push_str("\n    fn from_i32(value: i32) -> Self {\n");
+            out.push_str("        match value {\n");
+
+            for (variant, discriminant) in discriminants.iter() {
+                out.push_str(&format!(
+                    "            {discriminant} => {name}::{},\n",
+                    ident(variant)
+                ));
+            }
+
+            out.push_str(&format!(
+                "            value => panic!(\"'{}' has no variant with discriminant {{}}\", value),\n",
+                enum_def.type_name
+            ));
+            out.push_str("        }\n    }\n");
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    fn flags_def(&mut self, enum_def: &EnumDefNode) -> String {
+        let name = ident(&enum_def.type_name);
+        let discriminants = enum_def.discriminants();
+
+        let mut out = String::from("#[derive(Debug, Clone, Copy, PartialEq, Eq)]\n");
+        out.push_str(&format!("struct {name}(i32);\n\n"));
+
+        out.push_str(&format!("impl {name} {{\n"));
+        for (variant, discriminant) in discriminants.iter() {
+            out.push_str(&format!(
+                "    const {}: {name} = {name}({discriminant});\n",
+                ident(variant)
+            ));
+        }
+        out.push_str(&format!(
+            "\n    fn contains(self, other: {name}) -> bool {{\n        self.0 & other.0 == other.0\n    }}\n"
+        ));
+        out.push_str("\n    fn from_i32(value: i32) -> Self {\n");
+        out.push_str(&format!(
+            "        if value & !{} != 0 {{\n",
+            enum_def.all_flags()
+        ));
+        out.push_str(&format!(
+            "            panic!(\"{{}} sets bits that are not flags of '{}'\", value);\n",
+            enum_def.type_name
+        ));
+        out.push_str(&format!("        }}\n        {name}(value)\n    }}\n"));
+
+        let variants = discriminants
+            .iter()
+            .map(|(variant, d)| format!("(\"{variant}\", {d})"))
+            .collect::<Vec<_>>();
+        let variants = format!(
+            "        const VARIANTS: [(&str, i32); {}] = [{}];\n",
+            variants.len(),
+            variants.join(", ")
+        );
+
+        out.push_str("\n    fn parse(value: String) -> Self {\n");
+        out.push_str(&variants);
+        out.push_str("        let mut bits = 0;\n        if !value.trim().is_empty() {\n");
+        out.push_str("            for part in value.split('|').map(str::trim) {\n");
+        out.push_str("                match VARIANTS.iter().find(|(name, _)| *name == part) {\n");
+        out.push_str("                    Some((_, d)) => bits |= d,\n");
+        out.push_str(&format!(
+            "                    None => panic!(\"{}\", part),\n",
+            unknown_variant(enum_def)
+        ));
+        out.push_str("                }\n            }\n        }\n");
+        out.push_str(&format!("        {name}(bits)\n    }}\n"));
+
+        out.push_str("\n    fn variant_name(&self) -> String {\n");
+        out.push_str(&variants);
+        out.push_str(
+            "        if let Some((name, _)) = VARIANTS.iter().find(|(_, d)| *d == self.0) {\n",
+        );
+        out.push_str("            return name.to_string();\n        }\n");
+        out.push_str("        let mut parts = Vec::new();\n");
+        out.push_str("        let mut remaining = self.0;\n");
+        out.push_str("        for (name, d) in VARIANTS.iter() {\n");
+        out.push_str("            if *d != 0 && self.0 & d == *d && remaining & d != 0 {\n");
+        out.push_str("                parts.push(*name);\n");
+        out.push_str("                remaining &= !d;\n            }\n        }\n");
+        out.push_str("        parts.join(\" | \")\n    }\n}\n");
+
+        for (trait_name, method, symbol) in [
+            ("BitOr", "bitor", "|"),
+            ("BitAnd", "bitand", "&"),
+            ("BitXor", "bitxor", "^"),
+        ] {
+            out.push_str(&format!(
+                "\nimpl std::ops::{trait_name} for {name} {{\n    type Output = {name};\n\n"
+            ));
+            out.push_str(&format!(
+                "    fn {method}(self, other: {name}) -> {name} {{\n        {name}(self.0 {symbol} other.0)\n    }}\n}}\n"
+            ));
+        }
+
+        let names = discriminants
+            .iter()
+            .map(|(variant, d)| format!("(\"{}::{variant}\", {d})", enum_def.type_name))
+            .collect::<Vec<_>>();
+
+        out.push_str(&format!("\nimpl std::fmt::Display for {name} {{\n"));
+        out.push_str("    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n");
+        out.push_str(&format!(
+            "        const NAMES: [(&str, i32); {}] = [{}];\n",
+            names.len(),
+            names.join(", ")
+        ));
+        out.push_str(
+            "        if let Some((name, _)) = NAMES.iter().find(|(_, d)| *d == self.0) {\n",
+        );
+        out.push_str("            return write!(f, \"{}\", name);\n        }\n");
+        out.push_str("        let mut parts = Vec::new();\n");
+        out.push_str("        let mut remaining = self.0;\n");
+        out.push_str("        for (name, d) in NAMES.iter() {\n");
+        out.push_str("            if *d != 0 && self.0 & d == *d && remaining & d != 0 {\n");
+        out.push_str("                parts.push(name.to_string());\n");
+        out.push_str("                remaining &= !d;\n            }\n        }\n");
+        out.push_str("        if remaining != 0 || parts.is_empty() {\n");
+        out.push_str(&format!(
+            "            parts.push(format!(\"{}({{}})\", remaining));\n        }}\n",
+            enum_def.type_name
+        ));
+        out.push_str("        write!(f, \"{}\", parts.join(\" | \"))\n    }\n}\n");
+
+        out
+    }
+
+    fn proc_def(&mut self, proc_def: &ProcDefNode, depth: usize) -> String {
+        if proc_def.native {
+            return String::new();
+        }
+
+        let name = if proc_def.name == ENTRY_POINT && depth == 0 {
+            String::from(ENTRY_FN)
+        } else {
+            ident(&proc_def.name)
+        };
+
+        let args = proc_def
+            .args
+            .iter()
+            .map(|arg| {
+                let mutability = if arg.mutable { "mut " } else { "" };
+                if arg.name == "self" {
+                    let borrow = if arg.mutable { "&" } else { "" };
+                    format!("{borrow}{mutability}self")
+                } else {
+                    let type_name = self.rust_type(&arg.type_name, Some(&proc_def.position));
+                    format!("{mutability}{}: {type_name}", ident(&arg.name))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let return_type = proc_def
+            .return_type
+            .as_deref()
+            .filter(|t| *t != "None")
+            .map(|t| self.rust_type(t, Some(&proc_def.position)));
+
+        let mut out = format!("{}fn {name}({})", pad(depth), args.join(", "));
+        if let Some(return_type) = &return_type {
+            out.push_str(&format!(" -> {return_type}"));
+        }
+        out.push_str(" {\n");
+
+        let inner = depth + 1;
+
+        for contract in proc_def
+            .contracts
+            .iter()
+            .filter(|c| c.kind == ContractKind::Requires)
+        {
+            let condition = self.value(&contract.condition, inner);
+            out.push_str(&format!(
+                "{}assert!({condition}, \"{{}}\", {:?});\n",
+                pad(inner),
+                format!("{} '{}' does not hold", contract.kind, contract.source)
+            ));
+        }
+
+        let ensures: Vec<_> = proc_def
+            .contracts
+            .iter()
+            .filter(|c| c.kind == ContractKind::Ensures)
+            .collect();
+
+        let body_depth = if ensures.is_empty() { inner } else { inner + 1 };
+        let returns = std::mem::replace(
+            &mut self.returns,
+            proc_def.return_type.clone().filter(|t| t != "None"),
+        );
+        let mut body = self.statements(&proc_def.statements, body_depth);
+
+        if return_type.is_some()
+            && !matches!(
+                proc_def.statements.last(),
+                Some(Expression::ReturnStatement(..))
+            )
+        {
+            body.push_str(&format!(
+                "{}panic!(\"'{}' did not return a value\");\n",
+                pad(body_depth),
+                proc_def.name
+            ));
+        }
+
+        if ensures.is_empty() {
+            out.push_str(&body);
+        } else {
+            let result_type = return_type.clone().unwrap_or_else(|| String::from("()"));
+            out.push_str(&format!(
+                "{}let result: {result_type} = (|| -> {result_type} {{\n",
+                pad(inner)
+            ));
+            out.push_str(&body);
+            out.push_str(&format!("{}}})();\n", pad(inner)));
+
+            for contract in ensures {
+                let condition = self.value(&contract.condition, inner);
+                out.push_str(&format!(
+                    "{}assert!({condition}, \"{{}}\", {:?});\n",
+                    pad(inner),
+                    format!("{} '{}' does not hold", contract.kind, contract.source)
+                ));
+            }
+
+            out.push_str(&format!("{}result\n", pad(inner)));
+        }
+
+        self.returns = returns;
+
+        out.push_str(&format!("{}}}\n", pad(depth)));
+        out
+    }
+
+    fn closure(&mut self, closure_node: &ClosureNode, depth: usize) -> String {
+        let proc_def = &closure_node.proc_def;
+
+        if closure_node.syntax == ClosureSyntax::Reference {
+            let name = if proc_def.native {
+                let mapped = self
+                    .backend
+                    .natives
+                    .iter()
+                    .find(|(name, _)| *name == proc_def.name)
+                    .map(|(_, path)| path.clone());
+
+                mapped.unwrap_or_else(|| {
+                    self.error(
+                        Some(&proc_def.position),
+                        format!("native procedure '{}' has no Rust mapping", proc_def.name),
+                    );
+                    ident(&proc_def.name)
+                })
+            } else if proc_def.name == ENTRY_POINT {
+                String::from(ENTRY_FN)
+            } else {
+                ident(&proc_def.name)
+            };
+
+            return format!("std::rc::Rc::new({name})");
+        }
+
+        let clones = closure_node
+            .captures
+            .iter()
+            .filter(|capture| !is_copy(&capture.type_name))
+            .map(|capture| format!("let {0} = {0}.clone();", ident(&capture.name)))
+            .collect::<Vec<_>>();
+
+        let base = if clones.is_empty() { depth } else { depth + 1 };
+
+        let args = proc_def
+            .args
+            .iter()
+            .map(|arg| {
+                let mutability = if arg.mutable { "mut " } else { "" };
+                let type_name = self.rust_type(&arg.type_name, Some(&proc_def.position));
+                format!("{mutability}{}: {type_name}", ident(&arg.name))
+            })
+            .collect::<Vec<_>>();
+
+        let return_type = proc_def
+            .return_type
+            .as_deref()
+            .filter(|t| *t != "None")
+            .map(|t| self.rust_type(t, Some(&proc_def.position)));
+
+        let mut out = format!("std::rc::Rc::new(move |{}|", args.join(", "));
+        if let Some(return_type) = &return_type {
+            out.push_str(&format!(" -> {return_type}"));
+        }
+        out.push_str(" {\n");
+
+        let returns = std::mem::replace(
+            &mut self.returns,
+            proc_def.return_type.clone().filter(|t| t != "None"),
+        );
+        out.push_str(&self.statements(&proc_def.statements, base + 1));
+
+        if return_type.is_some()
+            && !matches!(
+                proc_def.statements.last(),
+                Some(Expression::ReturnStatement(..))
+            )
+        {
+            out.push_str(&format!(
+                "{}panic!(\"'{}' did not return a value\");\n",
+                pad(base + 1),
+                proc_def.name
+            ));
+        }
+
+        self.returns = returns;
+
+        out.push_str(&format!("{}}})", pad(base)));
+
+        if clones.is_empty() {
+            return out;
+        }
+
+        let mut block = String::from("{\n");
+        for clone in clones.iter() {
+            block.push_str(&format!("{}{clone}\n", pad(depth + 1)));
+        }
+        block.push_str(&format!("{}{out}\n{}}}", pad(depth + 1), pad(depth)));
+        block
+    }
+
+    fn statements(&mut self, statements: &[Expression], depth: usize) -> String {
+        let mut out = String::new();
+        for statement in statements.iter() {
+            match statement {
+                Expression::ProcDef(..)
+                | Expression::StructDef(..)
+                | Expression::EnumDef(..)
+                | Expression::ImplStatement(..)
+                | Expression::TraitDef(..) => out.push_str(&self.item(statement, depth)),
+                statement => {
+                    let statement = self.statement(statement, depth);
+                    out.push_str(&format!("{}{statement}\n", pad(depth)));
+                }
+            }
+        }
+
+        out
+    }
+
+    fn block(&mut self, statements: &[Expression], depth: usize) -> String {
+        if statements.is_empty() {
+            return String::from("{}");
+        }
+
+        format!(
+            "{{\n{}{}}}",
+            self.statements(statements, depth + 1),
+            pad(depth)
+        )
+    }
+
+    fn statement(&mut self, expr: &Expression, depth: usize) -> String {
+        match expr {
+            Expression::IfStatement(if_node) => self.if_expr(if_node, depth, false),
+            Expression::WhileStatement(while_node) => {
+                let condition = self.value(&while_node.value, depth);
+                let block = self.block(&while_node.statements, depth);
+                format!("while {condition} {block}")
+            }
+            Expression::ForLoop(for_node) => {
+                let mut range = self.value(&for_node.range, depth);
+                if for_node.range.type_name() == "String" {
+                    range = format!("{range}.chars()");
+                }
+
+                let block = self.block(&for_node.statements, depth);
+                format!(
+                    "for {} in {range} {block}",
+                    ident(&for_node.counter.metadata.name)
+                )
+            }
+            Expression::MatchStatement(match_node) => self.match_expr(match_node, depth, None),
+            Expression::LetStatement(let_node) => {
+                let mutability = if let_node.mutable { "mut " } else { "" };
+
+                // the element type of an unannotated 'Vec()' is left to rustc
+                if vec_type(&let_node.type_name).as_deref() == Some(UNKNOWN_TYPE) {
+                    let value = self.value(&let_node.value, depth);
+                    return format!("let {mutability}{} = {value};", ident(&let_node.name));
+                }
+
+                let type_name = self.rust_type(&let_node.type_name, Some(&let_node.position));
+                let value = self.coerce(&let_node.type_name, &let_node.value, depth);
+                format!(
+                    "let {mutability}{}: {type_name} = {value};",
+                    ident(&let_node.name)
+                )
+            }
+            Expression::AssignStatement(assign_node) => {
+                let value = self.coerce(
+                    &assign_node.value.metadata.type_name,
+                    &assign_node.new_value,
+                    depth,
+                );
+                format!("{} = {value};", ident(&assign_node.value.metadata.name))
+            }
+            Expression::ReturnStatement(return_node) => {
+                if let Some(return_type) = self.returns.clone() {
+                    let value = self.coerce(&return_type, &return_node.value, depth);
+                    return format!("return {value};");
+                }
+
+                if let Expression::Literal(..) = return_node.value.as_ref() {
+                    return String::from("return;");
+                }
+
+                let value = self.value(&return_node.value, depth);
+                format!("let _ = {value};\n{}return;", pad(depth))
+            }
+            Expression::BreakStatement(..) => String::from("break;"),
+            Expression::ContinueStatement(..) => String::from("continue;"),
+            Expression::StructFieldAssign(field_assign_node) => {
+                let mut target = ident(&field_assign_node.struct_instance.metadata.name);
+                for field in field_assign_node.fields.iter() {
+                    target.push('.');
+                    target.push_str(&ident(&field.metadata.name));
+                }
+
+                let value = match field_assign_node.fields.last() {
+                    Some(field) => self.coerce(
+                        &field.metadata.type_name,
+                        &field_assign_node.new_value,
+                        depth,
+                    ),
+                    None => self.value(&field_assign_node.new_value, depth),
+                };
+                format!("{target} = {value};")
+            }
+            Expression::Error(position) => {
+                self.error(
+                    Some(position),
+                    String::from("code that failed to parse cannot be emitted as Rust"),
+                );
+                String::new()
+            }
+            Expression::BinaryOp(binary_op_node) if binary_op_node.op.is_update() => {
+                format!("{};", self.binary_op(binary_op_node, depth))
+            }
+            expr => format!("{};", self.value(expr, depth)),
+        }
+    }
+
+    fn pattern(&mut self, pattern: &PatternNode) -> Option<String> {
+        let pattern = match pattern {
+            PatternNode::Literal(_, LiteralType::String) => return None,
+            PatternNode::Literal(token, lt) => self.literal(token, *lt),
+            PatternNode::Range { start, end, kind } => {
+                format!(
+                    "{}..{}",
+                    self.literal(start, *kind),
+                    self.literal(end, *kind)
+                )
+            }
+            PatternNode::Wildcard => String::from("_"),
+            PatternNode::Binding(binding) => ident(&binding.name),
+            PatternNode::Bind { binding, pattern } => {
+                let inner = self.pattern(pattern)?;
+                if let PatternNode::Or(..) = pattern.as_ref() {
+                    format!("{} @ ({inner})", ident(&binding.name))
+                } else {
+                    format!("{} @ {inner}", ident(&binding.name))
+                }
+            }
+            PatternNode::Variant {
+                type_name,
+                variant,
+                bindings,
+            } => {
+                let path = format!("{}::{}", ident(type_name), ident(variant));
+                if bindings.is_empty() {
+                    path
+                } else {
+                    let names = bindings
+                        .iter()
+                        .map(|b| ident(&b.name))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!("{path}({names})")
+                }
+            }
+            PatternNode::Or(alternatives) => alternatives
+                .iter()
+                .map(|alternative| self.pattern(alternative))
+                .collect::<Option<Vec<_>>>()?
+                .join(" | "),
+        };
+
+        Some(pattern)
+    }
+
+    fn match_expr(&mut self, match_node: &MatchNode, depth: usize, tail: Option<bool>) -> String {
+        let value = self.value(&match_node.value, depth);
+
+        let mut out = format!("match {value} {{\n");
+        let arm_depth = depth + 1;
+
+        let mut catch_all = false;
+        for arm in match_node.arms.iter() {
+            let mut guard = arm.guard.as_ref().map(|guard| self.value(guard, arm_depth));
+
+            let pattern = if let Some(pattern) = self.pattern(&arm.pattern) {
+                pattern
+            } else {
+                let subject = arm
+                    .pattern
+                    .bindings()
+                    .first()
+                    .map_or_else(|| String::from("__value"), |b| ident(&b.name));
+
+                let mut inner = &arm.pattern;
+                while let PatternNode::Bind { pattern, .. } = inner {
+                    inner = pattern;
+                }
+
+                let mut check = string_check(&arm.pattern, &subject);
+                if let PatternNode::Or(..) = inner {
+                    check = format!("({check})");
+                }
+
+                guard = Some(match guard {
+                    Some(guard) => format!("{check} && ({guard})"),
+                    None => check,
+                });
+                subject
+            };
+
+            if guard.is_none() && arm.pattern.is_irrefutable() {
+                catch_all = true;
+            }
+
+            let body = match tail {
+                Some(valued) => self.tail_block(&arm.statements, arm_depth, valued),
+                None => self.block(&arm.statements, arm_depth),
+            };
+
+            match guard {
+                Some(guard) => out.push_str(&format!(
+                    "{}{pattern} if {guard} => {body}\n",
+                    pad(arm_depth)
+                )),
+                None => out.push_str(&format!("{}{pattern} => {body}\n", pad(arm_depth))),
+            }
+        }
+
+        if !catch_all {
+            out.push_str(&format!(
+                "{}_ => panic!(\"no match arm matched\"),\n",
+                pad(arm_depth)
+            ));
+        }
+
+        out.push_str(&format!("{}}}", pad(depth)));
+        out
+    }
+
+    fn if_expr(&mut self, if_node: &IfNode, depth: usize, valued: bool) -> String {
+        let condition = self.value(&if_node.value, depth);
+        let mut out = format!(
+            "if {condition} {}",
+            self.branch(&if_node.statements, depth, valued)
+        );
+
+        if let Some(else_node) = &if_node.else_branch {
+            let otherwise = match else_node.statements.as_slice() {
+                [Expression::IfStatement(nested)] => self.if_expr(nested, depth, valued),
+                statements => self.branch(statements, depth, valued),
+            };
+            out.push_str(&format!(" else {otherwise}"));
+        }
+
+        out
+    }
+
+    fn branch(&mut self, statements: &[Expression], depth: usize, valued: bool) -> String {
+        if !valued {
+            return self.block(statements, depth);
+        }
+
+        if statements.is_empty() {
+            return String::from("{ panic!(\"if branch produced no value\") }");
+        }
+
+        self.tail_block(statements, depth, valued)
+    }
+
+    fn tail_block(&mut self, statements: &[Expression], depth: usize, valued: bool) -> String {
+        let Some((last, rest)) = statements.split_last() else {
+            if valued {
+                return String::from("panic!(\"match arm produced no value\"),");
+            }
+
+            return String::from("{}");
+        };
+
+        let mut out = String::from("{\n");
+        out.push_str(&self.statements(rest, depth + 1));
+
+        let last = match last {
+            Expression::ReturnStatement(..)
+            | Expression::BreakStatement(..)
+            | Expression::ContinueStatement(..)
+            | Expression::LetStatement(..)
+            | Expression::AssignStatement(..)
+            | Expression::StructFieldAssign(..) => self.statement(last, depth + 1),
+            last => self.value(last, depth + 1),
+        };
+
+        out.push_str(&format!("{}{last}\n{}}}", pad(depth + 1), pad(depth)));
+        out
+    }
+
+    fn literal(&mut self, token: &Token, lt: LiteralType) -> String {
+        match lt {
+            LiteralType::String => format!("String::from({:?})", token.value),
+            LiteralType::Char => match token.value.chars().next() {
+                Some(c) => format!("{c:?}"),
+                None => String::from("'\\0'"),
+            },
+            LiteralType::Float => {
+                if token.value.contains(['.', 'e', 'E']) {
+                    format!("{}_f32", token.value)
+                } else {
+                    format!("{}.0_f32", token.value)
+                }
+            }
+            LiteralType::None => String::from("()"),
+            LiteralType::Number | LiteralType::Bool => token.value.to_string(),
+        }
+    }
+
+    fn is_mapped(&self, name: &str) -> bool {
+        self.backend
+            .natives
+            .iter()
+            .any(|(native, _)| native == name)
+    }
+
+    fn builtin(&mut self, fun_call_node: &FunCallNode, depth: usize) -> Option<String> {
+        let capabilities = Capabilities::new("rust");
+
+        match fun_call_node.proc_def.name.as_str() {
+            "version" => Some(format!("String::from({VERSION:?})")),
+            "backend" => Some(format!("String::from({:?})", capabilities.backend)),
+            "read_lines" => {
+                self.uses_lines = true;
+                let path = self.value(&fun_call_node.args.first()?.value, depth);
+                Some(format!("MetaLines({path})"))
+            }
+            "has_feature" => {
+                let arg = fun_call_node.args.first()?;
+                if let Expression::Literal(token, LiteralType::String) = arg.value.as_ref() {
+                    return Some(capabilities.has_feature(&token.value).to_string());
+                }
+
+                let features = capabilities
+                    .features
+                    .iter()
+                    .map(|f| format!("{f:?}"))
+                    .collect::<Vec<_>>();
+
+                Some(format!(
+                    "matches!({}.as_str(), {})",
+                    self.operand(&arg.value, depth),
+                    features.join(" | ")
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    fn args(&mut self, args: &[VariableNode], depth: usize) -> Vec<String> {
+        args.iter()
+            .map(|arg| self.coerce(&arg.metadata.type_name, &arg.value, depth))
+            .collect()
+    }
+
+    // values flowing into a trait typed slot get wrapped in the trait's enum
+    fn coerce(&mut self, target: &str, expr: &Expression, depth: usize) -> String {
+        let value = self.value(expr, depth);
+        let type_name = expr.type_name();
+        if type_name == target {
+            return value;
+        }
+
+        let Some(trait_def) = self.traits.iter().find(|t| t.name == target) else {
+            return value;
+        };
+
+        if !trait_def.dispatch.implements(&type_name) {
+            self.error(
+                expr.position(),
+                format!("'{type_name}' does not implement '{target}'"),
+            );
+        }
+
+        format!(
+            "{}::{}(Box::new({value}))",
+            ident(target),
+            ident(&type_name)
+        )
+    }
+
+    fn place(&mut self, expr: &Expression, depth: usize) -> String {
+        match expr {
+            Expression::Variable(variable_node) => ident(&variable_node.metadata.name),
+            Expression::StructFieldAccess(field_access_node) => {
+                let base = self.place(&field_access_node.base, depth);
+                format!("{base}.{}", ident(&field_access_node.field.metadata.name))
+            }
+            expr => self.operand(expr, depth),
+        }
+    }
+
+    fn operand(&mut self, expr: &Expression, depth: usize) -> String {
+        let value = self.value(expr, depth);
+
+        if let Expression::BinaryOp(..) = expr {
+            return format!("({value})");
+        }
+
+        value
+    }
+
+    fn value(&mut self, expr: &Expression, depth: usize) -> String {
+        match expr {
+            Expression::Variable(..) | Expression::StructFieldAccess(..) => {
+                let place = self.place(expr, depth);
+                if is_copy(&expr.type_name()) {
+                    place
+                } else {
+                    format!("{place}.clone()")
+                }
+            }
+            Expression::Literal(token, lt) => self.literal(token, *lt),
+            Expression::RangeStatement(range_node) => {
+                let start = self.operand(&range_node.start, depth);
+                let end = self.operand(&range_node.end, depth);
+                let op = if range_node.inclusive { "..=" } else { ".." };
+                format!("{start}{op}{end}")
+            }
+            Expression::Pipe(pipe_node) => self.value(&pipe_node.call, depth),
+            Expression::Coalesce(coalesce_node) => self.coalesce(coalesce_node, depth),
+            Expression::CompareChain(compare_chain_node) => {
+                self.compare_chain(compare_chain_node, depth)
+            }
+            Expression::FunCall(fun_call_node) => {
+                let proc_def = &fun_call_node.proc_def;
+
+                if proc_def.native && !self.is_mapped(&proc_def.name) {
+                    if let Some(value) = self.builtin(fun_call_node, depth) {
+                        return value;
+                    }
+                }
+
+                let name = if proc_def.native {
+                    let mapped = self
+                        .backend
+                        .natives
+                        .iter()
+                        .find(|(name, _)| *name == proc_def.name)
+                        .map(|(_, path)| path.clone());
+
+                    match mapped {
+                        Some(path) => path,
+                        None => {
+                            self.error(
+                                Some(&fun_call_node.position),
+                                format!("native procedure '{}' has no Rust mapping", proc_def.name),
+                            );
+                            ident(&proc_def.name)
+                        }
+                    }
+                } else if proc_def.name == ENTRY_POINT {
+                    String::from(ENTRY_FN)
+                } else {
+                    ident(&proc_def.name)
+                };
+
+                let args = self.args(&fun_call_node.args, depth);
+
+                format!("{name}({})", args.join(", "))
+            }
+            Expression::Closure(closure_node) => self.closure(closure_node, depth),
+            Expression::ClosureCall(closure_call_node) => {
+                let callee = self.place(&closure_call_node.callee, depth);
+                let args = closure_call_node
+                    .args
+                    .iter()
+                    .map(|arg| self.value(arg, depth))
+                    .collect::<Vec<_>>();
+
+                format!("{callee}({})", args.join(", "))
+            }
+            Expression::ImplFunCall(impl_fun_call_node) => {
+                let Expression::FunCall(fun_call_node) = impl_fun_call_node.fun_call_node.as_ref()
+                else {
+                    return String::new();
+                };
+
+                let mutates = fun_call_node
+                    .proc_def
+                    .args
+                    .first()
+                    .is_some_and(|a| a.mutable);
+                let mut args = fun_call_node
+                    .args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| match i {
+                        0 if impl_fun_call_node.method && mutates => self.place(&arg.value, depth),
+                        _ => self.coerce(&arg.metadata.type_name, &arg.value, depth),
+                    })
+                    .collect::<Vec<_>>();
+
+                let name = ident(&fun_call_node.proc_def.name);
+
+                if impl_fun_call_node.method && !args.is_empty() {
+                    let receiver = args.remove(0);
+                    format!("{receiver}.{name}({})", args.join(", "))
+                } else {
+                    format!(
+                        "{}::{name}({})",
+                        ident(&impl_fun_call_node.impl_node.struct_def.type_name),
+                        args.join(", ")
+                    )
+                }
+            }
+            Expression::DynCall(dyn_call_node) => {
+                let fun_call_node = &dyn_call_node.fun_call_node;
+                let Some((receiver, args)) = fun_call_node.args.split_first() else {
+                    return String::new();
+                };
+
+                let receiver = if fun_call_node
+                    .proc_def
+                    .args
+                    .first()
+                    .is_some_and(|a| a.mutable)
+                {
+                    self.place(&receiver.value, depth)
+                } else {
+                    self.coerce(&dyn_call_node.trait_name, &receiver.value, depth)
+                };
+                let args = self.args(args, depth);
+
+                format!(
+                    "{receiver}.{}({})",
+                    ident(&fun_call_node.proc_def.name),
+                    args.join(", ")
+                )
+            }
+            Expression::MapNew(..) => {
+                self.uses_maps = true;
+                String::from("MetaMap::new()")
+            }
+            Expression::MapCall(map_call_node) => self.map_call(map_call_node, "", depth),
+            Expression::VecNew(..) => {
+                self.uses_vecs = true;
+                String::from("MetaVec::new()")
+            }
+            Expression::VecCall(vec_call_node) => self.vec_call(vec_call_node, "", depth),
+            Expression::StructInstance(struct_instance_node) => {
+                let fields = struct_instance_node
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let value = self.coerce(&field.metadata.type_name, &field.value, depth);
+                        format!("{}: {value}", ident(&field.metadata.name))
+                    })
+                    .collect::<Vec<_>>();
+
+                let name = ident(&struct_instance_node.struct_def.type_name);
+                if fields.is_empty() {
+                    return format!("{name} {{}}");
+                }
+
+                format!("{name} {{ {} }}", fields.join(", "))
+            }
+            Expression::EnumFrom(enum_from_node) => {
+                let value = self.value(&enum_from_node.value, depth);
+                format!(
+                    "{}::from_i32({value})",
+                    ident(&enum_from_node.enum_def.type_name)
+                )
+            }
+            Expression::EnumParse(enum_parse_node) => {
+                let value = self.value(&enum_parse_node.value, depth);
+                format!(
+                    "{}::parse({value})",
+                    ident(&enum_parse_node.enum_def.type_name)
+                )
+            }
+            Expression::VariantName(variant_name_node) => {
+                let value = self.operand(&variant_name_node.value, depth);
+                format!("{value}.variant_name()")
+            }
+            Expression::Cast(cast_node) => {
+                let value = self.operand(&cast_node.value, depth);
+                if cast_node.enum_def.flags {
+                    return format!("{value}.0");
+                }
+
+                format!("({value} as {})", cast_node.type_name)
+            }
+            Expression::Contains(contains_node) => {
+                let value = self.operand(&contains_node.value, depth);
+                let flag = self.value(&contains_node.flag, depth);
+                format!("{value}.contains({flag})")
+            }
+            Expression::EnumInstance(enum_instance_node) => {
+                let path = format!(
+                    "{}::{}",
+                    ident(&enum_instance_node.enum_def.type_name),
+                    ident(&enum_instance_node.variant)
+                );
+
+                if enum_instance_node.fields.is_empty() {
+                    return path;
+                }
+
+                let fields = enum_instance_node
+                    .fields
+                    .iter()
+                    .map(|field| self.value(&field.value, depth))
+                    .collect::<Vec<_>>();
+
+                format!("{path}({})", fields.join(", "))
+            }
+            Expression::BinaryOp(binary_op_node) if binary_op_node.op.is_update() => {
+                // rust assignments are unit, the update yields the new value like the interpreter
+                let update = self.binary_op(binary_op_node, depth);
+                let target = self.place(&binary_op_node.lhs, depth);
+                format!("{{ {update}; {target} }}")
+            }
+            Expression::BinaryOp(binary_op_node) => self.binary_op(binary_op_node, depth),
+            Expression::MatchStatement(match_node) => {
+                let valued = expr.type_name() != "None";
+                self.match_expr(match_node, depth, Some(valued))
+            }
+            Expression::IfStatement(if_node) if if_node.else_branch.is_some() => {
+                let valued = expr.type_name() != "None";
+                self.if_expr(if_node, depth, valued)
+            }
+            expr => {
+                let statement = self.statement(expr, depth);
+                format!("{{ {statement} }}")
+            }
+        }
+    }
+
+    fn binary_op(&mut self, binary_op_node: &BinaryOpNode, depth: usize) -> String {
+        let lhs_type = binary_op_node.lhs.type_name();
+        let rhs_type = binary_op_node.rhs.type_name();
+
+        let op = &binary_op_node.op;
+
+        if let BinaryOp::Inc | BinaryOp::Dec = op {
+            let target = self.place(&binary_op_node.lhs, depth);
+            let method = if *op == BinaryOp::Inc {
+                "wrapping_add"
+            } else {
+                "wrapping_sub"
+            };
+
+            if lhs_type == "f32" {
+                let symbol = if *op == BinaryOp::Inc { "+" } else { "-" };
+                return format!("{target} {symbol}= 1.0");
+            }
+
+            return format!("{target} = i32::{method}({target}, 1)");
+        }
+
+        let compound = match op {
+            BinaryOp::AddAssign => Some(BinaryOp::Add),
+            BinaryOp::SubAssign => Some(BinaryOp::Sub),
+            BinaryOp::MulAssign => Some(BinaryOp::Mul),
+            BinaryOp::DivAssign => Some(BinaryOp::Div),
+            _ => None,
+        };
+
+        if let Some(arithmetic) = compound {
+            let target = self.place(&binary_op_node.lhs, depth);
+            let current = self.value(&binary_op_node.lhs, depth);
+            let rhs = self.operand(&binary_op_node.rhs, depth);
+            let value = self.arithmetic(&arithmetic, &current, &lhs_type, &rhs, &rhs_type);
+            return format!("{target} = {value}");
+        }
+
+        let lhs = self.operand(&binary_op_node.lhs, depth);
+        let rhs = self.operand(&binary_op_node.rhs, depth);
+
+        match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div => {
+                self.arithmetic(op, &lhs, &lhs_type, &rhs, &rhs_type)
+            }
+            op => {
+                let (lhs, rhs) = match (lhs_type.as_str(), rhs_type.as_str()) {
+                    ("i32", "f32") => (format!("({lhs} as f32)"), rhs),
+                    ("f32", "i32") => (lhs, format!("({rhs} as f32)")),
+                    _ => (lhs, rhs),
+                };
+
+                format!("{lhs} {op} {rhs}")
+            }
+        }
+    }
+
+    fn map_call(&mut self, map_call_node: &MapCallNode, prefix: &str, depth: usize) -> String {
+        let receiver = self.place(&map_call_node.map, depth);
+        let (key, value) = map_types(&map_call_node.map.type_name()).unwrap_or_default();
+        let args: Vec<String> = map_call_node
+            .args
+            .iter()
+            .zip([key, value])
+            .map(|(arg, type_name)| self.coerce(&type_name, arg, depth))
+            .collect();
+
+        format!(
+            "{receiver}.{prefix}{}({})",
+            map_call_node.method,
+            args.join(", ")
+        )
+    }
+
+    fn vec_call(&mut self, vec_call_node: &VecCallNode, prefix: &str, depth: usize) -> String {
+        let receiver = self.place(&vec_call_node.vec, depth);
+        let element = vec_type(&vec_call_node.vec.type_name()).unwrap_or_default();
+        let params = match vec_call_node.method {
+            VecMethod::Push => vec![element],
+            VecMethod::Get => vec![String::from("i32")],
+            VecMethod::Set => vec![String::from("i32"), element],
+            VecMethod::Pop | VecMethod::Len => Vec::new(),
+        };
+
+        let args: Vec<String> = vec_call_node
+            .args
+            .iter()
+            .zip(params)
+            .map(|(arg, type_name)| self.coerce(&type_name, arg, depth))
+            .collect();
+
+        format!(
+            "{receiver}.{prefix}{}({})",
+            vec_call_node.method,
+            args.join(", ")
+        )
+    }
+
+    fn coalesce(&mut self, coalesce_node: &CoalesceNode, depth: usize) -> String {
+        let type_name = coalesce_node.value.type_name();
+
+        // lookups switch to their Option returning form, anything else is None only by type
+        let lookup = match coalesce_node.value.as_ref() {
+            Expression::MapCall(map_call_node) if map_call_node.method.is_lookup() => {
+                Some(self.map_call(map_call_node, "try_", depth))
+            }
+            Expression::VecCall(vec_call_node) if vec_call_node.method.is_lookup() => {
+                Some(self.vec_call(vec_call_node, "try_", depth))
+            }
+            _ => None,
+        };
+
+        if let Some(lookup) = lookup {
+            let fallback = self.coerce(&type_name, &coalesce_node.fallback, depth);
+            return format!("match {lookup} {{ Some(value) => value, None => {fallback} }}");
+        }
+
+        let value = self.value(&coalesce_node.value, depth);
+        if type_name == "None" {
+            let fallback = self.value(&coalesce_node.fallback, depth);
+            return format!("{{ {value}; {fallback} }}");
+        }
+
+        value
+    }
+
+    fn compare_chain(&mut self, compare_chain_node: &CompareChainNode, depth: usize) -> String {
+        let operands = &compare_chain_node.operands;
+
+        // every operand is bound once, later ones only when the chain is still true
+        let mut binds = Vec::new();
+        for (i, operand) in operands.iter().enumerate() {
+            let value = self.value(operand, depth);
+            binds.push(format!("let __cmp{i} = {value};"));
+        }
+
+        let links = compare_chain_node
+            .ops
+            .iter()
+            .enumerate()
+            .map(|(i, op)| {
+                let lhs_type = operands[i].type_name();
+                let rhs_type = operands[i + 1].type_name();
+                match (lhs_type.as_str(), rhs_type.as_str()) {
+                    ("i32", "f32") => format!("(__cmp{i} as f32) {op} __cmp{}", i + 1),
+                    ("f32", "i32") => format!("__cmp{i} {op} (__cmp{} as f32)", i + 1),
+                    _ => format!("__cmp{i} {op} __cmp{}", i + 1),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let Some((last, rest)) = links.split_last() else {
+            return String::from("true");
+        };
+
+        let mut chain = last.clone();
+        for (i, link) in rest.iter().enumerate().rev() {
+            chain = format!("{link} && {{ {} {chain} }}", binds[i + 2]);
+        }
+
+        format!("{{ {} {} {chain} }}", binds[0], binds[1])
+    }
+
+    fn arithmetic(
+        &mut self,
+        op: &BinaryOp,
+        lhs: &str,
+        lhs_type: &str,
+        rhs: &str,
+        rhs_type: &str,
+    ) -> String {
+        if lhs_type == "String" && *op == BinaryOp::Add {
+            return format!("format!(\"{{}}{}\", {lhs}, {rhs})", format_spec(rhs_type));
+        }
+
+        match (lhs_type, rhs_type) {
+            ("i32", "i32") => {
+                let method = match op {
+                    BinaryOp::Add => "wrapping_add",
+                    BinaryOp::Sub => "wrapping_sub",
+                    BinaryOp::Mul => "wrapping_mul",
+                    _ => "wrapping_div",
+                };
+
+                format!("i32::{method}({lhs}, {rhs})")
+            }
+            ("i32", "f32") => format!("({lhs} as f32) {op} {rhs}"),
+            ("f32", "i32") => format!("{lhs} {op} ({rhs} as f32)"),
+            _ => format!("{lhs} {op} {rhs}"),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use std::{fmt::Display, path::Path};
+
+use crate::{expression::Expression, token::Position, value::Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub file: String,
+    pub line: u32,
+}
+
+impl Breakpoint {
+    pub fn new(file: &str, line: u32) -> Self {
+        Self {
+            file: String::from(file),
+            line,
+        }
+    }
+
+    pub fn parse(spec: &str, default_file: &str) -> Option<Self> {
+        let (file, line) = match spec.rsplit_once(':') {
+            Some((file, line)) => (file, line),
+            None => (default_file, spec),
+        };
+
+        match line.trim().parse::<u32>() {
+            Ok(line) if line > 0 && !file.is_empty() => Some(Breakpoint::new(file, line)),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, position: &Position) -> bool {
+        position.row + 1 == self.line
+            && Path::new(&self.file).file_name() == Path::new(&position.filename).file_name()
+    }
+}
+
+impl Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}:{}", self.file, self.line))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugAction {
+    Continue,
+    Step,
+    Next,
+    Finish,
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    Entry,
+    Breakpoint,
+    Step,
+}
+
+pub struct DebugState<'a> {
+    pub reason: PauseReason,
+    pub position: &'a Position,
+    pub statement: &'a Expression,
+    pub call_stack: &'a [String],
+    pub scopes: &'a [Vec<(String, Value)>],
+    pub globals: &'a [(String, Value)],
+}
+
+impl DebugState<'_> {
+    pub fn depth(&self) -> usize {
+        self.call_stack.len()
+    }
+
+    pub fn locals(&self) -> Vec<(&str, &Value)> {
+        let mut locals: Vec<(&str, &Value)> = Vec::new();
+
+        for (name, value) in self.scopes.iter().flatten() {
+            locals.retain(|(n, _)| n != name);
+            locals.push((name, value));
+        }
+
+        locals
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&Value> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .chain(self.globals.iter().rev())
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v)
+    }
+}
+
+pub trait DebugHook {
+    fn breakpoints(&self) -> &[Breakpoint] {
+        &[]
+    }
+
+    fn pause(&mut self, state: &DebugState) -> DebugAction;
+}
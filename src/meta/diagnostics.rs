@@ -0,0 +1,380 @@
+use std::fmt::Display;
+
+use crate::{
+    analyzer::Lint,
+    json,
+    token::{Position, Span},
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => f.write_str("Error"),
+            Severity::Warning => f.write_str("Warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Edit {
+    pub position: Position,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Fix {
+    pub message: String,
+    pub edits: Vec<Edit>,
+}
+
+impl Fix {
+    pub fn apply(&self, source: &str) -> String {
+        apply_edits(source, &self.edits)
+    }
+}
+
+pub fn apply_edits(source: &str, edits: &[Edit]) -> String {
+    let mut offsets: Vec<(usize, &str)> = edits
+        .iter()
+        .filter_map(|edit| {
+            let line_start: usize = source
+                .split_inclusive('\n')
+                .take(edit.position.row as usize)
+                .map(str::len)
+                .sum();
+
+            let line = source.get(line_start..)?;
+            let column: usize = line
+                .chars()
+                .take(edit.position.column as usize)
+                .map(char::len_utf8)
+                .sum();
+
+            Some((line_start + column, edit.text.as_str()))
+        })
+        .collect();
+
+    offsets.sort_by_key(|(offset, _)| std::cmp::Reverse(*offset));
+
+    let mut output = String::from(source);
+    for (offset, text) in offsets {
+        output.insert_str(offset.min(output.len()), text);
+    }
+
+    output
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub position: Option<Position>,
+    pub message: String,
+    pub code: Option<&'static str>,
+    pub note: Option<String>,
+    pub fix: Option<Box<Fix>>,
+    pub span: Option<Span>,
+}
+
+impl Diagnostic {
+    pub fn error(position: Option<Position>, message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            position,
+            message,
+            code: None,
+            note: None,
+            fix: None,
+            span: None,
+        }
+    }
+
+    pub fn warning(position: Option<Position>, message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(position, message)
+        }
+    }
+
+    pub fn with_span(mut self, span: Option<Span>) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(position) = &self.position {
+            f.write_fmt(format_args!("<{position}> "))?;
+        }
+
+        f.write_fmt(format_args!("{}: {}", self.severity, self.message))?;
+
+        if let Some(code) = self.code {
+            f.write_fmt(format_args!(" [{code}]"))?;
+        }
+
+        if let Some(note) = &self.note {
+            f.write_fmt(format_args!("\n\tnote: {note}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Lint> for Diagnostic {
+    fn from(lint: Lint) -> Self {
+        Self {
+            severity: Severity::Warning,
+            position: Some(lint.position),
+            message: lint.message,
+            code: Some(lint.code.as_str()),
+            note: Some(String::from(lint.rationale)),
+            fix: None,
+            span: None,
+        }
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    color: bool,
+    format: OutputFormat,
+    sources: Vec<(String, String)>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self {
+            color: true,
+            format: OutputFormat::Human,
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn color(mut self, enabled: bool) -> Self {
+        self.color = enabled;
+        self
+    }
+
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn add_source(&mut self, filename: String, source: String) {
+        self.sources.push((filename, source));
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        match self.format {
+            OutputFormat::Human => self.render_human(diagnostic),
+            OutputFormat::Json => Renderer::render_json(diagnostic),
+        }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            return format!("{style}{text}{RESET}");
+        }
+
+        String::from(text)
+    }
+
+    fn source_line(&self, position: &Position) -> Option<String> {
+        let source = match self
+            .sources
+            .iter()
+            .rev()
+            .find(|(n, _)| *n == position.filename)
+        {
+            Some((_, source)) => source.clone(),
+            None => std::fs::read_to_string(&position.filename).ok()?,
+        };
+
+        source.lines().nth(position.row as usize).map(String::from)
+    }
+
+    fn render_human(&self, diagnostic: &Diagnostic) -> String {
+        let severity_style = match diagnostic.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+        };
+
+        let mut header = diagnostic.severity.to_string().to_lowercase();
+        if let Some(code) = diagnostic.code {
+            header.push_str(&format!("[{code}]"));
+        }
+
+        let mut output = format!(
+            "{}{}",
+            self.paint(&format!("{BOLD}{severity_style}"), &header),
+            self.paint(BOLD, &format!(": {}", diagnostic.message))
+        );
+
+        if let Some(position) = &diagnostic.position {
+            let line_number = (position.row + 1).to_string();
+            let gutter = " ".repeat(line_number.len());
+
+            output.push_str(&format!(
+                "\n{gutter}{} {position}",
+                self.paint(&format!("{BOLD}{BLUE}"), "-->")
+            ));
+
+            if let Some(line) = self.source_line(position) {
+                let (column, width) = match diagnostic.span {
+                    Some(span) if span.start.row == position.row && span.end > span.start => {
+                        let column = span.start.column as usize;
+                        let end = if span.end.row == span.start.row {
+                            span.end.column as usize
+                        } else {
+                            line.chars().count()
+                        };
+                        (column, end.saturating_sub(column).max(1))
+                    }
+                    _ => {
+                        let column = position.column as usize;
+                        (column, Renderer::span_width(&line, column))
+                    }
+                };
+                let padding: String = line
+                    .chars()
+                    .take(column)
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+
+                let bar = self.paint(&format!("{BOLD}{BLUE}"), "|");
+                output.push_str(&format!("\n{gutter} {bar}"));
+                output.push_str(&format!(
+                    "\n{} {bar} {line}",
+                    self.paint(&format!("{BOLD}{BLUE}"), &line_number)
+                ));
+                output.push_str(&format!(
+                    "\n{gutter} {bar} {padding}{}",
+                    self.paint(&format!("{BOLD}{severity_style}"), &"^".repeat(width))
+                ));
+            }
+
+            if let Some(note) = &diagnostic.note {
+                output.push_str(&format!(
+                    "\n{gutter} {} {}: {note}",
+                    self.paint(&format!("{BOLD}{BLUE}"), "="),
+                    self.paint(BOLD, "note")
+                ));
+            }
+
+            if let Some(fix) = &diagnostic.fix {
+                output.push_str(&format!(
+                    "\n{gutter} {} {}: {}",
+                    self.paint(&format!("{BOLD}{BLUE}"), "="),
+                    self.paint(BOLD, "help"),
+                    fix.message
+                ));
+            }
+        } else {
+            if let Some(note) = &diagnostic.note {
+                output.push_str(&format!("\n  = {}: {note}", self.paint(BOLD, "note")));
+            }
+
+            if let Some(fix) = &diagnostic.fix {
+                output.push_str(&format!(
+                    "\n  = {}: {}",
+                    self.paint(BOLD, "help"),
+                    fix.message
+                ));
+            }
+        }
+
+        output
+    }
+
+    pub fn span_width(line: &str, column: usize) -> usize {
+        let width = line
+            .chars()
+            .skip(column)
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .count();
+
+        width.max(1)
+    }
+
+    fn render_json(diagnostic: &Diagnostic) -> String {
+        let mut fields = vec![
+            format!(
+                "\"severity\":\"{}\"",
+                diagnostic.severity.to_string().to_lowercase()
+            ),
+            format!("\"message\":\"{}\"", json::escape(&diagnostic.message)),
+        ];
+
+        if let Some(position) = &diagnostic.position {
+            fields.push(format!("\"file\":\"{}\"", json::escape(&position.filename)));
+            fields.push(format!("\"line\":{}", position.row + 1));
+            fields.push(format!("\"column\":{}", position.column + 1));
+        }
+
+        if let Some(span) = diagnostic.span {
+            fields.push(format!("\"end_line\":{}", span.end.row + 1));
+            fields.push(format!("\"end_column\":{}", span.end.column + 1));
+        }
+
+        if let Some(code) = diagnostic.code {
+            fields.push(format!("\"code\":\"{code}\""));
+        }
+
+        if let Some(note) = &diagnostic.note {
+            fields.push(format!("\"note\":\"{}\"", json::escape(note)));
+        }
+
+        if let Some(fix) = &diagnostic.fix {
+            let edits = fix
+                .edits
+                .iter()
+                .map(|edit| {
+                    format!(
+                        "{{\"line\":{},\"column\":{},\"text\":\"{}\"}}",
+                        edit.position.row + 1,
+                        edit.position.column + 1,
+                        json::escape(&edit.text)
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            fields.push(format!(
+                "\"fix\":{{\"message\":\"{}\",\"edits\":[{}]}}",
+                json::escape(&fix.message),
+                edits.join(",")
+            ));
+        }
+
+        format!("{{{}}}", fields.join(","))
+    }
+}
@@ -0,0 +1,445 @@
+use std::{
+    cell::RefCell, fmt::Display, path::Path, rc::Rc, sync::atomic::AtomicBool, time::Duration,
+};
+
+use crate::{
+    diagnostics::Diagnostic,
+    error::RuntimeError,
+    executor::{Environment, Executor, ENTRY_POINT},
+    expression::Expression,
+    hooks::ExecutorHooks,
+    lexer::Lexer,
+    log::{self, Level, Logger},
+    native::NativeFn,
+    nodes::{EnumDefNode, ProcDefNode, StructDefNode},
+    parser::{assignable, Parser, Program},
+    token::Position,
+    value::{Value, ValueTypeError},
+};
+
+const SOURCE_FILENAME: &str = "<source>";
+
+#[derive(Debug)]
+pub enum EngineError {
+    Io(std::io::Error),
+    UndefinedProcedure(String),
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    TypeMismatch {
+        name: String,
+        expected: String,
+        found: String,
+    },
+    Conversion(ValueTypeError),
+    Compile(Vec<Diagnostic>),
+    Runtime(RuntimeError),
+}
+
+impl Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::Io(err) => f.write_fmt(format_args!("{err}")),
+            EngineError::UndefinedProcedure(name) => {
+                f.write_fmt(format_args!("undefined procedure '{name}'"))
+            }
+            EngineError::ArityMismatch {
+                name,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "'{name}' expects {expected} argument(s) found {found}"
+            )),
+            EngineError::TypeMismatch {
+                name,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "argument '{name}' expected '{expected}' found '{found}'"
+            )),
+            EngineError::Conversion(err) => f.write_fmt(format_args!("{err}")),
+            EngineError::Compile(diagnostics) => {
+                let errors: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+                f.write_str(&errors.join("\n"))
+            }
+            EngineError::Runtime(err) => f.write_fmt(format_args!("{err}")),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+impl From<std::io::Error> for EngineError {
+    fn from(err: std::io::Error) -> Self {
+        EngineError::Io(err)
+    }
+}
+
+impl From<RuntimeError> for EngineError {
+    fn from(err: RuntimeError) -> Self {
+        EngineError::Runtime(err)
+    }
+}
+
+impl From<ValueTypeError> for EngineError {
+    fn from(err: ValueTypeError) -> Self {
+        EngineError::Conversion(err)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SymbolKind {
+    Procedure,
+    Struct,
+    Enum,
+    Trait,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub file: String,
+    pub position: Position,
+}
+
+#[derive(Default)]
+pub struct Engine {
+    program: Program,
+    symbols: Vec<Symbol>,
+    mutable_globals: Vec<String>,
+    env: Environment,
+    diagnostics: Vec<Diagnostic>,
+    log_level: Option<Level>,
+    logger: Option<Rc<dyn Logger>>,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load_file<P: AsRef<Path> + Clone>(&mut self, path: P) -> Result<bool, EngineError> {
+        let file = path.as_ref().display().to_string();
+        let source = std::fs::read_to_string(path.as_ref())?;
+
+        Ok(self.load_parser(Parser::from_file(path, &source), file))
+    }
+
+    pub fn load_str(&mut self, name: &str, source: &str) -> bool {
+        let lexer = Lexer::new(source, String::from(name));
+        self.load_parser(Parser::new(lexer), String::from(name))
+    }
+
+    pub fn eval_file<P: AsRef<Path> + Clone>(&mut self, path: P) -> Result<Value, EngineError> {
+        let start = self.diagnostics.len();
+        let has_entry_point = self.load_file(path)?;
+        self.run_entry_point(has_entry_point, start)
+    }
+
+    pub fn eval_str(&mut self, source: &str) -> Result<Value, EngineError> {
+        let start = self.diagnostics.len();
+        let has_entry_point = self.load_str(SOURCE_FILENAME, source);
+        self.run_entry_point(has_entry_point, start)
+    }
+
+    fn run_entry_point(
+        &mut self,
+        has_entry_point: bool,
+        start: usize,
+    ) -> Result<Value, EngineError> {
+        let errors: Vec<Diagnostic> = self.diagnostics[start..]
+            .iter()
+            .filter(|d| d.is_error())
+            .cloned()
+            .collect();
+
+        if !errors.is_empty() {
+            return Err(EngineError::Compile(errors));
+        }
+
+        if has_entry_point {
+            return self.call(ENTRY_POINT, Vec::new());
+        }
+
+        Ok(Value::None)
+    }
+
+    pub fn symbol(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.iter().find(|s| s.name == name)
+    }
+
+    pub fn defined_in(&self, name: &str) -> Option<&str> {
+        self.symbol(name).map(|s| s.file.as_str())
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn procedure(&self, name: &str) -> Option<ProcDefNode> {
+        self.find_procedure(name)
+    }
+
+    pub fn struct_def(&self, name: &str) -> Option<&StructDefNode> {
+        self.program.iter().rev().find_map(|expr| match expr {
+            Expression::StructDef(struct_def_node) if struct_def_node.type_name == name => {
+                Some(struct_def_node)
+            }
+            _ => None,
+        })
+    }
+
+    pub fn enum_def(&self, name: &str) -> Option<&EnumDefNode> {
+        self.program.iter().rev().find_map(|expr| match expr {
+            Expression::EnumDef(enum_def_node) if enum_def_node.type_name == name => {
+                Some(enum_def_node)
+            }
+            _ => None,
+        })
+    }
+
+    pub fn step_limit(&self) -> Option<u64> {
+        self.env.step_limit
+    }
+
+    pub fn set_step_limit(&mut self, limit: Option<u64>) {
+        self.env.step_limit = limit;
+    }
+
+    pub fn backend(&self) -> &str {
+        &self.env.capabilities.backend
+    }
+
+    pub fn set_backend(&mut self, backend: &str) {
+        self.env.capabilities.backend = String::from(backend);
+    }
+
+    pub fn has_feature(&self, name: &str) -> bool {
+        self.env.capabilities.has_feature(name)
+    }
+
+    pub fn set_feature(&mut self, name: &str, enabled: bool) {
+        self.env.capabilities.set_feature(name, enabled);
+    }
+
+    pub fn native_timeout(&self) -> Option<Duration> {
+        self.env.native_timeout
+    }
+
+    // a timed native runs on a worker thread that is abandoned once the timeout passes
+    pub fn set_native_timeout(&mut self, timeout: Option<Duration>) {
+        self.env.native_timeout = timeout;
+    }
+
+    pub fn set_interrupt(&mut self, interrupt: Option<&'static AtomicBool>) {
+        self.env.interrupt = interrupt;
+    }
+
+    pub fn log_level(&self) -> Option<Level> {
+        self.log_level
+    }
+
+    // both only apply while this engine is parsing or running, other engines keep their own
+    pub fn set_log_level(&mut self, level: Option<Level>) {
+        self.log_level = level;
+    }
+
+    pub fn set_logger<L: Logger + 'static>(&mut self, logger: L) {
+        self.logger = Some(Rc::new(logger));
+    }
+
+    pub fn set_global<V: Into<Value>>(&mut self, name: &str, value: V) {
+        let value = value.into();
+
+        if let Some((_, global)) = self.env.globals.iter_mut().find(|(n, _)| n == name) {
+            *global = value;
+        } else {
+            self.env.globals.push((String::from(name), value));
+        }
+    }
+
+    // a mutable global can be reassigned by scripts and read back with get_global
+    pub fn set_global_mut<V: Into<Value>>(&mut self, name: &str, value: V) {
+        self.set_global(name, value);
+        if !self.mutable_globals.iter().any(|n| n == name) {
+            self.mutable_globals.push(String::from(name));
+        }
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<&Value> {
+        self.env
+            .globals
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn register_fn<F>(
+        &mut self,
+        name: &str,
+        params: &[&str],
+        return_type: Option<&str>,
+        func: F,
+    ) where
+        F: Fn(&[Value]) -> Value + Send + Sync + 'static,
+    {
+        self.env.natives.retain(|native| native.name != name);
+        self.env
+            .natives
+            .push(NativeFn::new(name, params, return_type, func));
+    }
+
+    pub fn register_hooks<H: ExecutorHooks + 'static>(&mut self, hooks: Rc<RefCell<H>>) {
+        self.env.hooks.push(hooks);
+    }
+
+    pub fn call(&mut self, name: &str, args: Vec<Value>) -> Result<Value, EngineError> {
+        let proc_def = self
+            .find_procedure(name)
+            .ok_or_else(|| EngineError::UndefinedProcedure(String::from(name)))?;
+
+        if proc_def.args.len() != args.len() {
+            return Err(EngineError::ArityMismatch {
+                name: String::from(name),
+                expected: proc_def.args.len(),
+                found: args.len(),
+            });
+        }
+
+        for (arg, value) in proc_def.args.iter().zip(args.iter()) {
+            if !assignable(&arg.type_name, &value.type_name(), &proc_def.generics) {
+                return Err(EngineError::TypeMismatch {
+                    name: arg.name.clone(),
+                    expected: arg.type_name.clone(),
+                    found: value.type_name(),
+                });
+            }
+        }
+
+        let _log = log::scope(self.log_level, self.logger.clone());
+        let result = Executor::execute_procedure(&proc_def, args, &mut self.env);
+        self.diagnostics.extend(result.diagnostics);
+
+        if let Some(err) = result.error {
+            return Err(EngineError::Runtime(err));
+        }
+
+        Ok(result.value)
+    }
+
+    fn load_parser(&mut self, mut parser: Parser, file: String) -> bool {
+        let _log = log::scope(self.log_level, self.logger.clone());
+
+        for (name, value) in self.env.globals.iter() {
+            if self.mutable_globals.contains(name) {
+                parser.declare_global_mut(name.clone(), value);
+            } else {
+                parser.declare_global(name.clone(), value);
+            }
+        }
+
+        for native in self.env.natives.iter() {
+            parser.declare_native(native);
+        }
+
+        parser.declare_definitions(&self.program);
+
+        let Ok(program) = parser.parse_program();
+        let diagnostics = parser.diagnostics();
+        self.diagnostics.extend_from_slice(diagnostics);
+
+        // a program with errors never reaches the executor
+        if diagnostics.iter().any(|d| d.is_error()) {
+            return false;
+        }
+
+        let mut symbols = Vec::new();
+        let mut duplicate = false;
+        let mut has_entry_point = false;
+        for expr in program.iter() {
+            let (name, kind, position) = match expr {
+                Expression::ProcDef(proc_def_node) if proc_def_node.is_instance() => continue,
+                Expression::StructDef(struct_def_node) if struct_def_node.is_instance() => continue,
+                Expression::ProcDef(proc_def_node) => {
+                    if proc_def_node.name == ENTRY_POINT {
+                        has_entry_point = true;
+                        continue;
+                    }
+
+                    (
+                        &proc_def_node.name,
+                        SymbolKind::Procedure,
+                        &proc_def_node.position,
+                    )
+                }
+                Expression::StructDef(struct_def_node) => (
+                    &struct_def_node.type_name,
+                    SymbolKind::Struct,
+                    &struct_def_node.position,
+                ),
+                Expression::EnumDef(enum_def_node) => (
+                    &enum_def_node.type_name,
+                    SymbolKind::Enum,
+                    &enum_def_node.position,
+                ),
+                Expression::TraitDef(trait_def) => {
+                    (&trait_def.name, SymbolKind::Trait, &trait_def.position)
+                }
+                _ => continue,
+            };
+
+            if let Some(symbol) = self.symbol(name) {
+                self.diagnostics.push(Diagnostic::error(
+                    Some(position.clone()),
+                    format!(
+                        "'{name}' in '{file}' is already defined in '{}'",
+                        symbol.file
+                    ),
+                ));
+                duplicate = true;
+                continue;
+            }
+
+            symbols.push(Symbol {
+                name: name.clone(),
+                kind,
+                file: file.clone(),
+                position: position.clone(),
+            });
+        }
+
+        // the whole file is rejected so earlier definitions keep resolving
+        if duplicate {
+            return false;
+        }
+
+        self.symbols.extend(symbols);
+        self.program.extend(program);
+
+        has_entry_point
+    }
+
+    fn find_procedure(&self, name: &str) -> Option<ProcDefNode> {
+        let script = self.program.iter().rev().find_map(|expr| {
+            if let Expression::ProcDef(proc_def_node) = expr {
+                if proc_def_node.name == name {
+                    return Some(proc_def_node.clone());
+                }
+            }
+
+            None
+        });
+
+        script.or_else(|| {
+            self.env
+                .natives
+                .iter()
+                .rev()
+                .find(|native| native.name == name)
+                .map(NativeFn::proc_def)
+        })
+    }
+}
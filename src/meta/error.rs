@@ -0,0 +1,251 @@
+use std::{fmt::Display, time::Duration};
+
+use crate::{
+    diagnostics::Diagnostic,
+    nodes::{BinaryOp, ContractKind},
+    token::Position,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    UndefinedVariable(String),
+    UndefinedField {
+        type_name: String,
+        field: String,
+    },
+    UndefinedMethod {
+        type_name: String,
+        method: String,
+    },
+    TypeMismatch {
+        op: BinaryOp,
+        lhs: String,
+        rhs: String,
+    },
+    DivisionByZero,
+    NoMatchingArm(String),
+    StepLimitExceeded(u64),
+    Aborted,
+    Interrupted {
+        hot_loop: Option<String>,
+    },
+    Unparsed,
+    ContractViolation {
+        kind: ContractKind,
+        condition: String,
+        call_site: Option<String>,
+    },
+    InvalidCondition(String),
+    InvalidRange {
+        start: String,
+        end: String,
+    },
+    NotIterable(String),
+    MissingKey(String),
+    IndexOutOfBounds {
+        index: i32,
+        len: usize,
+    },
+    EmptyVec,
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    InvalidCast {
+        from: String,
+        to: String,
+    },
+    InvalidDiscriminant {
+        type_name: String,
+        value: i32,
+    },
+    InvalidFlags {
+        type_name: String,
+        value: i32,
+    },
+    UnknownVariant {
+        type_name: String,
+        name: String,
+        variants: Box<[String]>,
+    },
+    UnregisteredNative(String),
+    NotCallable(String),
+    NotImplemented {
+        type_name: String,
+        trait_name: String,
+    },
+    Native {
+        name: String,
+        message: String,
+    },
+    HostTimeout {
+        name: String,
+        timeout: Duration,
+    },
+    AnyMismatch {
+        expected: String,
+        found: String,
+    },
+}
+
+impl Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeErrorKind::UndefinedVariable(name) => {
+                f.write_fmt(format_args!("undefined variable '{name}'"))
+            }
+            RuntimeErrorKind::UndefinedField { type_name, field } => {
+                f.write_fmt(format_args!("'{type_name}' has no field '{field}'"))
+            }
+            RuntimeErrorKind::UndefinedMethod { type_name, method } => {
+                f.write_fmt(format_args!("'{type_name}' has no method '{method}'"))
+            }
+            RuntimeErrorKind::TypeMismatch { op, lhs, rhs } => f.write_fmt(format_args!(
+                "mismatched types for '{op}': '{lhs}' and '{rhs}'"
+            )),
+            RuntimeErrorKind::DivisionByZero => f.write_str("division by zero"),
+            RuntimeErrorKind::NoMatchingArm(value) => {
+                f.write_fmt(format_args!("no match arm matches value '{value}'"))
+            }
+            RuntimeErrorKind::StepLimitExceeded(limit) => f.write_fmt(format_args!(
+                "exceeded the step limit of {limit} statements"
+            )),
+            RuntimeErrorKind::Aborted => f.write_str("execution aborted by the debugger"),
+            RuntimeErrorKind::Interrupted { .. } => f.write_str("execution interrupted"),
+            RuntimeErrorKind::Unparsed => f.write_str("cannot run code that failed to parse"),
+            RuntimeErrorKind::ContractViolation {
+                kind, condition, ..
+            } => f.write_fmt(format_args!("{kind} '{condition}' does not hold")),
+            RuntimeErrorKind::InvalidCondition(found) => f.write_fmt(format_args!(
+                "expected condition of type 'bool' found '{found}'"
+            )),
+            RuntimeErrorKind::InvalidRange { start, end } => f.write_fmt(format_args!(
+                "expected range bounds of type 'i32' found '{start}..{end}'"
+            )),
+            RuntimeErrorKind::NotIterable(type_name) => f.write_fmt(format_args!(
+                "values of type '{type_name}' cannot be iterated"
+            )),
+            RuntimeErrorKind::MissingKey(key) => {
+                f.write_fmt(format_args!("key '{key}' is not in the map"))
+            }
+            RuntimeErrorKind::IndexOutOfBounds { index, len } => f.write_fmt(format_args!(
+                "index {index} is out of bounds for a Vec of length {len}"
+            )),
+            RuntimeErrorKind::EmptyVec => f.write_str("cannot pop from an empty Vec"),
+            RuntimeErrorKind::ArityMismatch {
+                name,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "'{name}' expects {expected} argument(s) found {found}"
+            )),
+            RuntimeErrorKind::InvalidCast { from, to } => {
+                f.write_fmt(format_args!("cannot cast '{from}' as '{to}'"))
+            }
+            RuntimeErrorKind::InvalidDiscriminant { type_name, value } => f.write_fmt(
+                format_args!("'{type_name}' has no variant with discriminant {value}"),
+            ),
+            RuntimeErrorKind::InvalidFlags { type_name, value } => f.write_fmt(format_args!(
+                "{value} sets bits that are not flags of '{type_name}'"
+            )),
+            RuntimeErrorKind::UnknownVariant {
+                type_name,
+                name,
+                variants,
+            } => f.write_fmt(format_args!(
+                "'{name}' is not a variant of '{type_name}', expected one of: {}",
+                variants.join(", ")
+            )),
+            RuntimeErrorKind::UnregisteredNative(name) => {
+                f.write_fmt(format_args!("native procedure '{name}' is not registered"))
+            }
+            RuntimeErrorKind::NotCallable(type_name) => f.write_fmt(format_args!(
+                "values of type '{type_name}' cannot be called"
+            )),
+            RuntimeErrorKind::NotImplemented {
+                type_name,
+                trait_name,
+            } => f.write_fmt(format_args!(
+                "'{type_name}' does not implement '{trait_name}'"
+            )),
+            RuntimeErrorKind::Native { name, message } => {
+                f.write_fmt(format_args!("in native procedure '{name}': {message}"))
+            }
+            RuntimeErrorKind::HostTimeout { name, timeout } => f.write_fmt(format_args!(
+                "native procedure '{name}' exceeded its timeout of {} ms",
+                timeout.as_millis()
+            )),
+            RuntimeErrorKind::AnyMismatch { expected, found } => f.write_fmt(format_args!(
+                "'any' value of type '{found}' cannot be bound as '{expected}'"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub position: Option<Position>,
+    pub trace: Vec<String>,
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind, position: Option<Position>, trace: Vec<String>) -> Self {
+        Self {
+            kind,
+            position,
+            trace,
+        }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(position) = &self.position {
+            f.write_fmt(format_args!("<{position}> "))?;
+        }
+
+        f.write_fmt(format_args!("{}", self.kind))?;
+
+        for name in self.trace.iter() {
+            f.write_fmt(format_args!("\n\tat {name}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+impl From<RuntimeError> for Diagnostic {
+    fn from(err: RuntimeError) -> Self {
+        let mut diagnostic = Diagnostic::error(err.position, err.kind.to_string());
+
+        let mut notes = Vec::new();
+        if let RuntimeErrorKind::ContractViolation {
+            call_site: Some(call_site),
+            ..
+        } = &err.kind
+        {
+            notes.push(format!("called from {call_site}"));
+        }
+
+        if let RuntimeErrorKind::Interrupted {
+            hot_loop: Some(hot_loop),
+        } = &err.kind
+        {
+            notes.push(format!("inside the loop at {hot_loop}"));
+        }
+
+        if !err.trace.is_empty() {
+            notes.push(format!("call stack: {}", err.trace.join(" <- ")));
+        }
+
+        if !notes.is_empty() {
+            diagnostic.note = Some(notes.join(", "));
+        }
+
+        diagnostic
+    }
+}
@@ -1,43 +1,465 @@
-use std::path::Path;
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    path::Path,
+    rc::Rc,
+    sync::{
+        atomic::{self, AtomicBool},
+        Arc,
+    },
+    time::Duration,
+};
 
 use crate::{
+    analyzer::Analyzer,
+    builtins::Capabilities,
+    debugger::{DebugAction, DebugHook, DebugState, PauseReason},
+    diagnostics::Diagnostic,
+    error::{RuntimeError, RuntimeErrorKind},
     expression::Expression,
-    nodes::{ProcDefNode, StructInstanceNode, VarMetadataNode, VariableNode},
-    parser::{Parser, Program},
+    hooks::ExecutorHooks,
+    lexer::Lexer,
+    native::NativeFn,
+    nodes::{
+        vec_type, BinaryOp, ContractKind, GenericType, MapMethod, MatchNode, PatternNode,
+        ProcDefNode, VariableNode, VecMethod,
+    },
+    parser::{assignable, Parser, ParserOptions, Program, UNKNOWN_TYPE},
+    token::Position,
+    value::{
+        ClosureValue, EnumValue, FlagsValue, MapValue, RangeValue, StructValue, Value, VecValue,
+    },
 };
 
-const ENTRY_POINT: &str = "main";
+pub const ENTRY_POINT: &str = "main";
+
+type Items = Box<dyn Iterator<Item = Result<Value, RuntimeErrorKind>>>;
+pub const EVAL_FILENAME: &str = "<eval>";
+const EVAL_LINE_OFFSET: u32 = 1;
 
 pub struct Executor {}
 
-struct RuntimeVM {
-    pub variables: Vec<VariableNode>,
-    pub structs: Vec<StructInstanceNode>,
+enum Flow {
+    Next(Value),
+    Return(Value),
+    Break,
+    Continue,
+}
+
+struct Frame {
+    scopes: Vec<Vec<(String, Value)>>,
+}
+
+#[derive(Default, Clone)]
+pub struct Environment {
+    pub globals: Vec<(String, Value)>,
+    pub natives: Vec<NativeFn>,
+    pub capabilities: Capabilities,
+    pub step_limit: Option<u64>,
+    pub native_timeout: Option<Duration>,
+    pub interrupt: Option<&'static AtomicBool>,
+    pub hooks: Vec<Rc<RefCell<dyn ExecutorHooks>>>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ExecutionResult {
+    pub value: Value,
+    pub diagnostics: Vec<Diagnostic>,
+    pub error: Option<RuntimeError>,
 }
 
-impl RuntimeVM {
+impl ExecutionResult {
+    pub fn has_errors(&self) -> bool {
+        self.error.is_some() || self.diagnostics.iter().any(|d| d.is_error())
+    }
+
+    fn from_outcome(
+        outcome: Result<Value, RuntimeError>,
+        mut diagnostics: Vec<Diagnostic>,
+    ) -> Self {
+        match outcome {
+            Ok(value) => Self {
+                value,
+                diagnostics,
+                error: None,
+            },
+            Err(err) => {
+                diagnostics.push(Diagnostic::from(err.clone()));
+                Self {
+                    value: Value::None,
+                    diagnostics,
+                    error: Some(err),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum StepMode {
+    Entry,
+    Step,
+    Next(usize),
+    Finish(usize),
+    Continue,
+}
+
+struct Debugger<'a> {
+    hook: &'a mut dyn DebugHook,
+    mode: StepMode,
+}
+
+struct RuntimeVM<'a> {
+    frames: Vec<Frame>,
+    call_stack: Vec<String>,
+    position: Option<Position>,
+    loops: Vec<Position>,
+    steps: u64,
+    env: Environment,
+    debugger: Option<Debugger<'a>>,
+}
+
+impl<'a> RuntimeVM<'a> {
     fn new() -> Self {
+        Self::with_environment(Environment::default())
+    }
+
+    fn with_environment(env: Environment) -> Self {
         Self {
-            variables: Vec::new(),
-            structs: Vec::new(),
+            frames: Vec::new(),
+            call_stack: Vec::new(),
+            position: None,
+            loops: Vec::new(),
+            steps: 0,
+            env,
+            debugger: None,
+        }
+    }
+
+    fn with_debugger(hook: &'a mut dyn DebugHook) -> Self {
+        let mut memory = Self::new();
+        memory.debugger = Some(Debugger {
+            hook,
+            mode: StepMode::Entry,
+        });
+        memory
+    }
+
+    fn error(&self, kind: RuntimeErrorKind) -> RuntimeError {
+        let trace = self.call_stack.iter().rev().cloned().collect();
+        RuntimeError::new(kind, self.position.clone(), trace)
+    }
+
+    fn step(&mut self) -> Result<(), RuntimeError> {
+        self.steps += 1;
+
+        if let Some(limit) = self.env.step_limit {
+            if self.steps > limit {
+                return Err(self.error(RuntimeErrorKind::StepLimitExceeded(limit)));
+            }
+        }
+
+        if let Some(interrupt) = self.env.interrupt {
+            if interrupt.swap(false, atomic::Ordering::SeqCst) {
+                let hot_loop = self.loops.last().map(|p| p.to_string());
+                return Err(self.error(RuntimeErrorKind::Interrupted { hot_loop }));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn notify<F>(&self, mut callback: F)
+    where
+        F: FnMut(&mut dyn ExecutorHooks),
+    {
+        for hooks in self.env.hooks.iter() {
+            callback(&mut *hooks.borrow_mut());
         }
     }
+
+    fn pause(&mut self, statement: &Expression) -> Result<(), RuntimeError> {
+        let (Some(debugger), Some(position)) = (self.debugger.as_mut(), statement.position())
+        else {
+            return Ok(());
+        };
+
+        let depth = self.call_stack.len();
+        let breakpoint = debugger
+            .hook
+            .breakpoints()
+            .iter()
+            .any(|b| b.matches(position));
+
+        let reason = match debugger.mode {
+            StepMode::Entry => PauseReason::Entry,
+            _ if breakpoint => PauseReason::Breakpoint,
+            StepMode::Step => PauseReason::Step,
+            StepMode::Next(from) if depth <= from => PauseReason::Step,
+            StepMode::Finish(from) if depth < from => PauseReason::Step,
+            _ => return Ok(()),
+        };
+
+        let state = DebugState {
+            reason,
+            position,
+            statement,
+            call_stack: &self.call_stack,
+            scopes: self.frames.last().map_or(&[], |frame| &frame.scopes),
+            globals: &self.env.globals,
+        };
+
+        debugger.mode = match debugger.hook.pause(&state) {
+            DebugAction::Continue => StepMode::Continue,
+            DebugAction::Step => StepMode::Step,
+            DebugAction::Next => StepMode::Next(depth),
+            DebugAction::Finish => StepMode::Finish(depth),
+            DebugAction::Abort => {
+                self.position = Some(position.clone());
+                return Err(self.error(RuntimeErrorKind::Aborted));
+            }
+        };
+
+        Ok(())
+    }
+
+    fn push_frame(&mut self) {
+        self.frames.push(Frame {
+            scopes: vec![Vec::new()],
+        });
+    }
+
+    fn pop_frame(&mut self) {
+        self.frames.pop();
+    }
+
+    fn push_scope(&mut self) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.scopes.push(Vec::new());
+        }
+    }
+
+    fn pop_scope(&mut self) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.scopes.pop();
+        }
+    }
+
+    fn declare(&mut self, name: String, value: Value) {
+        if let Some(scope) = self
+            .frames
+            .last_mut()
+            .and_then(|frame| frame.scopes.last_mut())
+        {
+            scope.push((name, value));
+        }
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        self.frames.last().is_some_and(|frame| {
+            frame
+                .scopes
+                .iter()
+                .any(|scope| scope.iter().any(|(n, _)| n == name))
+        })
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Value> {
+        let local = self.frames.last().and_then(|frame| {
+            frame
+                .scopes
+                .iter()
+                .rev()
+                .flat_map(|scope| scope.iter().rev())
+                .find(|(n, _)| n == name)
+        });
+
+        local
+            .or_else(|| self.env.globals.iter().rev().find(|(n, _)| n == name))
+            .map(|(_, v)| v)
+    }
+
+    fn lookup_mut(&mut self, name: &str) -> Option<&mut Value> {
+        if !self.is_local(name) {
+            return self
+                .env
+                .globals
+                .iter_mut()
+                .rev()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v);
+        }
+
+        let frame = self.frames.last_mut()?;
+
+        frame
+            .scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v)
+    }
 }
 
 impl Executor {
-    pub fn run<P: AsRef<Path> + Clone>(path: P) {
-        let mut memory = RuntimeVM::new();
+    pub fn run<P: AsRef<Path> + Clone>(path: P) -> ExecutionResult {
+        Executor::run_with_options(path, ParserOptions::default())
+    }
+
+    pub fn run_with_options<P: AsRef<Path> + Clone>(
+        path: P,
+        options: ParserOptions,
+    ) -> ExecutionResult {
+        Executor::run_file(path, options, RuntimeVM::new())
+    }
+
+    pub fn run_with_environment<P: AsRef<Path> + Clone>(
+        path: P,
+        options: ParserOptions,
+        env: Environment,
+    ) -> ExecutionResult {
+        Executor::run_file(path, options, RuntimeVM::with_environment(env))
+    }
+
+    pub fn debug<P: AsRef<Path> + Clone>(
+        path: P,
+        options: ParserOptions,
+        hook: &mut dyn DebugHook,
+    ) -> ExecutionResult {
+        Executor::run_file(path, options, RuntimeVM::with_debugger(hook))
+    }
+
+    fn run_file<P: AsRef<Path> + Clone>(
+        path: P,
+        options: ParserOptions,
+        mut memory: RuntimeVM,
+    ) -> ExecutionResult {
+        let source = match std::fs::read_to_string(path.as_ref()) {
+            Ok(source) => source,
+            Err(err) => {
+                let message = format!("failed to read '{}': {err}", path.as_ref().display());
+                return ExecutionResult {
+                    diagnostics: vec![Diagnostic::error(None, message)],
+                    ..Default::default()
+                };
+            }
+        };
+
+        let mut parser = Parser::from_file(path, &source).with_options(options);
+        let Ok(program) = parser.parse_program();
+
+        let mut diagnostics = parser.diagnostics().to_vec();
+        diagnostics.extend(Analyzer::check(&program).into_iter().map(Diagnostic::from));
 
-        if let Ok(mut parser) = Parser::from_file(path) {
-            if let Ok(program) = parser.parse_program() {
-                if let Some(main_proc) = Executor::find_startup_proc(program, ENTRY_POINT) {
-                    Executor::execute_procedure(main_proc, &mut memory);
+        if diagnostics.iter().any(|d| d.is_error()) {
+            return ExecutionResult {
+                diagnostics,
+                ..Default::default()
+            };
+        }
+
+        let main_proc = match Executor::find_startup_proc(&program, ENTRY_POINT) {
+            Ok(main_proc) => main_proc,
+            Err(diagnostic) => {
+                diagnostics.push(*diagnostic);
+                return ExecutionResult {
+                    diagnostics,
+                    ..Default::default()
+                };
+            }
+        };
+
+        let outcome = Executor::call_procedure(&main_proc, Vec::new(), &mut memory);
+
+        ExecutionResult::from_outcome(outcome, diagnostics)
+    }
+
+    pub fn eval_source(snippet: &str) -> String {
+        format!("proc {ENTRY_POINT}() {{\n{snippet}\n}}\n")
+    }
+
+    // positions are reported against the snippet, not the procedure it is wrapped in
+    pub fn eval(snippet: &str, options: ParserOptions) -> ExecutionResult {
+        let mut result = Executor::eval_wrapped(snippet, options);
+
+        let unwrap = |position: &mut Position| {
+            if position.filename == EVAL_FILENAME {
+                position.row = position.row.saturating_sub(EVAL_LINE_OFFSET);
+            }
+        };
+
+        for diagnostic in result.diagnostics.iter_mut() {
+            if let Some(position) = diagnostic.position.as_mut() {
+                unwrap(position);
+
+                if let Some(span) = diagnostic.span.as_mut() {
+                    span.start.row = span.start.row.saturating_sub(EVAL_LINE_OFFSET);
+                    span.end.row = span.end.row.saturating_sub(EVAL_LINE_OFFSET);
                 }
             }
         }
+
+        if let Some(position) = result.error.as_mut().and_then(|e| e.position.as_mut()) {
+            unwrap(position);
+        }
+
+        result
+    }
+
+    fn eval_wrapped(snippet: &str, options: ParserOptions) -> ExecutionResult {
+        let lexer = Lexer::new(Executor::eval_source(snippet), String::from(EVAL_FILENAME));
+
+        let mut parser = Parser::new(lexer).with_options(options);
+        let Ok(program) = parser.parse_program();
+
+        let mut diagnostics = parser.diagnostics().to_vec();
+
+        if diagnostics.iter().any(|d| d.is_error()) {
+            return ExecutionResult {
+                diagnostics,
+                ..Default::default()
+            };
+        }
+
+        let main_proc = match Executor::find_startup_proc(&program, ENTRY_POINT) {
+            Ok(main_proc) => main_proc,
+            Err(diagnostic) => {
+                diagnostics.push(*diagnostic);
+                return ExecutionResult {
+                    diagnostics,
+                    ..Default::default()
+                };
+            }
+        };
+
+        let mut memory = RuntimeVM::new();
+        memory.push_frame();
+        memory.call_stack.push(main_proc.name.clone());
+
+        let outcome =
+            Executor::execute_block(&main_proc.statements, &mut memory).map(|flow| match flow {
+                Flow::Next(value) | Flow::Return(value) => value,
+                Flow::Break | Flow::Continue => Value::None,
+            });
+
+        ExecutionResult::from_outcome(outcome, diagnostics)
+    }
+
+    pub fn execute_procedure(
+        proc_def: &ProcDefNode,
+        args: Vec<Value>,
+        env: &mut Environment,
+    ) -> ExecutionResult {
+        let mut memory = RuntimeVM::with_environment(std::mem::take(env));
+        let outcome = Executor::call_procedure(proc_def, args, &mut memory);
+
+        *env = memory.env;
+
+        ExecutionResult::from_outcome(outcome, Vec::new())
     }
 
-    fn find_startup_proc(program: Program, target: &str) -> Option<ProcDefNode> {
+    fn find_startup_proc(program: &Program, target: &str) -> Result<ProcDefNode, Box<Diagnostic>> {
         let proc = program.iter().find(move |&expr| {
             if let Expression::ProcDef(ProcDefNode { name, .. }) = expr {
                 return name == target;
@@ -47,86 +469,1274 @@ impl Executor {
         });
 
         if let Some(Expression::ProcDef(proc_def_node)) = proc {
-            return Some(proc_def_node.clone());
+            return Ok(proc_def_node.clone());
+        }
+
+        Err(Box::new(Diagnostic::error(
+            None,
+            format!("failed to find entry point '{target}'"),
+        )))
+    }
+
+    fn call_procedure(
+        proc_def: &ProcDefNode,
+        args: Vec<Value>,
+        memory: &mut RuntimeVM,
+    ) -> Result<Value, RuntimeError> {
+        Executor::invoke(proc_def, Vec::new(), args, memory).map(|(value, _)| value)
+    }
+
+    fn invoke(
+        proc_def: &ProcDefNode,
+        captures: Vec<(String, Value)>,
+        args: Vec<Value>,
+        memory: &mut RuntimeVM,
+    ) -> Result<(Value, Option<Value>), RuntimeError> {
+        if proc_def.args.len() != args.len() {
+            return Err(memory.error(RuntimeErrorKind::ArityMismatch {
+                name: proc_def.name.clone(),
+                expected: proc_def.args.len(),
+                found: args.len(),
+            }));
+        }
+
+        memory.notify(|hooks| hooks.on_call(&proc_def.name, &args));
+
+        if proc_def.native {
+            let value = Executor::call_native(proc_def, args, memory)?;
+            memory.notify(|hooks| hooks.on_return(&proc_def.name, &value));
+
+            return Ok((value, None));
+        }
+
+        let call_site = memory.position.clone();
+
+        memory.push_frame();
+        memory.call_stack.push(proc_def.name.clone());
+
+        for (name, value) in captures {
+            memory.declare(name, value);
+        }
+
+        for (arg, value) in proc_def.args.iter().zip(args) {
+            memory.declare(arg.name.clone(), value);
+        }
+
+        Executor::check_contracts(proc_def, ContractKind::Requires, &call_site, memory)?;
+
+        let flow = Executor::execute_block(&proc_def.statements, memory)?;
+
+        let value = match flow {
+            Flow::Return(value) => value,
+            Flow::Next(..) | Flow::Break | Flow::Continue => Value::None,
+        };
+
+        if proc_def
+            .contracts
+            .iter()
+            .any(|c| c.kind == ContractKind::Ensures)
+        {
+            memory.declare(String::from("result"), value.clone());
+            Executor::check_contracts(proc_def, ContractKind::Ensures, &call_site, memory)?;
+        }
+
+        let receiver = proc_def
+            .args
+            .first()
+            .filter(|arg| arg.name == "self" && arg.mutable)
+            .and_then(|_| memory.lookup("self").cloned());
+
+        memory.call_stack.pop();
+        memory.pop_frame();
+
+        memory.notify(|hooks| hooks.on_return(&proc_def.name, &value));
+
+        Ok((value, receiver))
+    }
+
+    // the checker lets 'any' into typed bindings, so the value's tag is checked here
+    fn check_any(
+        expected: &str,
+        expr: &Expression,
+        value: &Value,
+        generics: &[String],
+        memory: &RuntimeVM,
+    ) -> Result<(), RuntimeError> {
+        if expected != "any"
+            && expr.type_name() == "any"
+            && !assignable(expected, &value.type_name(), generics)
+        {
+            return Err(memory.error(RuntimeErrorKind::AnyMismatch {
+                expected: expected.to_string(),
+                found: value.type_name(),
+            }));
         }
 
-        println!("Error: failed to find entry point '{target}'");
-        None
+        Ok(())
     }
 
-    fn execute_procedure(proc_def: ProcDefNode, memory: &mut RuntimeVM) {
-        for statement in proc_def.statements.iter() {
-            Executor::execute_statement(statement, memory);
+    // a lookup that found nothing counts as None on the left of '??'
+    fn failed_lookup(expr: &Expression, error: &RuntimeError) -> bool {
+        let position = match expr {
+            Expression::MapCall(map_call_node) if map_call_node.method.is_lookup() => {
+                &map_call_node.position
+            }
+            Expression::VecCall(vec_call_node) if vec_call_node.method.is_lookup() => {
+                &vec_call_node.position
+            }
+            _ => return false,
+        };
+
+        let missing = matches!(
+            error.kind,
+            RuntimeErrorKind::MissingKey(..)
+                | RuntimeErrorKind::IndexOutOfBounds { .. }
+                | RuntimeErrorKind::EmptyVec
+        );
+
+        if let (true, Some(at)) = (missing, &error.position) {
+            return at.filename == position.filename
+                && at.row == position.row
+                && at.column == position.column;
         }
+
+        false
     }
 
-    fn execute_statement(statement: &Expression, memory: &mut RuntimeVM) -> Option<Expression> {
+    fn place_mut<'a>(place: &Expression, memory: &'a mut RuntimeVM) -> Option<&'a mut Value> {
+        match place {
+            Expression::Variable(variable_node) => memory.lookup_mut(&variable_node.metadata.name),
+            Expression::StructFieldAccess(field_access_node) => {
+                match Executor::place_mut(&field_access_node.base, memory)? {
+                    Value::Struct(struct_value) => {
+                        struct_value.field_mut(&field_access_node.field.metadata.name)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    // runs update on the value a place names without copying it out first
+    fn with_place<F>(
+        place: &Expression,
+        position: &Position,
+        memory: &mut RuntimeVM,
+        update: F,
+    ) -> Result<Value, RuntimeError>
+    where
+        F: FnOnce(&mut Value) -> Result<Value, RuntimeErrorKind>,
+    {
+        let mut temporary = None;
+        if Executor::place_mut(place, memory).is_none() {
+            temporary = Some(Executor::evaluate(place, memory)?);
+        }
+
+        let outcome = match temporary.as_mut() {
+            Some(value) => update(value),
+            None => match Executor::place_mut(place, memory) {
+                Some(value) => update(value),
+                None => Ok(Value::None),
+            },
+        };
+
+        memory.position = Some(position.clone());
+        outcome.map_err(|kind| memory.error(kind))
+    }
+
+    fn map_method(
+        map_value: &mut MapValue,
+        method: MapMethod,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeErrorKind> {
+        let mut args = args.into_iter();
+        let key = args.next().unwrap_or_default();
+
+        match method {
+            MapMethod::Insert => {
+                map_value.insert(key, args.next().unwrap_or_default());
+                Ok(Value::None)
+            }
+            MapMethod::Get => match map_value.get(&key) {
+                Some(value) => Ok(value.clone()),
+                None => Err(RuntimeErrorKind::MissingKey(key.to_string())),
+            },
+            MapMethod::Remove => map_value
+                .remove(&key)
+                .ok_or_else(|| RuntimeErrorKind::MissingKey(key.to_string())),
+            MapMethod::ContainsKey => Ok(Value::Bool(map_value.get(&key).is_some())),
+            MapMethod::Len => Ok(Value::Number(map_value.len() as i32)),
+        }
+    }
+
+    fn vec_method(
+        vec_value: &mut VecValue,
+        method: VecMethod,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeErrorKind> {
+        let mut args = args.into_iter();
+        match method {
+            VecMethod::Push => {
+                let item = args.next().unwrap_or_default();
+                if vec_type(&vec_value.type_name).as_deref() == Some(UNKNOWN_TYPE) {
+                    vec_value.type_name = GenericType {
+                        name: String::from("Vec"),
+                        args: vec![item.type_name()],
+                    }
+                    .to_string();
+                }
+
+                vec_value.items.push(item);
+                Ok(Value::None)
+            }
+            VecMethod::Pop => vec_value.items.pop().ok_or(RuntimeErrorKind::EmptyVec),
+            VecMethod::Get | VecMethod::Set => {
+                let index = match args.next().unwrap_or_default() {
+                    Value::Number(index) => index,
+                    value => {
+                        return Err(RuntimeErrorKind::InvalidCast {
+                            from: value.type_name(),
+                            to: String::from("i32"),
+                        })
+                    }
+                };
+
+                let Some(slot) = vec_value.slot(index) else {
+                    return Err(RuntimeErrorKind::IndexOutOfBounds {
+                        index,
+                        len: vec_value.items.len(),
+                    });
+                };
+
+                if method == VecMethod::Set {
+                    vec_value.items[slot] = args.next().unwrap_or_default();
+                    return Ok(Value::None);
+                }
+
+                Ok(vec_value.items[slot].clone())
+            }
+            VecMethod::Len => Ok(Value::Number(vec_value.items.len() as i32)),
+        }
+    }
+
+    fn write_back(
+        place: &Expression,
+        value: Value,
+        memory: &mut RuntimeVM,
+    ) -> Result<(), RuntimeError> {
+        let mut fields = Vec::new();
+        let mut root = place;
+        while let Expression::StructFieldAccess(field_access_node) = root {
+            fields.push(field_access_node.field.clone());
+            root = &field_access_node.base;
+        }
+        fields.reverse();
+
+        let Expression::Variable(variable) = root else {
+            return Ok(());
+        };
+
+        let name = &variable.metadata.name;
+        let result = match memory.lookup_mut(name) {
+            Some(target) => Executor::assign_field(target, &fields, value),
+            None => Err(RuntimeErrorKind::UndefinedVariable(name.clone())),
+        };
+
+        result.map_err(|kind| memory.error(kind))
+    }
+
+    fn check_contracts(
+        proc_def: &ProcDefNode,
+        kind: ContractKind,
+        call_site: &Option<Position>,
+        memory: &mut RuntimeVM,
+    ) -> Result<(), RuntimeError> {
+        for contract in proc_def.contracts.iter().filter(|c| c.kind == kind) {
+            memory.position = Some(contract.position.clone());
+
+            if !Executor::evaluate_condition(&contract.condition, memory)? {
+                memory.position = Some(contract.position.clone());
+                return Err(memory.error(RuntimeErrorKind::ContractViolation {
+                    kind,
+                    condition: contract.source.clone(),
+                    call_site: call_site.as_ref().map(|p| p.to_string()),
+                }));
+            }
+        }
+
+        memory.position = call_site.clone();
+
+        Ok(())
+    }
+
+    fn call_native(
+        proc_def: &ProcDefNode,
+        args: Vec<Value>,
+        memory: &mut RuntimeVM,
+    ) -> Result<Value, RuntimeError> {
+        let native = memory
+            .env
+            .natives
+            .iter()
+            .rev()
+            .find(|n| n.name == proc_def.name)
+            .cloned()
+            .or_else(|| {
+                memory
+                    .env
+                    .capabilities
+                    .natives()
+                    .into_iter()
+                    .find(|n| n.name == proc_def.name)
+            });
+
+        if let Some(native) = native {
+            if let Err(message) = native.check_args(&args) {
+                return Err(memory.error(RuntimeErrorKind::Native {
+                    name: proc_def.name.clone(),
+                    message,
+                }));
+            }
+
+            let timeout = memory.env.native_timeout;
+            return match native.call_with_timeout(&args, timeout) {
+                Some(value) => match native.check_return(&value) {
+                    Ok(()) => Ok(value),
+                    Err(message) => Err(memory.error(RuntimeErrorKind::Native {
+                        name: proc_def.name.clone(),
+                        message,
+                    })),
+                },
+                None => Err(memory.error(RuntimeErrorKind::HostTimeout {
+                    name: proc_def.name.clone(),
+                    timeout: timeout.unwrap_or_default(),
+                })),
+            };
+        }
+
+        Err(memory.error(RuntimeErrorKind::UnregisteredNative(proc_def.name.clone())))
+    }
+
+    fn execute_block(
+        statements: &[Expression],
+        memory: &mut RuntimeVM,
+    ) -> Result<Flow, RuntimeError> {
+        memory.push_scope();
+
+        let mut last = Value::None;
+        for statement in statements.iter() {
+            match Executor::execute_statement(statement, memory)? {
+                Flow::Next(value) => last = value,
+                flow => {
+                    memory.pop_scope();
+                    return Ok(flow);
+                }
+            }
+        }
+
+        memory.pop_scope();
+
+        Ok(Flow::Next(last))
+    }
+
+    fn evaluate_condition(expr: &Expression, memory: &mut RuntimeVM) -> Result<bool, RuntimeError> {
+        match Executor::evaluate(expr, memory)? {
+            Value::Bool(b) => Ok(b),
+            value => Err(memory.error(RuntimeErrorKind::InvalidCondition(value.type_name()))),
+        }
+    }
+
+    fn iterate(expr: &Expression, memory: &mut RuntimeVM) -> Result<Items, RuntimeError> {
+        let value = Executor::evaluate(expr, memory)?;
+        Executor::items(value).map_err(|kind| memory.error(kind))
+    }
+
+    // every iterable kind of value hands out its items lazily, one per loop iteration
+    fn items(value: Value) -> Result<Items, RuntimeErrorKind> {
+        match value {
+            Value::Range(range_value) => {
+                let RangeValue {
+                    start,
+                    end,
+                    inclusive,
+                } = range_value;
+
+                let counters: Box<dyn Iterator<Item = i32>> = if inclusive {
+                    Box::new(start..=end)
+                } else {
+                    Box::new(start..end)
+                };
+
+                Ok(Box::new(counters.map(|i| Ok(Value::Number(i)))))
+            }
+            Value::Vec(vec_value) => Ok(Box::new(vec_value.items.into_iter().map(Ok))),
+            Value::Map(map_value) => Ok(Box::new(map_value.into_keys().map(Ok))),
+            Value::String(s) => {
+                let chars: Vec<char> = s.chars().collect();
+                Ok(Box::new(chars.into_iter().map(|c| Ok(Value::Char(c)))))
+            }
+            Value::Lines(lines_value) => {
+                let opened = lines_value.open();
+                let read_error = move |err: std::io::Error| RuntimeErrorKind::Native {
+                    name: String::from("read_lines"),
+                    message: format!("cannot read '{}': {err}", lines_value.path),
+                };
+
+                match opened {
+                    Ok(lines) => {
+                        Ok(Box::new(lines.map(move |line| {
+                            line.map(Value::String).map_err(&read_error)
+                        })))
+                    }
+                    Err(err) => Err(read_error(err)),
+                }
+            }
+            value => Err(RuntimeErrorKind::NotIterable(value.type_name())),
+        }
+    }
+
+    fn execute_statement(
+        statement: &Expression,
+        memory: &mut RuntimeVM,
+    ) -> Result<Flow, RuntimeError> {
+        memory.step()?;
+        memory.pause(statement)?;
+
+        if let Some(position) = statement.position() {
+            memory.notify(|hooks| hooks.on_statement(statement, position));
+        }
+
         match statement {
-            Expression::IfStatement(..) => {}
-            Expression::WhileStatement(..) => {}
-            Expression::ForLoop(..) => {}
-            Expression::RangeStatement(..) => {}
+            Expression::IfStatement(if_node) => {
+                memory.position = Some(if_node.position.clone());
+
+                if Executor::evaluate_condition(&if_node.value, memory)? {
+                    return Executor::execute_block(&if_node.statements, memory);
+                }
+
+                if let Some(else_node) = &if_node.else_branch {
+                    return Executor::execute_block(&else_node.statements, memory);
+                }
+            }
+            Expression::WhileStatement(while_node) => {
+                memory.position = Some(while_node.position.clone());
+                memory.loops.push(while_node.position.clone());
+
+                while Executor::evaluate_condition(&while_node.value, memory)? {
+                    memory.step()?;
+
+                    match Executor::execute_block(&while_node.statements, memory)? {
+                        Flow::Return(value) => {
+                            memory.loops.pop();
+                            return Ok(Flow::Return(value));
+                        }
+                        Flow::Break => break,
+                        Flow::Next(..) | Flow::Continue => {}
+                    }
+                }
+
+                memory.loops.pop();
+            }
+            Expression::ForLoop(for_node) => {
+                memory.position = Some(for_node.position.clone());
+
+                let items = Executor::iterate(&for_node.range, memory)?;
+
+                memory.loops.push(for_node.position.clone());
+
+                for item in items {
+                    let item = item.map_err(|kind| memory.error(kind))?;
+
+                    memory.push_scope();
+                    memory.declare(for_node.counter.metadata.name.clone(), item);
+
+                    let flow = Executor::execute_block(&for_node.statements, memory)?;
+
+                    memory.pop_scope();
+
+                    match flow {
+                        Flow::Return(value) => {
+                            memory.loops.pop();
+                            return Ok(Flow::Return(value));
+                        }
+                        Flow::Break => break,
+                        Flow::Next(..) | Flow::Continue => {}
+                    }
+                }
+
+                memory.loops.pop();
+            }
+            Expression::MatchStatement(match_node) => {
+                return Executor::execute_match(match_node, memory);
+            }
             Expression::LetStatement(let_node) => {
-                let metadata = VarMetadataNode {
-                    name: let_node.name.clone(),
-                    type_name: let_node.type_name.clone(),
+                memory.position = Some(let_node.position.clone());
+
+                let value = Executor::evaluate(&let_node.value, memory)?;
+                memory.position = Some(let_node.position.clone());
+                Executor::check_any(&let_node.type_name, &let_node.value, &value, &[], memory)?;
+                memory.notify(|hooks| hooks.on_assign(&let_node.name, &value, &let_node.position));
+                memory.declare(let_node.name.clone(), value);
+            }
+            Expression::AssignStatement(assign_node) => {
+                memory.position = Some(assign_node.position.clone());
+
+                let value = Executor::evaluate(&assign_node.new_value, memory)?;
+                memory.position = Some(assign_node.position.clone());
+                let metadata = &assign_node.value.metadata;
+                Executor::check_any(
+                    &metadata.type_name,
+                    &assign_node.new_value,
+                    &value,
+                    &[],
+                    memory,
+                )?;
+                let name = &metadata.name;
+                memory.notify(|hooks| hooks.on_assign(name, &value, &assign_node.position));
+
+                match memory.lookup_mut(name) {
+                    Some(variable) => *variable = value,
+                    None => {
+                        return Err(memory.error(RuntimeErrorKind::UndefinedVariable(name.clone())))
+                    }
+                }
+            }
+            Expression::ReturnStatement(return_node) => {
+                memory.position = Some(return_node.position.clone());
+
+                return Ok(Flow::Return(Executor::evaluate(
+                    &return_node.value,
+                    memory,
+                )?));
+            }
+            Expression::BreakStatement(..) => return Ok(Flow::Break),
+            Expression::ContinueStatement(..) => return Ok(Flow::Continue),
+            Expression::StructFieldAssign(field_assign_node) => {
+                memory.position = Some(field_assign_node.position.clone());
+
+                let value = Executor::evaluate(&field_assign_node.new_value, memory)?;
+                let name = &field_assign_node.struct_instance.metadata.name;
+
+                let path: Vec<&str> = std::iter::once(name.as_str())
+                    .chain(
+                        field_assign_node
+                            .fields
+                            .iter()
+                            .map(|f| f.metadata.name.as_str()),
+                    )
+                    .collect();
+                memory.notify(|hooks| {
+                    hooks.on_assign(&path.join("."), &value, &field_assign_node.position)
+                });
+
+                let result = match memory.lookup_mut(name) {
+                    Some(target) => {
+                        Executor::assign_field(target, &field_assign_node.fields, value)
+                    }
+                    None => Err(RuntimeErrorKind::UndefinedVariable(name.clone())),
                 };
 
-                let var = VariableNode {
-                    metadata,
-                    value: let_node.value.clone(),
+                result.map_err(|kind| memory.error(kind))?;
+            }
+            Expression::ProcDef(..)
+            | Expression::StructDef(..)
+            | Expression::EnumDef(..)
+            | Expression::ImplStatement(..)
+            | Expression::TraitDef(..) => {}
+            expr => return Ok(Flow::Next(Executor::evaluate(expr, memory)?)),
+        }
+
+        Ok(Flow::Next(Value::None))
+    }
+
+    fn assign_field(
+        target: &mut Value,
+        fields: &[VariableNode],
+        value: Value,
+    ) -> Result<(), RuntimeErrorKind> {
+        let (field, rest) = if let Some(path) = fields.split_first() {
+            path
+        } else {
+            *target = value;
+            return Ok(());
+        };
+
+        let field_name = &field.metadata.name;
+
+        match target {
+            Value::Struct(struct_value) => {
+                let type_name = struct_value.type_name.clone();
+
+                match struct_value.field_mut(field_name) {
+                    Some(target) => Executor::assign_field(target, rest, value),
+                    None => Err(RuntimeErrorKind::UndefinedField {
+                        type_name,
+                        field: field_name.clone(),
+                    }),
+                }
+            }
+            target => Err(RuntimeErrorKind::UndefinedField {
+                type_name: target.type_name(),
+                field: field_name.clone(),
+            }),
+        }
+    }
+
+    fn execute_match(match_node: &MatchNode, memory: &mut RuntimeVM) -> Result<Flow, RuntimeError> {
+        memory.position = Some(match_node.position.clone());
+
+        let value = Executor::evaluate(&match_node.value, memory)?;
+
+        for arm in match_node.arms.iter() {
+            let bindings = if let Some(bindings) = Executor::match_pattern(&arm.pattern, &value) {
+                bindings
+            } else {
+                continue;
+            };
+
+            memory.push_scope();
+
+            for (name, value) in bindings {
+                memory.declare(name, value);
+            }
+
+            if let Some(guard) = &arm.guard {
+                if !Executor::evaluate_condition(guard, memory)? {
+                    memory.pop_scope();
+                    continue;
+                }
+            }
+
+            let flow = Executor::execute_block(&arm.statements, memory)?;
+
+            memory.pop_scope();
+
+            return Ok(flow);
+        }
+
+        memory.position = Some(match_node.position.clone());
+        Err(memory.error(RuntimeErrorKind::NoMatchingArm(value.to_string())))
+    }
+
+    fn match_pattern(pattern: &PatternNode, value: &Value) -> Option<Vec<(String, Value)>> {
+        match pattern {
+            PatternNode::Wildcard => Some(Vec::new()),
+            PatternNode::Literal(token, lt) => {
+                if Value::from_literal(token, *lt) == *value {
+                    return Some(Vec::new());
+                }
+
+                None
+            }
+            PatternNode::Range { start, end, kind } => {
+                let start = Value::from_literal(start, *kind);
+                let end = Value::from_literal(end, *kind);
+
+                let contained = match (&start, value, &end) {
+                    (Value::Number(start), Value::Number(value), Value::Number(end)) => {
+                        start <= value && value < end
+                    }
+                    (Value::Float(start), Value::Float(value), Value::Float(end)) => {
+                        start <= value && value < end
+                    }
+                    (Value::Char(start), Value::Char(value), Value::Char(end)) => {
+                        start <= value && value < end
+                    }
+                    _ => false,
                 };
 
-                if let Expression::StructInstance(_) = let_node.value.as_ref() {
-                    Executor::execute_statement(let_node.value.as_ref(), memory);
+                if contained {
+                    return Some(Vec::new());
                 }
 
-                memory.variables.push(var);
+                None
             }
-            Expression::AssignStatement(assign_node) => {
-                let variable = memory
-                    .variables
-                    .iter_mut()
-                    .find(|v| *v.metadata.name == assign_node.value.metadata.name)
-                    .unwrap();
+            PatternNode::Binding(binding) => Some(vec![(binding.name.clone(), value.clone())]),
+            PatternNode::Bind { binding, pattern } => {
+                let mut bindings = vec![(binding.name.clone(), value.clone())];
+                bindings.extend(Executor::match_pattern(pattern, value)?);
+                Some(bindings)
+            }
+            PatternNode::Or(alternatives) => alternatives
+                .iter()
+                .find_map(|alternative| Executor::match_pattern(alternative, value)),
+            PatternNode::Variant {
+                type_name,
+                variant,
+                bindings,
+            } => {
+                if let Value::Flags(flags_value) = value {
+                    if flags_value.type_name == *type_name
+                        && flags_value.discriminant(variant) == Some(flags_value.bits)
+                    {
+                        return Some(Vec::new());
+                    }
+                }
+
+                if let Value::Enum(enum_value) = value {
+                    if enum_value.type_name == *type_name && enum_value.variant == *variant {
+                        let bindings = bindings
+                            .iter()
+                            .zip(enum_value.fields.iter())
+                            .filter(|(binding, _)| binding.name != "_")
+                            .map(|(binding, (_, value))| (binding.name.clone(), value.clone()))
+                            .collect();
+
+                        return Some(bindings);
+                    }
+                }
 
-                variable.value = assign_node.new_value.clone();
+                None
             }
-            Expression::ReturnStatement(..) => {}
-            Expression::Variable(..) => {}
-            Expression::ProcDef(proc_def_node) => {
-                Executor::execute_procedure(proc_def_node.clone(), memory)
+        }
+    }
+
+    fn evaluate(expr: &Expression, memory: &mut RuntimeVM) -> Result<Value, RuntimeError> {
+        match expr {
+            Expression::Literal(token, lt) => Ok(Value::from_literal(token, *lt)),
+            Expression::Variable(variable_node) => {
+                let name = &variable_node.metadata.name;
+
+                match memory.lookup(name) {
+                    Some(value) => Ok(value.clone()),
+                    None => Err(memory.error(RuntimeErrorKind::UndefinedVariable(name.clone()))),
+                }
+            }
+            Expression::BinaryOp(binary_op_node) => {
+                let rhs = Executor::evaluate(&binary_op_node.rhs, memory)?;
+
+                let op = match binary_op_node.op {
+                    BinaryOp::Inc | BinaryOp::AddAssign => Some(BinaryOp::Add),
+                    BinaryOp::Dec | BinaryOp::SubAssign => Some(BinaryOp::Sub),
+                    BinaryOp::MulAssign => Some(BinaryOp::Mul),
+                    BinaryOp::DivAssign => Some(BinaryOp::Div),
+                    _ => None,
+                };
+
+                if let (Some(op), Expression::Variable(variable_node)) =
+                    (op, binary_op_node.lhs.as_ref())
+                {
+                    let name = &variable_node.metadata.name;
+                    let current = Executor::evaluate(&binary_op_node.lhs, memory)?;
+
+                    memory.position = Some(binary_op_node.position.clone());
+                    let value = Executor::apply_binary_op(current, &op, rhs)
+                        .map_err(|kind| memory.error(kind))?;
+
+                    if let Some(variable) = memory.lookup_mut(name) {
+                        *variable = value.clone();
+                    }
+
+                    return Ok(value);
+                }
+
+                let lhs = Executor::evaluate(&binary_op_node.lhs, memory)?;
+
+                memory.position = Some(binary_op_node.position.clone());
+                Executor::apply_binary_op(lhs, &binary_op_node.op, rhs)
+                    .map_err(|kind| memory.error(kind))
+            }
+            Expression::Pipe(pipe_node) => Executor::evaluate(&pipe_node.call, memory),
+            Expression::Coalesce(coalesce_node) => {
+                let value = match Executor::evaluate(&coalesce_node.value, memory) {
+                    Err(error) if Executor::failed_lookup(&coalesce_node.value, &error) => {
+                        Value::None
+                    }
+                    value => value?,
+                };
+
+                // the fallback only runs when it is needed
+                match value {
+                    Value::None => Executor::evaluate(&coalesce_node.fallback, memory),
+                    value => Ok(value),
+                }
+            }
+            Expression::CompareChain(compare_chain_node) => {
+                let mut operands = compare_chain_node.operands.iter();
+                let Some(first) = operands.next() else {
+                    return Ok(Value::Bool(true));
+                };
+
+                // each operand is evaluated once, the chain stops at the first false link
+                let mut lhs = Executor::evaluate(first, memory)?;
+                for (op, operand) in compare_chain_node.ops.iter().zip(operands) {
+                    let rhs = Executor::evaluate(operand, memory)?;
+
+                    memory.position = Some(compare_chain_node.position.clone());
+                    let holds = Executor::apply_binary_op(lhs, op, rhs.clone())
+                        .map_err(|kind| memory.error(kind))?;
+                    if let Value::Bool(false) = holds {
+                        return Ok(holds);
+                    }
+
+                    lhs = rhs;
+                }
+
+                Ok(Value::Bool(true))
             }
             Expression::FunCall(fun_call_node) => {
-                Executor::execute_procedure(fun_call_node.proc_def.clone(), memory)
+                let generics = &fun_call_node.proc_def.generics;
+
+                let mut args = Vec::new();
+                for arg in fun_call_node.args.iter() {
+                    args.push(Executor::evaluate(&arg.value, memory)?);
+                }
+
+                memory.position = Some(fun_call_node.position.clone());
+                for (arg, value) in fun_call_node.args.iter().zip(&args) {
+                    let expected = &arg.metadata.type_name;
+                    Executor::check_any(expected, &arg.value, value, generics, memory)?;
+                }
+                Executor::call_procedure(&fun_call_node.proc_def, args, memory)
+            }
+            Expression::Closure(closure_node) => {
+                let mut captures = Vec::new();
+                for capture in closure_node.captures.iter() {
+                    let Some(value) = memory.lookup(&capture.name).cloned() else {
+                        return Err(
+                            memory.error(RuntimeErrorKind::UndefinedVariable(capture.name.clone()))
+                        );
+                    };
+
+                    captures.push((capture.name.clone(), value));
+                }
+
+                Ok(Value::Closure(ClosureValue {
+                    proc_def: Arc::new(closure_node.proc_def.clone()),
+                    captures,
+                }))
+            }
+            Expression::ClosureCall(closure_call_node) => {
+                let callee = Executor::evaluate(&closure_call_node.callee, memory)?;
+
+                let mut args = Vec::new();
+                for arg in closure_call_node.args.iter() {
+                    args.push(Executor::evaluate(arg, memory)?);
+                }
+
+                memory.position = Some(closure_call_node.position.clone());
+                let Value::Closure(closure_value) = callee else {
+                    return Err(memory.error(RuntimeErrorKind::NotCallable(callee.type_name())));
+                };
+
+                let params = closure_value.proc_def.args.iter();
+                for ((param, arg), value) in params.zip(&closure_call_node.args).zip(&args) {
+                    Executor::check_any(&param.type_name, arg, value, &[], memory)?;
+                }
+
+                Executor::invoke(
+                    &closure_value.proc_def,
+                    closure_value.captures,
+                    args,
+                    memory,
+                )
+                .map(|(value, _)| value)
             }
-            Expression::StructDef(..) => todo!(),
-            Expression::ImplStatement(..) => todo!(),
             Expression::ImplFunCall(impl_fun_call_node) => {
-                if let Expression::ProcDef(proc_def_node) =
-                    impl_fun_call_node.fun_call_node.as_ref()
-                {
-                    Executor::execute_procedure(proc_def_node.clone(), memory)
+                let Expression::FunCall(fun_call_node) = impl_fun_call_node.fun_call_node.as_ref()
+                else {
+                    return Executor::evaluate(&impl_fun_call_node.fun_call_node, memory);
+                };
+
+                let proc_def = &fun_call_node.proc_def;
+                let mutates =
+                    impl_fun_call_node.method && proc_def.args.first().is_some_and(|a| a.mutable);
+                if !mutates {
+                    return Executor::evaluate(&impl_fun_call_node.fun_call_node, memory);
+                }
+
+                let mut args = Vec::new();
+                for arg in fun_call_node.args.iter() {
+                    args.push(Executor::evaluate(&arg.value, memory)?);
+                }
+
+                memory.position = Some(fun_call_node.position.clone());
+                let (value, receiver) = Executor::invoke(proc_def, Vec::new(), args, memory)?;
+
+                if let (Some(place), Some(receiver)) = (fun_call_node.args.first(), receiver) {
+                    Executor::write_back(&place.value, receiver, memory)?;
                 }
+
+                Ok(value)
+            }
+            Expression::DynCall(dyn_call_node) => {
+                let fun_call_node = &dyn_call_node.fun_call_node;
+
+                let mut args = Vec::new();
+                for arg in fun_call_node.args.iter() {
+                    args.push(Executor::evaluate(&arg.value, memory)?);
+                }
+
+                memory.position = Some(fun_call_node.position.clone());
+                let type_name = args.first().map(Value::type_name).unwrap_or_default();
+                let method = &fun_call_node.proc_def.name;
+                let Some(proc_def) = dyn_call_node.dispatch.lookup(&type_name, method) else {
+                    return Err(memory.error(RuntimeErrorKind::NotImplemented {
+                        type_name,
+                        trait_name: dyn_call_node.trait_name.clone(),
+                    }));
+                };
+
+                let (value, receiver) = Executor::invoke(&proc_def, Vec::new(), args, memory)?;
+
+                if let (Some(place), Some(receiver)) = (fun_call_node.args.first(), receiver) {
+                    Executor::write_back(&place.value, receiver, memory)?;
+                }
+
+                Ok(value)
             }
             Expression::StructInstance(struct_instance_node) => {
-                memory.structs.push(struct_instance_node.clone());
+                let mut fields = Vec::new();
+                for field in struct_instance_node.fields.iter() {
+                    let value = Executor::evaluate(&field.value, memory)?;
+                    fields.push((field.metadata.name.clone(), value));
+                }
+
+                Ok(Value::Struct(StructValue {
+                    type_name: struct_instance_node.struct_def.type_name.clone(),
+                    fields,
+                }))
             }
-            Expression::StructFieldAssign(field_assign_node) => {
-                'outer: for (i, struct_instance) in memory.structs.clone().iter().enumerate() {
-                    for (j, field) in struct_instance.fields.iter().enumerate() {
-                        if field.metadata.name == field_assign_node.field.metadata.name {
-                            memory.structs[i].fields[j].value = field_assign_node.new_value.clone();
-                            break 'outer;
+            Expression::EnumInstance(enum_instance_node) => {
+                let mut fields = Vec::new();
+                for field in enum_instance_node.fields.iter() {
+                    let value = Executor::evaluate(&field.value, memory)?;
+                    fields.push((field.metadata.name.clone(), value));
+                }
+
+                let enum_def = &enum_instance_node.enum_def;
+                if enum_def.flags {
+                    let mut flags_value = FlagsValue::new(enum_def, 0);
+                    flags_value.bits = flags_value
+                        .discriminant(&enum_instance_node.variant)
+                        .unwrap_or_default();
+
+                    return Ok(Value::Flags(flags_value));
+                }
+
+                Ok(Value::Enum(EnumValue {
+                    type_name: enum_def.type_name.clone(),
+                    variant: enum_instance_node.variant.clone(),
+                    fields,
+                }))
+            }
+            Expression::EnumFrom(enum_from_node) => {
+                let value = Executor::evaluate(&enum_from_node.value, memory)?;
+                let enum_def = &enum_from_node.enum_def;
+
+                let kind = match value {
+                    Value::Number(n) if enum_def.flags => {
+                        if n & !enum_def.all_flags() == 0 {
+                            return Ok(Value::Flags(FlagsValue::new(enum_def, n)));
+                        }
+
+                        RuntimeErrorKind::InvalidFlags {
+                            type_name: enum_def.type_name.clone(),
+                            value: n,
+                        }
+                    }
+                    Value::Number(n) => {
+                        if let Some((variant, _)) =
+                            enum_def.discriminants().into_iter().find(|(_, d)| *d == n)
+                        {
+                            return Ok(Value::Enum(EnumValue {
+                                type_name: enum_def.type_name.clone(),
+                                variant: String::from(variant),
+                                fields: Vec::new(),
+                            }));
+                        }
+
+                        RuntimeErrorKind::InvalidDiscriminant {
+                            type_name: enum_def.type_name.clone(),
+                            value: n,
+                        }
+                    }
+                    value => RuntimeErrorKind::InvalidCast {
+                        from: value.type_name(),
+                        to: enum_def.type_name.clone(),
+                    },
+                };
+
+                memory.position = Some(enum_from_node.position.clone());
+                Err(memory.error(kind))
+            }
+            Expression::EnumParse(enum_parse_node) => {
+                let value = Executor::evaluate(&enum_parse_node.value, memory)?;
+                let enum_def = &enum_parse_node.enum_def;
+
+                let Value::String(text) = value else {
+                    memory.position = Some(enum_parse_node.position.clone());
+                    return Err(memory.error(RuntimeErrorKind::InvalidCast {
+                        from: value.type_name(),
+                        to: enum_def.type_name.clone(),
+                    }));
+                };
+
+                let discriminants = enum_def.discriminants();
+                let find = |name: &str| discriminants.iter().find(|(n, _)| *n == name);
+
+                let unknown = if enum_def.flags {
+                    let bits = text
+                        .split('|')
+                        .map(str::trim)
+                        .filter(|_| !text.trim().is_empty())
+                        .try_fold(0, |bits, part| {
+                            find(part).map(|(_, d)| bits | d).ok_or(part)
+                        });
+
+                    match bits {
+                        Ok(bits) => return Ok(Value::Flags(FlagsValue::new(enum_def, bits))),
+                        Err(part) => part,
+                    }
+                } else {
+                    if let Some((variant, _)) = find(&text) {
+                        return Ok(Value::Enum(EnumValue {
+                            type_name: enum_def.type_name.clone(),
+                            variant: String::from(*variant),
+                            fields: Vec::new(),
+                        }));
+                    }
+
+                    text.as_str()
+                };
+
+                memory.position = Some(enum_parse_node.position.clone());
+                Err(memory.error(RuntimeErrorKind::UnknownVariant {
+                    type_name: enum_def.type_name.clone(),
+                    name: String::from(unknown),
+                    variants: enum_def.variants.iter().map(|v| v.name.clone()).collect(),
+                }))
+            }
+            Expression::VariantName(variant_name_node) => {
+                match Executor::evaluate(&variant_name_node.value, memory)? {
+                    Value::Enum(enum_value) => Ok(Value::String(enum_value.variant)),
+                    Value::Flags(flags_value) => {
+                        Ok(Value::String(flags_value.names().0.join(" | ")))
+                    }
+                    value => {
+                        memory.position = Some(variant_name_node.position.clone());
+                        Err(memory.error(RuntimeErrorKind::InvalidCast {
+                            from: value.type_name(),
+                            to: String::from("String"),
+                        }))
+                    }
+                }
+            }
+            Expression::Cast(cast_node) => {
+                let value = Executor::evaluate(&cast_node.value, memory)?;
+
+                if let Value::Flags(flags_value) = &value {
+                    return Ok(Value::Number(flags_value.bits));
+                }
+
+                if let Value::Enum(enum_value) = &value {
+                    if let Some((_, discriminant)) = cast_node
+                        .enum_def
+                        .discriminants()
+                        .into_iter()
+                        .find(|(name, _)| *name == enum_value.variant)
+                    {
+                        return Ok(Value::Number(discriminant));
+                    }
+                }
+
+                memory.position = Some(cast_node.position.clone());
+                Err(memory.error(RuntimeErrorKind::InvalidCast {
+                    from: value.type_name(),
+                    to: cast_node.type_name.clone(),
+                }))
+            }
+            Expression::MapNew(map_new_node) => {
+                Ok(Value::Map(MapValue::new(&map_new_node.type_name)))
+            }
+            Expression::VecNew(vec_new_node) => {
+                Ok(Value::Vec(VecValue::new(&vec_new_node.type_name)))
+            }
+            Expression::VecCall(vec_call_node) => {
+                let mut args = Vec::new();
+                for arg in vec_call_node.args.iter() {
+                    args.push(Executor::evaluate(arg, memory)?);
+                }
+
+                let method = vec_call_node.method;
+
+                Executor::with_place(
+                    &vec_call_node.vec,
+                    &vec_call_node.position,
+                    memory,
+                    |target| match target {
+                        Value::Vec(vec_value) => Executor::vec_method(vec_value, method, args),
+                        value => Err(RuntimeErrorKind::UndefinedMethod {
+                            type_name: value.type_name(),
+                            method: method.to_string(),
+                        }),
+                    },
+                )
+            }
+            Expression::MapCall(map_call_node) => {
+                let mut args = Vec::new();
+                for arg in map_call_node.args.iter() {
+                    args.push(Executor::evaluate(arg, memory)?);
+                }
+
+                let method = map_call_node.method;
+                Executor::with_place(
+                    &map_call_node.map,
+                    &map_call_node.position,
+                    memory,
+                    |target| match target {
+                        Value::Map(map_value) => Executor::map_method(map_value, method, args),
+                        value => Err(RuntimeErrorKind::UndefinedMethod {
+                            type_name: value.type_name(),
+                            method: method.to_string(),
+                        }),
+                    },
+                )
+            }
+            Expression::Contains(contains_node) => {
+                let value = Executor::evaluate(&contains_node.value, memory)?;
+                let flag = Executor::evaluate(&contains_node.flag, memory)?;
+
+                if let (Value::Flags(value), Value::Flags(flag)) = (&value, &flag) {
+                    return Ok(Value::Bool(value.bits & flag.bits == flag.bits));
+                }
+
+                memory.position = Some(contains_node.position.clone());
+                Err(memory.error(RuntimeErrorKind::TypeMismatch {
+                    op: BinaryOp::BitAnd,
+                    lhs: value.type_name(),
+                    rhs: flag.type_name(),
+                }))
+            }
+            Expression::StructFieldAccess(field_access_node) => {
+                let base = Executor::evaluate(&field_access_node.base, memory)?;
+                let field_name = &field_access_node.field.metadata.name;
+
+                let kind = match base {
+                    Value::Struct(struct_value) => {
+                        if let Some(field) = struct_value.field(field_name) {
+                            return Ok(field.clone());
+                        }
+
+                        RuntimeErrorKind::UndefinedField {
+                            type_name: struct_value.type_name.clone(),
+                            field: field_name.clone(),
                         }
                     }
+                    value => RuntimeErrorKind::UndefinedField {
+                        type_name: value.type_name(),
+                        field: field_name.clone(),
+                    },
+                };
+
+                memory.position = Some(field_access_node.position.clone());
+                Err(memory.error(kind))
+            }
+            Expression::RangeStatement(range_node) => {
+                let start = Executor::evaluate(&range_node.start, memory)?;
+                let end = Executor::evaluate(&range_node.end, memory)?;
+
+                match (start, end) {
+                    (Value::Number(start), Value::Number(end)) => Ok(Value::Range(RangeValue {
+                        start,
+                        end,
+                        inclusive: range_node.inclusive,
+                    })),
+                    (start, end) => Err(memory.error(RuntimeErrorKind::InvalidRange {
+                        start: start.type_name(),
+                        end: end.type_name(),
+                    })),
+                }
+            }
+            Expression::Error(position) => {
+                memory.position = Some(position.clone());
+                Err(memory.error(RuntimeErrorKind::Unparsed))
+            }
+            statement => match Executor::execute_statement(statement, memory)? {
+                Flow::Next(value) | Flow::Return(value) => Ok(value),
+                Flow::Break | Flow::Continue => Ok(Value::None),
+            },
+        }
+    }
+
+    fn apply_binary_op(lhs: Value, op: &BinaryOp, rhs: Value) -> Result<Value, RuntimeErrorKind> {
+        let value = match (&lhs, &rhs) {
+            (Value::Number(a), Value::Number(b)) => match op {
+                BinaryOp::Add => Some(Value::Number(a.wrapping_add(*b))),
+                BinaryOp::Sub => Some(Value::Number(a.wrapping_sub(*b))),
+                BinaryOp::Mul => Some(Value::Number(a.wrapping_mul(*b))),
+                BinaryOp::Div => match a.checked_div(*b) {
+                    Some(n) => Some(Value::Number(n)),
+                    None if *b == 0 => return Err(RuntimeErrorKind::DivisionByZero),
+                    None => Some(Value::Number(a.wrapping_div(*b))),
+                },
+                BinaryOp::BitOr => Some(Value::Number(a | b)),
+                BinaryOp::BitAnd => Some(Value::Number(a & b)),
+                BinaryOp::BitXor => Some(Value::Number(a ^ b)),
+                _ => Executor::compare(op, a.partial_cmp(b)),
+            },
+            (Value::Flags(a), Value::Flags(b)) if a.type_name == b.type_name => {
+                let bits = match op {
+                    BinaryOp::BitOr => Some(a.bits | b.bits),
+                    BinaryOp::BitAnd => Some(a.bits & b.bits),
+                    BinaryOp::BitXor => Some(a.bits ^ b.bits),
+                    _ => None,
+                };
+
+                match bits {
+                    Some(bits) => Some(Value::Flags(FlagsValue { bits, ..a.clone() })),
+                    None => Executor::compare(op, a.bits.partial_cmp(&b.bits))
+                        .filter(|_| matches!(op, BinaryOp::Eq | BinaryOp::Ne)),
                 }
             }
-            Expression::StructFieldAccess(..) => {}
-            Expression::BinaryOp(..) => {}
-            Expression::Literal(..) => {}
+            (Value::Float(a), Value::Float(b)) => Executor::apply_float_op(*a, op, *b),
+            (Value::Number(a), Value::Float(b)) => Executor::apply_float_op(*a as f32, op, *b),
+            (Value::Float(a), Value::Number(b)) => Executor::apply_float_op(*a, op, *b as f32),
+            (Value::String(a), b) if matches!(op, BinaryOp::Add) => {
+                Some(Value::String(format!("{a}{b}")))
+            }
+            (Value::String(a), Value::String(b)) => Executor::compare(op, a.partial_cmp(b)),
+            (Value::Char(a), Value::Char(b)) => Executor::compare(op, a.partial_cmp(b)),
+            (a, b) => match op {
+                BinaryOp::Eq => Some(Value::Bool(a == b)),
+                BinaryOp::Ne => Some(Value::Bool(a != b)),
+                _ => None,
+            },
+        };
+
+        value.ok_or_else(|| RuntimeErrorKind::TypeMismatch {
+            op: op.clone(),
+            lhs: lhs.type_name(),
+            rhs: rhs.type_name(),
+        })
+    }
+
+    fn apply_float_op(a: f32, op: &BinaryOp, b: f32) -> Option<Value> {
+        match op {
+            BinaryOp::Add => Some(Value::Float(a + b)),
+            BinaryOp::Sub => Some(Value::Float(a - b)),
+            BinaryOp::Mul => Some(Value::Float(a * b)),
+            BinaryOp::Div => Some(Value::Float(a / b)),
+            _ => Executor::compare(op, a.partial_cmp(&b)),
         }
+    }
+
+    fn compare(op: &BinaryOp, ordering: Option<Ordering>) -> Option<Value> {
+        let ordering = if let Some(ordering) = ordering {
+            ordering
+        } else {
+            return Some(Value::Bool(matches!(op, BinaryOp::Ne)));
+        };
 
-        None
+        match op {
+            BinaryOp::Eq => Some(Value::Bool(ordering.is_eq())),
+            BinaryOp::Ne => Some(Value::Bool(ordering.is_ne())),
+            BinaryOp::Lt => Some(Value::Bool(ordering.is_lt())),
+            BinaryOp::Lte => Some(Value::Bool(ordering.is_le())),
+            BinaryOp::Gt => Some(Value::Bool(ordering.is_gt())),
+            BinaryOp::Gte => Some(Value::Bool(ordering.is_ge())),
+            _ => None,
+        }
     }
 }
@@ -1,12 +1,17 @@
-use std::fmt::{Display, Write};
+use std::fmt::Display;
 
 use crate::{
+    ast_printer::AstPrinter,
     nodes::{
-        AssignNode, BinaryOpNode, FieldAccessNode, FieldAssignNode, ForNode, FunCallNode, IfNode,
-        ImplFunCallNode, ImplNode, LetNode, ProcDefNode, RangeNode, ReturnNode, StructDefNode,
-        StructInstanceNode, VariableNode, WhileNode,
+        AssignNode, BinaryOp, BinaryOpNode, CastNode, ClosureCallNode, ClosureNode, CoalesceNode,
+        CompareChainNode, ContainsNode, DynCallNode, EnumDefNode, EnumFromNode, EnumInstanceNode,
+        EnumParseNode, FieldAccessNode, FieldAssignNode, ForNode, FunCallNode, IfNode,
+        ImplFunCallNode, ImplNode, LetNode, MapCallNode, MapNewNode, MatchNode, PatternNode,
+        PipeNode, ProcDefNode, ProcType, RangeNode, ReturnNode, StructDefNode, StructInstanceNode,
+        TraitDefNode, VariableNode, VariantNameNode, VecCallNode, VecNewNode, WhileNode,
     },
-    token::{LiteralType, Token},
+    parser::UNKNOWN_TYPE,
+    token::{LiteralType, Position, Span, Token},
 };
 
 #[derive(Debug, Clone)]
@@ -14,235 +19,323 @@ pub enum Expression {
     IfStatement(IfNode),
     WhileStatement(WhileNode),
     ForLoop(ForNode),
+    MatchStatement(MatchNode),
     RangeStatement(RangeNode),
     LetStatement(LetNode),
     AssignStatement(AssignNode),
     ReturnStatement(ReturnNode),
+    BreakStatement(Span),
+    ContinueStatement(Span),
     Variable(VariableNode),
     ProcDef(ProcDefNode),
     FunCall(FunCallNode),
+    Closure(ClosureNode),
+    ClosureCall(ClosureCallNode),
     StructDef(StructDefNode),
+    EnumDef(EnumDefNode),
+    EnumInstance(EnumInstanceNode),
+    EnumFrom(EnumFromNode),
+    EnumParse(EnumParseNode),
+    VariantName(VariantNameNode),
+    Cast(CastNode),
+    Contains(ContainsNode),
     ImplStatement(ImplNode),
     ImplFunCall(ImplFunCallNode),
+    TraitDef(TraitDefNode),
+    DynCall(DynCallNode),
+    MapNew(MapNewNode),
+    MapCall(MapCallNode),
+    VecNew(VecNewNode),
+    VecCall(VecCallNode),
+    Pipe(PipeNode),
+    CompareChain(CompareChainNode),
+    Coalesce(CoalesceNode),
     StructInstance(StructInstanceNode),
     StructFieldAssign(FieldAssignNode),
     StructFieldAccess(FieldAccessNode),
     BinaryOp(BinaryOpNode),
-    Literal(Token, LiteralType),
+    Literal(Token<'static>, LiteralType),
+    Error(Position),
 }
 
-impl Display for Expression {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Expression::IfStatement(if_node) => {
-                let mut statements = String::new();
-                if !if_node.statements.is_empty() {
-                    statements.push('\n');
-                }
-                for statement in if_node.statements.iter() {
-                    statements
-                        .write_fmt(format_args!("\t\t\t{statement}\n"))
-                        .unwrap();
-                }
-                if !if_node.statements.is_empty() {
-                    statements.push_str("\t\t");
-                }
-
-                f.write_fmt(format_args!("If({}: [{statements}])", if_node.value))
-            }
-            Expression::WhileStatement(while_node) => {
-                let mut statements = String::new();
-                if !while_node.statements.is_empty() {
-                    statements.push('\n');
-                }
-                for statement in while_node.statements.iter() {
-                    statements
-                        .write_fmt(format_args!("\t\t\t{statement}\n"))
-                        .unwrap();
-                }
-                if !while_node.statements.is_empty() {
-                    statements.push_str("\t\t");
-                }
+impl Expression {
+    pub fn to_dot(&self) -> String {
+        AstPrinter::new().print_dot(std::slice::from_ref(self))
+    }
 
-                f.write_fmt(format_args!("While({}: [{statements}])", while_node.value))
+    pub fn type_name(&self) -> String {
+        match self {
+            Expression::Literal(_, lt) => lt.type_name(),
+            Expression::Variable(variable_node) => variable_node.metadata.type_name.clone(),
+            Expression::FunCall(fun_call_node) => fun_call_node
+                .proc_def
+                .return_type
+                .clone()
+                .unwrap_or_else(|| String::from("None")),
+            Expression::Closure(closure_node) => ProcType::of(&closure_node.proc_def).to_string(),
+            Expression::ClosureCall(closure_call_node) => closure_call_node
+                .return_type
+                .clone()
+                .unwrap_or_else(|| String::from("None")),
+            Expression::StructInstance(struct_instance_node) => {
+                struct_instance_node.struct_def.type_name.clone()
             }
-            Expression::ForLoop(for_node) => {
-                let mut statements = String::new();
-                if !for_node.statements.is_empty() {
-                    statements.push('\n');
-                }
-                for statement in for_node.statements.iter() {
-                    statements
-                        .write_fmt(format_args!("\t\t\t{statement}\n"))
-                        .unwrap()
-                }
-                if !for_node.statements.is_empty() {
-                    statements.push_str("\t\t");
-                }
-
-                f.write_fmt(format_args!(
-                    "For({}: {}: [{statements}])",
-                    for_node.counter.metadata.name, for_node.range
-                ))
+            Expression::EnumInstance(enum_instance_node) => {
+                enum_instance_node.enum_def.type_name.clone()
             }
-            Expression::RangeStatement(range_node) => f.write_fmt(format_args!(
-                "Range({}..{})",
-                range_node.start, range_node.end
-            )),
-            Expression::LetStatement(let_node) => {
-                f.write_fmt(format_args!("Let('{}': {})", let_node.name, let_node.value))
-            }
-            Expression::AssignStatement(assign_node) => {
-                let name = assign_node.value.metadata.name.clone();
-                f.write_fmt(format_args!("Assign('{name}': {})", assign_node.new_value))
+            Expression::EnumFrom(enum_from_node) => enum_from_node.enum_def.type_name.clone(),
+            Expression::EnumParse(enum_parse_node) => enum_parse_node.enum_def.type_name.clone(),
+            Expression::VariantName(..) => String::from("String"),
+            Expression::Cast(cast_node) => cast_node.type_name.clone(),
+            Expression::Contains(..) => String::from("bool"),
+            Expression::ImplFunCall(impl_fun_call_node) => {
+                impl_fun_call_node.fun_call_node.type_name()
             }
-            Expression::ReturnStatement(return_node) => {
-                f.write_fmt(format_args!("Return({})", return_node.value))
+            Expression::DynCall(dyn_call_node) => dyn_call_node
+                .fun_call_node
+                .proc_def
+                .return_type
+                .clone()
+                .unwrap_or_else(|| String::from("None")),
+            Expression::MapNew(map_new_node) => map_new_node.type_name.clone(),
+            Expression::MapCall(map_call_node) => map_call_node.type_name.clone(),
+            Expression::VecNew(vec_new_node) => vec_new_node.type_name.clone(),
+            Expression::VecCall(vec_call_node) => vec_call_node.type_name.clone(),
+            Expression::Pipe(pipe_node) => pipe_node.call.type_name(),
+            Expression::CompareChain(..) => String::from("bool"),
+            Expression::Coalesce(coalesce_node) => match coalesce_node.value.type_name().as_str() {
+                "None" => coalesce_node.fallback.type_name(),
+                type_name => String::from(type_name),
+            },
+            Expression::StructFieldAccess(field_access_node) => {
+                field_access_node.field.metadata.type_name.clone()
             }
-            Expression::Variable(var) => f.write_fmt(format_args!(
-                "Variable('{}': {})",
-                var.metadata.name, var.value,
-            )),
-            Expression::ProcDef(proc_def) => {
-                let mut arguments = String::new();
-                if !proc_def.args.is_empty() {
-                    arguments.push('\n');
-                }
-                for arg in proc_def.args.iter() {
-                    arguments
-                        .write_fmt(format_args!("\t\t{}: {},\n", arg.name, arg.type_name))
-                        .unwrap();
-                }
-                if !proc_def.args.is_empty() {
-                    arguments.push('\t');
-                }
-
-                let mut content = String::new();
-                if !proc_def.statements.is_empty() {
-                    content.push('\n');
-                }
-                for statement in proc_def.statements.iter() {
-                    content
-                        .write_fmt(format_args!("\t\t{statement}\n"))
-                        .unwrap();
-                }
-                if !proc_def.statements.is_empty() {
-                    content.push('\t');
-                }
+            Expression::MatchStatement(match_node) => match_node
+                .arms
+                .iter()
+                .filter_map(|arm| arm.statements.last())
+                .map(|expr| expr.type_name())
+                .find(|type_name| type_name != "None" && type_name != UNKNOWN_TYPE)
+                .unwrap_or_else(|| String::from(UNKNOWN_TYPE)),
+            Expression::IfStatement(if_node) => match &if_node.else_branch {
+                Some(else_node) => [&if_node.statements, &else_node.statements]
+                    .into_iter()
+                    .filter_map(|statements| statements.last())
+                    .map(|expr| expr.type_name())
+                    .find(|type_name| type_name != "None" && type_name != UNKNOWN_TYPE)
+                    .unwrap_or_else(|| String::from("None")),
+                None => String::from("None"),
+            },
+            Expression::EnumDef(..)
+            | Expression::ProcDef(..)
+            | Expression::StructDef(..)
+            | Expression::ImplStatement(..)
+            | Expression::TraitDef(..)
+            | Expression::LetStatement(..)
+            | Expression::AssignStatement(..)
+            | Expression::StructFieldAssign(..)
+            | Expression::ReturnStatement(..)
+            | Expression::BreakStatement(..)
+            | Expression::ContinueStatement(..)
+            | Expression::WhileStatement(..)
+            | Expression::ForLoop(..) => String::from("None"),
+            Expression::BinaryOp(binary_op_node) => match binary_op_node.op {
+                BinaryOp::Eq
+                | BinaryOp::Ne
+                | BinaryOp::Lt
+                | BinaryOp::Lte
+                | BinaryOp::Gt
+                | BinaryOp::Gte => String::from("bool"),
+                _ => {
+                    let lhs = binary_op_node.lhs.type_name();
+                    let rhs = binary_op_node.rhs.type_name();
 
-                let mut return_type_str = String::from("None");
-                if let Some(rt) = proc_def.return_type.clone() {
-                    return_type_str = rt;
+                    match (lhs.as_str(), rhs.as_str()) {
+                        (UNKNOWN_TYPE, _) => rhs,
+                        ("String", _) => lhs,
+                        ("i32", "f32") | ("f32", "i32") => String::from("f32"),
+                        _ => lhs,
+                    }
                 }
+            },
+            Expression::RangeStatement(..) | Expression::Error(..) => String::from(UNKNOWN_TYPE),
+        }
+    }
 
-                f.write_fmt(format_args!(
-                    "ProcDef('{}':
-\treturn_type: {return_type_str}
-\targs: [{arguments}]
-\tcontent: [{content}]\n)\n",
-                    proc_def.name
-                ))
+    pub fn position(&self) -> Option<&Position> {
+        match self {
+            Expression::IfStatement(if_node) => Some(&if_node.position),
+            Expression::WhileStatement(while_node) => Some(&while_node.position),
+            Expression::ForLoop(for_node) => Some(&for_node.position),
+            Expression::MatchStatement(match_node) => Some(&match_node.position),
+            Expression::LetStatement(let_node) => Some(&let_node.position),
+            Expression::AssignStatement(assign_node) => Some(&assign_node.position),
+            Expression::ReturnStatement(return_node) => Some(&return_node.position),
+            Expression::ProcDef(proc_def) => Some(&proc_def.position),
+            Expression::FunCall(fun_call_node) => Some(&fun_call_node.position),
+            Expression::Closure(closure_node) => Some(&closure_node.proc_def.position),
+            Expression::ClosureCall(closure_call_node) => Some(&closure_call_node.position),
+            Expression::StructDef(struct_def) => Some(&struct_def.position),
+            Expression::EnumDef(enum_def) => Some(&enum_def.position),
+            Expression::EnumInstance(enum_instance_node) => Some(&enum_instance_node.position),
+            Expression::EnumFrom(enum_from_node) => Some(&enum_from_node.position),
+            Expression::EnumParse(enum_parse_node) => Some(&enum_parse_node.position),
+            Expression::VariantName(variant_name_node) => Some(&variant_name_node.position),
+            Expression::Cast(cast_node) => Some(&cast_node.position),
+            Expression::Contains(contains_node) => Some(&contains_node.position),
+            Expression::ImplStatement(impl_node) => Some(&impl_node.position),
+            Expression::ImplFunCall(impl_fun_call_node) => {
+                impl_fun_call_node.fun_call_node.position()
             }
-            Expression::FunCall(fun_call_node) => {
-                let mut arguments = String::new();
-
-                if !fun_call_node.args.is_empty() {
-                    arguments.push('\n');
-                }
-                for arg in fun_call_node.args.iter() {
-                    arguments
-                        .write_fmt(format_args!("\t\t\t{}: {}\n", arg.metadata.name, arg.value))
-                        .unwrap();
-                }
-                if !fun_call_node.args.is_empty() {
-                    arguments.push_str("\t\t");
-                }
-
-                let name = fun_call_node.proc_def.name.clone();
-                f.write_fmt(format_args!("FunCall('{name}': args: [{arguments}])"))
+            Expression::TraitDef(trait_def) => Some(&trait_def.position),
+            Expression::DynCall(dyn_call_node) => Some(&dyn_call_node.fun_call_node.position),
+            Expression::MapNew(map_new_node) => Some(&map_new_node.position),
+            Expression::MapCall(map_call_node) => Some(&map_call_node.position),
+            Expression::VecNew(vec_new_node) => Some(&vec_new_node.position),
+            Expression::VecCall(vec_call_node) => Some(&vec_call_node.position),
+            Expression::Pipe(pipe_node) => Some(&pipe_node.position),
+            Expression::CompareChain(compare_chain_node) => Some(&compare_chain_node.position),
+            Expression::Coalesce(coalesce_node) => Some(&coalesce_node.position),
+            Expression::StructInstance(struct_instance_node) => {
+                Some(&struct_instance_node.position)
             }
-            Expression::StructDef(struct_def) => {
-                let mut fields = String::new();
-                if !struct_def.fields.is_empty() {
-                    fields.push('\n');
-                }
-                for field in struct_def.fields.iter() {
-                    fields
-                        .write_fmt(format_args!("\t{}: {},\n", field.name, field.type_name))
-                        .unwrap();
-                }
+            Expression::StructFieldAssign(field_assign_node) => Some(&field_assign_node.position),
+            Expression::StructFieldAccess(field_access_node) => Some(&field_access_node.position),
+            Expression::BinaryOp(binary_op_node) => Some(&binary_op_node.position),
+            Expression::Literal(token, _) => Some(&token.position),
+            Expression::Error(position) => Some(position),
+            Expression::RangeStatement(..)
+            | Expression::BreakStatement(..)
+            | Expression::ContinueStatement(..)
+            | Expression::Variable(..) => None,
+        }
+    }
 
-                f.write_fmt(format_args!(
-                    "StructDef('{}': fields: [{fields}])\n",
-                    struct_def.type_name
-                ))
-            }
-            Expression::ImplStatement(impl_node) => {
-                let mut procedures = String::new();
-                if !impl_node.procedures.is_empty() {
-                    procedures.push('\n');
-                }
-                for procedure in impl_node.procedures.iter() {
-                    procedures
-                        .write_fmt(format_args!("\t\t{procedure}\n"))
-                        .unwrap()
-                }
-                if !impl_node.procedures.is_empty() {
-                    procedures.push('\t');
-                }
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Expression::IfStatement(if_node) => Some(if_node.span),
+            Expression::WhileStatement(while_node) => Some(while_node.span),
+            Expression::ForLoop(for_node) => Some(for_node.span),
+            Expression::MatchStatement(match_node) => Some(match_node.span),
+            Expression::RangeStatement(range_node) => Some(range_node.span),
+            Expression::LetStatement(let_node) => Some(let_node.span),
+            Expression::AssignStatement(assign_node) => Some(assign_node.span),
+            Expression::ReturnStatement(return_node) => Some(return_node.span),
+            Expression::BreakStatement(span) | Expression::ContinueStatement(span) => Some(*span),
+            Expression::Variable(variable_node) => Some(variable_node.span),
+            Expression::ProcDef(proc_def) => Some(proc_def.span),
+            Expression::FunCall(fun_call_node) => Some(fun_call_node.span),
+            Expression::Closure(closure_node) => Some(closure_node.proc_def.span),
+            Expression::ClosureCall(closure_call_node) => Some(closure_call_node.span),
+            Expression::StructDef(struct_def) => Some(struct_def.span),
+            Expression::EnumDef(enum_def) => Some(enum_def.span),
+            Expression::EnumInstance(enum_instance_node) => Some(enum_instance_node.span),
+            Expression::EnumFrom(enum_from_node) => Some(enum_from_node.span),
+            Expression::EnumParse(enum_parse_node) => Some(enum_parse_node.span),
+            Expression::VariantName(variant_name_node) => Some(variant_name_node.span),
+            Expression::Cast(cast_node) => Some(cast_node.span),
+            Expression::Contains(contains_node) => Some(contains_node.span),
+            Expression::ImplStatement(impl_node) => Some(impl_node.span),
+            Expression::ImplFunCall(impl_fun_call_node) => impl_fun_call_node.fun_call_node.span(),
+            Expression::TraitDef(trait_def) => Some(trait_def.span),
+            Expression::DynCall(dyn_call_node) => Some(dyn_call_node.fun_call_node.span),
+            Expression::MapNew(map_new_node) => Some(map_new_node.span),
+            Expression::MapCall(map_call_node) => Some(map_call_node.span),
+            Expression::VecNew(vec_new_node) => Some(vec_new_node.span),
+            Expression::VecCall(vec_call_node) => Some(vec_call_node.span),
+            Expression::Pipe(pipe_node) => Some(pipe_node.span),
+            Expression::CompareChain(compare_chain_node) => Some(compare_chain_node.span),
+            Expression::Coalesce(coalesce_node) => Some(coalesce_node.span),
+            Expression::StructInstance(struct_instance_node) => Some(struct_instance_node.span),
+            Expression::StructFieldAssign(field_assign_node) => Some(field_assign_node.span),
+            Expression::StructFieldAccess(field_access_node) => Some(field_access_node.span),
+            Expression::BinaryOp(binary_op_node) => Some(binary_op_node.span),
+            Expression::Literal(token, _) => Some(token.span),
+            Expression::Error(..) => None,
+        }
+    }
 
-                f.write_fmt(format_args!(
-                    "Impl('{}': [{procedures}])",
-                    impl_node.struct_def.type_name
-                ))
-            }
+    pub fn span_mut(&mut self) -> Option<&mut Span> {
+        match self {
+            Expression::IfStatement(if_node) => Some(&mut if_node.span),
+            Expression::WhileStatement(while_node) => Some(&mut while_node.span),
+            Expression::ForLoop(for_node) => Some(&mut for_node.span),
+            Expression::MatchStatement(match_node) => Some(&mut match_node.span),
+            Expression::RangeStatement(range_node) => Some(&mut range_node.span),
+            Expression::LetStatement(let_node) => Some(&mut let_node.span),
+            Expression::AssignStatement(assign_node) => Some(&mut assign_node.span),
+            Expression::ReturnStatement(return_node) => Some(&mut return_node.span),
+            Expression::BreakStatement(span) | Expression::ContinueStatement(span) => Some(span),
+            Expression::Variable(variable_node) => Some(&mut variable_node.span),
+            Expression::ProcDef(proc_def) => Some(&mut proc_def.span),
+            Expression::FunCall(fun_call_node) => Some(&mut fun_call_node.span),
+            Expression::Closure(closure_node) => Some(&mut closure_node.proc_def.span),
+            Expression::ClosureCall(closure_call_node) => Some(&mut closure_call_node.span),
+            Expression::StructDef(struct_def) => Some(&mut struct_def.span),
+            Expression::EnumDef(enum_def) => Some(&mut enum_def.span),
+            Expression::EnumInstance(enum_instance_node) => Some(&mut enum_instance_node.span),
+            Expression::EnumFrom(enum_from_node) => Some(&mut enum_from_node.span),
+            Expression::EnumParse(enum_parse_node) => Some(&mut enum_parse_node.span),
+            Expression::VariantName(variant_name_node) => Some(&mut variant_name_node.span),
+            Expression::Cast(cast_node) => Some(&mut cast_node.span),
+            Expression::Contains(contains_node) => Some(&mut contains_node.span),
+            Expression::ImplStatement(impl_node) => Some(&mut impl_node.span),
             Expression::ImplFunCall(impl_fun_call_node) => {
-                let type_name = impl_fun_call_node.impl_node.struct_def.type_name.clone();
-                f.write_fmt(format_args!(
-                    "ImplFunCall('{}': {})",
-                    type_name, impl_fun_call_node.fun_call_node
-                ))
+                impl_fun_call_node.fun_call_node.span_mut()
             }
+            Expression::TraitDef(trait_def) => Some(&mut trait_def.span),
+            Expression::DynCall(dyn_call_node) => Some(&mut dyn_call_node.fun_call_node.span),
+            Expression::MapNew(map_new_node) => Some(&mut map_new_node.span),
+            Expression::MapCall(map_call_node) => Some(&mut map_call_node.span),
+            Expression::VecNew(vec_new_node) => Some(&mut vec_new_node.span),
+            Expression::VecCall(vec_call_node) => Some(&mut vec_call_node.span),
+            Expression::Pipe(pipe_node) => Some(&mut pipe_node.span),
+            Expression::CompareChain(compare_chain_node) => Some(&mut compare_chain_node.span),
+            Expression::Coalesce(coalesce_node) => Some(&mut coalesce_node.span),
             Expression::StructInstance(struct_instance_node) => {
-                let mut fields = String::new();
-                if !struct_instance_node.fields.is_empty() {
-                    fields.push('\n');
-                }
-                for field in struct_instance_node.fields.iter() {
-                    fields
-                        .write_fmt(format_args!(
-                            "\t\t\t{}: {},\n",
-                            field.metadata.name, field.value
-                        ))
-                        .unwrap();
-                }
-                if !struct_instance_node.fields.is_empty() {
-                    fields.push_str("\t\t");
-                }
+                Some(&mut struct_instance_node.span)
+            }
+            Expression::StructFieldAssign(field_assign_node) => Some(&mut field_assign_node.span),
+            Expression::StructFieldAccess(field_access_node) => Some(&mut field_access_node.span),
+            Expression::BinaryOp(binary_op_node) => Some(&mut binary_op_node.span),
+            Expression::Literal(token, _) => Some(&mut token.span),
+            Expression::Error(..) => None,
+        }
+    }
+}
 
+impl Display for PatternNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatternNode::Wildcard => f.write_str("Wildcard"),
+            PatternNode::Literal(token, _type) => {
+                f.write_fmt(format_args!("Literal('{}': {_type:?})", token.value))
+            }
+            PatternNode::Binding(binding) => {
+                f.write_fmt(format_args!("Binding('{}')", binding.name))
+            }
+            PatternNode::Variant {
+                type_name,
+                variant,
+                bindings,
+            } => {
+                let names: Vec<&str> = bindings.iter().map(|b| b.name.as_str()).collect();
                 f.write_fmt(format_args!(
-                    "Struct('{}': fields: [{fields}])",
-                    struct_instance_node.struct_def.type_name
+                    "Variant('{type_name}::{variant}': [{}])",
+                    names.join(", ")
                 ))
             }
-            Expression::StructFieldAssign(field_assign_node) => f.write_fmt(format_args!(
-                "StructFieldAssign('{}': field: '{}': value: {})",
-                field_assign_node.struct_instance.metadata.name,
-                field_assign_node.field.metadata.name,
-                field_assign_node.new_value
+            PatternNode::Range { start, end, kind } => f.write_fmt(format_args!(
+                "Range('{}'..'{}': {kind:?})",
+                start.value, end.value
             )),
-            Expression::StructFieldAccess(field_access_node) => f.write_fmt(format_args!(
-                "StructFieldAccess('{}': field: '{}': value: {})",
-                field_access_node.struct_instance.metadata.name,
-                field_access_node.field.metadata.name,
-                field_access_node.field.value,
-            )),
-            Expression::BinaryOp(binary_op_node) => f.write_fmt(format_args!(
-                "BinaryOp({}, {:?}, {})",
-                binary_op_node.lhs, binary_op_node.op, binary_op_node.rhs
-            )),
-            Expression::Literal(token, _type) => {
-                f.write_fmt(format_args!("Literal('{}': {_type:?})", token.value))
+            PatternNode::Bind { binding, pattern } => {
+                f.write_fmt(format_args!("Bind('{}' @ {pattern})", binding.name))
+            }
+            PatternNode::Or(alternatives) => {
+                let alternatives: Vec<String> =
+                    alternatives.iter().map(|a| a.to_string()).collect();
+                f.write_fmt(format_args!("Or([{}])", alternatives.join(", ")))
             }
         }
     }
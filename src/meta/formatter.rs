@@ -0,0 +1,1265 @@
+use crate::{
+    ast_printer::AstPrinter,
+    diagnostics::Diagnostic,
+    executor::ENTRY_POINT,
+    expression::Expression,
+    lexer::Lexer,
+    nodes::{
+        BinaryOp, BinaryOpNode, ClosureNode, ClosureSyntax, CoalesceNode, CompareChainNode,
+        ContractKind, GenericType, IfNode, MatchArmNode, PatternNode, PipeNode, ProcDefNode,
+        VarMetadataNode, COALESCE_PRECEDENCE, PIPE_PRECEDENCE,
+    },
+    parser::{Parser, Program},
+    token::{LiteralType, Position, Span, Token},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct Formatter {
+    indent: usize,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self { indent: 4 }
+    }
+}
+
+impl Formatter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn format(&self, source: &str, filename: &str) -> Result<String, Vec<Diagnostic>> {
+        let (program, comments) = parse(source, filename)?;
+
+        let mut emitter = Emitter {
+            indent: self.indent,
+            out: String::new(),
+            lines: source.lines().collect(),
+            comments,
+            next_comment: 0,
+            tail: false,
+        };
+
+        let mut items = Vec::new();
+        for expr in program.iter() {
+            match expr {
+                Expression::ProcDef(proc_def) if is_script_main(proc_def) => {
+                    items.extend(proc_def.statements.iter());
+                }
+                Expression::ImplStatement(impl_node) if impl_node.derived => {}
+                Expression::ProcDef(proc_def) if proc_def.is_instance() => {}
+                Expression::StructDef(struct_def) if struct_def.is_instance() => {}
+                expr => items.push(expr),
+            }
+        }
+
+        let mut last_row = None;
+        let mut keyed: Vec<(Option<u32>, &Expression)> = items
+            .into_iter()
+            .map(|expr| {
+                last_row = expression_row(expr).or(last_row);
+                (last_row, expr)
+            })
+            .collect();
+        keyed.sort_by_key(|(row, _)| *row);
+
+        let items: Vec<&Expression> = keyed.into_iter().map(|(_, expr)| expr).collect();
+
+        emitter.items(&items, 0, None, false);
+        emitter.flush_comments(0, |_, _| true);
+
+        let formatted = emitter.out;
+
+        let (reparsed, _) = parse(&formatted, filename)?;
+        let printer = AstPrinter::new();
+        if printer.print(&program) != printer.print(&reparsed) {
+            return Err(vec![Diagnostic::error(
+                None,
+                format!("formatting '{filename}' would change its meaning"),
+            )]);
+        }
+
+        Ok(formatted)
+    }
+}
+
+type Comments = Vec<(Position, String)>;
+
+fn parse(source: &str, filename: &str) -> Result<(Program, Comments), Vec<Diagnostic>> {
+    let lexer = Lexer::new(source, String::from(filename));
+    let mut parser = Parser::new(lexer);
+    let Ok(program) = parser.parse_program();
+
+    if parser.diagnostics().iter().any(|d| d.is_error()) {
+        return Err(parser.diagnostics().to_vec());
+    }
+
+    Ok((program, parser.comments().to_vec()))
+}
+
+fn is_script_main(proc_def: &ProcDefNode) -> bool {
+    proc_def.name == ENTRY_POINT && proc_def.position.filename.is_empty()
+}
+
+fn is_definition(expr: &Expression) -> bool {
+    matches!(
+        expr,
+        Expression::ProcDef(..)
+            | Expression::StructDef(..)
+            | Expression::EnumDef(..)
+            | Expression::ImplStatement(..)
+            | Expression::TraitDef(..)
+    ) || nested_proc(expr).is_some()
+}
+
+fn nested_proc(expr: &Expression) -> Option<&ClosureNode> {
+    let Expression::LetStatement(let_node) = expr else {
+        return None;
+    };
+
+    match let_node.value.as_ref() {
+        Expression::Closure(closure_node) if closure_node.syntax == ClosureSyntax::Named => {
+            Some(closure_node)
+        }
+        _ => None,
+    }
+}
+
+fn is_simple_statement(expr: &Expression) -> bool {
+    !is_block(expr)
+        && !matches!(
+            expr,
+            Expression::LetStatement(..)
+                | Expression::AssignStatement(..)
+                | Expression::StructFieldAssign(..)
+                | Expression::ReturnStatement(..)
+                | Expression::BreakStatement(..)
+                | Expression::ContinueStatement(..)
+        )
+}
+
+fn parameters(args: &[VarMetadataNode]) -> String {
+    args.iter()
+        .map(|arg| {
+            let mutability = if arg.mutable { "mut " } else { "" };
+            if arg.name == "self" {
+                format!("{mutability}self")
+            } else {
+                format!("{mutability}{}: {}", arg.name, arg.type_name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn generics(params: &[String]) -> String {
+    if params.is_empty() {
+        return String::new();
+    }
+
+    format!("<{}>", params.join(", "))
+}
+
+fn is_block(expr: &Expression) -> bool {
+    is_definition(expr)
+        || matches!(
+            expr,
+            Expression::IfStatement(..)
+                | Expression::WhileStatement(..)
+                | Expression::ForLoop(..)
+                | Expression::MatchStatement(..)
+        )
+}
+
+fn is_inline_if(if_node: &IfNode) -> bool {
+    let header = row(&if_node.position);
+    let simple = |statements: &[Expression]| match statements {
+        [expr] => {
+            !is_block(expr)
+                && !matches!(
+                    expr,
+                    Expression::LetStatement(..)
+                        | Expression::AssignStatement(..)
+                        | Expression::StructFieldAssign(..)
+                        | Expression::StructInstance(..)
+                        | Expression::ReturnStatement(..)
+                        | Expression::BreakStatement(..)
+                        | Expression::ContinueStatement(..)
+                )
+                && expression_row(expr).is_none_or(|r| Some(r) == header)
+        }
+        _ => false,
+    };
+
+    let Some(else_node) = &if_node.else_branch else {
+        return false;
+    };
+
+    if !simple(&if_node.statements) || row(&else_node.position) != header {
+        return false;
+    }
+
+    match else_node.statements.as_slice() {
+        [Expression::IfStatement(nested)] => {
+            row(&nested.position) == header && is_inline_if(nested)
+        }
+        statements => simple(statements),
+    }
+}
+
+fn is_implicit_return(expr: &Expression) -> bool {
+    matches!(expr, Expression::ReturnStatement(return_node) if return_node.implicit)
+}
+
+fn row(position: &Position) -> Option<u32> {
+    if position.filename.is_empty() {
+        return None;
+    }
+
+    Some(position.row)
+}
+
+fn same_position(a: &Position, b: &Position) -> bool {
+    a.filename == b.filename && a.row == b.row && a.column == b.column
+}
+
+fn expression_row(expr: &Expression) -> Option<u32> {
+    match expr {
+        Expression::IfStatement(if_node) => row(&if_node.position),
+        Expression::WhileStatement(while_node) => row(&while_node.position),
+        Expression::ForLoop(for_node) => row(&for_node.position),
+        Expression::MatchStatement(match_node) => row(&match_node.position),
+        Expression::LetStatement(let_node) => row(&let_node.position),
+        Expression::AssignStatement(assign_node) => row(&assign_node.position),
+        Expression::ReturnStatement(return_node) => row(&return_node.position),
+        Expression::ProcDef(proc_def) => proc_def
+            .contracts
+            .iter()
+            .filter_map(|contract| row(&contract.position))
+            .chain(row(&proc_def.position))
+            .min(),
+        Expression::FunCall(fun_call_node) => row(&fun_call_node.position),
+        Expression::Closure(closure_node) => row(&closure_node.proc_def.position),
+        Expression::ClosureCall(closure_call_node) => row(&closure_call_node.position),
+        Expression::StructDef(struct_def) => row(&struct_def.position),
+        Expression::EnumDef(enum_def) => row(&enum_def.position),
+        Expression::EnumInstance(enum_instance_node) => row(&enum_instance_node.position),
+        Expression::EnumFrom(enum_from_node) => row(&enum_from_node.position),
+        Expression::EnumParse(enum_parse_node) => row(&enum_parse_node.position),
+        Expression::VariantName(variant_name_node) => expression_row(&variant_name_node.value),
+        Expression::Cast(cast_node) => expression_row(&cast_node.value),
+        Expression::Contains(contains_node) => expression_row(&contains_node.value),
+        Expression::ImplStatement(impl_node) => row(&impl_node.position),
+        Expression::ImplFunCall(impl_fun_call_node) => {
+            expression_row(&impl_fun_call_node.fun_call_node)
+        }
+        Expression::TraitDef(trait_def) => row(&trait_def.position),
+        Expression::DynCall(dyn_call_node) => row(&dyn_call_node.fun_call_node.position),
+        Expression::Pipe(pipe_node) => expression_row(&pipe_node.call),
+        Expression::Coalesce(coalesce_node) => expression_row(&coalesce_node.value),
+        Expression::MapNew(map_new_node) => row(&map_new_node.position),
+        Expression::MapCall(map_call_node) => expression_row(&map_call_node.map),
+        Expression::VecNew(vec_new_node) => row(&vec_new_node.position),
+        Expression::VecCall(vec_call_node) => expression_row(&vec_call_node.vec),
+        Expression::CompareChain(compare_chain_node) => row(&compare_chain_node.position),
+        Expression::StructInstance(struct_instance_node) => row(&struct_instance_node.position),
+        Expression::StructFieldAssign(field_assign_node) => row(&field_assign_node.position),
+        Expression::StructFieldAccess(field_access_node) => row(&field_access_node.position),
+        Expression::BinaryOp(binary_op_node) => row(&binary_op_node.position),
+        Expression::Literal(token, _) => row(&token.position),
+        Expression::Error(position) => row(position),
+        Expression::RangeStatement(..)
+        | Expression::BreakStatement(..)
+        | Expression::ContinueStatement(..)
+        | Expression::Variable(..) => None,
+    }
+}
+
+fn arm_row(arm: &MatchArmNode) -> Option<u32> {
+    if let Some(position) = arm.pattern.position() {
+        return row(position);
+    }
+
+    arm.guard
+        .as_deref()
+        .and_then(expression_row)
+        .or_else(|| arm.statements.first().and_then(expression_row))
+}
+
+fn escape(value: &str, quote: char) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            '\\' => escaped.push_str("\\\\"),
+            '{' | '}' if quote == '"' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn literal(token: &Token, lt: LiteralType, lines: &[&str]) -> String {
+    match lt {
+        LiteralType::String => format!("\"{}\"", escape(&token.value, '"')),
+        LiteralType::Char => format!("'{}'", escape(&token.value, '\'')),
+        LiteralType::Number | LiteralType::Float => {
+            written_number(token, lines).unwrap_or_else(|| token.value.to_string())
+        }
+        _ => token.value.to_string(),
+    }
+}
+
+// the lexer stores numbers in decimal, hex, octal, binary and '_' separated ones keep their spelling
+fn written_number(token: &Token, lines: &[&str]) -> Option<String> {
+    let Span { start, end } = token.span;
+    if start.row != end.row || end.column <= start.column {
+        return None;
+    }
+
+    let text: String = lines
+        .get(start.row as usize)?
+        .chars()
+        .skip(start.column as usize)
+        .take((end.column - start.column) as usize)
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    let negative = token.value.starts_with('-');
+    let digits = match text.strip_prefix('-') {
+        Some(digits) if negative => digits,
+        None if !negative => text.as_str(),
+        _ => return None,
+    };
+
+    let mut lexer = Lexer::new(digits, String::new());
+    match (lexer.next(), lexer.next()) {
+        (Some(lexed), None) if lexed.value == token.value.trim_start_matches('-') => Some(text),
+        _ => None,
+    }
+}
+
+fn negated_operand(binary_op_node: &BinaryOpNode) -> Option<&Expression> {
+    if binary_op_node.op != BinaryOp::Sub {
+        return None;
+    }
+
+    if let Expression::Literal(token, LiteralType::Number) = binary_op_node.lhs.as_ref() {
+        if token.value == "0" && same_position(&token.position, &binary_op_node.position) {
+            return Some(&binary_op_node.rhs);
+        }
+    }
+
+    None
+}
+
+fn interpolation_parts(binary_op_node: &BinaryOpNode) -> Option<Vec<&Expression>> {
+    let position = &binary_op_node.position;
+
+    let mut parts = vec![binary_op_node.rhs.as_ref()];
+    let mut lhs = binary_op_node.lhs.as_ref();
+
+    loop {
+        match lhs {
+            Expression::BinaryOp(node)
+                if node.op == BinaryOp::Add && same_position(&node.position, position) =>
+            {
+                parts.push(&node.rhs);
+                lhs = &node.lhs;
+            }
+            Expression::Literal(token, LiteralType::String)
+                if same_position(&token.position, position) =>
+            {
+                parts.push(lhs);
+                parts.reverse();
+                return Some(parts);
+            }
+            _ => return None,
+        }
+    }
+}
+
+struct Emitter<'a> {
+    indent: usize,
+    out: String,
+    lines: Vec<&'a str>,
+    comments: Comments,
+    next_comment: usize,
+    tail: bool,
+}
+
+impl Emitter<'_> {
+    fn pad(&mut self, depth: usize) {
+        self.out.push_str(&" ".repeat(self.indent * depth));
+    }
+
+    fn is_blank(&self, row: u32) -> bool {
+        self.lines
+            .get(row as usize)
+            .is_some_and(|line| line.trim().is_empty())
+    }
+
+    fn line_indent(&self, row: u32) -> usize {
+        self.lines.get(row as usize).map_or(0, |line| {
+            line.chars().take_while(|c| c.is_whitespace()).count()
+        })
+    }
+
+    fn blank_line(&mut self) {
+        if self.out.is_empty() || self.out.ends_with("{\n") || self.out.ends_with("\n\n") {
+            return;
+        }
+
+        self.out.push('\n');
+    }
+
+    fn flush_comments<F>(&mut self, depth: usize, belongs: F)
+    where
+        F: Fn(&Position, usize) -> bool,
+    {
+        while let Some((position, text)) = self.comments.get(self.next_comment).cloned() {
+            if !belongs(&position, self.line_indent(position.row)) {
+                break;
+            }
+
+            if position.row > 0 && self.is_blank(position.row - 1) {
+                self.blank_line();
+            }
+
+            self.pad(depth);
+            self.out.push_str(&text);
+            self.out.push('\n');
+
+            self.next_comment += 1;
+        }
+    }
+
+    fn begin_item(&mut self, row: Option<u32>, depth: usize) {
+        let Some(row) = row else {
+            return;
+        };
+
+        self.flush_comments(depth, |position, _| position.row < row);
+
+        if row > 0 && self.is_blank(row - 1) {
+            self.blank_line();
+        }
+    }
+
+    fn trailing_comment(&mut self, row: Option<u32>) {
+        let Some(row) = row else {
+            return;
+        };
+
+        if let Some((position, text)) = self.comments.get(self.next_comment) {
+            if position.row == row && !text.contains('\n') {
+                self.out.push(' ');
+                self.out.push_str(text);
+                self.next_comment += 1;
+            }
+        }
+    }
+
+    fn items(&mut self, items: &[&Expression], depth: usize, bound: Option<u32>, tail: bool) {
+        for (i, expr) in items.iter().enumerate() {
+            let next_row = items[i + 1..]
+                .iter()
+                .find_map(|expr| expression_row(expr))
+                .or(bound);
+
+            if i > 0 && (is_definition(expr) || is_definition(items[i - 1])) {
+                self.blank_line();
+            }
+
+            let row = expression_row(expr);
+            self.begin_item(row, depth);
+
+            let start = self.out.len();
+
+            self.pad(depth);
+            self.expr(expr, depth, next_row);
+
+            let value = tail && i + 1 == items.len();
+            if !is_block(expr) && !is_implicit_return(expr) && !value {
+                self.out.push(';');
+            }
+
+            if !self.out[start..].contains('\n') {
+                self.trailing_comment(row);
+            }
+
+            self.out.push('\n');
+        }
+    }
+
+    fn block(
+        &mut self,
+        statements: &[Expression],
+        depth: usize,
+        header: Option<u32>,
+        bound: Option<u32>,
+    ) {
+        let statements: Vec<&Expression> = statements.iter().collect();
+        self.braced(&statements, depth, header, bound);
+    }
+
+    fn braced(
+        &mut self,
+        items: &[&Expression],
+        depth: usize,
+        header: Option<u32>,
+        bound: Option<u32>,
+    ) {
+        let tail = std::mem::take(&mut self.tail);
+        let header_indent = header.map_or(0, |row| self.line_indent(row));
+        let belongs = |position: &Position, indent: usize| {
+            bound.is_none_or(|bound| position.row < bound) && indent > header_indent
+        };
+
+        let has_comments = self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|(position, _)| belongs(position, self.line_indent(position.row)));
+
+        if items.is_empty() && !has_comments {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push_str("{\n");
+        self.items(items, depth + 1, bound, tail);
+
+        self.flush_comments(depth + 1, belongs);
+
+        self.pad(depth);
+        self.out.push('}');
+    }
+
+    fn expr(&mut self, expr: &Expression, depth: usize, bound: Option<u32>) {
+        match expr {
+            Expression::IfStatement(if_node) => {
+                self.out.push_str("if ");
+                self.expr(&if_node.value, depth, bound);
+                self.out.push(' ');
+
+                let Some(else_node) = &if_node.else_branch else {
+                    self.block(&if_node.statements, depth, row(&if_node.position), bound);
+                    return;
+                };
+
+                let valued = expr.type_name() != "None";
+                if valued && is_inline_if(if_node) {
+                    self.inline_branch(&if_node.statements, depth, bound);
+                    self.out.push_str(" else ");
+
+                    match else_node.statements.as_slice() {
+                        [nested @ Expression::IfStatement(..)] => self.expr(nested, depth, bound),
+                        statements => self.inline_branch(statements, depth, bound),
+                    }
+                    return;
+                }
+
+                let else_row = row(&else_node.position);
+                self.tail = valued;
+                self.block(
+                    &if_node.statements,
+                    depth,
+                    row(&if_node.position),
+                    else_row.or(bound),
+                );
+                self.out.push_str(" else ");
+
+                match else_node.statements.as_slice() {
+                    [nested @ Expression::IfStatement(..)] => self.expr(nested, depth, bound),
+                    statements => {
+                        self.tail = valued;
+                        self.block(statements, depth, else_row, bound);
+                    }
+                }
+            }
+            Expression::WhileStatement(while_node) => {
+                self.out.push_str("while ");
+                self.expr(&while_node.value, depth, bound);
+                self.out.push(' ');
+                self.block(
+                    &while_node.statements,
+                    depth,
+                    row(&while_node.position),
+                    bound,
+                );
+            }
+            Expression::ForLoop(for_node) => {
+                self.out.push_str("for ");
+                self.out.push_str(&for_node.counter.metadata.name);
+                self.out.push_str(" in ");
+                self.expr(&for_node.range, depth, bound);
+                self.out.push(' ');
+                self.block(&for_node.statements, depth, row(&for_node.position), bound);
+            }
+            Expression::MatchStatement(match_node) => {
+                self.out.push_str("match ");
+                self.expr(&match_node.value, depth, bound);
+                self.out.push_str(" {\n");
+
+                for (i, arm) in match_node.arms.iter().enumerate() {
+                    let next_row = match_node.arms[i + 1..].iter().find_map(arm_row).or(bound);
+
+                    self.begin_item(arm_row(arm), depth + 1);
+                    self.pad(depth + 1);
+                    self.arm(arm, depth + 1, next_row);
+                    self.out.push('\n');
+                }
+
+                self.pad(depth);
+                self.out.push('}');
+            }
+            Expression::RangeStatement(range_node) => {
+                self.expr(&range_node.start, depth, bound);
+                self.out
+                    .push_str(if range_node.inclusive { "..=" } else { ".." });
+                self.expr(&range_node.end, depth, bound);
+            }
+            Expression::LetStatement(let_node) => {
+                if let Some(closure_node) = nested_proc(expr) {
+                    self.proc_def(&closure_node.proc_def, depth, bound);
+                    return;
+                }
+
+                self.out.push_str("let ");
+                if let_node.mutable {
+                    self.out.push_str("mut ");
+                }
+                self.out.push_str(&let_node.name);
+                if let_node.annotated {
+                    self.out.push_str(": ");
+                    self.out.push_str(&let_node.type_name);
+                }
+                self.out.push_str(" = ");
+                self.expr(&let_node.value, depth, bound);
+            }
+            Expression::AssignStatement(assign_node) => {
+                self.out.push_str(&assign_node.value.metadata.name);
+                self.out.push_str(" = ");
+                self.expr(&assign_node.new_value, depth, bound);
+            }
+            Expression::ReturnStatement(return_node) => {
+                if !return_node.implicit {
+                    self.out.push_str("return ");
+                }
+                self.expr(&return_node.value, depth, bound);
+            }
+            Expression::BreakStatement(..) => self.out.push_str("break"),
+            Expression::ContinueStatement(..) => self.out.push_str("continue"),
+            Expression::Variable(variable_node) => {
+                self.out.push_str(&variable_node.metadata.name);
+            }
+            Expression::ProcDef(proc_def) => self.proc_def(proc_def, depth, bound),
+            Expression::FunCall(fun_call_node) => {
+                let name = &fun_call_node.proc_def.name;
+                match GenericType::parse(name) {
+                    Some(generic_type) => self.out.push_str(&generic_type.name),
+                    None => self.out.push_str(name),
+                }
+                self.out.push_str(&generics(&fun_call_node.type_args));
+                self.args(
+                    fun_call_node.args.iter().map(|arg| arg.value.as_ref()),
+                    depth,
+                    bound,
+                );
+            }
+            Expression::Closure(closure_node) => self.closure(closure_node, depth, bound),
+            Expression::ClosureCall(closure_call_node) => {
+                self.expr(&closure_call_node.callee, depth, bound);
+                self.args(closure_call_node.args.iter(), depth, bound);
+            }
+            Expression::StructDef(struct_def) => {
+                if !struct_def.derives.is_empty() {
+                    self.out
+                        .push_str(&format!("@derive({})\n", struct_def.derives.join(", ")));
+                    self.pad(depth);
+                }
+
+                self.out.push_str("struct ");
+                self.out.push_str(&struct_def.type_name);
+                self.out.push_str(&generics(&struct_def.generics));
+
+                if struct_def.fields.is_empty() {
+                    self.out.push_str(" {}");
+                    return;
+                }
+
+                self.out.push_str(" {\n");
+                for field in struct_def.fields.iter() {
+                    self.pad(depth + 1);
+                    self.out
+                        .push_str(&format!("{}: {},\n", field.name, field.type_name));
+                }
+                self.pad(depth);
+                self.out.push('}');
+            }
+            Expression::EnumDef(enum_def) => {
+                if enum_def.flags {
+                    self.out.push_str("@flags\n");
+                    self.pad(depth);
+                }
+
+                self.out.push_str("enum ");
+                self.out.push_str(&enum_def.type_name);
+
+                if enum_def.variants.is_empty() {
+                    self.out.push_str(" {}");
+                    return;
+                }
+
+                self.out.push_str(" {\n");
+                for variant in enum_def.variants.iter() {
+                    self.pad(depth + 1);
+                    self.out.push_str(&variant.name);
+
+                    if !variant.fields.is_empty() {
+                        let fields = variant
+                            .fields
+                            .iter()
+                            .map(|field| format!("{}: {}", field.name, field.type_name))
+                            .collect::<Vec<_>>();
+
+                        self.out.push_str(&format!("({})", fields.join(", ")));
+                    }
+
+                    if let Some(discriminant) = variant.discriminant {
+                        self.out.push_str(&format!(" = {discriminant}"));
+                    }
+
+                    self.out.push_str(",\n");
+                }
+                self.pad(depth);
+                self.out.push('}');
+            }
+            Expression::EnumInstance(enum_instance_node) => {
+                self.out.push_str(&format!(
+                    "{}::{}",
+                    enum_instance_node.enum_def.type_name, enum_instance_node.variant
+                ));
+
+                if !enum_instance_node.fields.is_empty() {
+                    let fields = enum_instance_node.fields.iter().map(|f| f.value.as_ref());
+                    self.args(fields, depth, bound);
+                }
+            }
+            Expression::EnumFrom(enum_from_node) => {
+                self.out.push_str(&enum_from_node.enum_def.type_name);
+                self.out.push_str("::from");
+                self.args(std::iter::once(enum_from_node.value.as_ref()), depth, bound);
+            }
+            Expression::EnumParse(enum_parse_node) => {
+                self.out.push_str(&enum_parse_node.enum_def.type_name);
+                self.out.push_str("::parse");
+                self.args(
+                    std::iter::once(enum_parse_node.value.as_ref()),
+                    depth,
+                    bound,
+                );
+            }
+            Expression::VariantName(variant_name_node) => {
+                self.expr(&variant_name_node.value, depth, bound);
+                self.out.push_str(".to_string()");
+            }
+            Expression::Cast(cast_node) => {
+                self.expr(&cast_node.value, depth, bound);
+                self.out.push_str(" as ");
+                self.out.push_str(&cast_node.type_name);
+            }
+            Expression::Contains(contains_node) => {
+                self.expr(&contains_node.value, depth, bound);
+                self.out.push_str(".contains");
+                self.args(std::iter::once(contains_node.flag.as_ref()), depth, bound);
+            }
+            Expression::ImplStatement(impl_node) => {
+                self.out.push_str("impl ");
+                if let Some(trait_name) = &impl_node.trait_name {
+                    self.out.push_str(trait_name);
+                    self.out.push_str(" for ");
+                }
+                self.out.push_str(&impl_node.struct_def.type_name);
+                self.out.push(' ');
+
+                let procedures: Vec<&Expression> = impl_node.procedures.iter().collect();
+                self.braced(&procedures, depth, row(&impl_node.position), bound);
+            }
+            Expression::ImplFunCall(impl_fun_call_node) => {
+                let Expression::FunCall(fun_call_node) = impl_fun_call_node.fun_call_node.as_ref()
+                else {
+                    return;
+                };
+
+                let mut args = fun_call_node.args.iter().map(|arg| arg.value.as_ref());
+
+                if impl_fun_call_node.method {
+                    if let Some(receiver) = args.next() {
+                        self.expr(receiver, depth, bound);
+                    }
+                    self.out.push('.');
+                } else {
+                    self.out
+                        .push_str(&impl_fun_call_node.impl_node.struct_def.type_name);
+                    self.out.push_str("::");
+                }
+
+                self.out.push_str(&fun_call_node.proc_def.name);
+                self.args(args, depth, bound);
+            }
+            Expression::TraitDef(trait_def) => {
+                self.out.push_str("trait ");
+                self.out.push_str(&trait_def.name);
+
+                if trait_def.methods.is_empty() {
+                    self.out.push_str(" {}");
+                    return;
+                }
+
+                self.out.push_str(" {\n");
+                for method in trait_def.methods.iter() {
+                    self.pad(depth + 1);
+                    self.out.push_str(&format!(
+                        "proc {}({})",
+                        method.name,
+                        parameters(&method.args)
+                    ));
+
+                    if let Some(return_type) = &method.return_type {
+                        self.out.push_str(": ");
+                        self.out.push_str(return_type);
+                    }
+
+                    self.out.push_str(";\n");
+                }
+                self.pad(depth);
+                self.out.push('}');
+            }
+            Expression::DynCall(dyn_call_node) => {
+                let fun_call_node = &dyn_call_node.fun_call_node;
+                let mut args = fun_call_node.args.iter().map(|arg| arg.value.as_ref());
+
+                if let Some(receiver) = args.next() {
+                    self.expr(receiver, depth, bound);
+                }
+                self.out.push('.');
+                self.out.push_str(&fun_call_node.proc_def.name);
+                self.args(args, depth, bound);
+            }
+            Expression::Pipe(pipe_node) => self.pipe(pipe_node, depth, bound),
+            Expression::Coalesce(coalesce_node) => self.coalesce(coalesce_node, depth, bound),
+            Expression::CompareChain(compare_chain_node) => {
+                self.compare_chain(compare_chain_node, depth, bound)
+            }
+            Expression::MapNew(map_new_node) => {
+                if map_new_node.explicit {
+                    self.out.push_str(&map_new_node.type_name);
+                } else {
+                    self.out.push_str("Map");
+                }
+                self.out.push_str("()");
+            }
+            Expression::MapCall(map_call_node) => {
+                self.expr(&map_call_node.map, depth, bound);
+                self.out.push('.');
+                self.out.push_str(&map_call_node.method.to_string());
+                self.args(map_call_node.args.iter(), depth, bound);
+            }
+            Expression::VecNew(vec_new_node) => {
+                if vec_new_node.explicit {
+                    self.out.push_str(&vec_new_node.type_name);
+                } else {
+                    self.out.push_str("Vec");
+                }
+                self.out.push_str("()");
+            }
+            Expression::VecCall(vec_call_node) if vec_call_node.indexed => {
+                let mut args = vec_call_node.args.iter();
+
+                self.expr(&vec_call_node.vec, depth, bound);
+                self.out.push('[');
+                if let Some(index) = args.next() {
+                    self.expr(index, depth, bound);
+                }
+                self.out.push(']');
+
+                if let Some(value) = args.next() {
+                    self.out.push_str(" = ");
+                    self.expr(value, depth, bound);
+                }
+            }
+            Expression::VecCall(vec_call_node) => {
+                self.expr(&vec_call_node.vec, depth, bound);
+                self.out.push('.');
+                self.out.push_str(&vec_call_node.method.to_string());
+                self.args(vec_call_node.args.iter(), depth, bound);
+            }
+            Expression::StructInstance(struct_instance_node) => {
+                let type_name = &struct_instance_node.struct_def.type_name;
+                match GenericType::parse(type_name) {
+                    Some(generic_type) => self.out.push_str(&generic_type.name),
+                    None => self.out.push_str(type_name),
+                }
+
+                if struct_instance_node.fields.is_empty() {
+                    self.out.push_str(" {}");
+                    return;
+                }
+
+                self.out.push_str(" {\n");
+                for field in struct_instance_node.fields.iter() {
+                    self.pad(depth + 1);
+                    self.out.push_str(&field.metadata.name);
+                    self.out.push_str(": ");
+                    self.expr(&field.value, depth + 1, bound);
+                    self.out.push_str(",\n");
+                }
+                self.pad(depth);
+                self.out.push('}');
+            }
+            Expression::StructFieldAssign(field_assign_node) => {
+                self.out
+                    .push_str(&field_assign_node.struct_instance.metadata.name);
+                for field in field_assign_node.fields.iter() {
+                    self.out.push('.');
+                    self.out.push_str(&field.metadata.name);
+                }
+                self.out.push_str(" = ");
+                self.expr(&field_assign_node.new_value, depth, bound);
+            }
+            Expression::StructFieldAccess(field_access_node) => {
+                self.expr(&field_access_node.base, depth, bound);
+                self.out.push('.');
+                self.out.push_str(&field_access_node.field.metadata.name);
+            }
+            Expression::BinaryOp(binary_op_node) => self.binary_op(binary_op_node, depth, bound),
+            Expression::Literal(token, lt) => {
+                let literal = literal(token, *lt, &self.lines);
+                self.out.push_str(&literal);
+            }
+            Expression::Error(..) => {}
+        }
+    }
+
+    fn args<'e, I>(&mut self, args: I, depth: usize, bound: Option<u32>)
+    where
+        I: Iterator<Item = &'e Expression>,
+    {
+        self.out.push('(');
+        for (i, arg) in args.enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.expr(arg, depth, bound);
+        }
+        self.out.push(')');
+    }
+
+    fn proc_def(&mut self, proc_def: &ProcDefNode, depth: usize, bound: Option<u32>) {
+        for (i, contract) in proc_def.contracts.iter().enumerate() {
+            if i > 0 {
+                self.pad(depth);
+            }
+
+            let name = match contract.kind {
+                ContractKind::Requires => "requires",
+                ContractKind::Ensures => "ensures",
+            };
+
+            self.out
+                .push_str(&format!("@{name}({})\n", contract.source));
+        }
+
+        if !proc_def.contracts.is_empty() {
+            self.pad(depth);
+        }
+
+        self.out.push_str(&format!(
+            "proc {}{}({})",
+            proc_def.name,
+            generics(&proc_def.generics),
+            parameters(&proc_def.args)
+        ));
+
+        if let Some(return_type) = &proc_def.return_type {
+            self.out.push_str(": ");
+            self.out.push_str(return_type);
+        }
+
+        self.out.push(' ');
+        self.block(&proc_def.statements, depth, row(&proc_def.position), bound);
+    }
+
+    fn closure(&mut self, closure_node: &ClosureNode, depth: usize, bound: Option<u32>) {
+        let proc_def = &closure_node.proc_def;
+        let header = row(&proc_def.position);
+
+        match closure_node.syntax {
+            ClosureSyntax::Named => self.proc_def(proc_def, depth, bound),
+            ClosureSyntax::Reference => self.out.push_str(&proc_def.name),
+            ClosureSyntax::Proc => {
+                self.out
+                    .push_str(&format!("proc({})", parameters(&proc_def.args)));
+
+                if let Some(return_type) = &proc_def.return_type {
+                    self.out.push_str(": ");
+                    self.out.push_str(return_type);
+                }
+
+                self.out.push(' ');
+                self.block(&proc_def.statements, depth, header, bound);
+            }
+            ClosureSyntax::Pipe => {
+                self.out
+                    .push_str(&format!("|{}| ", parameters(&proc_def.args)));
+
+                match proc_def.statements.as_slice() {
+                    [statement]
+                        if is_implicit_return(statement) || is_simple_statement(statement) =>
+                    {
+                        self.expr(statement, depth, bound)
+                    }
+                    statements => self.block(statements, depth, header, bound),
+                }
+            }
+        }
+    }
+
+    fn inline_branch(&mut self, statements: &[Expression], depth: usize, bound: Option<u32>) {
+        self.out.push_str("{ ");
+        for statement in statements.iter() {
+            self.expr(statement, depth, bound);
+        }
+        self.out.push_str(" }");
+    }
+
+    fn arm(&mut self, arm: &MatchArmNode, depth: usize, bound: Option<u32>) {
+        let pattern = pattern(&arm.pattern, &self.lines);
+        self.out.push_str(&pattern);
+
+        if let Some(guard) = &arm.guard {
+            self.out.push_str(" if ");
+            self.expr(guard, depth, bound);
+        }
+
+        self.out.push_str(" => ");
+
+        if let [statement] = arm.statements.as_slice() {
+            if !is_block(statement)
+                && !matches!(
+                    statement,
+                    Expression::LetStatement(..)
+                        | Expression::AssignStatement(..)
+                        | Expression::StructFieldAssign(..)
+                        | Expression::StructInstance(..)
+                )
+            {
+                self.expr(statement, depth, bound);
+                self.out.push(',');
+                return;
+            }
+        }
+
+        let header = arm_row(arm);
+        self.block(&arm.statements, depth, header, bound);
+    }
+
+    fn binary_op(&mut self, binary_op_node: &BinaryOpNode, depth: usize, bound: Option<u32>) {
+        if let Some(operand) = negated_operand(binary_op_node) {
+            self.out.push('-');
+            self.operand(operand, BinaryOp::Inc.precedence(), depth, bound);
+            return;
+        }
+
+        if let Some(parts) = interpolation_parts(binary_op_node) {
+            self.out.push('"');
+            for part in parts {
+                match part {
+                    Expression::Literal(token, LiteralType::String)
+                        if same_position(&token.position, &binary_op_node.position) =>
+                    {
+                        self.out.push_str(&escape(&token.value, '"'));
+                    }
+                    part => {
+                        self.out.push('{');
+                        self.expr(part, depth, bound);
+                        self.out.push('}');
+                    }
+                }
+            }
+            self.out.push('"');
+            return;
+        }
+
+        // an ordering lhs of an ordering op was written in parens, otherwise it would chain
+        let precedence = binary_op_node.op.precedence();
+        let lhs_precedence = precedence + u8::from(binary_op_node.op.is_ordering());
+        self.operand(&binary_op_node.lhs, lhs_precedence, depth, bound);
+
+        if let BinaryOp::Inc | BinaryOp::Dec = binary_op_node.op {
+            self.out.push_str(&binary_op_node.op.to_string());
+            return;
+        }
+
+        self.out.push_str(&format!(" {} ", binary_op_node.op));
+        self.operand(&binary_op_node.rhs, precedence + 1, depth, bound);
+    }
+
+    fn compare_chain(
+        &mut self,
+        compare_chain_node: &CompareChainNode,
+        depth: usize,
+        bound: Option<u32>,
+    ) {
+        let mut operands = compare_chain_node.operands.iter();
+        let precedence = BinaryOp::Lt.precedence() + 1;
+
+        if let Some(first) = operands.next() {
+            self.operand(first, precedence, depth, bound);
+        }
+
+        for (op, operand) in compare_chain_node.ops.iter().zip(operands) {
+            self.out.push_str(&format!(" {op} "));
+            self.operand(operand, precedence, depth, bound);
+        }
+    }
+
+    fn coalesce(&mut self, coalesce_node: &CoalesceNode, depth: usize, bound: Option<u32>) {
+        self.operand(&coalesce_node.value, COALESCE_PRECEDENCE + 1, depth, bound);
+        self.out.push_str(" ?? ");
+        self.operand(&coalesce_node.fallback, COALESCE_PRECEDENCE, depth, bound);
+    }
+
+    fn pipe(&mut self, pipe_node: &PipeNode, depth: usize, bound: Option<u32>) {
+        let (callee, args): (String, Vec<&Expression>) = match pipe_node.call.as_ref() {
+            Expression::FunCall(fun_call_node) => {
+                let name = &fun_call_node.proc_def.name;
+                let name = match GenericType::parse(name) {
+                    Some(generic_type) => generic_type.name,
+                    None => name.clone(),
+                };
+
+                (
+                    name + &generics(&fun_call_node.type_args),
+                    fun_call_node
+                        .args
+                        .iter()
+                        .map(|arg| arg.value.as_ref())
+                        .collect(),
+                )
+            }
+            Expression::ClosureCall(closure_call_node) => {
+                let Expression::Variable(variable) = closure_call_node.callee.as_ref() else {
+                    return;
+                };
+
+                (
+                    variable.metadata.name.clone(),
+                    closure_call_node.args.iter().collect(),
+                )
+            }
+            _ => return,
+        };
+
+        let Some((value, args)) = args.split_first() else {
+            return;
+        };
+
+        self.operand(value, PIPE_PRECEDENCE, depth, bound);
+        self.out.push_str(" |> ");
+        self.out.push_str(&callee);
+
+        if !pipe_node.bare {
+            self.args(args.iter().copied(), depth, bound);
+        }
+    }
+
+    fn operand(&mut self, expr: &Expression, min_precedence: u8, depth: usize, bound: Option<u32>) {
+        let needs_parens = match expr {
+            Expression::BinaryOp(binary_op_node) => {
+                negated_operand(binary_op_node).is_none()
+                    && interpolation_parts(binary_op_node).is_none()
+                    && binary_op_node.op.precedence() < min_precedence
+            }
+            Expression::Pipe(..) => PIPE_PRECEDENCE < min_precedence,
+            Expression::Coalesce(..) => COALESCE_PRECEDENCE < min_precedence,
+            Expression::CompareChain(..) => BinaryOp::Lt.precedence() < min_precedence,
+            _ => false,
+        };
+
+        if needs_parens {
+            self.out.push('(');
+        }
+
+        self.expr(expr, depth, bound);
+
+        if needs_parens {
+            self.out.push(')');
+        }
+    }
+}
+
+fn pattern(node: &PatternNode, lines: &[&str]) -> String {
+    match node {
+        PatternNode::Wildcard => String::from("_"),
+        PatternNode::Literal(token, lt) => literal(token, *lt, lines),
+        PatternNode::Binding(binding) => binding.name.clone(),
+        PatternNode::Variant {
+            type_name,
+            variant,
+            bindings,
+        } => {
+            if bindings.is_empty() {
+                return format!("{type_name}::{variant}");
+            }
+
+            let names = bindings
+                .iter()
+                .map(|b| b.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            format!("{type_name}::{variant}({names})")
+        }
+        PatternNode::Range { start, end, kind } => {
+            format!(
+                "{}..{}",
+                literal(start, *kind, lines),
+                literal(end, *kind, lines)
+            )
+        }
+        PatternNode::Bind {
+            binding,
+            pattern: inner,
+        } => {
+            if let PatternNode::Or(..) = inner.as_ref() {
+                return format!("{} @ ({})", binding.name, pattern(inner, lines));
+            }
+
+            format!("{} @ {}", binding.name, pattern(inner, lines))
+        }
+        PatternNode::Or(alternatives) => alternatives
+            .iter()
+            .map(|alternative| pattern(alternative, lines))
+            .collect::<Vec<_>>()
+            .join(" | "),
+    }
+}
@@ -0,0 +1,467 @@
+use std::{
+    fmt::Display,
+    mem::discriminant,
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::{
+    analyzer::Analyzer,
+    ast_printer::AstPrinter,
+    astc,
+    engine::{Engine, EngineError},
+    error::RuntimeError,
+    generate::{generate_value, Rng},
+    lexer::Lexer,
+    nodes::ProcDefNode,
+    parser::{panic_message, Parser, ParserOptions},
+    pretty::PrettyPrinter,
+    value::{MapValue, Value},
+};
+
+const PARSER_CORPUS: &[&str] = &[
+    "proc main(): i32 { let x = 1 + 2 * 3; return x; }",
+    "struct P { x: i32, y: f32 } proc f(p: P): f32 { return p.y; }",
+    "enum Shape { Circle(r: i32), Empty } proc f(s: Shape): i32 { return match s { Shape::Circle(r) | Shape::Circle(r) if r > 0 => r, n @ _ => 0 }; }",
+    "@derive(eq, ord, to_string) struct S { a: String } impl S { proc get(self): String { return self.a; } }",
+    "@flags enum F { A = 1, B = 2 } let f = F::A | F::B; if f.contains(F::B) { f = F::A; }",
+    "proc g(n: i32): bool { while n > 0 { n -= 1; if n == 3 { break; } } for i in 0..10 { continue; } return true; }",
+    "let s = \"a {1 + 2} b\"; let c = 'x'; let m = match s { \"a\" | \"b\" => 1.5, _ => -2.0 };",
+    "@requires(n >= 0) @ensures(result >= 0) proc h(n: i32): i32 { return n as f32 as i32; }",
+];
+
+const PARSER_ALPHABET: &[u8] = b"(){}[]<>;:,.@|&!=+-*/%'\" _aAzZ019\n proclettrueif";
+
+#[derive(Debug, Clone, Copy)]
+pub struct FuzzOptions {
+    runs: usize,
+    seed: u64,
+    step_limit: u64,
+    shrink_limit: usize,
+}
+
+impl Default for FuzzOptions {
+    fn default() -> Self {
+        Self {
+            runs: 1000,
+            seed: 0,
+            step_limit: 10_000,
+            shrink_limit: 1000,
+        }
+    }
+}
+
+impl FuzzOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn runs(mut self, runs: usize) -> Self {
+        self.runs = runs;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn step_limit(mut self, step_limit: u64) -> Self {
+        self.step_limit = step_limit;
+        self
+    }
+
+    pub fn shrink_limit(mut self, shrink_limit: usize) -> Self {
+        self.shrink_limit = shrink_limit;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzFailure {
+    pub args: Vec<Value>,
+    pub error: RuntimeError,
+    pub shrinks: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzReport {
+    pub target: String,
+    pub seed: u64,
+    pub runs: usize,
+    pub failure: Option<FuzzFailure>,
+}
+
+impl Display for FuzzReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(failure) = &self.failure else {
+            return f.write_fmt(format_args!(
+                "'{}' passed {} runs (seed {})",
+                self.target, self.runs, self.seed
+            ));
+        };
+
+        let printer = PrettyPrinter::new();
+        let args = failure
+            .args
+            .iter()
+            .map(|arg| match arg {
+                Value::String(s) => format!("{s:?}"),
+                Value::Char(c) => format!("{c:?}"),
+                arg => printer.print(arg),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        f.write_fmt(format_args!(
+            "'{}' failed after {} runs (seed {}, shrunk {} times)\n\tinput: {}({args})\n\terror: {}",
+            self.target, self.runs, self.seed, failure.shrinks, self.target, failure.error.kind
+        ))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParserFuzzFailure {
+    pub input: String,
+    pub message: String,
+    pub shrinks: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParserFuzzReport {
+    pub seed: u64,
+    pub runs: usize,
+    pub failure: Option<ParserFuzzFailure>,
+}
+
+impl Display for ParserFuzzReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(failure) = &self.failure else {
+            return f.write_fmt(format_args!(
+                "the parser passed {} runs (seed {})",
+                self.runs, self.seed
+            ));
+        };
+
+        f.write_fmt(format_args!(
+            "the parser panicked after {} runs (seed {}, shrunk {} times)\n\tinput: {:?}\n\tpanic: {}",
+            self.runs, self.seed, failure.shrinks, failure.input, failure.message
+        ))
+    }
+}
+
+pub fn fuzz(
+    engine: &mut Engine,
+    target: &str,
+    options: FuzzOptions,
+) -> Result<FuzzReport, EngineError> {
+    let proc_def = engine
+        .procedure(target)
+        .ok_or_else(|| EngineError::UndefinedProcedure(String::from(target)))?;
+
+    let step_limit = engine.step_limit();
+    engine.set_step_limit(Some(options.step_limit));
+
+    let report = run_fuzzer(engine, &proc_def, options);
+
+    engine.set_step_limit(step_limit);
+
+    report
+}
+
+pub fn fuzz_parser(corpus: &[String], options: FuzzOptions) -> ParserFuzzReport {
+    let mut corpus = corpus.to_vec();
+    corpus.extend(PARSER_CORPUS.iter().map(|s| String::from(*s)));
+
+    let mut rng = Rng::new(options.seed);
+    let mut report = ParserFuzzReport {
+        seed: options.seed,
+        runs: 0,
+        failure: None,
+    };
+
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    for _ in 0..options.runs {
+        let input = parser_input(&corpus, &mut rng);
+        report.runs += 1;
+
+        if let Some(message) = parse_case(&input) {
+            report.failure = Some(shrink_input(input, message, options));
+            break;
+        }
+    }
+
+    panic::set_hook(hook);
+
+    report
+}
+
+fn parser_input(corpus: &[String], rng: &mut Rng) -> String {
+    let mut bytes = corpus[rng.below(corpus.len() as u64) as usize]
+        .as_bytes()
+        .to_vec();
+
+    match rng.below(4) {
+        0 => {
+            bytes = (0..rng.below(64))
+                .map(|_| PARSER_ALPHABET[rng.below(PARSER_ALPHABET.len() as u64) as usize])
+                .collect();
+        }
+        1 => bytes.truncate(rng.below(bytes.len() as u64 + 1) as usize),
+        _ => {
+            for _ in 0..=rng.below(8) {
+                let at = rng.below(bytes.len() as u64 + 1) as usize;
+                match rng.below(3) {
+                    0 if at < bytes.len() => {
+                        bytes.remove(at);
+                    }
+                    1 => bytes.insert(at, rng.below(256) as u8),
+                    _ => bytes.insert(
+                        at,
+                        PARSER_ALPHABET[rng.below(PARSER_ALPHABET.len() as u64) as usize],
+                    ),
+                }
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn parse_case(input: &str) -> Option<String> {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let lexer = Lexer::new(input, String::from("fuzz.mt"));
+        let mut parser = Parser::new(lexer).with_options(ParserOptions::new().hardened(false));
+        let Ok(program) = parser.parse_program();
+
+        let _ = Analyzer::check(&program);
+        let _ = AstPrinter::new().print(&program);
+        assert!(
+            astc::decode(&astc::encode(&program, &[], &[], 0), 0).is_some(),
+            "the AST cache failed to round-trip"
+        );
+    }));
+
+    result.err().map(|payload| panic_message(payload.as_ref()))
+}
+
+fn shrink_input(mut input: String, message: String, options: FuzzOptions) -> ParserFuzzFailure {
+    let mut shrinks = 0;
+    let mut attempts = 0;
+
+    'shrink: loop {
+        let chars = input.chars().collect::<Vec<_>>();
+
+        let mut len = chars.len() / 2;
+        while len > 0 {
+            for start in (0..chars.len()).step_by(len) {
+                if attempts >= options.shrink_limit {
+                    break 'shrink;
+                }
+
+                attempts += 1;
+
+                let trial = chars[..start]
+                    .iter()
+                    .chain(chars[(start + len).min(chars.len())..].iter())
+                    .collect::<String>();
+
+                if parse_case(&trial).is_some() {
+                    input = trial;
+                    shrinks += 1;
+                    continue 'shrink;
+                }
+            }
+
+            len /= 2;
+        }
+
+        break;
+    }
+
+    ParserFuzzFailure {
+        input,
+        message,
+        shrinks,
+    }
+}
+
+fn run_fuzzer(
+    engine: &mut Engine,
+    proc_def: &ProcDefNode,
+    options: FuzzOptions,
+) -> Result<FuzzReport, EngineError> {
+    let mut rng = Rng::new(options.seed);
+    let mut report = FuzzReport {
+        target: proc_def.name.clone(),
+        seed: options.seed,
+        runs: 0,
+        failure: None,
+    };
+
+    for _ in 0..options.runs {
+        let args = proc_def
+            .args
+            .iter()
+            .map(|arg| generate_value(engine, &arg.type_name, &mut rng))
+            .collect::<Vec<_>>();
+
+        report.runs += 1;
+
+        if let Some(error) = run_case(engine, &proc_def.name, &args)? {
+            let failure = shrink_case(engine, &proc_def.name, args, error, options)?;
+            report.failure = Some(failure);
+            break;
+        }
+    }
+
+    Ok(report)
+}
+
+fn run_case(
+    engine: &mut Engine,
+    target: &str,
+    args: &[Value],
+) -> Result<Option<RuntimeError>, EngineError> {
+    match engine.call(target, args.to_vec()) {
+        Ok(_) => Ok(None),
+        Err(EngineError::Runtime(err)) => Ok(Some(err)),
+        Err(err) => Err(err),
+    }
+}
+
+fn shrink_case(
+    engine: &mut Engine,
+    target: &str,
+    mut args: Vec<Value>,
+    mut error: RuntimeError,
+    options: FuzzOptions,
+) -> Result<FuzzFailure, EngineError> {
+    let mut shrinks = 0;
+    let mut attempts = 0;
+
+    'shrink: loop {
+        for i in 0..args.len() {
+            for candidate in shrink_value(&args[i]) {
+                if attempts >= options.shrink_limit {
+                    break 'shrink;
+                }
+
+                attempts += 1;
+
+                let mut trial = args.clone();
+                trial[i] = candidate;
+
+                if let Some(err) = run_case(engine, target, &trial)? {
+                    if discriminant(&err.kind) == discriminant(&error.kind) {
+                        args = trial;
+                        error = err;
+                        shrinks += 1;
+                        continue 'shrink;
+                    }
+                }
+            }
+        }
+
+        break;
+    }
+
+    Ok(FuzzFailure {
+        args,
+        error,
+        shrinks,
+    })
+}
+
+fn shrink_value(value: &Value) -> Vec<Value> {
+    let mut candidates = Vec::new();
+
+    match value {
+        Value::Number(n) if *n != 0 => {
+            candidates.push(Value::Number(0));
+            candidates.push(Value::Number(n / 2));
+            candidates.push(Value::Number(n - n.signum()));
+
+            if let Some(abs) = n.checked_abs().filter(|abs| abs != n) {
+                candidates.push(Value::Number(abs));
+            }
+        }
+        Value::Float(n) if *n != 0.0 => {
+            candidates.push(Value::Float(0.0));
+            candidates.push(Value::Float(n.trunc()));
+            candidates.push(Value::Float(n / 2.0));
+
+            if *n < 0.0 {
+                candidates.push(Value::Float(-n));
+            }
+        }
+        Value::Bool(true) => candidates.push(Value::Bool(false)),
+        Value::Flags(flags_value) if flags_value.bits != 0 => {
+            for (_, d) in flags_value.variants.iter() {
+                if *d != 0 && flags_value.bits & d == *d {
+                    let mut shrunk = flags_value.clone();
+                    shrunk.bits &= !d;
+                    candidates.push(Value::Flags(shrunk));
+                }
+            }
+        }
+        Value::Char(c) if *c != 'a' => candidates.push(Value::Char('a')),
+        Value::String(s) if !s.is_empty() => {
+            let chars = s.chars().collect::<Vec<_>>();
+
+            candidates.push(Value::String(String::new()));
+            candidates.push(Value::String(chars[..chars.len() / 2].iter().collect()));
+            candidates.push(Value::String(chars[1..].iter().collect()));
+            candidates.push(Value::String(chars[..chars.len() - 1].iter().collect()));
+        }
+        Value::Struct(struct_value) => {
+            for (i, (_, field)) in struct_value.fields.iter().enumerate() {
+                for candidate in shrink_value(field) {
+                    let mut shrunk = struct_value.clone();
+                    shrunk.fields[i].1 = candidate;
+                    candidates.push(Value::Struct(shrunk));
+                }
+            }
+        }
+        Value::Enum(enum_value) => {
+            for (i, (_, field)) in enum_value.fields.iter().enumerate() {
+                for candidate in shrink_value(field) {
+                    let mut shrunk = enum_value.clone();
+                    shrunk.fields[i].1 = candidate;
+                    candidates.push(Value::Enum(shrunk));
+                }
+            }
+        }
+        Value::Vec(vec_value) if !vec_value.items.is_empty() => {
+            let items = &vec_value.items;
+            for range in [0..0, 0..items.len() / 2, 1..items.len(), 0..items.len() - 1] {
+                let mut shrunk = vec_value.clone();
+                shrunk.items = items[range].to_vec();
+                candidates.push(Value::Vec(shrunk));
+            }
+
+            for (i, item) in items.iter().enumerate() {
+                for candidate in shrink_value(item) {
+                    let mut shrunk = vec_value.clone();
+                    shrunk.items[i] = candidate;
+                    candidates.push(Value::Vec(shrunk));
+                }
+            }
+        }
+        Value::Map(map_value) if !map_value.is_empty() => {
+            candidates.push(Value::Map(MapValue::new(&map_value.type_name)));
+
+            for key in map_value.iter().map(|(key, _)| key) {
+                let mut shrunk = map_value.clone();
+                shrunk.remove(key);
+                candidates.push(Value::Map(shrunk));
+            }
+        }
+        _ => {}
+    }
+
+    candidates.dedup();
+    candidates.retain(|candidate| candidate != value);
+    candidates
+}
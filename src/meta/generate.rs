@@ -0,0 +1,182 @@
+use crate::{
+    engine::Engine,
+    nodes::{map_types, vec_type, GenericType},
+    parser::substitute_type,
+    value::{EnumValue, FlagsValue, MapValue, StructValue, Value, VecValue},
+};
+
+const MAX_GENERATION_DEPTH: usize = 4;
+const MAX_COLLECTION_LEN: u64 = 8;
+const ANY_TYPES: [&str; 5] = ["i32", "f32", "bool", "char", "String"];
+const INTERESTING_I32: [i32; 8] = [0, 1, -1, 2, 10, 255, i32::MAX, i32::MIN];
+const INTERESTING_F32: [f32; 6] = [0.0, 1.0, -1.0, 0.5, f32::MAX, f32::MIN];
+
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed ^ 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+
+        self.next_u64() % bound
+    }
+}
+
+pub fn generate_value(engine: &Engine, type_name: &str, rng: &mut Rng) -> Value {
+    generate(engine, type_name, rng, 0)
+}
+
+fn generate(engine: &Engine, type_name: &str, rng: &mut Rng, depth: usize) -> Value {
+    match type_name {
+        "i32" => Value::Number(generate_i32(rng)),
+        "f32" => Value::Float(generate_f32(rng)),
+        "bool" => Value::Bool(rng.below(2) == 1),
+        "char" => Value::Char(generate_char(rng)),
+        "String" => {
+            let len = rng.below(16);
+            Value::String((0..len).map(|_| generate_char(rng)).collect())
+        }
+        "any" => {
+            let type_name = ANY_TYPES[rng.below(ANY_TYPES.len() as u64) as usize];
+            generate(engine, type_name, rng, depth)
+        }
+        _ => {
+            // collections bottom out as empty rather than None so they still type check
+            let len = if depth >= MAX_GENERATION_DEPTH {
+                0
+            } else {
+                rng.below(MAX_COLLECTION_LEN)
+            };
+
+            if let Some(element) = vec_type(type_name) {
+                let mut vec_value = VecValue::new(type_name);
+                for _ in 0..len {
+                    let item = generate(engine, &element, rng, depth + 1);
+                    vec_value.items.push(item);
+                }
+
+                return Value::Vec(vec_value);
+            }
+
+            if let Some((key, value)) = map_types(type_name) {
+                let mut map_value = MapValue::new(type_name);
+                for _ in 0..len {
+                    let key = generate(engine, &key, rng, depth + 1);
+                    let value = generate(engine, &value, rng, depth + 1);
+                    map_value.insert(key, value);
+                }
+
+                return Value::Map(map_value);
+            }
+
+            if depth >= MAX_GENERATION_DEPTH {
+                return Value::None;
+            }
+
+            generate_named(engine, type_name, rng, depth)
+        }
+    }
+}
+
+fn generate_named(engine: &Engine, type_name: &str, rng: &mut Rng, depth: usize) -> Value {
+    // a generic instance like 'Pair<i32, String>' is generated from its definition
+    let (name, bindings) = match GenericType::parse(type_name) {
+        Some(generic) => {
+            let generics = engine
+                .struct_def(&generic.name)
+                .map(|s| s.generics.clone())
+                .unwrap_or_default();
+            let bindings: Vec<(String, String)> = generics.into_iter().zip(generic.args).collect();
+            (generic.name, bindings)
+        }
+        None => (String::from(type_name), Vec::new()),
+    };
+
+    if let Some(struct_def) = engine.struct_def(&name) {
+        let fields = struct_def
+            .fields
+            .iter()
+            .map(|f| {
+                let field_type = substitute_type(&f.type_name, &bindings);
+                let value = generate(engine, &field_type, rng, depth + 1);
+                (f.name.clone(), value)
+            })
+            .collect();
+
+        return Value::Struct(StructValue {
+            type_name: String::from(type_name),
+            fields,
+        });
+    }
+
+    if let Some(enum_def) = engine.enum_def(type_name) {
+        if enum_def.flags {
+            let bits = enum_def
+                .discriminants()
+                .into_iter()
+                .filter(|_| rng.below(2) == 0)
+                .fold(0, |bits, (_, d)| bits | d);
+
+            return Value::Flags(FlagsValue::new(enum_def, bits));
+        }
+
+        let index = rng.below(enum_def.variants.len() as u64) as usize;
+        if let Some(variant) = enum_def.variants.get(index) {
+            let fields = variant
+                .fields
+                .iter()
+                .map(|f| {
+                    let value = generate(engine, &f.type_name, rng, depth + 1);
+                    (f.name.clone(), value)
+                })
+                .collect();
+
+            return Value::Enum(EnumValue {
+                type_name: String::from(type_name),
+                variant: variant.name.clone(),
+                fields,
+            });
+        }
+    }
+
+    Value::None
+}
+
+fn generate_i32(rng: &mut Rng) -> i32 {
+    match rng.below(4) {
+        0 => INTERESTING_I32[rng.below(INTERESTING_I32.len() as u64) as usize],
+        1 => rng.next_u64() as i32,
+        _ => rng.below(201) as i32 - 100,
+    }
+}
+
+fn generate_f32(rng: &mut Rng) -> f32 {
+    match rng.below(4) {
+        0 => INTERESTING_F32[rng.below(INTERESTING_F32.len() as u64) as usize],
+        1 => (rng.next_u64() as i32) as f32 / 64.0,
+        _ => (rng.below(2001) as f32 - 1000.0) / 8.0,
+    }
+}
+
+fn generate_char(rng: &mut Rng) -> char {
+    char::from(b' ' + rng.below(95) as u8)
+}
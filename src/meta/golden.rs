@@ -0,0 +1,215 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    diagnostics::Renderer,
+    executor::{Environment, Executor},
+    parser::ParserOptions,
+    pretty::PrettyPrinter,
+};
+
+pub const FIXTURE_EXTENSION: &str = "mt";
+pub const EXPECTED_EXTENSION: &str = "expected";
+
+#[derive(Debug, Clone, Copy)]
+pub struct GoldenOptions {
+    bless: bool,
+    step_limit: u64,
+}
+
+impl Default for GoldenOptions {
+    fn default() -> Self {
+        Self {
+            bless: false,
+            step_limit: 1_000_000,
+        }
+    }
+}
+
+impl GoldenOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bless(mut self, enabled: bool) -> Self {
+        self.bless = enabled;
+        self
+    }
+
+    pub fn step_limit(mut self, step_limit: u64) -> Self {
+        self.step_limit = step_limit;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Passed,
+    Blessed,
+    Missing,
+    Failed { expected: String, actual: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct FixtureResult {
+    pub path: PathBuf,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GoldenReport {
+    pub results: Vec<FixtureResult>,
+}
+
+impl GoldenReport {
+    pub fn passed(&self) -> bool {
+        self.results
+            .iter()
+            .all(|r| matches!(r.outcome, Outcome::Passed | Outcome::Blessed))
+    }
+}
+
+impl Display for GoldenReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut failed = 0;
+
+        for result in self.results.iter() {
+            let path = result.path.display();
+
+            match &result.outcome {
+                Outcome::Passed => f.write_fmt(format_args!("ok      {path}\n"))?,
+                Outcome::Blessed => f.write_fmt(format_args!("blessed {path}\n"))?,
+                Outcome::Missing => {
+                    failed += 1;
+                    f.write_fmt(format_args!(
+                        "FAILED  {path}: no .{EXPECTED_EXTENSION} file, run with --bless to create it\n"
+                    ))?;
+                }
+                Outcome::Failed { expected, actual } => {
+                    failed += 1;
+                    f.write_fmt(format_args!("FAILED  {path}\n{}", diff(expected, actual)))?;
+                }
+            }
+        }
+
+        f.write_fmt(format_args!(
+            "\n{} passed, {failed} failed",
+            self.results.len() - failed
+        ))
+    }
+}
+
+pub fn expected_path(fixture: &Path) -> PathBuf {
+    fixture.with_extension(EXPECTED_EXTENSION)
+}
+
+pub fn discover(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut fixtures = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            fixtures.extend(discover(&path)?);
+        } else if path.extension().is_some_and(|e| e == FIXTURE_EXTENSION) {
+            fixtures.push(path);
+        }
+    }
+
+    fixtures.sort();
+    Ok(fixtures)
+}
+
+pub fn run_fixture(path: &Path, options: GoldenOptions) -> String {
+    let mut renderer = Renderer::new().color(false);
+    if let (Some(filename), Ok(source)) = (path.file_name(), std::fs::read_to_string(path)) {
+        renderer.add_source(filename.to_string_lossy().into_owned(), source);
+    }
+
+    let env = Environment {
+        step_limit: Some(options.step_limit),
+        ..Default::default()
+    };
+    let result = Executor::run_with_environment(path, ParserOptions::new(), env);
+
+    let mut output = String::new();
+    if !result.value.is_none() {
+        output.push_str(&PrettyPrinter::new().print(&result.value));
+        output.push('\n');
+    }
+
+    for diagnostic in result.diagnostics.iter() {
+        output.push_str(&renderer.render(diagnostic));
+        output.push('\n');
+    }
+
+    output
+}
+
+pub fn run(path: &Path, options: GoldenOptions) -> std::io::Result<GoldenReport> {
+    let mut report = GoldenReport::default();
+
+    for fixture in discover(path)? {
+        let actual = run_fixture(&fixture, options);
+        let expected_path = expected_path(&fixture);
+
+        let outcome = if options.bless {
+            std::fs::write(&expected_path, &actual)?;
+            Outcome::Blessed
+        } else {
+            match std::fs::read_to_string(&expected_path) {
+                Ok(expected) if expected == actual => Outcome::Passed,
+                Ok(expected) => Outcome::Failed { expected, actual },
+                Err(..) => Outcome::Missing,
+            }
+        };
+
+        report.results.push(FixtureResult {
+            path: fixture,
+            outcome,
+        });
+    }
+
+    Ok(report)
+}
+
+pub fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    let mut lengths = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lengths[i][j] = if expected[i] == actual[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            out.push_str(&format!("   {}\n", expected[i]));
+            i += 1;
+            j += 1;
+        } else if i < expected.len()
+            && (j == actual.len() || lengths[i + 1][j] >= lengths[i][j + 1])
+        {
+            out.push_str(&format!(" - {}\n", expected[i]));
+            i += 1;
+        } else {
+            out.push_str(&format!(" + {}\n", actual[j]));
+            j += 1;
+        }
+    }
+
+    out
+}
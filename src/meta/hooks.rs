@@ -0,0 +1,61 @@
+use crate::{expression::Expression, token::Position, value::Value};
+
+pub trait ExecutorHooks {
+    fn on_statement(&mut self, _statement: &Expression, _position: &Position) {}
+
+    fn on_call(&mut self, _name: &str, _args: &[Value]) {}
+
+    fn on_return(&mut self, _name: &str, _value: &Value) {}
+
+    fn on_assign(&mut self, _name: &str, _value: &Value, _position: &Position) {}
+}
+
+pub struct Tracer {
+    lines: Vec<String>,
+    depth: usize,
+}
+
+impl Tracer {
+    pub fn new(source: &str) -> Self {
+        Self {
+            lines: source.lines().map(String::from).collect(),
+            depth: 0,
+        }
+    }
+
+    fn indent(&self) -> String {
+        "  ".repeat(self.depth.saturating_sub(1))
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("{s:?}"),
+        Value::Char(c) => format!("{c:?}"),
+        value => value.to_string(),
+    }
+}
+
+impl ExecutorHooks for Tracer {
+    fn on_statement(&mut self, _statement: &Expression, position: &Position) {
+        let line = self
+            .lines
+            .get(position.row as usize)
+            .map_or("", |line| line.trim());
+
+        eprintln!("{}{position}: {line}", self.indent());
+    }
+
+    fn on_call(&mut self, name: &str, args: &[Value]) {
+        self.depth += 1;
+
+        let args: Vec<String> = args.iter().map(describe).collect();
+        eprintln!("{}call {name}({})", self.indent(), args.join(", "));
+    }
+
+    fn on_return(&mut self, name: &str, value: &Value) {
+        eprintln!("{}return {name} = {}", self.indent(), describe(value));
+
+        self.depth = self.depth.saturating_sub(1);
+    }
+}
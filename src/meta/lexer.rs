@@ -1,36 +1,58 @@
-use crate::token::{LiteralType, Position, Token, TokenType};
+use std::{borrow::Cow, ops::Range};
 
-pub struct Lexer {
+use crate::token::{LiteralType, Location, Position, Span, Token, TokenClass, TokenType};
+
+pub struct Lexer<'src> {
     filename: String,
-    chars: Vec<char>,
-    source: String,
+    source: Cow<'src, str>,
     cursor: usize,
     row: usize,
-    line_start: usize,
+    column: usize,
+    last_end: Location,
+    docs: Vec<String>,
+    comments: Vec<(Position, String)>,
+    errors: Vec<(Position, String)>,
 }
 
-impl Lexer {
-    pub fn new(source: String, filename: String) -> Self {
+impl<'src> Lexer<'src> {
+    pub fn new(source: impl Into<Cow<'src, str>>, filename: String) -> Self {
         Self {
             filename,
-            chars: source.clone().chars().collect(),
-            source,
+            source: source.into(),
             cursor: 0,
             row: 0,
-            line_start: 0,
+            column: 0,
+            last_end: Location::default(),
+            docs: Vec::new(),
+            comments: Vec::new(),
+            errors: Vec::new(),
         }
     }
 
     pub fn advance(&mut self) {
-        self.cursor += 1;
+        let c = self.character();
+        self.cursor += c.len_utf8();
+
+        if c == '\n' {
+            self.row += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+    }
+
+    fn advance_by(&mut self, amount: usize) {
+        for _ in 0..amount {
+            self.advance();
+        }
     }
 
     pub fn valid(&self) -> bool {
-        self.cursor < self.chars.len()
+        self.cursor < self.source.len()
     }
 
     pub fn character(&self) -> char {
-        self.chars[self.cursor]
+        self.rest().chars().next().unwrap_or('\0')
     }
 
     pub fn peek_char(&self) -> Option<char> {
@@ -38,19 +60,147 @@ impl Lexer {
     }
 
     pub fn peek_char_by_amount(&self, amount: usize) -> Option<char> {
-        if self.cursor + amount < self.chars.len() {
-            return Some(self.chars[self.cursor + amount]);
+        self.rest().chars().nth(amount)
+    }
+
+    fn rest(&self) -> &str {
+        self.source.get(self.cursor..).unwrap_or_default()
+    }
+
+    // tokens borrow from a borrowed source and copy out of an owned one
+    fn slice(&self, start: usize) -> Cow<'src, str> {
+        let end = self.cursor.min(self.source.len());
+        match self.source {
+            Cow::Borrowed(source) => Cow::Borrowed(&source[start..end]),
+            Cow::Owned(ref source) => Cow::Owned(String::from(&source[start..end])),
+        }
+    }
+
+    pub fn peek_token(&mut self) -> Option<Token<'src>> {
+        let (cursor, row, column) = (self.cursor, self.row, self.column);
+        let docs = std::mem::take(&mut self.docs);
+        let (comments, errors) = (self.comments.len(), self.errors.len());
+
+        let token = get_next_token(self);
+
+        self.cursor = cursor;
+        self.row = row;
+        self.column = column;
+        self.docs = docs;
+        self.comments.truncate(comments);
+        self.errors.truncate(errors);
+
+        token
+    }
+
+    pub fn group_source(&mut self) -> Option<(String, Position)> {
+        let position = self.get_cursor_pos();
+        let mut source = String::new();
+        let mut depth = 0;
+        let mut quote = None;
+        let mut escaped = false;
+
+        while self.valid() {
+            let c = self.character();
+            self.advance();
+
+            match quote {
+                Some(_) if escaped => escaped = false,
+                Some(_) if c == '\\' => escaped = true,
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None => match c {
+                    '"' | '\'' => quote = Some(c),
+                    '(' => depth += 1,
+                    ')' if depth == 0 => return Some((source, position)),
+                    ')' => depth -= 1,
+                    _ => {}
+                },
+            }
+
+            source.push(c);
         }
 
         None
     }
 
+    pub fn doc_comment(&self) -> Option<String> {
+        if self.docs.is_empty() {
+            return None;
+        }
+
+        Some(self.docs.join("\n"))
+    }
+
+    pub fn source_since(&self, position: &Position) -> String {
+        let line_start: usize = self
+            .source
+            .split_inclusive('\n')
+            .take(position.row as usize)
+            .map(str::len)
+            .sum();
+
+        let start = self.source[line_start..]
+            .char_indices()
+            .nth(position.column as usize)
+            .map_or(self.source.len(), |(i, _)| line_start + i);
+
+        self.slice(start.min(self.cursor)).into_owned()
+    }
+
+    pub fn source_line(&self, row: u32) -> Option<&str> {
+        self.source.lines().nth(row as usize)
+    }
+
+    pub fn comments(&self) -> &[(Position, String)] {
+        &self.comments
+    }
+
+    pub fn restore_comments(&mut self, comments: Vec<(Position, String)>) {
+        self.comments = comments;
+    }
+
+    pub fn errors(&self) -> &[(Position, String)] {
+        &self.errors
+    }
+
+    pub fn unclosed_delimiter(&self) -> Option<Token<'static>> {
+        let lexer = Lexer::new(&*self.source, self.filename.clone());
+        let mut open: Vec<Token> = Vec::new();
+
+        for token in lexer {
+            let opener = match token.kind {
+                TokenType::Cparen => TokenType::Oparen,
+                TokenType::Ccurly => TokenType::Ocurly,
+                TokenType::Cbracket => TokenType::Obracket,
+                TokenType::Oparen | TokenType::Ocurly | TokenType::Obracket => {
+                    open.push(token);
+                    continue;
+                }
+                _ => continue,
+            };
+
+            if open.last().is_some_and(|t| t.kind == opener) {
+                open.pop();
+            }
+        }
+
+        open.pop().map(Token::into_owned)
+    }
+
     pub fn get_cursor_pos(&self) -> Position {
-        Position::from(
-            self.filename.clone(),
-            self.row as u32,
-            (self.cursor - self.line_start) as u32,
-        )
+        Position::from(self.filename.clone(), self.row as u32, self.column as u32)
+    }
+
+    fn location(&self) -> Location {
+        Location {
+            row: self.row as u32,
+            column: self.column as u32,
+        }
+    }
+
+    pub fn last_end(&self) -> Location {
+        self.last_end
     }
 
     pub fn trim(&mut self) {
@@ -58,11 +208,6 @@ impl Lexer {
         while self.valid() && c.is_ascii_whitespace() {
             self.advance();
 
-            if c == '\n' {
-                self.row += 1;
-                self.line_start = self.cursor;
-            }
-
             if !self.valid() {
                 break;
             }
@@ -75,99 +220,217 @@ impl Lexer {
         while self.valid() && self.character() != '\n' {
             self.advance();
         }
+    }
 
-        self.advance();
+    fn parse_doc_comment(&mut self) {
+        self.advance_by(3);
+
+        let start = self.cursor;
+        self.drop_line();
+
+        let line = self.slice(start);
+        self.docs.push(String::from(line.trim()));
     }
 
-    fn parse_string_token(&mut self, pos: Position) -> Option<Token> {
+    fn drop_block_comment(&mut self) {
+        self.advance_by(2);
+
+        let mut depth = 1;
+        while self.valid() {
+            let c = self.character();
+            let next = self.peek_char();
+
+            if c == '/' && next == Some('*') {
+                depth += 1;
+                self.advance_by(2);
+            } else if c == '*' && next == Some('/') {
+                depth -= 1;
+                self.advance_by(2);
+
+                if depth == 0 {
+                    return;
+                }
+            } else {
+                self.advance();
+            }
+        }
+    }
+
+    fn skip_trivia(&mut self) {
+        self.docs.clear();
+
+        while self.valid() {
+            let c = self.character();
+
+            if c.is_ascii_whitespace() {
+                self.trim();
+            } else if c == '/' && self.peek_char() == Some('/') {
+                let is_doc = self.peek_char_by_amount(2) == Some('/')
+                    && self.peek_char_by_amount(3) != Some('/');
+
+                let (position, start) = (self.get_cursor_pos(), self.cursor);
+
+                if is_doc {
+                    self.parse_doc_comment();
+                } else {
+                    self.docs.clear();
+                    self.drop_line();
+                }
+
+                let comment = self.slice(start);
+                self.comments
+                    .push((position, String::from(comment.trim_end())));
+            } else if c == '/' && self.peek_char() == Some('*') {
+                let (position, start) = (self.get_cursor_pos(), self.cursor);
+
+                self.docs.clear();
+                self.drop_block_comment();
+
+                self.comments
+                    .push((position, self.slice(start).into_owned()));
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn parse_string_token(&mut self, pos: Position) -> Option<Token<'src>> {
         self.advance();
 
         let start = self.cursor;
+        let mut interpolated = false;
+
+        while self.valid() && self.character() != '"' {
+            match self.character() {
+                '\\' => self.escape(),
+                '{' => interpolated = true,
+                _ => {}
+            }
 
-        let mut c = self.character();
-        while self.valid()
-            && c != '"'
-            && (c.is_alphanumeric() || c.is_ascii_whitespace() || c.is_ascii_punctuation())
-        {
             self.advance();
-            c = self.character();
         }
 
-        let value = String::from(&self.source[start..self.cursor]);
-        let token = Some(Token::from(
+        let raw = self.slice(start);
+
+        if !self.valid() {
+            self.errors
+                .push((pos.clone(), String::from("unterminated string literal")));
+        }
+
+        self.advance();
+
+        if interpolated {
+            return Some(Token::from(TokenType::Interpolation, raw, pos));
+        }
+
+        Some(Token::from(
             TokenType::Literal(LiteralType::String),
-            value,
+            unescaped(raw),
             pos,
-        ));
+        ))
+    }
 
+    // steps over the '\\' and reports escapes that unescape() would keep verbatim
+    fn escape(&mut self) {
+        let position = self.get_cursor_pos();
         self.advance();
 
-        token
+        if self.valid()
+            && !matches!(
+                self.character(),
+                'n' | 't' | 'r' | '0' | '\\' | '"' | '\'' | '{' | '}'
+            )
+        {
+            let message = format!("unknown escape sequence '\\{}'", self.character());
+            self.errors.push((position, message));
+        }
     }
 
-    fn parse_char_token(&mut self, pos: Position) -> Option<Token> {
+    fn parse_char_token(&mut self, pos: Position) -> Option<Token<'src>> {
         self.advance();
 
-        let c = self.character();
+        let start = self.cursor;
+        if self.valid() && self.character() == '\\' {
+            self.escape();
+        }
+
+        self.advance();
+
+        let raw = self.slice(start);
+
+        if self.character() != '\'' {
+            self.errors
+                .push((pos.clone(), String::from("unterminated character literal")));
+
+            return Some(Token::from(
+                TokenType::Literal(LiteralType::Char),
+                unescaped(raw),
+                pos,
+            ));
+        }
 
         let token = Some(Token::from(
             TokenType::Literal(LiteralType::Char),
-            String::from(c),
+            unescaped(raw),
             pos,
         ));
 
         self.advance();
 
-        self.advance();
-
         token
     }
 
-    fn parse_punctuation_token(&mut self, pos: Position) -> Option<Token> {
+    fn parse_punctuation_token(&mut self, pos: Position) -> Option<Token<'src>> {
+        let start = self.cursor;
         let token = self.character();
 
         self.advance();
 
-        let value = String::from(token);
-
         let next = if self.valid() {
             self.character()
         } else {
             0 as char
         };
 
-        match token {
-            '(' => Some(Token::from(TokenType::Oparen, value, pos)),
-            ')' => Some(Token::from(TokenType::Cparen, value, pos)),
-            '{' => Some(Token::from(TokenType::Ocurly, value, pos)),
-            '}' => Some(Token::from(TokenType::Ccurly, value, pos)),
+        let kind = match token {
+            '(' => TokenType::Oparen,
+            ')' => TokenType::Cparen,
+            '{' => TokenType::Ocurly,
+            '}' => TokenType::Ccurly,
+            '[' => TokenType::Obracket,
+            ']' => TokenType::Cbracket,
             ':' => {
                 if next == ':' {
                     self.advance();
-                    Some(Token::from(
-                        TokenType::ScopeResolution,
-                        String::from("::"),
-                        pos,
-                    ))
+                    TokenType::ScopeResolution
                 } else {
-                    Some(Token::from(TokenType::Colon, value, pos))
+                    TokenType::Colon
                 }
             }
-            ';' => Some(Token::from(TokenType::Semicolon, value, pos)),
-            ',' => Some(Token::from(TokenType::Comma, value, pos)),
+            ';' => TokenType::Semicolon,
+            ',' => TokenType::Comma,
+            '@' => TokenType::At,
             '.' => {
                 if next == '.' {
                     self.advance();
-                    Some(Token::from(TokenType::Range, String::from(".."), pos))
+                    if self.valid() && self.character() == '=' {
+                        self.advance();
+                        TokenType::RangeInclusive
+                    } else {
+                        TokenType::Range
+                    }
                 } else {
-                    Some(Token::from(TokenType::Period, value, pos))
+                    TokenType::Period
                 }
             }
-            _ => None,
-        }
+            _ => return None,
+        };
+
+        Some(Token::from(kind, self.slice(start), pos))
     }
 
-    fn parse_operator_token(&mut self, pos: Position) -> Option<Token> {
+    fn parse_operator_token(&mut self, pos: Position) -> Option<Token<'src>> {
+        let start = self.cursor;
         let op = self.character();
 
         let next = if let Some(c) = self.peek_char() {
@@ -178,82 +441,43 @@ impl Lexer {
 
         self.advance();
 
-        match op {
-            '+' => {
-                if next == '=' {
-                    self.advance();
-                    Some(Token::from(TokenType::AddAssign, String::from("+="), pos))
-                } else if next == '+' {
-                    self.advance();
-                    Some(Token::from(TokenType::Inc, String::from("++"), pos))
-                } else {
-                    Some(Token::from(TokenType::Add, String::from(op), pos))
-                }
-            }
-            '-' => {
-                if next == '=' {
-                    self.advance();
-                    Some(Token::from(TokenType::SubAssign, String::from("-="), pos))
-                } else if next == '-' {
-                    self.advance();
-                    Some(Token::from(TokenType::Dec, String::from("--"), pos))
-                } else {
-                    Some(Token::from(TokenType::Sub, String::from(op), pos))
-                }
-            }
-            '*' => {
-                if next == '=' {
-                    self.advance();
-                    Some(Token::from(TokenType::MulAssign, String::from("*="), pos))
-                } else {
-                    Some(Token::from(TokenType::Mul, String::from(op), pos))
-                }
-            }
-            '/' => {
-                if next == '=' {
-                    self.advance();
-                    Some(Token::from(TokenType::DivAssign, String::from("/="), pos))
-                } else {
-                    Some(Token::from(TokenType::Div, String::from(op), pos))
-                }
-            }
-            '=' => {
-                if next == '=' {
-                    self.advance();
-                    Some(Token::from(TokenType::Eq, String::from("=="), pos))
-                } else {
-                    Some(Token::from(TokenType::Assign, String::from(op), pos))
-                }
-            }
-            '<' => {
-                if next == '=' {
-                    self.advance();
-                    Some(Token::from(TokenType::Lte, String::from("<="), pos))
-                } else {
-                    Some(Token::from(TokenType::Lt, String::from(op), pos))
-                }
-            }
-            '>' => {
-                if next == '=' {
-                    self.advance();
-                    Some(Token::from(TokenType::Gte, String::from(">="), pos))
-                } else {
-                    Some(Token::from(TokenType::Gt, String::from(op), pos))
-                }
-            }
-            '!' => {
-                if next == '=' {
-                    self.advance();
-                    Some(Token::from(TokenType::Ne, String::from("!="), pos))
-                } else {
-                    Some(Token::from(TokenType::Neg, String::from(op), pos))
-                }
-            }
-            _ => None,
+        let (kind, compound) = match (op, next) {
+            ('+', '=') => (TokenType::AddAssign, true),
+            ('+', '+') => (TokenType::Inc, true),
+            ('+', _) => (TokenType::Add, false),
+            ('-', '=') => (TokenType::SubAssign, true),
+            ('-', '-') => (TokenType::Dec, true),
+            ('-', _) => (TokenType::Sub, false),
+            ('*', '=') => (TokenType::MulAssign, true),
+            ('*', _) => (TokenType::Mul, false),
+            ('/', '=') => (TokenType::DivAssign, true),
+            ('/', _) => (TokenType::Div, false),
+            ('=', '=') => (TokenType::Eq, true),
+            ('=', '>') => (TokenType::FatArrow, true),
+            ('=', _) => (TokenType::Assign, false),
+            ('<', '=') => (TokenType::Lte, true),
+            ('<', _) => (TokenType::Lt, false),
+            ('>', '=') => (TokenType::Gte, true),
+            ('>', _) => (TokenType::Gt, false),
+            ('!', '=') => (TokenType::Ne, true),
+            ('!', _) => (TokenType::Neg, false),
+            ('|', '>') => (TokenType::Pipe, true),
+            ('|', _) => (TokenType::BitOr, false),
+            ('?', '?') => (TokenType::Coalesce, true),
+            ('?', _) => (TokenType::None, false),
+            ('&', _) => (TokenType::BitAnd, false),
+            ('^', _) => (TokenType::BitXor, false),
+            _ => return None,
+        };
+
+        if compound {
+            self.advance();
         }
+
+        Some(Token::from(kind, self.slice(start), pos))
     }
 
-    fn parse_ident_token(&mut self, pos: Position) -> Option<Token> {
+    fn parse_ident_token(&mut self, pos: Position) -> Option<Token<'src>> {
         let start = self.cursor;
         let mut c = self.character();
         while self.valid() && c.is_alphanumeric() || c == '_' {
@@ -261,18 +485,26 @@ impl Lexer {
             c = self.character();
         }
 
-        let value = String::from(&self.source[start..self.cursor]);
+        let value = self.slice(start);
 
-        let token_type = match value.as_str() {
+        let token_type = match &*value {
             "if" => TokenType::If,
+            "else" => TokenType::Else,
             "while" => TokenType::While,
             "for" => TokenType::For,
+            "match" => TokenType::Match,
             "in" => TokenType::In,
             "let" => TokenType::Let,
+            "mut" => TokenType::Mut,
             "impl" => TokenType::Impl,
+            "trait" => TokenType::Trait,
             "proc" => TokenType::Proc,
             "struct" => TokenType::Struct,
+            "enum" => TokenType::Enum,
             "return" => TokenType::Return,
+            "break" => TokenType::Break,
+            "continue" => TokenType::Continue,
+            "as" => TokenType::As,
             "true" | "false" => TokenType::Literal(LiteralType::Bool),
             _ => TokenType::Ident,
         };
@@ -280,25 +512,45 @@ impl Lexer {
         Some(Token::from(token_type, value, pos))
     }
 
-    fn parse_digit_token(&mut self, pos: Position) -> Option<Token> {
-        let start = self.cursor;
-        let mut c = self.character();
+    fn parse_digit_token(&mut self, pos: Position) -> Option<Token<'src>> {
+        let radix = match (self.character(), self.peek_char()) {
+            ('0', Some('x' | 'X')) => 16,
+            ('0', Some('o' | 'O')) => 8,
+            ('0', Some('b' | 'B')) => 2,
+            _ => 10,
+        };
 
+        if radix != 10 {
+            return self.parse_radix_token(pos, radix);
+        }
+
+        let mut digits = self.take_digits(10);
         let mut is_float = false;
-        while self.valid() && c.is_ascii_digit() {
+
+        if self.character() == '.' && self.peek_char().is_some_and(|c| c.is_ascii_digit()) {
+            is_float = true;
             self.advance();
-            c = self.character();
 
-            if c == '.' {
-                if let Some(n) = self.peek_char() {
-                    if n == '.' {
-                        is_float = false;
-                    } else {
-                        is_float = true;
-                        self.advance();
-                        c = self.character();
-                    }
+            digits.push('.');
+            digits.push_str(&self.take_digits(10));
+        }
+
+        if let 'e' | 'E' = self.character() {
+            let sign = self.peek_char().filter(|c| *c == '+' || *c == '-');
+            let offset = if sign.is_some() { 2 } else { 1 };
+
+            if self
+                .peek_char_by_amount(offset)
+                .is_some_and(|c| c.is_ascii_digit())
+            {
+                is_float = true;
+                self.advance_by(offset);
+
+                digits.push('e');
+                if let Some(sign) = sign {
+                    digits.push(sign);
                 }
+                digits.push_str(&self.take_digits(10));
             }
         }
 
@@ -308,39 +560,111 @@ impl Lexer {
             LiteralType::Number
         };
 
-        let value = String::from(&self.source[start..self.cursor]);
-        Some(Token::from(TokenType::Literal(lt), value, pos))
+        Some(Token::from(TokenType::Literal(lt), digits, pos))
     }
-}
 
-fn get_next_token(lexer: &mut Lexer) -> Option<Token> {
-    if !lexer.valid() {
-        return None;
+    fn parse_radix_token(&mut self, pos: Position, radix: u32) -> Option<Token<'src>> {
+        let start = self.cursor;
+        self.advance_by(2);
+
+        let digits = self.take_digits(radix);
+
+        // the rest of the word still belongs to the literal, '0b1012' is one bad token
+        if self.valid() && self.character().is_alphanumeric() {
+            let name = match radix {
+                2 => "binary",
+                8 => "octal",
+                _ => "hexadecimal",
+            };
+            let message = format!("invalid digit '{}' in {name} literal", self.character());
+            self.errors.push((self.get_cursor_pos(), message));
+
+            while self.valid() && (self.character().is_alphanumeric() || self.character() == '_') {
+                self.advance();
+            }
+        }
+
+        let value = match u32::from_str_radix(&digits, radix) {
+            Ok(n) => (n as i32).to_string(),
+            Err(..) => self.slice(start).into_owned(),
+        };
+
+        Some(Token::from(
+            TokenType::Literal(LiteralType::Number),
+            value,
+            pos,
+        ))
     }
 
-    if lexer.character().is_ascii_whitespace() {
-        lexer.trim();
+    fn take_digits(&mut self, radix: u32) -> String {
+        let mut digits = String::new();
 
-        if !lexer.valid() {
-            return None;
+        while self.valid() {
+            let c = self.character();
+
+            if c.is_digit(radix) {
+                digits.push(c);
+            } else if c != '_' {
+                break;
+            }
+
+            self.advance();
         }
+
+        digits
     }
+}
+
+pub fn unescape(raw: &str) -> String {
+    let mut value = String::new();
+
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
 
-    if lexer.character() == '/' {
-        if let Some(c) = lexer.peek_char() {
-            if c == '/' {
-                lexer.drop_line();
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('r') => value.push('\r'),
+            Some('0') => value.push('\0'),
+            Some(c @ ('\\' | '"' | '\'' | '{' | '}')) => value.push(c),
+            Some(c) => {
+                value.push('\\');
+                value.push(c);
             }
+            None => value.push('\\'),
         }
     }
 
+    value
+}
+
+fn unescaped(raw: Cow<'_, str>) -> Cow<'_, str> {
+    if raw.contains('\\') {
+        Cow::Owned(unescape(&raw))
+    } else {
+        raw
+    }
+}
+
+fn get_next_token<'src>(lexer: &mut Lexer<'src>) -> Option<Token<'src>> {
+    lexer.skip_trivia();
+
+    if !lexer.valid() {
+        return None;
+    }
+
     let first = lexer.character();
     let pos = lexer.get_cursor_pos();
+    let start = lexer.location();
 
-    let punctuation_tokens = "(){};:,.";
-    let operator_tokens = "+-*/=<>!";
+    let punctuation_tokens = "(){}[];:,.@";
+    let operator_tokens = "+-*/=<>!|&^?";
 
-    if first == '"' {
+    let token = if first == '"' {
         lexer.parse_string_token(pos)
     } else if first == '\'' {
         lexer.parse_char_token(pos)
@@ -353,14 +677,79 @@ fn get_next_token(lexer: &mut Lexer) -> Option<Token> {
     } else if first.is_ascii_digit() {
         lexer.parse_digit_token(pos)
     } else {
-        None
+        lexer.advance();
+        Some(Token::from(TokenType::None, String::from(first), pos))
+    };
+
+    token.map(|mut token| {
+        token.span = Span::new(start, lexer.location());
+        token
+    })
+}
+
+pub const BUILTIN_TYPES: &[&str] = &["i32", "f32", "bool", "char", "String", "any", "None"];
+
+pub fn classify(source: &str) -> Vec<(Range<usize>, TokenClass)> {
+    let mut lexer = Lexer::new(source, String::new());
+    let mut classes: Vec<(Range<usize>, TokenClass)> = Vec::new();
+    let mut tokens: Vec<(usize, Token)> = Vec::new();
+
+    loop {
+        let (before, comments) = (lexer.cursor, lexer.comments.len());
+        lexer.skip_trivia();
+
+        let mut offset = before;
+        for (_, text) in &lexer.comments[comments..] {
+            let start = offset + source[offset..].find(text.as_str()).unwrap_or_default();
+            offset = start + text.len();
+
+            let class = if text.starts_with("///") && !text.starts_with("////") {
+                TokenClass::DocComment
+            } else {
+                TokenClass::Comment
+            };
+            classes.push((start..offset, class));
+        }
+
+        let start = lexer.cursor;
+        let Some(token) = get_next_token(&mut lexer) else {
+            break;
+        };
+
+        let end = lexer.cursor.min(source.len());
+        tokens.push((classes.len(), token));
+        classes.push((start..end, TokenClass::Unknown));
     }
+
+    for (i, (at, token)) in tokens.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|j| tokens[j].1.kind);
+        let next = tokens.get(i + 1).map(|(_, t)| t.kind);
+
+        let class = match token.kind {
+            TokenType::Ident if previous == Some(TokenType::At) => TokenClass::Attribute,
+            TokenType::Ident if next == Some(TokenType::Oparen) => TokenClass::Function,
+            TokenType::Ident
+                if BUILTIN_TYPES.contains(&token.value.as_ref())
+                    || token.value.starts_with(|c: char| c.is_ascii_uppercase()) =>
+            {
+                TokenClass::Type
+            }
+            kind => TokenClass::of(kind),
+        };
+
+        classes[*at].1 = class;
+    }
+
+    classes
 }
 
-impl Iterator for Lexer {
-    type Item = Token;
+impl<'src> Iterator for Lexer<'src> {
+    type Item = Token<'src>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        get_next_token(self)
+        let token = get_next_token(self)?;
+        self.last_end = token.span.end;
+
+        Some(token)
     }
 }
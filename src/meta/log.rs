@@ -0,0 +1,126 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt::{Arguments, Display},
+    rc::Rc,
+    str::FromStr,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Trace,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Error => f.write_str("error"),
+            Level::Warn => f.write_str("warn"),
+            Level::Info => f.write_str("info"),
+            Level::Trace => f.write_str("trace"),
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "trace" => Ok(Level::Trace),
+            _ => Err(format!(
+                "unknown log level '{s}', expected one of: error, warn, info, trace"
+            )),
+        }
+    }
+}
+
+pub trait Logger {
+    fn log(&self, level: Level, target: &str, message: Arguments);
+}
+
+pub struct StderrLogger;
+
+impl Logger for StderrLogger {
+    fn log(&self, level: Level, target: &str, message: Arguments) {
+        eprintln!("[{level} {target}] {message}");
+    }
+}
+
+thread_local! {
+    static LEVEL: Cell<Option<Level>> = const { Cell::new(None) };
+    static LOGGER: RefCell<Rc<dyn Logger>> = RefCell::new(Rc::new(StderrLogger));
+}
+
+pub fn level() -> Option<Level> {
+    LEVEL.with(Cell::get)
+}
+
+pub fn set_level(level: Option<Level>) {
+    LEVEL.with(|cell| cell.set(level));
+}
+
+pub fn set_logger<L: Logger + 'static>(logger: L) {
+    LOGGER.with(|cell| *cell.borrow_mut() = Rc::new(logger));
+}
+
+// installs a level and logger until the returned scope is dropped, then restores the previous ones
+pub fn scope(level: Option<Level>, logger: Option<Rc<dyn Logger>>) -> LogScope {
+    let previous = LogScope {
+        level: self::level(),
+        logger: LOGGER.with(|cell| cell.borrow().clone()),
+    };
+
+    set_level(level);
+    let logger = logger.unwrap_or_else(|| Rc::new(StderrLogger));
+    LOGGER.with(|cell| *cell.borrow_mut() = logger);
+
+    previous
+}
+
+pub struct LogScope {
+    level: Option<Level>,
+    logger: Rc<dyn Logger>,
+}
+
+impl Drop for LogScope {
+    fn drop(&mut self) {
+        set_level(self.level);
+        let logger = self.logger.clone();
+        LOGGER.with(|cell| *cell.borrow_mut() = logger);
+    }
+}
+
+pub fn enabled(level: Level) -> bool {
+    self::level().is_some_and(|max| level <= max)
+}
+
+pub fn log(level: Level, target: &str, message: Arguments) {
+    if !enabled(level) {
+        return;
+    }
+
+    let logger = LOGGER.with(|cell| cell.borrow().clone());
+    logger.log(level, target, message);
+}
+
+pub fn error(target: &str, message: Arguments) {
+    log(Level::Error, target, message);
+}
+
+pub fn warn(target: &str, message: Arguments) {
+    log(Level::Warn, target, message);
+}
+
+pub fn info(target: &str, message: Arguments) {
+    log(Level::Info, target, message);
+}
+
+pub fn trace(target: &str, message: Arguments) {
+    log(Level::Trace, target, message);
+}
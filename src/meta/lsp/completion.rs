@@ -0,0 +1,677 @@
+use crate::{
+    expression::Expression,
+    lexer::{self, BUILTIN_TYPES},
+    nodes::{ClosureSyntax, EnumDefNode, ProcDefNode, StructDefNode},
+    parser::{Program, UNKNOWN_TYPE},
+    token::{LiteralType, TokenClass, TokenType},
+};
+
+use super::index::{key, token_end, Symbol, SymbolIndex, SymbolKind};
+
+const KEYWORDS: &[&str] = &[
+    "let", "mut", "if", "else", "while", "for", "in", "match", "return", "break", "continue",
+    "proc", "struct", "enum", "impl", "trait", "as", "true", "false",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Method,
+    Function,
+    Field,
+    Variable,
+    Struct,
+    Enum,
+    Trait,
+    Variant,
+    Keyword,
+    Type,
+}
+
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub detail: Option<String>,
+}
+
+impl Completion {
+    fn new(label: &str, kind: CompletionKind, detail: Option<String>) -> Self {
+        Self {
+            label: String::from(label),
+            kind,
+            detail,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignatureHelp {
+    pub label: String,
+    pub parameters: Vec<String>,
+    pub active: usize,
+    pub docs: Option<String>,
+}
+
+struct Local {
+    name: String,
+    row: Option<u32>,
+    type_name: String,
+}
+
+pub struct Completer<'a> {
+    source: &'a str,
+    index: &'a SymbolIndex,
+    structs: Vec<&'a StructDefNode>,
+    enums: Vec<&'a EnumDefNode>,
+    procedures: Vec<&'a ProcDefNode>,
+    methods: Vec<(&'a str, &'a ProcDefNode)>,
+    locals: Vec<Local>,
+}
+
+impl<'a> Completer<'a> {
+    pub fn new(source: &'a str, index: &'a SymbolIndex, program: &'a Program) -> Self {
+        let mut completer = Self {
+            source,
+            index,
+            structs: Vec::new(),
+            enums: Vec::new(),
+            procedures: Vec::new(),
+            methods: Vec::new(),
+            locals: Vec::new(),
+        };
+        completer.collect(program);
+        completer
+    }
+
+    pub fn complete(&self, row: u32, column: u32) -> Vec<Completion> {
+        if self.in_literal(row, column) {
+            return Vec::new();
+        }
+
+        let at = (row, column);
+        let tokens = &self.index.tokens;
+
+        let Some(mut last) = tokens.iter().rposition(|t| key(&t.position) < at) else {
+            return self.expression(at, None);
+        };
+
+        let mut prefix = "";
+        let token = &tokens[last];
+        if token
+            .value
+            .starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && at <= key(&token_end(token))
+        {
+            let typed = (column - token.position.column) as usize;
+            prefix = token
+                .value
+                .char_indices()
+                .nth(typed)
+                .map_or(token.value.as_ref(), |(i, _)| &token.value[..i]);
+
+            let Some(previous) = last.checked_sub(1) else {
+                return filter(self.expression(at, Some(last)), prefix);
+            };
+            last = previous;
+        }
+
+        let completions = match tokens[last].kind {
+            TokenType::Period => last
+                .checked_sub(1)
+                .and_then(|receiver| self.type_of(receiver))
+                .map(|type_name| self.members(&type_name, true))
+                .unwrap_or_default(),
+            TokenType::ScopeResolution => last
+                .checked_sub(1)
+                .map(|owner| self.members(&tokens[owner].value, false))
+                .unwrap_or_default(),
+            TokenType::Colon if self.is_type_position(last) => self.types(),
+            TokenType::As => self.types(),
+            _ => self.expression(at, Some(last + 1)),
+        };
+
+        filter(completions, prefix)
+    }
+
+    pub fn signature_help(&self, row: u32, column: u32) -> Option<SignatureHelp> {
+        if self.in_literal(row, column) {
+            return None;
+        }
+
+        let at = (row, column);
+        let tokens = &self.index.tokens;
+        let last = tokens.iter().rposition(|t| key(&t.position) < at)?;
+
+        let mut depth = 0;
+        let mut active = 0;
+        for open in (0..=last).rev() {
+            match tokens[open].kind {
+                TokenType::Cparen | TokenType::Ccurly => depth += 1,
+                TokenType::Ocurly if depth == 0 => return None,
+                TokenType::Ocurly => depth -= 1,
+                TokenType::Comma if depth == 0 => active += 1,
+                TokenType::Oparen if depth == 0 => {
+                    if let Some(mut help) = self.call_signature(open) {
+                        help.active = active;
+                        return Some(help);
+                    }
+                    active = 0;
+                }
+                TokenType::Oparen => depth -= 1,
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    fn call_signature(&self, open: usize) -> Option<SignatureHelp> {
+        let tokens = &self.index.tokens;
+        let callee = tokens.get(open.checked_sub(1)?)?;
+        if callee.kind != TokenType::Ident {
+            return None;
+        }
+
+        let previous = open.checked_sub(2).map(|i| tokens[i].kind);
+        let proc_def = match previous {
+            Some(TokenType::Proc) => return None,
+            Some(TokenType::Period) => {
+                let receiver = self.type_of(open.checked_sub(3)?)?;
+                self.method(&receiver, &callee.value)?
+            }
+            Some(TokenType::ScopeResolution) => {
+                let owner = &tokens[open.checked_sub(3)?].value;
+                if let Some(method) = self.method(owner, &callee.value) {
+                    method
+                } else {
+                    let variant = self
+                        .enums
+                        .iter()
+                        .find(|e| e.type_name == *owner)?
+                        .variants
+                        .iter()
+                        .find(|v| v.name == callee.value)?;
+
+                    let parameters: Vec<String> = variant
+                        .fields
+                        .iter()
+                        .map(|f| format!("{}: {}", f.name, f.type_name))
+                        .collect();
+
+                    return Some(SignatureHelp {
+                        label: format!("{owner}::{}({})", variant.name, parameters.join(", ")),
+                        parameters,
+                        active: 0,
+                        docs: None,
+                    });
+                }
+            }
+            _ => *self.procedures.iter().find(|p| p.name == callee.value)?,
+        };
+
+        let parameters = proc_def
+            .args
+            .iter()
+            .filter(|arg| arg.name != "self")
+            .map(|arg| format!("{}: {}", arg.name, arg.type_name))
+            .collect();
+
+        Some(SignatureHelp {
+            label: signature(proc_def),
+            parameters,
+            active: 0,
+            docs: proc_def.docs.clone(),
+        })
+    }
+
+    fn in_literal(&self, row: u32, column: u32) -> bool {
+        let mut offset = 0;
+        for (i, line) in self.source.split('\n').enumerate() {
+            if i == row as usize {
+                offset += line
+                    .char_indices()
+                    .nth(column as usize)
+                    .map_or(line.len(), |(i, _)| i);
+                break;
+            }
+            offset += line.len() + 1;
+        }
+
+        lexer::classify(self.source)
+            .iter()
+            .any(|(span, class)| match class {
+                TokenClass::Comment | TokenClass::DocComment => {
+                    span.start < offset && offset <= span.end
+                }
+                TokenClass::String | TokenClass::Char => span.start < offset && offset < span.end,
+                _ => false,
+            })
+    }
+
+    fn collect(&mut self, statements: &'a [Expression]) {
+        for statement in statements.iter() {
+            match statement {
+                Expression::ProcDef(proc_def_node) if proc_def_node.is_instance() => {}
+                Expression::StructDef(struct_def_node) if struct_def_node.is_instance() => {}
+                Expression::ProcDef(proc_def_node) => {
+                    self.procedures.push(proc_def_node);
+                    self.collect(&proc_def_node.statements);
+                }
+                Expression::ImplStatement(impl_node) => {
+                    for procedure in impl_node.procedures.iter() {
+                        if let Expression::ProcDef(proc_def_node) = procedure {
+                            self.methods
+                                .push((&impl_node.struct_def.type_name, proc_def_node));
+                            self.collect(&proc_def_node.statements);
+                        }
+                    }
+                }
+                Expression::TraitDef(trait_def) => {
+                    for method in trait_def.methods.iter() {
+                        self.methods.push((&trait_def.name, method));
+                    }
+                }
+                Expression::StructDef(struct_def_node) => self.structs.push(struct_def_node),
+                Expression::EnumDef(enum_def_node) => self.enums.push(enum_def_node),
+                Expression::LetStatement(let_node) => {
+                    self.locals.push(Local {
+                        name: let_node.name.clone(),
+                        row: Some(let_node.position.row),
+                        type_name: let_node.type_name.clone(),
+                    });
+
+                    if let Expression::Closure(closure_node) = let_node.value.as_ref() {
+                        if closure_node.syntax == ClosureSyntax::Reference {
+                            continue;
+                        }
+
+                        if closure_node.syntax == ClosureSyntax::Named {
+                            self.procedures.push(&closure_node.proc_def);
+                        }
+
+                        for arg in closure_node.proc_def.args.iter() {
+                            self.locals.push(Local {
+                                name: arg.name.clone(),
+                                row: Some(closure_node.proc_def.position.row),
+                                type_name: arg.type_name.clone(),
+                            });
+                        }
+                        self.collect(&closure_node.proc_def.statements);
+                    }
+                }
+                Expression::IfStatement(if_node) => {
+                    self.collect(&if_node.statements);
+                    if let Some(else_node) = &if_node.else_branch {
+                        self.collect(&else_node.statements);
+                    }
+                }
+                Expression::WhileStatement(while_node) => self.collect(&while_node.statements),
+                Expression::ForLoop(for_node) => {
+                    self.locals.push(Local {
+                        name: for_node.counter.metadata.name.clone(),
+                        row: Some(for_node.position.row),
+                        type_name: for_node.counter.metadata.type_name.clone(),
+                    });
+                    self.collect(&for_node.statements);
+                }
+                Expression::MatchStatement(match_node) => {
+                    for arm in match_node.arms.iter() {
+                        for binding in arm.pattern.bindings() {
+                            self.locals.push(Local {
+                                name: binding.name,
+                                row: None,
+                                type_name: binding.type_name,
+                            });
+                        }
+                        self.collect(&arm.statements);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn is_type_position(&self, colon: usize) -> bool {
+        let kind = |i: usize| self.index.tokens.get(i).map(|t| t.kind);
+
+        if colon == 0 {
+            return false;
+        }
+
+        if kind(colon - 1) == Some(TokenType::Cparen) {
+            return true;
+        }
+
+        if kind(colon - 1) == Some(TokenType::Ident) {
+            let before = colon.checked_sub(2).and_then(kind);
+            let let_mut = colon.checked_sub(3).and_then(kind);
+            if before == Some(TokenType::Let)
+                || (before == Some(TokenType::Mut) && let_mut == Some(TokenType::Let))
+            {
+                return true;
+            }
+        }
+
+        let Some(open) = self.enclosing(colon) else {
+            return false;
+        };
+
+        let item = open.checked_sub(2).and_then(kind);
+        match kind(open) {
+            Some(TokenType::Oparen) if item == Some(TokenType::Proc) => true,
+            Some(TokenType::Oparen) => {
+                self.enclosing(open)
+                    .and_then(|outer| outer.checked_sub(2))
+                    .and_then(kind)
+                    == Some(TokenType::Enum)
+            }
+            Some(TokenType::Ocurly) => item == Some(TokenType::Struct),
+            _ => false,
+        }
+    }
+
+    fn enclosing(&self, from: usize) -> Option<usize> {
+        let mut depth = 0;
+        for i in (0..from).rev() {
+            match self.index.tokens[i].kind {
+                TokenType::Cparen | TokenType::Ccurly => depth += 1,
+                TokenType::Oparen | TokenType::Ocurly if depth == 0 => return Some(i),
+                TokenType::Oparen | TokenType::Ocurly => depth -= 1,
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    fn type_of(&self, i: usize) -> Option<String> {
+        let tokens = &self.index.tokens;
+        let token = tokens.get(i)?;
+        let kind = |j: usize| tokens.get(j).map(|t| t.kind);
+
+        let type_name = match token.kind {
+            TokenType::Literal(LiteralType::Number) => String::from("i32"),
+            TokenType::Literal(LiteralType::Float) => String::from("f32"),
+            TokenType::Literal(LiteralType::String) => String::from("String"),
+            TokenType::Literal(LiteralType::Char) => String::from("char"),
+            TokenType::Literal(LiteralType::Bool) => String::from("bool"),
+            TokenType::Cparen => {
+                let open = self.matching_paren(i)?;
+                let callee = tokens.get(open.checked_sub(1)?)?;
+
+                match open.checked_sub(2).and_then(kind) {
+                    Some(TokenType::Period) => {
+                        let receiver = self.type_of(open.checked_sub(3)?)?;
+                        self.method(&receiver, &callee.value)?.return_type.clone()?
+                    }
+                    Some(TokenType::ScopeResolution) => {
+                        let owner = &tokens[open - 3].value;
+                        self.method(owner, &callee.value)?.return_type.clone()?
+                    }
+                    _ => self
+                        .procedures
+                        .iter()
+                        .find(|p| p.name == callee.value)?
+                        .return_type
+                        .clone()?,
+                }
+            }
+            TokenType::Ident if i.checked_sub(1).and_then(kind) == Some(TokenType::Period) => {
+                let receiver = self.type_of(i.checked_sub(2)?)?;
+                self.structs
+                    .iter()
+                    .find(|s| s.type_name == receiver)?
+                    .fields
+                    .iter()
+                    .find(|f| f.name == token.value)?
+                    .type_name
+                    .clone()
+            }
+            TokenType::Ident => {
+                let symbol = self
+                    .index
+                    .definition(token.position.row, token.position.column)?;
+                self.local_type(symbol)?
+            }
+            _ => return None,
+        };
+
+        (type_name != UNKNOWN_TYPE).then_some(type_name)
+    }
+
+    fn matching_paren(&self, close: usize) -> Option<usize> {
+        let mut depth = 0;
+        for i in (0..=close).rev() {
+            match self.index.tokens[i].kind {
+                TokenType::Cparen => depth += 1,
+                TokenType::Oparen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(i);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    fn local_type(&self, symbol: &Symbol) -> Option<String> {
+        match symbol.kind {
+            SymbolKind::Parameter => {
+                let owner = &self.index.symbols[symbol.parent?];
+                let proc_def = match owner.kind {
+                    SymbolKind::Method if symbol.name == "self" => return owner.container.clone(),
+                    SymbolKind::Method => self.method(owner.container.as_deref()?, &owner.name)?,
+                    _ => *self.procedures.iter().find(|p| p.name == owner.name)?,
+                };
+
+                proc_def
+                    .args
+                    .iter()
+                    .find(|arg| arg.name == symbol.name)
+                    .map(|arg| arg.type_name.clone())
+                    .or_else(|| {
+                        self.locals
+                            .iter()
+                            .find(|local| {
+                                local.name == symbol.name && local.row == Some(symbol.position.row)
+                            })
+                            .map(|local| local.type_name.clone())
+                    })
+            }
+            SymbolKind::Variable => self
+                .locals
+                .iter()
+                .find(|local| local.name == symbol.name && local.row == Some(symbol.position.row))
+                .or_else(|| self.locals.iter().find(|local| local.name == symbol.name))
+                .map(|local| local.type_name.clone()),
+            _ => None,
+        }
+    }
+
+    fn method(&self, type_name: &str, name: &str) -> Option<&'a ProcDefNode> {
+        self.methods
+            .iter()
+            .find(|(owner, method)| *owner == type_name && method.name == name)
+            .map(|(_, method)| *method)
+    }
+
+    fn members(&self, type_name: &str, receiver: bool) -> Vec<Completion> {
+        let mut completions = Vec::new();
+
+        if receiver {
+            if let Some(struct_def) = self.structs.iter().find(|s| s.type_name == type_name) {
+                for field in struct_def.fields.iter() {
+                    completions.push(Completion::new(
+                        &field.name,
+                        CompletionKind::Field,
+                        Some(field.type_name.clone()),
+                    ));
+                }
+            }
+        } else if let Some(enum_def) = self.enums.iter().find(|e| e.type_name == type_name) {
+            for variant in enum_def.variants.iter() {
+                let fields: Vec<String> = variant
+                    .fields
+                    .iter()
+                    .map(|f| format!("{}: {}", f.name, f.type_name))
+                    .collect();
+                let detail = if fields.is_empty() {
+                    type_name.to_string()
+                } else {
+                    format!("{type_name}::{}({})", variant.name, fields.join(", "))
+                };
+
+                completions.push(Completion::new(
+                    &variant.name,
+                    CompletionKind::Variant,
+                    Some(detail),
+                ));
+            }
+        }
+
+        for (owner, method) in self.methods.iter() {
+            let takes_self = method.args.first().is_some_and(|arg| arg.name == "self");
+            if *owner == type_name && takes_self == receiver {
+                completions.push(Completion::new(
+                    &method.name,
+                    CompletionKind::Method,
+                    Some(signature(method)),
+                ));
+            }
+        }
+
+        completions
+    }
+
+    fn is_initializing(&self, symbol: &Symbol, at: (u32, u32)) -> bool {
+        let tokens = &self.index.tokens;
+        let Some(name) = tokens
+            .iter()
+            .position(|t| key(&t.position) == key(&symbol.position))
+        else {
+            return false;
+        };
+
+        let declared = name
+            .checked_sub(1)
+            .is_some_and(|i| matches!(tokens[i].kind, TokenType::Let | TokenType::Mut));
+
+        declared
+            && !tokens[name..]
+                .iter()
+                .take_while(|t| key(&t.position) < at)
+                .any(|t| t.position.row != symbol.position.row)
+    }
+
+    fn types(&self) -> Vec<Completion> {
+        let mut completions: Vec<Completion> = self
+            .index
+            .symbols
+            .iter()
+            .filter_map(|symbol| match symbol.kind {
+                SymbolKind::Struct => {
+                    Some(Completion::new(&symbol.name, CompletionKind::Struct, None))
+                }
+                SymbolKind::Enum => Some(Completion::new(&symbol.name, CompletionKind::Enum, None)),
+                SymbolKind::Trait => {
+                    Some(Completion::new(&symbol.name, CompletionKind::Trait, None))
+                }
+                _ => None,
+            })
+            .collect();
+
+        for builtin in BUILTIN_TYPES.iter() {
+            completions.push(Completion::new(builtin, CompletionKind::Type, None));
+        }
+
+        completions
+    }
+
+    fn expression(&self, at: (u32, u32), typing: Option<usize>) -> Vec<Completion> {
+        let typed = typing
+            .and_then(|i| self.index.tokens.get(i))
+            .map(|token| key(&token.position));
+
+        let mut locals: Vec<&Symbol> = self
+            .index
+            .symbols
+            .iter()
+            .filter(|symbol| {
+                symbol.kind.is_local()
+                    && key(&symbol.position) < at
+                    && Some(key(&symbol.position)) != typed
+                    && symbol.scope_end.as_ref().is_none_or(|end| at <= key(end))
+                    && !self.is_initializing(symbol, at)
+            })
+            .collect();
+        locals.sort_by_key(|symbol| std::cmp::Reverse(key(&symbol.position)));
+
+        let mut completions: Vec<Completion> = Vec::new();
+        for symbol in locals {
+            if completions.iter().any(|c| c.label == symbol.name) {
+                continue;
+            }
+
+            completions.push(Completion::new(
+                &symbol.name,
+                CompletionKind::Variable,
+                self.local_type(symbol).filter(|t| t != UNKNOWN_TYPE),
+            ));
+        }
+
+        for symbol in self.index.symbols.iter() {
+            if symbol.kind == SymbolKind::Procedure {
+                let detail = self
+                    .procedures
+                    .iter()
+                    .find(|p| p.name == symbol.name)
+                    .map(|p| signature(p));
+                completions.push(Completion::new(
+                    &symbol.name,
+                    CompletionKind::Function,
+                    detail,
+                ));
+            }
+        }
+
+        completions.extend(
+            self.types()
+                .into_iter()
+                .filter(|c| c.kind != CompletionKind::Type),
+        );
+
+        for keyword in KEYWORDS.iter() {
+            completions.push(Completion::new(keyword, CompletionKind::Keyword, None));
+        }
+
+        completions
+    }
+}
+
+fn filter(completions: Vec<Completion>, prefix: &str) -> Vec<Completion> {
+    completions
+        .into_iter()
+        .filter(|c| c.label.starts_with(prefix))
+        .collect()
+}
+
+fn signature(proc_def: &ProcDefNode) -> String {
+    let args: Vec<String> = proc_def
+        .args
+        .iter()
+        .map(|arg| match arg.name.as_str() {
+            "self" => String::from("self"),
+            name => format!("{name}: {}", arg.type_name),
+        })
+        .collect();
+
+    let mut signature = format!("proc {}({})", proc_def.name, args.join(", "));
+    if let Some(return_type) = &proc_def.return_type {
+        signature.push_str(&format!(": {return_type}"));
+    }
+
+    signature
+}
@@ -0,0 +1,290 @@
+use crate::{
+    expression::Expression,
+    json::Json,
+    nodes::{ClosureSyntax, FunCallNode},
+    parser::{Program, UNKNOWN_TYPE},
+    token::{Position, TokenType},
+};
+
+use super::index::{key, token_end, SymbolIndex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HintOptions {
+    pub types: bool,
+    pub parameters: bool,
+}
+
+impl Default for HintOptions {
+    fn default() -> Self {
+        Self {
+            types: true,
+            parameters: true,
+        }
+    }
+}
+
+impl HintOptions {
+    pub fn configure(&mut self, settings: &Json) {
+        if let Some(types) = settings.get("types").and_then(Json::as_bool) {
+            self.types = types;
+        }
+
+        if let Some(parameters) = settings.get("parameters").and_then(Json::as_bool) {
+            self.parameters = parameters;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InlayHintKind {
+    Type,
+    Parameter,
+}
+
+#[derive(Debug, Clone)]
+pub struct InlayHint {
+    pub position: Position,
+    pub label: String,
+    pub kind: InlayHintKind,
+}
+
+struct Collector<'a> {
+    lets: Vec<(Position, &'a str)>,
+    calls: Vec<&'a FunCallNode>,
+}
+
+pub fn inlay_hints(index: &SymbolIndex, program: &Program, options: HintOptions) -> Vec<InlayHint> {
+    let mut collector = Collector {
+        lets: Vec::new(),
+        calls: Vec::new(),
+    };
+    collector.visit_all(program);
+
+    let tokens = &index.tokens;
+    let find = |position: &Position| {
+        tokens
+            .iter()
+            .position(|t| key(&t.position) == key(position))
+    };
+
+    let mut hints = Vec::new();
+
+    if options.types {
+        for (position, type_name) in collector.lets {
+            let Some(mut name) = find(&position).map(|i| i + 1) else {
+                continue;
+            };
+            if tokens.get(name).map(|t| t.kind) == Some(TokenType::Mut) {
+                name += 1;
+            }
+
+            if let Some(token) = tokens.get(name).filter(|t| t.kind == TokenType::Ident) {
+                hints.push(InlayHint {
+                    position: token_end(token),
+                    label: format!(": {type_name}"),
+                    kind: InlayHintKind::Type,
+                });
+            }
+        }
+    }
+
+    if options.parameters {
+        for call in collector.calls {
+            let Some(callee) = find(&call.position) else {
+                continue;
+            };
+
+            let names = call
+                .proc_def
+                .args
+                .iter()
+                .filter(|arg| arg.name != "self")
+                .map(|arg| arg.name.as_str());
+
+            for (name, start) in names.zip(argument_starts(index, callee + 1)) {
+                let token = &tokens[start];
+                let plain = matches!(
+                    tokens.get(start + 1).map(|t| t.kind),
+                    Some(TokenType::Comma | TokenType::Cparen)
+                );
+                if plain && token.kind == TokenType::Ident && token.value == name {
+                    continue;
+                }
+
+                hints.push(InlayHint {
+                    position: token.position.clone(),
+                    label: format!("{name}:"),
+                    kind: InlayHintKind::Parameter,
+                });
+            }
+        }
+    }
+
+    hints.sort_by_key(|hint| key(&hint.position));
+    hints
+}
+
+fn argument_starts(index: &SymbolIndex, open: usize) -> Vec<usize> {
+    let tokens = &index.tokens;
+    if tokens.get(open).map(|t| t.kind) != Some(TokenType::Oparen) {
+        return Vec::new();
+    }
+
+    let mut starts = Vec::new();
+    let mut depth = 0;
+    let mut expecting = true;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token.kind {
+            TokenType::Oparen | TokenType::Ocurly => depth += 1,
+            TokenType::Cparen | TokenType::Ccurly => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            TokenType::Comma if depth == 1 => {
+                expecting = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        if expecting && i > open {
+            starts.push(i);
+            expecting = false;
+        }
+    }
+
+    starts
+}
+
+impl<'a> Collector<'a> {
+    fn visit_all(&mut self, exprs: &'a [Expression]) {
+        for expr in exprs.iter() {
+            self.visit(expr);
+        }
+    }
+
+    fn visit(&mut self, expr: &'a Expression) {
+        match expr {
+            Expression::IfStatement(if_node) => {
+                self.visit(&if_node.value);
+                self.visit_all(&if_node.statements);
+                if let Some(else_node) = &if_node.else_branch {
+                    self.visit_all(&else_node.statements);
+                }
+            }
+            Expression::WhileStatement(while_node) => {
+                self.visit(&while_node.value);
+                self.visit_all(&while_node.statements);
+            }
+            Expression::ForLoop(for_node) => {
+                self.visit(&for_node.range);
+                self.visit_all(&for_node.statements);
+            }
+            Expression::MatchStatement(match_node) => {
+                self.visit(&match_node.value);
+                for arm in match_node.arms.iter() {
+                    if let Some(guard) = &arm.guard {
+                        self.visit(guard);
+                    }
+                    self.visit_all(&arm.statements);
+                }
+            }
+            Expression::RangeStatement(range_node) => {
+                self.visit(&range_node.start);
+                self.visit(&range_node.end);
+            }
+            Expression::LetStatement(let_node) => {
+                if !let_node.annotated && let_node.type_name != UNKNOWN_TYPE {
+                    self.lets
+                        .push((let_node.position.clone(), &let_node.type_name));
+                }
+                self.visit(&let_node.value);
+            }
+            Expression::AssignStatement(assign_node) => self.visit(&assign_node.new_value),
+            Expression::ReturnStatement(return_node) => self.visit(&return_node.value),
+            Expression::ProcDef(proc_def_node) if proc_def_node.is_instance() => {}
+            Expression::ProcDef(proc_def_node) => self.visit_all(&proc_def_node.statements),
+            Expression::ImplStatement(impl_node) if !impl_node.derived => {
+                self.visit_all(&impl_node.procedures)
+            }
+            Expression::FunCall(fun_call_node) => {
+                self.calls.push(fun_call_node);
+                for arg in fun_call_node.args.iter() {
+                    self.visit(&arg.value);
+                }
+            }
+            Expression::Closure(closure_node) => {
+                if closure_node.syntax != ClosureSyntax::Reference {
+                    self.visit_all(&closure_node.proc_def.statements);
+                }
+            }
+            Expression::ClosureCall(closure_call_node) => {
+                self.visit(&closure_call_node.callee);
+                self.visit_all(&closure_call_node.args);
+            }
+            Expression::ImplFunCall(impl_fun_call_node) => {
+                self.visit(&impl_fun_call_node.fun_call_node)
+            }
+            Expression::DynCall(dyn_call_node) => {
+                for arg in dyn_call_node.fun_call_node.args.iter() {
+                    self.visit(&arg.value);
+                }
+            }
+            Expression::Pipe(pipe_node) => self.visit(&pipe_node.call),
+            Expression::Coalesce(coalesce_node) => {
+                self.visit(&coalesce_node.value);
+                self.visit(&coalesce_node.fallback);
+            }
+            Expression::CompareChain(compare_chain_node) => {
+                self.visit_all(&compare_chain_node.operands)
+            }
+            Expression::MapCall(map_call_node) => {
+                self.visit(&map_call_node.map);
+                self.visit_all(&map_call_node.args);
+            }
+            Expression::VecCall(vec_call_node) => {
+                self.visit(&vec_call_node.vec);
+                self.visit_all(&vec_call_node.args);
+            }
+            Expression::StructInstance(struct_instance_node) => {
+                for field in struct_instance_node.fields.iter() {
+                    self.visit(&field.value);
+                }
+            }
+            Expression::EnumInstance(enum_instance_node) => {
+                for field in enum_instance_node.fields.iter() {
+                    self.visit(&field.value);
+                }
+            }
+            Expression::EnumFrom(enum_from_node) => self.visit(&enum_from_node.value),
+            Expression::EnumParse(enum_parse_node) => self.visit(&enum_parse_node.value),
+            Expression::VariantName(variant_name_node) => self.visit(&variant_name_node.value),
+            Expression::Cast(cast_node) => self.visit(&cast_node.value),
+            Expression::Contains(contains_node) => {
+                self.visit(&contains_node.value);
+                self.visit(&contains_node.flag);
+            }
+            Expression::StructFieldAssign(field_assign_node) => {
+                self.visit(&field_assign_node.new_value)
+            }
+            Expression::StructFieldAccess(field_access_node) => self.visit(&field_access_node.base),
+            Expression::BinaryOp(binary_op_node) => {
+                self.visit(&binary_op_node.lhs);
+                self.visit(&binary_op_node.rhs);
+            }
+            Expression::ImplStatement(..)
+            | Expression::TraitDef(..)
+            | Expression::MapNew(..)
+            | Expression::VecNew(..)
+            | Expression::StructDef(..)
+            | Expression::EnumDef(..)
+            | Expression::BreakStatement(..)
+            | Expression::ContinueStatement(..)
+            | Expression::Variable(..)
+            | Expression::Literal(..)
+            | Expression::Error(..) => {}
+        }
+    }
+}
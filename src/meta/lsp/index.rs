@@ -0,0 +1,728 @@
+use crate::{
+    lexer::Lexer,
+    token::{Position, Token, TokenType},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Procedure,
+    Method,
+    Struct,
+    Field,
+    Enum,
+    Variant,
+    Trait,
+    Impl,
+    Variable,
+    Parameter,
+}
+
+impl SymbolKind {
+    pub fn is_local(&self) -> bool {
+        matches!(self, SymbolKind::Variable | SymbolKind::Parameter)
+    }
+}
+
+impl std::fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SymbolKind::Procedure => f.write_str("proc"),
+            SymbolKind::Method => f.write_str("method"),
+            SymbolKind::Struct => f.write_str("struct"),
+            SymbolKind::Field => f.write_str("field"),
+            SymbolKind::Enum => f.write_str("enum"),
+            SymbolKind::Variant => f.write_str("variant"),
+            SymbolKind::Trait => f.write_str("trait"),
+            SymbolKind::Impl => f.write_str("impl"),
+            SymbolKind::Variable => f.write_str("variable"),
+            SymbolKind::Parameter => f.write_str("parameter"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub container: Option<String>,
+    pub parent: Option<usize>,
+    pub position: Position,
+    pub start: Position,
+    pub end: Position,
+    pub scope_end: Option<Position>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    Declaration,
+    Read,
+    Write,
+    Call,
+    Construct,
+}
+
+impl std::fmt::Display for ReferenceKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReferenceKind::Declaration => f.write_str("declaration"),
+            ReferenceKind::Read => f.write_str("read"),
+            ReferenceKind::Write => f.write_str("write"),
+            ReferenceKind::Call => f.write_str("call"),
+            ReferenceKind::Construct => f.write_str("construct"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub token: usize,
+    pub position: Position,
+    pub end: Position,
+    pub kind: ReferenceKind,
+}
+
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    pub tokens: Vec<Token<'static>>,
+    pub symbols: Vec<Symbol>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FrameKind {
+    Block,
+    Item,
+    Match,
+}
+
+struct Frame {
+    kind: FrameKind,
+    symbol: Option<usize>,
+    parens: usize,
+    arm_start: bool,
+}
+
+struct Local {
+    symbol: usize,
+    depth: usize,
+    arm: bool,
+}
+
+pub fn fuzzy_score(query: &str, name: &str) -> Option<u32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    let name: Vec<char> = name.chars().collect();
+    let mut score = 0;
+    let mut last: Option<usize> = None;
+    let mut from = 0;
+
+    for q in query.chars() {
+        let q = q.to_ascii_lowercase();
+        let i = from
+            + name[from..]
+                .iter()
+                .position(|c| c.to_ascii_lowercase() == q)?;
+
+        score += 1;
+        if last.is_some_and(|last| last + 1 == i) {
+            score += 4;
+        }
+        if i == 0 || name[i - 1] == '_' || (name[i].is_uppercase() && name[i - 1].is_lowercase()) {
+            score += 3;
+        }
+
+        last = Some(i);
+        from = i + 1;
+    }
+
+    if name.len() == query.chars().count() {
+        score += 10;
+    }
+
+    Some(score)
+}
+
+pub fn key(position: &Position) -> (u32, u32) {
+    (position.row, position.column)
+}
+
+pub fn token_end(token: &Token) -> Position {
+    let mut end = token.position.clone();
+    end.column += token.value.chars().count() as u32;
+    end
+}
+
+impl SymbolIndex {
+    pub fn new(source: &str, filename: &str) -> Self {
+        let tokens = Lexer::new(source, String::from(filename))
+            .map(Token::into_owned)
+            .collect();
+
+        let mut index = Self {
+            tokens,
+            symbols: Vec::new(),
+        };
+        index.scan();
+        index
+    }
+
+    pub fn children(&self, parent: Option<usize>) -> impl Iterator<Item = (usize, &Symbol)> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter(move |(_, symbol)| symbol.parent == parent)
+    }
+
+    pub fn search(&self, query: &str) -> Vec<(u32, &Symbol)> {
+        let mut found: Vec<(u32, &Symbol)> = self
+            .symbols
+            .iter()
+            .filter(|symbol| match symbol.kind {
+                SymbolKind::Impl | SymbolKind::Parameter => false,
+                SymbolKind::Variable => symbol.parent.is_none(),
+                _ => true,
+            })
+            .filter_map(|symbol| fuzzy_score(query, &symbol.name).map(|score| (score, symbol)))
+            .collect();
+
+        found.sort_by(|(a, _), (b, _)| b.cmp(a));
+        found
+    }
+
+    pub fn ident_at(&self, row: u32, column: u32) -> Option<usize> {
+        self.tokens.iter().position(|token| {
+            token.kind == TokenType::Ident
+                && token.position.row == row
+                && token.position.column <= column
+                && column <= token_end(token).column
+        })
+    }
+
+    pub fn definition(&self, row: u32, column: u32) -> Option<&Symbol> {
+        let i = self.ident_at(row, column)?;
+        self.resolve(i).map(|s| &self.symbols[s])
+    }
+
+    pub fn symbol_at(&self, row: u32, column: u32) -> Option<usize> {
+        self.resolve(self.ident_at(row, column)?)
+    }
+
+    pub fn resolve(&self, i: usize) -> Option<usize> {
+        let token = self.tokens.get(i).filter(|t| t.kind == TokenType::Ident)?;
+
+        let at = key(&token.position);
+        if let Some(declared) = self
+            .symbols
+            .iter()
+            .position(|symbol| symbol.kind != SymbolKind::Impl && key(&symbol.position) == at)
+        {
+            return Some(declared);
+        }
+
+        let name = token.value.as_ref();
+        let previous = i.checked_sub(1).and_then(|j| self.kind(j));
+        let find = |filter: &dyn Fn(&Symbol) -> bool| {
+            self.symbols
+                .iter()
+                .position(|symbol| symbol.name == name && filter(symbol))
+        };
+
+        if previous == Some(TokenType::ScopeResolution) {
+            let owner = i.checked_sub(2).map(|j| self.tokens[j].value.as_ref());
+            return find(&|symbol| {
+                symbol.container.as_deref() == owner
+                    && matches!(symbol.kind, SymbolKind::Variant | SymbolKind::Method)
+            });
+        }
+
+        if previous == Some(TokenType::Period) {
+            return find(&|symbol| matches!(symbol.kind, SymbolKind::Field | SymbolKind::Method));
+        }
+
+        if matches!(previous, Some(TokenType::Ocurly | TokenType::Comma))
+            && self.kind(i + 1) == Some(TokenType::Colon)
+        {
+            if let Some(owner) = self.literal_owner(i) {
+                return find(&|symbol| {
+                    symbol.kind == SymbolKind::Field && symbol.container.as_deref() == Some(owner)
+                });
+            }
+        }
+
+        let local = self
+            .symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| {
+                symbol.kind.is_local()
+                    && symbol.name == name
+                    && key(&symbol.position) <= at
+                    && symbol.scope_end.as_ref().is_none_or(|end| at <= key(end))
+            })
+            .max_by_key(|(_, symbol)| key(&symbol.position))
+            .map(|(s, _)| s);
+
+        local.or_else(|| {
+            find(&|symbol| {
+                matches!(
+                    symbol.kind,
+                    SymbolKind::Procedure | SymbolKind::Struct | SymbolKind::Enum
+                )
+            })
+        })
+    }
+
+    pub fn references(&self, symbol: usize) -> Vec<Reference> {
+        let declared = &self.symbols[symbol];
+        let callable = matches!(declared.kind, SymbolKind::Procedure | SymbolKind::Method);
+
+        self.tokens
+            .iter()
+            .enumerate()
+            .filter(|(i, token)| token.value == declared.name && self.resolve(*i) == Some(symbol))
+            .map(|(i, token)| {
+                let next = self.kind(i + 1);
+                let previous = i.checked_sub(1).and_then(|j| self.kind(j));
+                let kind = if key(&token.position) == key(&declared.position) {
+                    ReferenceKind::Declaration
+                } else if callable
+                    && (next == Some(TokenType::Oparen) || previous == Some(TokenType::Pipe))
+                {
+                    ReferenceKind::Call
+                } else if declared.kind == SymbolKind::Variant
+                    || (declared.kind == SymbolKind::Struct
+                        && next == Some(TokenType::Ocurly)
+                        && !matches!(previous, Some(TokenType::Impl | TokenType::Colon)))
+                {
+                    ReferenceKind::Construct
+                } else if (declared.kind == SymbolKind::Field && next == Some(TokenType::Colon))
+                    || matches!(
+                        next,
+                        Some(
+                            TokenType::Assign
+                                | TokenType::AddAssign
+                                | TokenType::SubAssign
+                                | TokenType::MulAssign
+                                | TokenType::DivAssign
+                                | TokenType::Inc
+                                | TokenType::Dec
+                        )
+                    )
+                {
+                    ReferenceKind::Write
+                } else {
+                    ReferenceKind::Read
+                };
+
+                Reference {
+                    token: i,
+                    position: token.position.clone(),
+                    end: token_end(token),
+                    kind,
+                }
+            })
+            .collect()
+    }
+
+    pub fn enclosing(&self, position: &Position) -> Option<usize> {
+        let at = key(position);
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter(|(_, symbol)| {
+                matches!(symbol.kind, SymbolKind::Procedure | SymbolKind::Method)
+                    && key(&symbol.start) <= at
+                    && at <= key(&symbol.end)
+            })
+            .max_by_key(|(_, symbol)| key(&symbol.start))
+            .map(|(s, _)| s)
+    }
+
+    fn literal_owner(&self, i: usize) -> Option<&str> {
+        let mut depth = 0;
+        for j in (0..i).rev() {
+            match self.tokens[j].kind {
+                TokenType::Ccurly | TokenType::Cparen => depth += 1,
+                TokenType::Oparen => depth -= 1,
+                TokenType::Ocurly if depth == 0 => {
+                    return j
+                        .checked_sub(1)
+                        .map(|k| &self.tokens[k])
+                        .filter(|t| t.kind == TokenType::Ident)
+                        .map(|t| t.value.as_ref());
+                }
+                TokenType::Ocurly => depth -= 1,
+                _ => {}
+            }
+        }
+
+        None
+    }
+
+    fn kind(&self, i: usize) -> Option<TokenType> {
+        self.tokens.get(i).map(|t| t.kind)
+    }
+
+    fn is_closure_start(&self, i: usize) -> bool {
+        let previous = i.checked_sub(1).and_then(|j| self.kind(j));
+        matches!(
+            previous,
+            None | Some(
+                TokenType::Assign
+                    | TokenType::Oparen
+                    | TokenType::Comma
+                    | TokenType::Return
+                    | TokenType::FatArrow
+                    | TokenType::Ocurly
+                    | TokenType::Semicolon
+            )
+        )
+    }
+
+    fn closing(&self, start: usize, kind: TokenType) -> usize {
+        let mut i = start;
+        while let Some(next) = self.kind(i) {
+            if next == kind {
+                break;
+            }
+            i += 1;
+        }
+
+        i
+    }
+
+    fn closure_params(&self, start: usize, close: usize) -> Vec<usize> {
+        (start..close)
+            .filter(|&i| {
+                self.kind(i) == Some(TokenType::Ident) && self.kind(i + 1) == Some(TokenType::Colon)
+            })
+            .collect()
+    }
+
+    fn expression_end(&self, start: usize) -> Position {
+        let mut depth = 0;
+        let mut i = start;
+        while let Some(kind) = self.kind(i) {
+            match kind {
+                TokenType::Oparen | TokenType::Ocurly => depth += 1,
+                TokenType::Cparen | TokenType::Ccurly if depth == 0 => break,
+                TokenType::Cparen | TokenType::Ccurly => depth -= 1,
+                TokenType::Semicolon | TokenType::Comma if depth == 0 => break,
+                _ => {}
+            }
+            i += 1;
+        }
+
+        match self.tokens.get(i).or(self.tokens.last()) {
+            Some(token) => token.position.clone(),
+            None => Position::default(),
+        }
+    }
+
+    fn push(&mut self, token: usize, kind: SymbolKind, parent: Option<usize>) -> usize {
+        let name = &self.tokens[token];
+        let container = match kind {
+            SymbolKind::Method | SymbolKind::Field | SymbolKind::Variant => {
+                parent.map(|p| self.symbols[p].name.clone())
+            }
+            _ => None,
+        };
+
+        self.symbols.push(Symbol {
+            name: name.value.to_string(),
+            kind,
+            container,
+            parent,
+            position: name.position.clone(),
+            start: name.position.clone(),
+            end: token_end(name),
+            scope_end: None,
+        });
+
+        self.symbols.len() - 1
+    }
+
+    fn scan(&mut self) {
+        let mut frames: Vec<Frame> = Vec::new();
+        let mut locals: Vec<Local> = Vec::new();
+        let mut pending: Option<(FrameKind, Option<usize>)> = None;
+        let mut pending_locals: Vec<usize> = Vec::new();
+        let mut parens = 0;
+
+        let close =
+            |symbols: &mut Vec<Symbol>, locals: &mut Vec<Local>, depth, arm, at: &Position| {
+                locals.retain(|local| {
+                    let closed = if arm {
+                        local.arm && local.depth == depth
+                    } else {
+                        local.depth >= depth
+                    };
+
+                    if closed {
+                        symbols[local.symbol].scope_end = Some(at.clone());
+                    }
+
+                    !closed
+                });
+            };
+
+        let mut i = 0;
+        while i < self.tokens.len() {
+            let token = self.tokens[i].clone();
+            let owner = frames
+                .iter()
+                .rev()
+                .find_map(|frame| frame.symbol)
+                .map(|s| (s, self.symbols[s].kind));
+            let proc_owner = frames.iter().rev().find_map(|frame| {
+                frame.symbol.filter(|&s| {
+                    matches!(
+                        self.symbols[s].kind,
+                        SymbolKind::Procedure | SymbolKind::Method
+                    )
+                })
+            });
+
+            if let Some(frame) = frames.last_mut() {
+                if frame.kind == FrameKind::Match
+                    && frame.arm_start
+                    && !matches!(token.kind, TokenType::Ccurly | TokenType::Comma)
+                {
+                    frame.arm_start = false;
+                    let depth = frames.len();
+
+                    for binding in self.pattern_bindings(i) {
+                        let symbol = self.push(binding, SymbolKind::Variable, proc_owner);
+                        locals.push(Local {
+                            symbol,
+                            depth,
+                            arm: true,
+                        });
+                    }
+                }
+            }
+
+            let frame_level = frames.last().is_some_and(|frame| frame.parens == parens);
+
+            match token.kind {
+                TokenType::Proc if self.kind(i + 1) == Some(TokenType::Ident) => {
+                    let (kind, parent) = match owner {
+                        Some((owner, SymbolKind::Impl | SymbolKind::Trait)) => {
+                            (SymbolKind::Method, Some(owner))
+                        }
+                        _ => (SymbolKind::Procedure, None),
+                    };
+
+                    let symbol = self.push(i + 1, kind, parent);
+                    self.symbols[symbol].start = token.position.clone();
+                    pending = Some((FrameKind::Block, Some(symbol)));
+
+                    i += 2;
+                    if self.kind(i) == Some(TokenType::Lt) {
+                        while self.kind(i).is_some_and(|kind| kind != TokenType::Gt) {
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+
+                    if self.kind(i) == Some(TokenType::Oparen) {
+                        let mut depth = 0;
+                        while let Some(kind) = self.kind(i) {
+                            match kind {
+                                TokenType::Oparen => depth += 1,
+                                TokenType::Cparen => depth -= 1,
+                                TokenType::Ident
+                                    if depth == 1
+                                        && (self.tokens[i].value == "self"
+                                            || self.kind(i + 1) == Some(TokenType::Colon)) =>
+                                {
+                                    let param = self.push(i, SymbolKind::Parameter, Some(symbol));
+                                    pending_locals.push(param);
+                                }
+                                _ => {}
+                            }
+
+                            if depth == 0 {
+                                break;
+                            }
+
+                            i += 1;
+                        }
+                    }
+                }
+                TokenType::Proc if self.kind(i + 1) == Some(TokenType::Oparen) => {
+                    let close = self.closing(i + 1, TokenType::Cparen);
+                    for param in self.closure_params(i + 2, close) {
+                        let symbol = self.push(param, SymbolKind::Parameter, proc_owner);
+                        pending_locals.push(symbol);
+                    }
+                }
+                TokenType::BitOr if self.is_closure_start(i) => {
+                    let close = self.closing(i + 1, TokenType::BitOr);
+                    let params = self.closure_params(i + 1, close);
+
+                    if self.kind(close + 1) == Some(TokenType::Ocurly) {
+                        for param in params {
+                            let symbol = self.push(param, SymbolKind::Parameter, proc_owner);
+                            pending_locals.push(symbol);
+                        }
+                    } else {
+                        let end = self.expression_end(close + 1);
+                        for param in params {
+                            let symbol = self.push(param, SymbolKind::Parameter, proc_owner);
+                            self.symbols[symbol].scope_end = Some(end.clone());
+                        }
+                    }
+                }
+                TokenType::Struct | TokenType::Enum | TokenType::Trait | TokenType::Impl
+                    if self.kind(i + 1) == Some(TokenType::Ident) =>
+                {
+                    let kind = match token.kind {
+                        TokenType::Struct => SymbolKind::Struct,
+                        TokenType::Enum => SymbolKind::Enum,
+                        TokenType::Trait => SymbolKind::Trait,
+                        _ => SymbolKind::Impl,
+                    };
+
+                    // 'impl Trait for Type' belongs to the type
+                    if kind == SymbolKind::Impl
+                        && self.kind(i + 2) == Some(TokenType::For)
+                        && self.kind(i + 3) == Some(TokenType::Ident)
+                    {
+                        i += 2;
+                    }
+
+                    let symbol = self.push(i + 1, kind, None);
+                    self.symbols[symbol].start = token.position.clone();
+                    pending = Some((FrameKind::Item, Some(symbol)));
+                    i += 1;
+                }
+                TokenType::Let => {
+                    let mut name = i + 1;
+                    if self.kind(name) == Some(TokenType::Mut) {
+                        name += 1;
+                    }
+
+                    if self.kind(name) == Some(TokenType::Ident) {
+                        let symbol = self.push(name, SymbolKind::Variable, proc_owner);
+                        locals.push(Local {
+                            symbol,
+                            depth: frames.len(),
+                            arm: false,
+                        });
+                        i = name;
+                    }
+                }
+                TokenType::For if self.kind(i + 1) == Some(TokenType::Ident) => {
+                    let symbol = self.push(i + 1, SymbolKind::Variable, proc_owner);
+                    pending_locals.push(symbol);
+                    i += 1;
+                }
+                TokenType::Match => pending = Some((FrameKind::Match, None)),
+                TokenType::Ident if frame_level => {
+                    let previous = i.checked_sub(1).and_then(|j| self.kind(j));
+                    match owner {
+                        Some((symbol, SymbolKind::Struct))
+                            if self.kind(i + 1) == Some(TokenType::Colon) =>
+                        {
+                            self.push(i, SymbolKind::Field, Some(symbol));
+                        }
+                        Some((symbol, SymbolKind::Enum))
+                            if matches!(previous, Some(TokenType::Ocurly | TokenType::Comma)) =>
+                        {
+                            self.push(i, SymbolKind::Variant, Some(symbol));
+                        }
+                        _ => {}
+                    }
+                }
+                TokenType::Oparen => parens += 1,
+                TokenType::Cparen => parens = parens.saturating_sub(1),
+                TokenType::Semicolon => {
+                    pending = None;
+
+                    for symbol in pending_locals.drain(..) {
+                        self.symbols[symbol].scope_end = Some(token.position.clone());
+                    }
+                }
+                TokenType::Ocurly => {
+                    let (kind, symbol) = pending.take().unwrap_or((FrameKind::Block, None));
+                    frames.push(Frame {
+                        kind,
+                        symbol,
+                        parens,
+                        arm_start: kind == FrameKind::Match,
+                    });
+
+                    for symbol in pending_locals.drain(..) {
+                        locals.push(Local {
+                            symbol,
+                            depth: frames.len(),
+                            arm: false,
+                        });
+                    }
+                }
+                TokenType::Ccurly => {
+                    let depth = frames.len();
+                    let end = token_end(&token);
+                    close(&mut self.symbols, &mut locals, depth, false, &end);
+
+                    if let Some(frame) = frames.pop() {
+                        if let Some(symbol) = frame.symbol {
+                            self.symbols[symbol].end = end.clone();
+                        }
+                    }
+
+                    if let Some(frame) = frames.last_mut() {
+                        if frame.kind == FrameKind::Match {
+                            frame.arm_start = true;
+                            close(&mut self.symbols, &mut locals, depth - 1, true, &end);
+                        }
+                    }
+                }
+                TokenType::Comma if frame_level => {
+                    let depth = frames.len();
+                    if let Some(frame) = frames.last_mut() {
+                        if frame.kind == FrameKind::Match {
+                            frame.arm_start = true;
+                            close(&mut self.symbols, &mut locals, depth, true, &token.position);
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            i += 1;
+        }
+    }
+
+    fn pattern_bindings(&self, start: usize) -> Vec<usize> {
+        let mut bindings: Vec<usize> = Vec::new();
+        let mut depth = 0;
+
+        let mut i = start;
+        while let Some(kind) = self.kind(i) {
+            match kind {
+                TokenType::Oparen => depth += 1,
+                TokenType::Cparen => depth -= 1,
+                TokenType::FatArrow => break,
+                TokenType::If if depth == 0 => break,
+                TokenType::Ident
+                    if self.tokens[i].value != "_"
+                        && self.kind(i + 1) != Some(TokenType::ScopeResolution)
+                        && (i == start || self.kind(i - 1) != Some(TokenType::ScopeResolution))
+                        && !bindings
+                            .iter()
+                            .any(|&b| self.tokens[b].value == self.tokens[i].value) =>
+                {
+                    bindings.push(i);
+                }
+                _ => {}
+            }
+
+            i += 1;
+        }
+
+        bindings
+    }
+}
@@ -0,0 +1,1041 @@
+pub mod completion;
+pub mod hints;
+pub mod index;
+
+use std::{
+    io::{self, BufRead, Write},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    analyzer::Analyzer,
+    diagnostics::{Diagnostic, Renderer, Severity},
+    formatter::Formatter,
+    golden,
+    json::Json,
+    lexer::Lexer,
+    parser::{Parser, Program},
+    token::{Position, TokenType},
+};
+
+use self::{
+    completion::{Completer, CompletionKind, SignatureHelp},
+    hints::{HintOptions, InlayHintKind},
+    index::{ReferenceKind, Symbol, SymbolIndex, SymbolKind},
+};
+
+pub const SERVER_NAME: &str = "meta-lsp";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Debug, Clone)]
+pub struct Document {
+    pub uri: String,
+    pub text: String,
+    pub version: i64,
+}
+
+impl Document {
+    pub fn filename(&self) -> String {
+        uri_filename(&self.uri)
+    }
+
+    pub fn index(&self) -> SymbolIndex {
+        SymbolIndex::new(&self.text, &self.filename())
+    }
+
+    pub fn program(&self) -> Program {
+        let filename = self.filename();
+
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut parser = Parser::new(Lexer::new(self.text.as_str(), filename));
+            let Ok(program) = parser.parse_program();
+            program
+        }))
+        .unwrap_or_default()
+    }
+
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let filename = self.filename();
+
+        let analyzed = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut parser = Parser::new(Lexer::new(self.text.as_str(), filename.clone()));
+            let Ok(program) = parser.parse_program();
+
+            let mut diagnostics = parser.diagnostics().to_vec();
+            diagnostics.extend(Analyzer::check(&program).into_iter().map(Into::into));
+
+            diagnostics
+        }));
+
+        let diagnostics = analyzed.unwrap_or_else(|_| {
+            vec![Diagnostic::error(
+                None,
+                String::from("the parser stopped unexpectedly on this document"),
+            )]
+        });
+
+        diagnostics
+            .into_iter()
+            .filter(|d| d.position.as_ref().is_none_or(|p| p.filename == filename))
+            .collect()
+    }
+
+    pub fn position(&self, row: u32, column: u32) -> Json {
+        let character = self
+            .text
+            .lines()
+            .nth(row as usize)
+            .map_or(column as usize, |line| {
+                line.chars()
+                    .take(column as usize)
+                    .map(char::len_utf16)
+                    .sum()
+            });
+
+        Json::object([("line", row.into()), ("character", character.into())])
+    }
+
+    pub fn range(&self, start: &Position, end: &Position) -> Json {
+        Json::object([
+            ("start", self.position(start.row, start.column)),
+            ("end", self.position(end.row, end.column)),
+        ])
+    }
+
+    pub fn from_lsp(&self, position: &Json) -> Option<(u32, u32)> {
+        let row = position.get("line")?.as_i64()? as u32;
+        let character = position.get("character")?.as_i64()? as usize;
+
+        let line = self.text.lines().nth(row as usize).unwrap_or_default();
+
+        let mut units = 0;
+        let mut column = 0;
+        for c in line.chars() {
+            if units >= character {
+                break;
+            }
+            units += c.len_utf16();
+            column += 1;
+        }
+
+        Some((row, column))
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Server {
+    documents: Vec<Document>,
+    root: Option<PathBuf>,
+    hints: HintOptions,
+    shutdown: bool,
+    exit_code: Option<i32>,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn document(&self, uri: &str) -> Option<&Document> {
+        self.documents.iter().find(|d| d.uri == uri)
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").and_then(Json::as_str);
+        let params = message.get("params").cloned().unwrap_or_default();
+
+        match (method, message.get("id")) {
+            (Some(method), Some(id)) => {
+                let result = self.request(method, &params);
+                vec![response(id.clone(), result)]
+            }
+            (Some(method), None) => self.notification(method, &params),
+            (None, _) => Vec::new(),
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, (i64, String)> {
+        match method {
+            "initialize" => Ok(self.initialize(params)),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            "workspace/symbol" => Ok(self.workspace_symbols(params)),
+            "textDocument/references" => self.references(params),
+            "textDocument/prepareCallHierarchy" => self.prepare_call_hierarchy(params),
+            "callHierarchy/incomingCalls" => self.calls(params, true),
+            "callHierarchy/outgoingCalls" => self.calls(params, false),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/signatureHelp" => self.signature_help(params),
+            "textDocument/inlayHint" => self.inlay_hints(params),
+            "textDocument/formatting" => self.formatting(params, None),
+            "textDocument/rangeFormatting" => {
+                let lines = params
+                    .path(&["range", "start", "line"])
+                    .and_then(Json::as_i64)
+                    .zip(
+                        params
+                            .path(&["range", "end", "line"])
+                            .and_then(Json::as_i64),
+                    );
+
+                match lines {
+                    Some((start, end)) => {
+                        self.formatting(params, Some((start as usize, end as usize)))
+                    }
+                    None => Err((INVALID_PARAMS, String::from("missing 'range'"))),
+                }
+            }
+            method => Err((METHOD_NOT_FOUND, format!("unhandled method '{method}'"))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = params
+            .path(&["textDocument", "uri"])
+            .and_then(Json::as_str)
+            .map(String::from);
+        let version = params
+            .path(&["textDocument", "version"])
+            .and_then(Json::as_i64)
+            .unwrap_or_default();
+
+        match (method, uri) {
+            ("textDocument/didOpen", Some(uri)) => {
+                let text = params
+                    .path(&["textDocument", "text"])
+                    .and_then(Json::as_str)
+                    .unwrap_or_default();
+
+                self.documents.retain(|d| d.uri != uri);
+                self.documents.push(Document {
+                    uri: uri.clone(),
+                    text: String::from(text),
+                    version,
+                });
+
+                self.publish_diagnostics(&uri)
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+
+                if let (Some(document), Some(text)) =
+                    (self.documents.iter_mut().find(|d| d.uri == uri), text)
+                {
+                    document.text = String::from(text);
+                    document.version = version;
+                }
+
+                self.publish_diagnostics(&uri)
+            }
+            ("textDocument/didClose", Some(uri)) => {
+                self.documents.retain(|d| d.uri != uri);
+                vec![notification(
+                    "textDocument/publishDiagnostics",
+                    Json::object([
+                        ("uri", uri.into()),
+                        ("diagnostics", Json::Array(Vec::new())),
+                    ]),
+                )]
+            }
+            ("workspace/didChangeConfiguration", _) => {
+                if let Some(settings) = params.path(&["settings", "meta", "inlayHints"]) {
+                    self.hints.configure(settings);
+                }
+                Vec::new()
+            }
+            ("exit", _) => {
+                self.exit_code = Some(if self.shutdown { 0 } else { 1 });
+                Vec::new()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn initialize(&mut self, params: &Json) -> Json {
+        if let Some(settings) = params.path(&["initializationOptions", "inlayHints"]) {
+            self.hints.configure(settings);
+        }
+
+        self.root = params
+            .get("rootUri")
+            .and_then(Json::as_str)
+            .and_then(uri_path)
+            .or_else(|| {
+                params
+                    .get("rootPath")
+                    .and_then(Json::as_str)
+                    .map(PathBuf::from)
+            });
+
+        Json::object([
+            (
+                "capabilities",
+                Json::object([
+                    ("textDocumentSync", 1u32.into()),
+                    ("definitionProvider", true.into()),
+                    ("documentSymbolProvider", true.into()),
+                    ("workspaceSymbolProvider", true.into()),
+                    ("referencesProvider", true.into()),
+                    ("callHierarchyProvider", true.into()),
+                    (
+                        "completionProvider",
+                        Json::object([(
+                            "triggerCharacters",
+                            Json::Array(vec![".".into(), ":".into()]),
+                        )]),
+                    ),
+                    ("inlayHintProvider", true.into()),
+                    ("documentFormattingProvider", true.into()),
+                    ("documentRangeFormattingProvider", true.into()),
+                    (
+                        "signatureHelpProvider",
+                        Json::object([(
+                            "triggerCharacters",
+                            Json::Array(vec!["(".into(), ",".into()]),
+                        )]),
+                    ),
+                ]),
+            ),
+            (
+                "serverInfo",
+                Json::object([
+                    ("name", SERVER_NAME.into()),
+                    ("version", env!("CARGO_PKG_VERSION").into()),
+                ]),
+            ),
+        ])
+    }
+
+    fn publish_diagnostics(&self, uri: &str) -> Vec<Json> {
+        let Some(document) = self.document(uri) else {
+            return Vec::new();
+        };
+
+        let lines: Vec<&str> = document.text.lines().collect();
+
+        let diagnostics = document
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| {
+                let mut start = diagnostic.position.clone().unwrap_or_default();
+                let mut end = start.clone();
+                if let Some(span) = diagnostic.span.filter(|s| s.end > s.start) {
+                    start = Position::from(start.filename, span.start.row, span.start.column);
+                    end = span.end_position(&start.filename);
+                } else if let Some(line) = lines.get(start.row as usize) {
+                    end.column += Renderer::span_width(line, start.column as usize) as u32;
+                }
+
+                let mut message = diagnostic.message.clone();
+                if let Some(note) = &diagnostic.note {
+                    message.push_str(&format!("\nnote: {note}"));
+                }
+
+                let severity: u32 = match diagnostic.severity {
+                    Severity::Error => 1,
+                    Severity::Warning => 2,
+                };
+
+                let mut fields = vec![
+                    ("range", document.range(&start, &end)),
+                    ("severity", severity.into()),
+                    ("source", "meta".into()),
+                    ("message", message.into()),
+                ];
+                if let Some(code) = diagnostic.code {
+                    fields.push(("code", code.into()));
+                }
+
+                Json::object(fields)
+            })
+            .collect();
+
+        vec![notification(
+            "textDocument/publishDiagnostics",
+            Json::object([
+                ("uri", uri.into()),
+                ("version", document.version.into()),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        )]
+    }
+
+    fn text_document(&self, params: &Json) -> Result<&Document, (i64, String)> {
+        let uri = params
+            .path(&["textDocument", "uri"])
+            .and_then(Json::as_str)
+            .ok_or((INVALID_PARAMS, String::from("missing 'textDocument.uri'")))?;
+
+        self.document(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("'{uri}' is not open")))
+    }
+
+    fn definition(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let document = self.text_document(params)?;
+
+        let Some((row, column)) = params.get("position").and_then(|p| document.from_lsp(p)) else {
+            return Err((INVALID_PARAMS, String::from("missing 'position'")));
+        };
+
+        let index = document.index();
+        let location = index.definition(row, column).map(|symbol| {
+            Json::object([
+                ("uri", document.uri.as_str().into()),
+                ("range", document.range(&symbol.position, &name_end(symbol))),
+            ])
+        });
+
+        Ok(location.into())
+    }
+
+    fn document_symbols(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let document = self.text_document(params)?;
+        let index = document.index();
+
+        Ok(Json::Array(document_symbols(document, &index, None)))
+    }
+
+    fn references(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let document = self.text_document(params)?;
+
+        let Some((row, column)) = params.get("position").and_then(|p| document.from_lsp(p)) else {
+            return Err((INVALID_PARAMS, String::from("missing 'position'")));
+        };
+
+        let declaration = params
+            .path(&["context", "includeDeclaration"])
+            .and_then(Json::as_bool)
+            .unwrap_or(true);
+
+        let index = document.index();
+        let Some(symbol) = index.symbol_at(row, column) else {
+            return Ok(Json::Null);
+        };
+
+        let locations = index
+            .references(symbol)
+            .into_iter()
+            .filter(|reference| declaration || reference.kind != ReferenceKind::Declaration)
+            .map(|reference| {
+                Json::object([
+                    ("uri", document.uri.as_str().into()),
+                    ("range", document.range(&reference.position, &reference.end)),
+                ])
+            })
+            .collect();
+
+        Ok(Json::Array(locations))
+    }
+
+    fn prepare_call_hierarchy(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let document = self.text_document(params)?;
+
+        let Some((row, column)) = params.get("position").and_then(|p| document.from_lsp(p)) else {
+            return Err((INVALID_PARAMS, String::from("missing 'position'")));
+        };
+
+        let index = document.index();
+        let item = index
+            .symbol_at(row, column)
+            .filter(|&s| {
+                matches!(
+                    index.symbols[s].kind,
+                    SymbolKind::Procedure | SymbolKind::Method
+                )
+            })
+            .map(|s| call_hierarchy_item(document, &index.symbols[s]));
+
+        Ok(item.map_or(Json::Null, |item| Json::Array(vec![item])))
+    }
+
+    fn calls(&self, params: &Json, incoming: bool) -> Result<Json, (i64, String)> {
+        let uri = params
+            .path(&["item", "uri"])
+            .and_then(Json::as_str)
+            .ok_or((INVALID_PARAMS, String::from("missing 'item.uri'")))?;
+        let document = self
+            .document(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("'{uri}' is not open")))?;
+
+        let Some((row, column)) = params
+            .path(&["item", "selectionRange", "start"])
+            .and_then(|p| document.from_lsp(p))
+        else {
+            return Err((
+                INVALID_PARAMS,
+                String::from("missing 'item.selectionRange'"),
+            ));
+        };
+
+        let index = document.index();
+        let Some(item) = index.symbol_at(row, column) else {
+            return Ok(Json::Null);
+        };
+
+        let mut groups: Vec<(usize, Vec<Json>)> = Vec::new();
+        let mut add =
+            |symbol: usize, range: Json| match groups.iter_mut().find(|(s, _)| *s == symbol) {
+                Some((_, ranges)) => ranges.push(range),
+                None => groups.push((symbol, vec![range])),
+            };
+
+        if incoming {
+            for reference in index.references(item) {
+                if reference.kind != ReferenceKind::Call {
+                    continue;
+                }
+
+                if let Some(caller) = index.enclosing(&reference.position) {
+                    add(caller, document.range(&reference.position, &reference.end));
+                }
+            }
+        } else {
+            let symbol = &index.symbols[item];
+            let (start, end) = (index::key(&symbol.start), index::key(&symbol.end));
+
+            for (i, token) in index.tokens.iter().enumerate() {
+                let at = index::key(&token.position);
+                let call = index.tokens.get(i + 1).map(|t| t.kind) == Some(TokenType::Oparen)
+                    || i.checked_sub(1).map(|j| index.tokens[j].kind) == Some(TokenType::Pipe);
+                if at < start || end < at || !call {
+                    continue;
+                }
+
+                let callee = index.resolve(i).filter(|&s| {
+                    index::key(&index.symbols[s].position) != at
+                        && matches!(
+                            index.symbols[s].kind,
+                            SymbolKind::Procedure | SymbolKind::Method
+                        )
+                });
+                if let Some(callee) = callee {
+                    add(
+                        callee,
+                        document.range(&token.position, &index::token_end(token)),
+                    );
+                }
+            }
+        }
+
+        let field = if incoming { "from" } else { "to" };
+        let calls = groups
+            .into_iter()
+            .map(|(symbol, ranges)| {
+                Json::object([
+                    (field, call_hierarchy_item(document, &index.symbols[symbol])),
+                    ("fromRanges", Json::Array(ranges)),
+                ])
+            })
+            .collect();
+
+        Ok(Json::Array(calls))
+    }
+
+    fn workspace_symbols(&self, params: &Json) -> Json {
+        let query = params
+            .get("query")
+            .and_then(Json::as_str)
+            .unwrap_or_default();
+
+        let mut documents: Vec<Document> = self.documents.clone();
+        if let Some(root) = &self.root {
+            for path in golden::discover(root).unwrap_or_default() {
+                let uri = path_uri(&path);
+                if self.document(&uri).is_some() {
+                    continue;
+                }
+
+                if let Ok(text) = std::fs::read_to_string(&path) {
+                    documents.push(Document {
+                        uri,
+                        text,
+                        version: 0,
+                    });
+                }
+            }
+        }
+
+        let mut found: Vec<(u32, Json)> = Vec::new();
+        for document in documents.iter() {
+            let index = document.index();
+            for (score, symbol) in index.search(query) {
+                let mut fields = vec![
+                    ("name", symbol.name.as_str().into()),
+                    ("kind", symbol_kind(symbol.kind).into()),
+                    (
+                        "location",
+                        Json::object([
+                            ("uri", document.uri.as_str().into()),
+                            ("range", document.range(&symbol.start, &symbol.end)),
+                        ]),
+                    ),
+                ];
+                if let Some(container) = &symbol.container {
+                    fields.push(("containerName", container.as_str().into()));
+                }
+
+                found.push((score, Json::object(fields)));
+            }
+        }
+
+        found.sort_by(|(a, _), (b, _)| b.cmp(a));
+        Json::Array(found.into_iter().map(|(_, symbol)| symbol).collect())
+    }
+
+    fn completion(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let document = self.text_document(params)?;
+
+        let Some((row, column)) = params.get("position").and_then(|p| document.from_lsp(p)) else {
+            return Err((INVALID_PARAMS, String::from("missing 'position'")));
+        };
+
+        let index = document.index();
+        let program = document.program();
+        let completer = Completer::new(&document.text, &index, &program);
+
+        let items = completer
+            .complete(row, column)
+            .into_iter()
+            .enumerate()
+            .map(|(rank, completion)| {
+                let kind: u32 = match completion.kind {
+                    CompletionKind::Method => 2,
+                    CompletionKind::Function => 3,
+                    CompletionKind::Field => 5,
+                    CompletionKind::Variable => 6,
+                    CompletionKind::Struct => 22,
+                    CompletionKind::Enum => 13,
+                    CompletionKind::Trait => 8,
+                    CompletionKind::Variant => 20,
+                    CompletionKind::Keyword => 14,
+                    CompletionKind::Type => 25,
+                };
+
+                let mut fields = vec![
+                    ("label", completion.label.into()),
+                    ("kind", kind.into()),
+                    ("sortText", format!("{rank:04}").into()),
+                ];
+                if let Some(detail) = completion.detail {
+                    fields.push(("detail", detail.into()));
+                }
+
+                Json::object(fields)
+            })
+            .collect();
+
+        Ok(Json::object([
+            ("isIncomplete", false.into()),
+            ("items", Json::Array(items)),
+        ]))
+    }
+
+    fn signature_help(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let document = self.text_document(params)?;
+
+        let Some((row, column)) = params.get("position").and_then(|p| document.from_lsp(p)) else {
+            return Err((INVALID_PARAMS, String::from("missing 'position'")));
+        };
+
+        let index = document.index();
+        let program = document.program();
+        let completer = Completer::new(&document.text, &index, &program);
+
+        let Some(SignatureHelp {
+            label,
+            parameters,
+            active,
+            docs,
+        }) = completer.signature_help(row, column)
+        else {
+            return Ok(Json::Null);
+        };
+
+        let parameters = parameters
+            .into_iter()
+            .map(|parameter| Json::object([("label", parameter.into())]))
+            .collect();
+
+        let mut signature = vec![
+            ("label", label.into()),
+            ("parameters", Json::Array(parameters)),
+        ];
+        if let Some(docs) = docs {
+            signature.push(("documentation", docs.into()));
+        }
+
+        Ok(Json::object([
+            ("signatures", Json::Array(vec![Json::object(signature)])),
+            ("activeSignature", 0u32.into()),
+            ("activeParameter", (active as u32).into()),
+        ]))
+    }
+
+    fn inlay_hints(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let document = self.text_document(params)?;
+
+        let rows = params
+            .path(&["range", "start", "line"])
+            .and_then(Json::as_i64)
+            .zip(
+                params
+                    .path(&["range", "end", "line"])
+                    .and_then(Json::as_i64),
+            );
+
+        let index = document.index();
+        let program = document.program();
+
+        let hints = hints::inlay_hints(&index, &program, self.hints)
+            .into_iter()
+            .filter(|hint| {
+                let row = hint.position.row as i64;
+                rows.is_none_or(|(first, last)| first <= row && row <= last)
+            })
+            .map(|hint| {
+                let kind: u32 = match hint.kind {
+                    InlayHintKind::Type => 1,
+                    InlayHintKind::Parameter => 2,
+                };
+
+                let mut fields = vec![
+                    (
+                        "position",
+                        document.position(hint.position.row, hint.position.column),
+                    ),
+                    ("label", hint.label.into()),
+                    ("kind", kind.into()),
+                ];
+                if hint.kind == InlayHintKind::Parameter {
+                    fields.push(("paddingRight", true.into()));
+                }
+
+                Json::object(fields)
+            })
+            .collect();
+
+        Ok(Json::Array(hints))
+    }
+
+    fn formatting(
+        &self,
+        params: &Json,
+        lines: Option<(usize, usize)>,
+    ) -> Result<Json, (i64, String)> {
+        let document = self.text_document(params)?;
+
+        let mut formatter = Formatter::new();
+        if let Some(tab_size) = params.path(&["options", "tabSize"]).and_then(Json::as_i64) {
+            formatter = formatter.indent(tab_size as usize);
+        }
+
+        let Ok(formatted) = formatter.format(&document.text, &document.filename()) else {
+            return Ok(Json::Null);
+        };
+
+        let edits = line_edits(&document.text, &formatted)
+            .into_iter()
+            .filter(|&(start, end, _)| {
+                lines.is_none_or(|(first, last)| start <= last && first < end.max(start + 1))
+            })
+            .map(|(start, end, text)| {
+                Json::object([
+                    (
+                        "range",
+                        Json::object([
+                            ("start", document.position(start as u32, 0)),
+                            ("end", document.position(end as u32, 0)),
+                        ]),
+                    ),
+                    ("newText", text.into()),
+                ])
+            })
+            .collect();
+
+        Ok(Json::Array(edits))
+    }
+}
+
+fn line_edits(original: &str, formatted: &str) -> Vec<(usize, usize, String)> {
+    let old: Vec<&str> = original.lines().collect();
+    let new: Vec<&str> = formatted.lines().collect();
+
+    let prefix = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+
+    let mut lengths = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        let (start, inserted) = (i, j);
+        while (i < old.len() || j < new.len())
+            && !(i < old.len() && j < new.len() && old[i] == new[j])
+        {
+            if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        let text: String = new[inserted..j]
+            .iter()
+            .map(|line| format!("{line}\n"))
+            .collect();
+        edits.push((prefix + start, prefix + i, text));
+    }
+
+    edits
+}
+
+fn document_symbols(document: &Document, index: &SymbolIndex, parent: Option<usize>) -> Vec<Json> {
+    index
+        .children(parent)
+        .filter(|(_, symbol)| match symbol.kind {
+            SymbolKind::Parameter => false,
+            SymbolKind::Variable => symbol.parent.is_none(),
+            _ => true,
+        })
+        .map(|(i, symbol)| {
+            let name = match symbol.kind {
+                SymbolKind::Impl => format!("impl {}", symbol.name),
+                _ => symbol.name.clone(),
+            };
+
+            Json::object([
+                ("name", name.into()),
+                ("kind", symbol_kind(symbol.kind).into()),
+                ("range", document.range(&symbol.start, &symbol.end)),
+                (
+                    "selectionRange",
+                    document.range(&symbol.position, &name_end(symbol)),
+                ),
+                (
+                    "children",
+                    Json::Array(document_symbols(document, index, Some(i))),
+                ),
+            ])
+        })
+        .collect()
+}
+
+fn symbol_kind(kind: SymbolKind) -> u32 {
+    match kind {
+        SymbolKind::Procedure => 12,
+        SymbolKind::Method => 6,
+        SymbolKind::Struct => 23,
+        SymbolKind::Field => 8,
+        SymbolKind::Enum => 10,
+        SymbolKind::Variant => 22,
+        SymbolKind::Trait => 11,
+        SymbolKind::Impl => 5,
+        SymbolKind::Variable | SymbolKind::Parameter => 13,
+    }
+}
+
+fn call_hierarchy_item(document: &Document, symbol: &Symbol) -> Json {
+    let kind: u32 = match symbol.kind {
+        SymbolKind::Method => 6,
+        _ => 12,
+    };
+
+    let mut fields = vec![
+        ("name", symbol.name.as_str().into()),
+        ("kind", kind.into()),
+        ("uri", document.uri.as_str().into()),
+        ("range", document.range(&symbol.start, &symbol.end)),
+        (
+            "selectionRange",
+            document.range(&symbol.position, &name_end(symbol)),
+        ),
+    ];
+    if let Some(container) = &symbol.container {
+        fields.push(("detail", container.as_str().into()));
+    }
+
+    Json::object(fields)
+}
+
+fn name_end(symbol: &Symbol) -> Position {
+    let mut end = symbol.position.clone();
+    end.column += symbol.name.chars().count() as u32;
+    end
+}
+
+fn response(id: Json, result: Result<Json, (i64, String)>) -> Json {
+    match result {
+        Ok(result) => Json::object([("jsonrpc", "2.0".into()), ("id", id), ("result", result)]),
+        Err((code, message)) => Json::object([
+            ("jsonrpc", "2.0".into()),
+            ("id", id),
+            (
+                "error",
+                Json::object([("code", code.into()), ("message", message.into())]),
+            ),
+        ]),
+    }
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+pub fn uri_filename(uri: &str) -> String {
+    percent_decode(uri.rsplit('/').next().unwrap_or(uri))
+}
+
+pub fn uri_path(uri: &str) -> Option<PathBuf> {
+    uri.strip_prefix("file://")
+        .map(|path| PathBuf::from(percent_decode(path)))
+}
+
+pub fn path_uri(path: &Path) -> String {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    let mut uri = String::from("file://");
+    for b in path.to_string_lossy().bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{b:02X}"));
+        }
+    }
+
+    uri
+}
+
+fn percent_decode(path: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut chars = path.bytes();
+    while let Some(b) = chars.next() {
+        if b == b'%' {
+            let hex: Vec<u8> = chars.by_ref().take(2).collect();
+            if let Some(decoded) = std::str::from_utf8(&hex)
+                .ok()
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+            {
+                bytes.push(decoded);
+                continue;
+            }
+            bytes.push(b);
+            bytes.extend(hex);
+        } else {
+            bytes.push(b);
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut length = None;
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing Content-Length header",
+        ));
+    };
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub fn write_message<W: Write>(writer: &mut W, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    writer.write_fmt(format_args!("Content-Length: {}\r\n\r\n{body}", body.len()))?;
+    writer.flush()
+}
+
+pub fn serve<R: BufRead, W: Write>(mut reader: R, mut writer: W) -> io::Result<i32> {
+    let mut server = Server::new();
+
+    while let Some(body) = read_message(&mut reader)? {
+        let replies = match Json::parse(&body) {
+            Ok(message) => server.handle(&message),
+            Err(err) => vec![response(
+                Json::Null,
+                Err((PARSE_ERROR, format!("invalid message: {err}"))),
+            )],
+        };
+
+        for reply in replies.iter() {
+            write_message(&mut writer, reply)?;
+        }
+
+        if let Some(code) = server.exit_code() {
+            return Ok(code);
+        }
+    }
+
+    Ok(if server.shutdown { 0 } else { 1 })
+}
@@ -1,7 +1,29 @@
+pub mod analyzer;
+pub mod ast_printer;
+pub mod astc;
+pub mod builtins;
+pub mod codegen;
+pub mod debugger;
+pub mod diagnostics;
+pub mod engine;
+pub mod error;
 pub mod executor;
 pub mod expression;
+pub mod formatter;
+pub mod fuzz;
+pub mod generate;
+pub mod golden;
+pub mod hooks;
+pub mod json;
 pub mod lexer;
+pub mod log;
+pub mod lsp;
+pub mod native;
 pub mod nodes;
 pub mod parser;
+pub mod pretty;
 pub mod timer;
 pub mod token;
+pub mod value;
+
+pub use engine::Engine;
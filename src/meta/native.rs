@@ -0,0 +1,142 @@
+use std::{
+    cell::Cell,
+    panic,
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    nodes::{ProcDefNode, VarMetadataNode},
+    parser::assignable,
+    token::{Position, Span},
+    value::Value,
+};
+
+pub type NativeProc = Arc<dyn Fn(&[Value]) -> Value + Send + Sync>;
+
+thread_local! {
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+// whether the timed native call in progress was already given up on, so it can stop early
+pub fn timed_out() -> bool {
+    DEADLINE
+        .with(Cell::get)
+        .is_some_and(|deadline| Instant::now() >= deadline)
+}
+
+#[derive(Clone)]
+pub struct NativeFn {
+    pub name: String,
+    pub params: Vec<String>,
+    pub return_type: Option<String>,
+    pub func: NativeProc,
+}
+
+impl NativeFn {
+    pub fn new<F>(name: &str, params: &[&str], return_type: Option<&str>, func: F) -> Self
+    where
+        F: Fn(&[Value]) -> Value + Send + Sync + 'static,
+    {
+        Self {
+            name: String::from(name),
+            params: params.iter().map(|p| String::from(*p)).collect(),
+            return_type: return_type.map(String::from),
+            func: Arc::new(func),
+        }
+    }
+
+    pub fn check_args(&self, args: &[Value]) -> Result<(), String> {
+        if args.len() != self.params.len() {
+            return Err(format!(
+                "'{}' expects {} argument(s) found {}",
+                self.name,
+                self.params.len(),
+                args.len()
+            ));
+        }
+
+        for (i, (param, arg)) in self.params.iter().zip(args.iter()).enumerate() {
+            if !assignable(param, &arg.type_name(), &[]) {
+                return Err(format!(
+                    "argument {} of '{}' expected '{param}' found '{}'",
+                    i + 1,
+                    self.name,
+                    arg.type_name()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn check_return(&self, value: &Value) -> Result<(), String> {
+        let expected = self.return_type.as_deref().unwrap_or("None");
+        if !assignable(expected, &value.type_name(), &[]) {
+            return Err(format!(
+                "declared to return '{expected}' but returned '{}'",
+                value.type_name()
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn proc_def(&self) -> ProcDefNode {
+        let args = self
+            .params
+            .iter()
+            .enumerate()
+            .map(|(i, type_name)| VarMetadataNode {
+                name: format!("arg{i}"),
+                type_name: type_name.clone(),
+                mutable: false,
+            })
+            .collect();
+
+        ProcDefNode {
+            name: self.name.clone(),
+            return_type: self.return_type.clone(),
+            args,
+            statements: Vec::new(),
+            contracts: Vec::new(),
+            native: true,
+            generics: Vec::new(),
+            template: None,
+            docs: None,
+            position: Position::default(),
+            span: Span::default(),
+        }
+    }
+
+    pub fn call(&self, args: &[Value]) -> Value {
+        (self.func)(args)
+    }
+
+    // a native that never returns keeps its worker thread, but the script moves on without it
+    pub fn call_with_timeout(&self, args: &[Value], timeout: Option<Duration>) -> Option<Value> {
+        let Some(timeout) = timeout else {
+            return Some(self.call(args));
+        };
+
+        let func = self.func.clone();
+        let args = args.to_vec();
+        let deadline = Instant::now() + timeout;
+        let (sender, receiver) = mpsc::channel();
+
+        let worker = thread::spawn(move || {
+            DEADLINE.with(|cell| cell.set(Some(deadline)));
+            let _ = sender.send(func(&args));
+        });
+
+        match receiver.recv_timeout(timeout) {
+            Ok(value) => Some(value),
+            Err(mpsc::RecvTimeoutError::Timeout) => None,
+            Err(mpsc::RecvTimeoutError::Disconnected) => match worker.join() {
+                Err(payload) => panic::resume_unwind(payload),
+                Ok(()) => None,
+            },
+        }
+    }
+}
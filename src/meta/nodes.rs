@@ -1,6 +1,14 @@
-use crate::expression::Expression;
+use std::{
+    fmt::Display,
+    sync::{Arc, PoisonError, RwLock},
+};
 
-#[derive(Debug, Clone)]
+use crate::{
+    expression::Expression,
+    token::{LiteralType, Position, Span, Token},
+};
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum BinaryOp {
     None,
     Inc,
@@ -20,18 +28,108 @@ pub enum BinaryOp {
     Gt,
     Gte,
     Neg,
+    BitOr,
+    BitAnd,
+    BitXor,
+}
+
+pub const COALESCE_PRECEDENCE: u8 = 2;
+pub const PIPE_PRECEDENCE: u8 = 5;
+
+impl BinaryOp {
+    pub fn is_ordering(&self) -> bool {
+        matches!(
+            self,
+            BinaryOp::Lt | BinaryOp::Lte | BinaryOp::Gt | BinaryOp::Gte
+        )
+    }
+
+    pub fn is_ascending(&self) -> bool {
+        matches!(self, BinaryOp::Lt | BinaryOp::Lte)
+    }
+
+    pub fn is_update(&self) -> bool {
+        matches!(
+            self,
+            BinaryOp::Inc
+                | BinaryOp::Dec
+                | BinaryOp::AddAssign
+                | BinaryOp::SubAssign
+                | BinaryOp::MulAssign
+                | BinaryOp::DivAssign
+        )
+    }
+
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOp::None | BinaryOp::Neg => 0,
+            BinaryOp::AddAssign
+            | BinaryOp::SubAssign
+            | BinaryOp::MulAssign
+            | BinaryOp::DivAssign => 1,
+            BinaryOp::Eq | BinaryOp::Ne => 3,
+            BinaryOp::Lt | BinaryOp::Lte | BinaryOp::Gt | BinaryOp::Gte => 4,
+            BinaryOp::BitOr => 6,
+            BinaryOp::BitXor => 7,
+            BinaryOp::BitAnd => 8,
+            BinaryOp::Add | BinaryOp::Sub => 9,
+            BinaryOp::Mul | BinaryOp::Div => 10,
+            BinaryOp::Inc | BinaryOp::Dec => 11,
+        }
+    }
+}
+
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = match self {
+            BinaryOp::None => "",
+            BinaryOp::Inc => "++",
+            BinaryOp::Dec => "--",
+            BinaryOp::Add => "+",
+            BinaryOp::AddAssign => "+=",
+            BinaryOp::Sub => "-",
+            BinaryOp::SubAssign => "-=",
+            BinaryOp::Mul => "*",
+            BinaryOp::MulAssign => "*=",
+            BinaryOp::Div => "/",
+            BinaryOp::DivAssign => "/=",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Lte => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Gte => ">=",
+            BinaryOp::Neg => "!",
+            BinaryOp::BitOr => "|",
+            BinaryOp::BitAnd => "&",
+            BinaryOp::BitXor => "^",
+        };
+
+        f.write_str(symbol)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct IfNode {
     pub value: Box<Expression>,
     pub statements: Vec<Expression>,
+    pub else_branch: Option<ElseNode>,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ElseNode {
+    pub statements: Vec<Expression>,
+    pub position: Position,
 }
 
 #[derive(Debug, Clone)]
 pub struct WhileNode {
     pub value: Box<Expression>,
     pub statements: Vec<Expression>,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -39,42 +137,173 @@ pub struct ForNode {
     pub counter: VariableNode,
     pub range: Box<Expression>,
     pub statements: Vec<Expression>,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct RangeNode {
     pub start: Box<Expression>,
     pub end: Box<Expression>,
+    pub inclusive: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum PatternNode {
+    Wildcard,
+    Literal(Token<'static>, LiteralType),
+    Range {
+        start: Token<'static>,
+        end: Token<'static>,
+        kind: LiteralType,
+    },
+    Binding(VarMetadataNode),
+    Bind {
+        binding: VarMetadataNode,
+        pattern: Box<PatternNode>,
+    },
+    Variant {
+        type_name: String,
+        variant: String,
+        bindings: Vec<VarMetadataNode>,
+    },
+    Or(Vec<PatternNode>),
+}
+
+impl PatternNode {
+    pub fn bindings(&self) -> Vec<VarMetadataNode> {
+        match self {
+            PatternNode::Binding(binding) => vec![binding.clone()],
+            PatternNode::Bind { binding, pattern } => {
+                let mut bindings = vec![binding.clone()];
+                bindings.extend(pattern.bindings());
+                bindings
+            }
+            PatternNode::Variant { bindings, .. } => {
+                bindings.iter().filter(|b| b.name != "_").cloned().collect()
+            }
+            PatternNode::Or(alternatives) => alternatives
+                .first()
+                .map(PatternNode::bindings)
+                .unwrap_or_default(),
+            PatternNode::Wildcard | PatternNode::Literal(..) | PatternNode::Range { .. } => {
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn is_irrefutable(&self) -> bool {
+        match self {
+            PatternNode::Wildcard | PatternNode::Binding(..) => true,
+            PatternNode::Bind { pattern, .. } => pattern.is_irrefutable(),
+            PatternNode::Or(alternatives) => alternatives.iter().any(PatternNode::is_irrefutable),
+            PatternNode::Literal(..) | PatternNode::Range { .. } | PatternNode::Variant { .. } => {
+                false
+            }
+        }
+    }
+
+    pub fn variants(&self) -> Vec<&str> {
+        match self {
+            PatternNode::Variant { variant, .. } => vec![variant.as_str()],
+            PatternNode::Bind { pattern, .. } => pattern.variants(),
+            PatternNode::Or(alternatives) => alternatives
+                .iter()
+                .flat_map(PatternNode::variants)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn position(&self) -> Option<&Position> {
+        match self {
+            PatternNode::Literal(token, _) => Some(&token.position),
+            PatternNode::Range { start, .. } => Some(&start.position),
+            PatternNode::Bind { pattern, .. } => pattern.position(),
+            PatternNode::Or(alternatives) => alternatives.iter().find_map(PatternNode::position),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchArmNode {
+    pub pattern: PatternNode,
+    pub guard: Option<Box<Expression>>,
+    pub statements: Vec<Expression>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatchNode {
+    pub value: Box<Expression>,
+    pub arms: Vec<MatchArmNode>,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct LetNode {
     pub name: String,
     pub type_name: String,
+    pub mutable: bool,
+    pub annotated: bool,
     pub value: Box<Expression>,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct AssignNode {
     pub value: VariableNode,
     pub new_value: Box<Expression>,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct ReturnNode {
     pub value: Box<Expression>,
+    pub implicit: bool,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct VarMetadataNode {
     pub name: String,
     pub type_name: String,
+    pub mutable: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct VariableNode {
     pub metadata: VarMetadataNode,
     pub value: Box<Expression>,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ContractKind {
+    Requires,
+    Ensures,
+}
+
+impl Display for ContractKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContractKind::Requires => f.write_str("precondition"),
+            ContractKind::Ensures => f.write_str("postcondition"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContractNode {
+    pub kind: ContractKind,
+    pub condition: Expression,
+    pub source: String,
+    pub position: Position,
 }
 
 #[derive(Debug, Clone)]
@@ -83,49 +312,549 @@ pub struct ProcDefNode {
     pub return_type: Option<String>,
     pub args: Vec<VarMetadataNode>,
     pub statements: Vec<Expression>,
+    pub contracts: Vec<ContractNode>,
+    pub native: bool,
+    pub generics: Vec<String>,
+    pub template: Option<String>,
+    pub docs: Option<String>,
+    pub position: Position,
+    pub span: Span,
+}
+
+impl ProcDefNode {
+    pub fn is_instance(&self) -> bool {
+        self.name.contains('<')
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcType {
+    pub args: Vec<String>,
+    pub return_type: Option<String>,
+}
+
+impl ProcType {
+    pub fn of(proc_def: &ProcDefNode) -> Self {
+        Self {
+            args: proc_def
+                .args
+                .iter()
+                .map(|arg| arg.type_name.clone())
+                .collect(),
+            return_type: proc_def.return_type.clone(),
+        }
+    }
+
+    pub fn parse(type_name: &str) -> Option<Self> {
+        let rest = type_name.strip_prefix("proc(")?;
+        let close = closing_bracket(rest, ')')?;
+        let args = split_type_args(&rest[..close]);
+
+        let tail = rest[close + 1..].trim();
+        let return_type = match tail.strip_prefix(':') {
+            Some(return_type) => Some(String::from(return_type.trim())),
+            None if tail.is_empty() => None,
+            None => return None,
+        };
+
+        Some(Self { args, return_type })
+    }
+}
+
+impl Display for ProcType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("proc({})", self.args.join(", ")))?;
+
+        if let Some(return_type) = &self.return_type {
+            f.write_fmt(format_args!(": {return_type}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenericType {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl GenericType {
+    pub fn parse(type_name: &str) -> Option<Self> {
+        let (name, rest) = type_name.split_once('<')?;
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+
+        let close = closing_bracket(rest, '>')?;
+        if !rest[close + 1..].trim().is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            name: String::from(name),
+            args: split_type_args(&rest[..close]),
+        })
+    }
+}
+
+impl Display for GenericType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}<{}>", self.name, self.args.join(", ")))
+    }
+}
+
+pub fn map_types(type_name: &str) -> Option<(String, String)> {
+    let mut generic_type =
+        GenericType::parse(type_name).filter(|g| g.name == "Map" && g.args.len() == 2)?;
+
+    let value = generic_type.args.pop()?;
+    let key = generic_type.args.pop()?;
+
+    Some((key, value))
+}
+
+pub fn vec_type(type_name: &str) -> Option<String> {
+    let mut generic_type =
+        GenericType::parse(type_name).filter(|g| g.name == "Vec" && g.args.len() == 1)?;
+
+    generic_type.args.pop()
+}
+
+fn closing_bracket(rest: &str, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in rest.char_indices() {
+        match c {
+            '(' | '<' => depth += 1,
+            c if c == close && depth == 0 => return Some(i),
+            ')' | '>' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn split_type_args(list: &str) -> Vec<String> {
+    let mut depth = 0;
+    let mut args = Vec::new();
+    let mut start = 0;
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' | '<' => depth += 1,
+            ')' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(String::from(list[start..i].trim()));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    let last = list[start..].trim();
+    if !last.is_empty() {
+        args.push(String::from(last));
+    }
+
+    args
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ClosureSyntax {
+    Pipe,
+    Proc,
+    Named,
+    Reference,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClosureNode {
+    pub proc_def: ProcDefNode,
+    pub captures: Vec<VarMetadataNode>,
+    pub syntax: ClosureSyntax,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClosureCallNode {
+    pub callee: Box<Expression>,
+    pub args: Vec<Expression>,
+    pub return_type: Option<String>,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct FunCallNode {
     pub proc_def: ProcDefNode,
     pub args: Vec<VariableNode>,
+    pub type_args: Vec<String>,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct StructDefNode {
     pub type_name: String,
     pub fields: Vec<VarMetadataNode>,
+    pub derives: Vec<String>,
+    pub generics: Vec<String>,
+    pub docs: Option<String>,
+    pub position: Position,
+    pub span: Span,
+}
+
+impl StructDefNode {
+    pub fn is_instance(&self) -> bool {
+        self.type_name.contains('<')
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumVariantNode {
+    pub name: String,
+    pub fields: Vec<VarMetadataNode>,
+    pub discriminant: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumDefNode {
+    pub type_name: String,
+    pub variants: Vec<EnumVariantNode>,
+    pub flags: bool,
+    pub docs: Option<String>,
+    pub position: Position,
+    pub span: Span,
+}
+
+impl EnumDefNode {
+    pub fn discriminants(&self) -> Vec<(&str, i32)> {
+        let mut next = 0;
+        let mut used = 0;
+        self.variants
+            .iter()
+            .map(|variant| {
+                let discriminant = if self.flags {
+                    let next_bit = 32 - (used as u32).leading_zeros();
+                    variant
+                        .discriminant
+                        .unwrap_or_else(|| 1i32.checked_shl(next_bit).unwrap_or(0))
+                } else {
+                    variant.discriminant.unwrap_or(next)
+                };
+                next = discriminant.wrapping_add(1);
+                used |= discriminant;
+                (variant.name.as_str(), discriminant)
+            })
+            .collect()
+    }
+
+    pub fn all_flags(&self) -> i32 {
+        self.discriminants().iter().fold(0, |bits, (_, d)| bits | d)
+    }
+
+    pub fn is_fieldless(&self) -> bool {
+        self.variants.iter().all(|v| v.fields.is_empty())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumInstanceNode {
+    pub enum_def: EnumDefNode,
+    pub variant: String,
+    pub fields: Vec<VariableNode>,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct CastNode {
+    pub value: Box<Expression>,
+    pub type_name: String,
+    pub enum_def: EnumDefNode,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContainsNode {
+    pub value: Box<Expression>,
+    pub flag: Box<Expression>,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumFromNode {
+    pub enum_def: EnumDefNode,
+    pub value: Box<Expression>,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnumParseNode {
+    pub enum_def: EnumDefNode,
+    pub value: Box<Expression>,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct VariantNameNode {
+    pub value: Box<Expression>,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct ImplNode {
     pub procedures: Vec<Expression>,
     pub struct_def: StructDefNode,
+    pub trait_name: Option<String>,
+    pub derived: bool,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Clone, Default)]
+pub struct DispatchTable(Arc<RwLock<Vec<(String, ProcDefNode)>>>);
+
+impl DispatchTable {
+    pub fn same(&self, other: &DispatchTable) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    pub fn insert(&self, type_name: &str, proc_def: ProcDefNode) {
+        self.0
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((String::from(type_name), proc_def));
+    }
+
+    pub fn lookup(&self, type_name: &str, method: &str) -> Option<ProcDefNode> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .find(|(t, p)| t == type_name && p.name == method)
+            .map(|(_, p)| p.clone())
+    }
+
+    pub fn implements(&self, type_name: &str) -> bool {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .any(|(t, _)| t == type_name)
+    }
+
+    pub fn entries(&self) -> Vec<(String, ProcDefNode)> {
+        self.0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+// impl bodies can dispatch through their own trait, so only print the keys
+impl std::fmt::Debug for DispatchTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(
+                self.0
+                    .read()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .iter()
+                    .map(|(type_name, proc_def)| format!("{type_name}::{}", proc_def.name)),
+            )
+            .finish()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TraitDefNode {
+    pub name: String,
+    pub methods: Vec<ProcDefNode>,
+    pub dispatch: DispatchTable,
+    pub docs: Option<String>,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapMethod {
+    Insert,
+    Get,
+    Remove,
+    ContainsKey,
+    Len,
+}
+
+impl MapMethod {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "insert" => Some(MapMethod::Insert),
+            "get" => Some(MapMethod::Get),
+            "remove" => Some(MapMethod::Remove),
+            "contains_key" => Some(MapMethod::ContainsKey),
+            "len" => Some(MapMethod::Len),
+            _ => None,
+        }
+    }
+
+    pub fn mutates(&self) -> bool {
+        matches!(self, MapMethod::Insert | MapMethod::Remove)
+    }
+
+    pub fn is_lookup(&self) -> bool {
+        matches!(self, MapMethod::Get | MapMethod::Remove)
+    }
+}
+
+impl Display for MapMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapMethod::Insert => f.write_str("insert"),
+            MapMethod::Get => f.write_str("get"),
+            MapMethod::Remove => f.write_str("remove"),
+            MapMethod::ContainsKey => f.write_str("contains_key"),
+            MapMethod::Len => f.write_str("len"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MapNewNode {
+    pub type_name: String,
+    pub explicit: bool,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct MapCallNode {
+    pub map: Box<Expression>,
+    pub method: MapMethod,
+    pub args: Vec<Expression>,
+    pub type_name: String,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VecMethod {
+    Push,
+    Pop,
+    Get,
+    Set,
+    Len,
+}
+
+impl VecMethod {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "push" => Some(VecMethod::Push),
+            "pop" => Some(VecMethod::Pop),
+            "get" => Some(VecMethod::Get),
+            "set" => Some(VecMethod::Set),
+            "len" => Some(VecMethod::Len),
+            _ => None,
+        }
+    }
+
+    pub fn mutates(&self) -> bool {
+        matches!(self, VecMethod::Push | VecMethod::Pop | VecMethod::Set)
+    }
+
+    pub fn is_lookup(&self) -> bool {
+        matches!(self, VecMethod::Get | VecMethod::Pop)
+    }
+}
+
+impl Display for VecMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VecMethod::Push => f.write_str("push"),
+            VecMethod::Pop => f.write_str("pop"),
+            VecMethod::Get => f.write_str("get"),
+            VecMethod::Set => f.write_str("set"),
+            VecMethod::Len => f.write_str("len"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VecNewNode {
+    pub type_name: String,
+    pub explicit: bool,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct VecCallNode {
+    pub vec: Box<Expression>,
+    pub method: VecMethod,
+    pub args: Vec<Expression>,
+    pub indexed: bool,
+    pub type_name: String,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct DynCallNode {
+    pub trait_name: String,
+    pub fun_call_node: FunCallNode,
+    pub dispatch: DispatchTable,
+}
+
+#[derive(Debug, Clone)]
+pub struct CoalesceNode {
+    pub value: Box<Expression>,
+    pub fallback: Box<Expression>,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct PipeNode {
+    pub call: Box<Expression>,
+    pub bare: bool,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct ImplFunCallNode {
     pub impl_node: ImplNode,
     pub fun_call_node: Box<Expression>,
+    pub method: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct StructInstanceNode {
     pub struct_def: StructDefNode,
     pub fields: Vec<VariableNode>,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct FieldAssignNode {
     pub struct_instance: VariableNode,
-    pub field: VariableNode,
+    pub fields: Vec<VariableNode>,
     pub new_value: Box<Expression>,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
 pub struct FieldAccessNode {
-    pub struct_instance: VariableNode,
+    pub base: Box<Expression>,
     pub field: VariableNode,
+    pub position: Position,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -133,4 +862,14 @@ pub struct BinaryOpNode {
     pub lhs: Box<Expression>,
     pub op: BinaryOp,
     pub rhs: Box<Expression>,
+    pub position: Position,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct CompareChainNode {
+    pub operands: Vec<Expression>,
+    pub ops: Vec<BinaryOp>,
+    pub position: Position,
+    pub span: Span,
 }
@@ -1,920 +1,5221 @@
-use std::{fs::File, path::Path, string::ParseError};
+use std::{
+    any::Any,
+    fs::File,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    string::ParseError,
+};
 
 use crate::{
+    ast_printer::AstPrinter,
+    astc,
+    builtins::{Capabilities, VERSION},
+    diagnostics::{Diagnostic, Edit, Fix},
+    executor::ENTRY_POINT,
     expression::Expression,
-    lexer::Lexer,
+    lexer::{self, Lexer},
+    log,
+    native::NativeFn,
     nodes::{
-        AssignNode, BinaryOp, BinaryOpNode, FieldAccessNode, FieldAssignNode, ForNode, FunCallNode,
-        IfNode, ImplFunCallNode, ImplNode, LetNode, ProcDefNode, RangeNode, ReturnNode,
-        StructDefNode, StructInstanceNode, VarMetadataNode, VariableNode, WhileNode,
+        map_types, vec_type, AssignNode, BinaryOp, BinaryOpNode, CastNode, ClosureCallNode,
+        ClosureNode, ClosureSyntax, CoalesceNode, CompareChainNode, ContainsNode, ContractKind,
+        ContractNode, DispatchTable, DynCallNode, ElseNode, EnumDefNode, EnumFromNode,
+        EnumInstanceNode, EnumParseNode, EnumVariantNode, FieldAccessNode, FieldAssignNode,
+        ForNode, FunCallNode, GenericType, IfNode, ImplFunCallNode, ImplNode, LetNode, MapCallNode,
+        MapMethod, MapNewNode, MatchArmNode, MatchNode, PatternNode, PipeNode, ProcDefNode,
+        ProcType, RangeNode, ReturnNode, StructDefNode, StructInstanceNode, TraitDefNode,
+        VarMetadataNode, VariableNode, VariantNameNode, VecCallNode, VecMethod, VecNewNode,
+        WhileNode, COALESCE_PRECEDENCE, PIPE_PRECEDENCE,
     },
     timer::Timer,
-    token::{LiteralType, Token, TokenType},
+    token::{LiteralType, Location, Position, Span, Token, TokenType},
+    value::Value,
 };
 
 pub type Program = Vec<Expression>;
 
-pub struct Parser {
-    lexer: Lexer,
-    program: Program,
-    variables: Vec<VariableNode>,
-    procedures: Vec<ProcDefNode>,
-    structs: Vec<StructDefNode>,
-    struct_instances: Vec<StructInstanceNode>,
-    impl_blocks: Vec<ImplNode>,
+pub const UNKNOWN_TYPE: &str = "{unknown}";
+
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| String::from(*s))
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown panic"))
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum AstFormat {
+    #[default]
+    Tree,
+    Dot,
+    Debug,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParserOptions {
+    pub dump_ast: Option<PathBuf>,
+    pub ast_format: AstFormat,
+    pub ast_printer: AstPrinter,
+    pub cache_ast: bool,
+    pub release: bool,
+    pub strict: bool,
+    pub hardened: bool,
 }
 
-impl Parser {
-    pub fn new(lexer: Lexer) -> Self {
+impl Default for ParserOptions {
+    fn default() -> Self {
         Self {
-            lexer,
-            program: Program::new(),
-            variables: Vec::new(),
-            procedures: Vec::new(),
-            structs: Vec::new(),
-            struct_instances: Vec::new(),
-            impl_blocks: Vec::new(),
+            dump_ast: None,
+            ast_format: AstFormat::default(),
+            ast_printer: AstPrinter::default(),
+            cache_ast: false,
+            release: false,
+            strict: false,
+            hardened: true,
         }
     }
+}
 
-    pub fn from_file<P: AsRef<Path> + Clone>(path: P) -> std::io::Result<Self> {
-        let source = std::fs::read_to_string(path.clone())?;
-        let filename = path
-            .as_ref()
-            .file_name()
-            .unwrap()
-            .to_os_string()
-            .into_string()
-            .unwrap();
+impl ParserOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        let lexer = Lexer::new(source, filename);
-        let this = Self::new(lexer);
+    pub fn dump_ast<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.dump_ast = Some(path.into());
+        self
+    }
 
-        Ok(this)
+    pub fn ast_format(mut self, format: AstFormat) -> Self {
+        self.ast_format = format;
+        self
     }
 
-    pub fn parse_program(&mut self) -> Result<Program, ParseError> {
-        {
-            let _timer = Timer::start("Parsing");
+    pub fn ast_printer(mut self, printer: AstPrinter) -> Self {
+        self.ast_printer = printer;
+        self
+    }
 
-            while let Some(token) = &self.lexer.next() {
-                if let Some(expr) = self.parse_expr(token) {
-                    self.program.push(expr);
-                }
-            }
-        }
+    pub fn cache_ast(mut self, enabled: bool) -> Self {
+        self.cache_ast = enabled;
+        self
+    }
 
-        self.write_to_file("ast.dat");
-        Ok(self.program.clone())
+    pub fn release(mut self, enabled: bool) -> Self {
+        self.release = enabled;
+        self
     }
 
-    fn parse_expr(&mut self, token: &Token) -> Option<Expression> {
-        type TT = TokenType;
+    pub fn strict(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
+    }
 
-        match token.kind {
-            TT::If => self.visit_if_statement(),
-            TT::While => self.visit_while_statement(),
-            TT::For => self.visit_for_loop(),
-            TT::Let => self.visit_let_statement(),
-            TT::Impl => self.visit_impl_block(),
-            TT::Return => self.visit_return_statement(),
-            TT::Proc => self.visit_procedure_def(),
-            TT::Ident => self.visit_identifier(token),
-            TT::Struct => self.visit_struct_def(),
-            TT::Literal(lt) => {
-                let literal = Some(Expression::Literal(token.clone(), lt));
-                self.visit_binary_op(literal)
-            }
-            _ => None,
-        }
+    pub fn hardened(mut self, enabled: bool) -> Self {
+        self.hardened = enabled;
+        self
     }
+}
 
-    fn visit_if_statement(&mut self) -> Option<Expression> {
-        let first = self.lexer.next().unwrap();
-        if let Some(expr) = self.parse_expr(&first) {
-            let boolean_expr = self.visit_boolean_expr(expr);
+const DERIVES: &[&str] = &["eq", "clone", "to_string", "ord"];
+const ORDERED_TYPES: &[&str] = &["i32", "f32", "char", "String"];
+const MAP_KEY_TYPES: &[&str] = &["i32", "char", "bool", "String"];
 
-            boolean_expr.as_ref()?;
+fn derived_method(derive: &str) -> &str {
+    match derive {
+        "ord" => "cmp",
+        derive => derive,
+    }
+}
 
-            if let Some(_ocurly) = self.lexer.next() {
-                let mut statements = Vec::new();
+fn element_type(type_name: &str) -> Option<String> {
+    match type_name {
+        "Lines" => Some(String::from("String")),
+        "String" => Some(String::from("char")),
+        "any" => Some(String::from("any")),
+        UNKNOWN_TYPE => Some(String::from(UNKNOWN_TYPE)),
+        _ => map_types(type_name)
+            .map(|(key, _)| key)
+            .or_else(|| vec_type(type_name)),
+    }
+}
 
-                while let Some(next) = self.lexer.next() {
-                    if let TokenType::Ccurly = next.kind {
-                        break;
-                    } else if let TokenType::Semicolon = next.kind {
-                        continue;
-                    }
+fn type_words(type_name: &str) -> impl Iterator<Item = &str> {
+    type_name
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+}
 
-                    if let Some(expr) = self.parse_expr(&next) {
-                        statements.push(expr.clone());
-                    }
-                }
+pub fn substitute_type(type_name: &str, bindings: &[(String, String)]) -> String {
+    let mut result = String::new();
+    let mut word = String::new();
 
-                let if_node = IfNode {
-                    value: Box::new(boolean_expr.unwrap()),
-                    statements,
-                };
+    for c in type_name.chars().chain(std::iter::once('\0')) {
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
 
-                return Some(Expression::IfStatement(if_node));
-            }
+        match bindings.iter().find(|(param, _)| *param == word) {
+            Some((_, bound)) => result.push_str(bound),
+            None => result.push_str(&word),
         }
+        word.clear();
 
-        None
+        if c != '\0' {
+            result.push(c);
+        }
     }
 
-    fn visit_while_statement(&mut self) -> Option<Expression> {
-        let first = self.lexer.next().unwrap();
-        if let Some(expr) = self.parse_expr(&first) {
-            let boolean_expr = self.visit_boolean_expr(expr);
+    result
+}
 
-            boolean_expr.as_ref()?;
+// whether a value of type found may be passed where expected is declared
+pub fn assignable(expected: &str, found: &str, generics: &[String]) -> bool {
+    if expected == found
+        || [expected, found].contains(&"any")
+        || [expected, found].contains(&UNKNOWN_TYPE)
+        || generics.iter().any(|g| g == expected)
+    {
+        return true;
+    }
 
-            if let Some(_ocurly) = self.lexer.next() {
-                let mut statements = Vec::new();
+    if let (Some(expected), Some(found)) = (ProcType::parse(expected), ProcType::parse(found)) {
+        let returns = match (&expected.return_type, &found.return_type) {
+            (Some(e), Some(f)) => assignable(e, f, generics),
+            (e, f) => e.is_none() && f.is_none(),
+        };
 
-                while let Some(next) = self.lexer.next() {
-                    if let TokenType::Ccurly = next.kind {
-                        break;
-                    } else if let TokenType::Semicolon = next.kind {
-                        continue;
-                    }
+        return returns
+            && expected.args.len() == found.args.len()
+            && expected
+                .args
+                .iter()
+                .zip(found.args.iter())
+                .all(|(e, f)| assignable(e, f, generics));
+    }
 
-                    if let Some(expr) = self.parse_expr(&next) {
-                        statements.push(expr.clone());
-                    }
-                }
+    if let (Some(expected), Some(found)) = (GenericType::parse(expected), GenericType::parse(found))
+    {
+        return expected.name == found.name
+            && expected.args.len() == found.args.len()
+            && expected
+                .args
+                .iter()
+                .zip(found.args.iter())
+                .all(|(e, f)| assignable(e, f, generics));
+    }
 
-                let while_node = WhileNode {
-                    value: Box::new(boolean_expr.unwrap()),
-                    statements,
-                };
+    false
+}
 
-                return Some(Expression::WhileStatement(while_node));
-            }
+fn unify(
+    param: &str,
+    arg: &str,
+    generics: &[String],
+    bindings: &mut Vec<(String, String)>,
+) -> bool {
+    if generics.iter().any(|g| g == param) {
+        if arg == UNKNOWN_TYPE {
+            return true;
         }
 
-        None
-    }
-
-    fn visit_for_loop(&mut self) -> Option<Expression> {
-        if let Some(counter_token) = self.lexer.next() {
-            let _in = self.lexer.next().unwrap();
+        if let Some((_, bound)) = bindings.iter().find(|(name, _)| name == param) {
+            return bound == arg;
+        }
 
-            let start_token = self.lexer.next().unwrap();
+        bindings.push((String::from(param), String::from(arg)));
+        return true;
+    }
 
-            let start;
-            let end;
+    if let (Some(param), Some(arg)) = (ProcType::parse(param), ProcType::parse(arg)) {
+        let returns = match (&param.return_type, &arg.return_type) {
+            (Some(p), Some(a)) => unify(p, a, generics, bindings),
+            _ => true,
+        };
 
-            if let Some(s) = self.parse_expr(&start_token) {
-                let _range_op = self.lexer.next().unwrap();
-                start = Box::new(s);
+        return param
+            .args
+            .iter()
+            .zip(arg.args.iter())
+            .all(|(p, a)| unify(p, a, generics, bindings))
+            && returns;
+    }
 
-                let initial_counter_value = start.clone();
-                let counter = self.make_variable(
-                    counter_token.value,
-                    "i32".to_string(),
-                    initial_counter_value,
-                );
+    if let (Some(param), Some(arg)) = (GenericType::parse(param), GenericType::parse(arg)) {
+        if param.name == arg.name {
+            return param
+                .args
+                .iter()
+                .zip(arg.args.iter())
+                .all(|(p, a)| unify(p, a, generics, bindings));
+        }
+    }
 
-                self.variables.push(counter.clone());
-                let counter_index = self.variables.len() - 1;
+    true
+}
 
-                let end_token = self.lexer.next().unwrap();
-                if let Some(e) = self.parse_expr(&end_token) {
-                    end = Box::new(e);
+#[derive(Default)]
+struct Attributes<'src> {
+    docs: Option<String>,
+    flags: bool,
+    derives: Vec<Token<'src>>,
+    contracts: Vec<(ContractKind, String, Position)>,
+}
 
-                    let range_node = RangeNode { start, end };
-                    let range = Box::new(Expression::RangeStatement(range_node));
+struct ClosureScope {
+    scope_start: usize,
+    captures: Vec<VarMetadataNode>,
+}
 
-                    if let Some(_ocurly) = self.lexer.next() {
-                        let mut statements = Vec::new();
+pub struct Parser<'src> {
+    lexer: Lexer<'src>,
+    options: ParserOptions,
+    source_path: Option<PathBuf>,
+    source_hash: u64,
+    dependency_hash: u64,
+    program: Program,
+    diagnostics: Vec<Diagnostic>,
+    variables: Vec<VariableNode>,
+    procedures: Vec<ProcDefNode>,
+    structs: Vec<StructDefNode>,
+    enums: Vec<EnumDefNode>,
+    impl_blocks: Vec<ImplNode>,
+    traits: Vec<TraitDefNode>,
+    derived_impls: Vec<Expression>,
+    loop_depth: usize,
+    proc_depth: usize,
+    closures: Vec<ClosureScope>,
+    impl_type: Option<String>,
+    generic_params: Vec<String>,
+    type_bindings: Vec<(String, String)>,
+    instance_name: Option<String>,
+    instances: Vec<Expression>,
+    global_scope: usize,
+    host_globals: usize,
+}
 
-                        while let Some(next) = self.lexer.next() {
-                            if let TokenType::Ccurly = next.kind {
-                                break;
-                            } else if let TokenType::Semicolon = next.kind {
-                                continue;
-                            }
+impl<'src> Parser<'src> {
+    pub fn new(lexer: Lexer<'src>) -> Self {
+        let mut this = Self {
+            lexer,
+            options: ParserOptions::default(),
+            source_path: None,
+            source_hash: 0,
+            dependency_hash: 0,
+            program: Program::new(),
+            diagnostics: Vec::new(),
+            variables: Vec::new(),
+            procedures: Vec::new(),
+            structs: Vec::new(),
+            enums: Vec::new(),
+            impl_blocks: Vec::new(),
+            traits: Vec::new(),
+            derived_impls: Vec::new(),
+            loop_depth: 0,
+            proc_depth: 0,
+            closures: Vec::new(),
+            impl_type: None,
+            generic_params: Vec::new(),
+            type_bindings: Vec::new(),
+            instance_name: None,
+            instances: Vec::new(),
+            global_scope: 0,
+            host_globals: 0,
+        };
 
-                            if let Some(statement) = self.parse_expr(&next) {
-                                statements.push(statement);
-                            }
-                        }
+        for native in Capabilities::default().natives().iter() {
+            this.declare_native(native);
+        }
 
-                        let for_node = ForNode {
-                            counter,
-                            range,
-                            statements,
-                        };
+        this
+    }
 
-                        self.variables.remove(counter_index);
+    pub fn from_file<P: AsRef<Path>>(path: P, source: &'src str) -> Self {
+        let filename = path
+            .as_ref()
+            .file_name()
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_default();
 
-                        return Some(Expression::ForLoop(for_node));
-                    }
-                }
-            }
-        }
+        let mut this = Self::new(Lexer::new(source, filename));
+        this.source_path = Some(path.as_ref().to_path_buf());
+        this.source_hash = astc::source_hash(source);
 
-        None
+        this
     }
 
-    fn visit_boolean_expr(&mut self, expr: Expression) -> Option<Expression> {
-        match expr.clone() {
-            Expression::FunCall(fun_call_node) => {
-                if let Some(return_type) = fun_call_node.proc_def.return_type {
-                    if return_type == "bool" {
-                        return self.visit_binary_op(Some(expr));
-                    }
-                }
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
 
-                None
-            }
-            Expression::Variable(variable_node) => {
-                if variable_node.metadata.type_name == "bool" {
-                    return self.visit_binary_op(Some(expr));
-                }
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
 
-                None
-            }
-            Expression::StructFieldAccess(..) => self.visit_binary_op(Some(expr)),
-            Expression::BinaryOp(..) => Some(expr),
-            Expression::Literal(..) => self.visit_binary_op(Some(expr)),
-            _ => None,
-        }
+    pub fn comments(&self) -> &[(Position, String)] {
+        self.lexer.comments()
     }
 
-    fn visit_let_statement(&mut self) -> Option<Expression> {
-        if let Some(ident) = self.lexer.next() {
-            if let Some(next) = self.lexer.next() {
-                let mut type_hint = None;
+    pub fn declare_global(&mut self, name: String, value: &Value) {
+        self.depend_on(&format!("global {name}: {} = {value}", value.type_name()));
 
-                if let TokenType::Colon = next.kind {
-                    let type_name = self.lexer.next().unwrap();
-                    if let TokenType::Ident = type_name.kind {
-                        type_hint = Some(type_name.value);
-                    }
+        let expr = self.expression_from_value(value);
+        let variable = self.make_variable(name, value.type_name(), Box::new(expr));
 
-                    let _equal_op = self.lexer.next().unwrap();
-                }
+        self.variables.push(variable);
+        self.host_globals = self.variables.len();
+    }
 
-                let first = self.lexer.next().unwrap();
+    pub fn declare_global_mut(&mut self, name: String, value: &Value) {
+        self.declare_global(name, value);
+        if let Some(variable) = self.variables.last_mut() {
+            variable.metadata.mutable = true;
+        }
+    }
 
-                if let Some(value) = self.parse_expr(&first) {
-                    let name = ident.value;
-                    let value = Box::new(value);
-
-                    let kind_str = match first.kind {
-                        TokenType::Literal(lt) => self.string_from_literal_type(lt),
-                        TokenType::Ident => {
-                            if let Some(var) = self
-                                .variables
-                                .iter()
-                                .find(|&v| v.metadata.name == first.value)
-                            {
-                                var.metadata.type_name.clone()
-                            } else if let Some(proc_def) =
-                                self.procedures.iter().find(|&f| f.name == first.value)
-                            {
-                                if let Some(return_type) = proc_def.return_type.clone() {
-                                    return_type
-                                } else {
-                                    "None".to_string()
-                                }
-                            } else if let Some(struct_def) =
-                                self.structs.iter().find(|&s| s.type_name == first.value)
-                            {
-                                struct_def.type_name.clone()
-                            } else {
-                                "None".to_string()
-                            }
-                        }
-                        _ => "None".to_string(),
-                    };
+    pub fn declare_native(&mut self, native: &NativeFn) {
+        self.depend_on(&format!(
+            "native {}({}): {:?}",
+            native.name,
+            native.params.join(", "),
+            native.return_type
+        ));
 
-                    if let Some(hint) = type_hint {
-                        if kind_str != hint {
-                            println!(
-                                "<{}> Error: expected '{hint}' found '{kind_str}'",
-                                first.position,
-                            );
-                        }
-                    }
+        self.procedures.push(native.proc_def());
+    }
 
-                    let variable =
-                        self.make_variable(name.clone(), kind_str.clone(), value.clone());
-                    self.variables.push(variable);
+    pub fn declare_definitions(&mut self, program: &[Expression]) {
+        let bytes = astc::encode(program, &[], &[], 0);
+        self.dependency_hash = astc::mix(self.dependency_hash, &bytes);
 
-                    let let_node = LetNode {
-                        name,
-                        type_name: kind_str,
-                        value,
-                    };
+        self.register(program);
+    }
 
-                    return Some(Expression::LetStatement(let_node));
+    // declarations made before parsing can be resolved into the AST, so they key the cache
+    fn depend_on(&mut self, declaration: &str) {
+        self.dependency_hash = astc::mix(self.dependency_hash, declaration.as_bytes());
+    }
+
+    fn register(&mut self, program: &[Expression]) {
+        for expr in program.iter() {
+            match expr {
+                Expression::ProcDef(proc_def_node) => self.procedures.push(proc_def_node.clone()),
+                Expression::StructDef(struct_def_node) => {
+                    self.structs.push(struct_def_node.clone())
                 }
+                Expression::EnumDef(enum_def_node) => self.enums.push(enum_def_node.clone()),
+                Expression::ImplStatement(impl_node) => self.impl_blocks.push(impl_node.clone()),
+                Expression::TraitDef(trait_def) => self.traits.push(trait_def.clone()),
+                _ => {}
             }
         }
-
-        None
     }
 
-    fn visit_impl_block(&mut self) -> Option<Expression> {
-        if let Some(type_name) = self.lexer.next() {
-            if let Some(struct_def) = self
-                .structs
-                .clone()
-                .iter()
-                .find(|&s| s.type_name == type_name.value)
+    pub fn parse_program(&mut self) -> Result<Program, ParseError> {
+        if let Some(program) = self.read_cache() {
+            self.program = program;
+            self.register_definitions();
+        } else {
+            self.global_scope = self.variables.len();
+
             {
-                let mut procedures = Vec::new();
+                let _timer = Timer::start("Parsing");
 
-                while let Some(next) = self.lexer.next() {
-                    if let TokenType::Ccurly = next.kind {
-                        break;
-                    } else if let TokenType::Semicolon = next.kind {
-                        continue;
-                    }
+                if self.options.hardened {
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| self.parse_tokens()));
 
-                    if let TokenType::Proc = next.kind {
-                        if let Some(proc_def_node) = self.parse_expr(&next) {
-                            procedures.push(proc_def_node);
-                        }
+                    if let Err(payload) = result {
+                        let mut diagnostic = Diagnostic::error(
+                            None,
+                            format!("internal parser error: {}", panic_message(payload.as_ref())),
+                        );
+                        diagnostic.note = Some(String::from(
+                            "this is a bug in the parser, please report it with the input that caused it",
+                        ));
+                        self.diagnostics.push(diagnostic);
                     }
+                } else {
+                    self.parse_tokens();
                 }
+            }
 
-                let impl_node = ImplNode {
-                    procedures,
-                    struct_def: struct_def.clone(),
-                };
+            self.wrap_script_statements();
 
-                self.impl_blocks.push(impl_node.clone());
+            self.write_cache();
+        }
 
-                return Some(Expression::ImplStatement(impl_node));
-            }
+        if let Some(path) = &self.options.dump_ast {
+            self.write_to_file(path, self.options.ast_format);
         }
 
-        None
+        Ok(std::mem::take(&mut self.program))
     }
 
-    fn visit_return_statement(&mut self) -> Option<Expression> {
-        if let Some(first) = self.lexer.next() {
-            if let Some(return_value) = self.parse_expr(&first) {
-                let return_node = ReturnNode {
-                    value: Box::new(return_value),
-                };
+    fn parse_tokens(&mut self) {
+        while let Some(token) = &self.lexer.next() {
+            if let TokenType::Semicolon = token.kind {
+                continue;
+            }
 
-                return Some(Expression::ReturnStatement(return_node));
+            let diagnostics = self.diagnostics.len();
+
+            if let Some(expr) = self.parse_expr(token) {
+                self.program.push(expr);
+                self.program.append(&mut self.derived_impls);
+                self.program.append(&mut self.instances);
+            } else {
+                let error = self.recover(token.clone(), diagnostics);
+                self.program.push(error);
             }
         }
 
-        None
+        for (position, message) in self.lexer.errors().to_vec() {
+            self.error(&position, message);
+        }
+
+        if let Some(open) = self.lexer.unclosed_delimiter() {
+            let mut diagnostic = Diagnostic::error(
+                Some(open.position),
+                format!("unclosed delimiter '{}'", open.value),
+            );
+            diagnostic.note = Some(String::from("the file ended before it was closed"));
+            self.diagnostics.push(diagnostic);
+        }
     }
 
-    fn visit_procedure_def(&mut self) -> Option<Expression> {
-        type TT = TokenType;
+    fn wrap_script_statements(&mut self) {
+        let (definitions, statements): (Program, Program) = std::mem::take(&mut self.program)
+            .into_iter()
+            .partition(|expr| {
+                matches!(
+                    expr,
+                    Expression::ProcDef(..)
+                        | Expression::StructDef(..)
+                        | Expression::EnumDef(..)
+                        | Expression::ImplStatement(..)
+                        | Expression::TraitDef(..)
+                        | Expression::Error(..)
+                )
+            });
 
-        if let Some(ident) = self.lexer.next() {
-            let mut args = Vec::new();
-            let mut statements = Vec::new();
+        self.program = definitions;
 
-            if let Some(_oparen) = self.lexer.next() {
-                // args
-                self.visit_args(&mut args);
+        if statements.is_empty() {
+            return;
+        }
 
-                let mut return_type = None;
+        let has_entry_point = self.program.iter().any(|expr| {
+            if let Expression::ProcDef(proc_def_node) = expr {
+                return proc_def_node.name == ENTRY_POINT;
+            }
 
-                // statements
-                if let Some(n) = self.lexer.next() {
-                    if n.kind == TT::Colon {
-                        let rt = self.lexer.next().unwrap();
-                        return_type = Some(rt.value);
+            false
+        });
 
-                        let _ocurly = self.lexer.next().unwrap();
-                    }
+        if has_entry_point {
+            self.diagnostics.push(Diagnostic::error(
+                statements[0].position().cloned(),
+                format!("top-level statements cannot be mixed with a '{ENTRY_POINT}' procedure"),
+            ));
 
-                    while let Some(next) = self.lexer.next() {
-                        if let TT::Ccurly = next.kind {
-                            break;
-                        } else if let TT::Semicolon = next.kind {
-                            continue;
-                        }
+            return;
+        }
 
-                        if let Some(expr) = self.parse_expr(&next) {
-                            statements.push(expr);
-                        } else {
-                            break;
-                        }
-                    }
-                }
+        // a trailing value is returned from the script, as it is for a proc body
+        let mut statements = statements;
+        let mut return_type = None;
+        if let Some(last) = statements.pop() {
+            let type_name = last.type_name();
+            if type_name == "None" {
+                statements.push(last);
+            } else {
+                let position = last.position().cloned().unwrap_or_default();
+                let span = last.span().unwrap_or_default();
+                statements.push(Expression::ReturnStatement(ReturnNode {
+                    value: Box::new(last),
+                    implicit: true,
+                    position,
+                    span,
+                }));
+                return_type = Some(type_name);
+            }
+        }
 
-                for arg in args.clone().iter() {
-                    let pos = self
-                        .variables
-                        .iter()
-                        .position(|v| v.metadata.name == arg.name)
-                        .unwrap();
+        let span = match (statements.first(), statements.last()) {
+            (Some(first), Some(last)) => first
+                .span()
+                .unwrap_or_default()
+                .to(last.span().unwrap_or_default()),
+            _ => Span::default(),
+        };
 
-                    self.variables.remove(pos);
-                }
+        let proc_def_node = ProcDefNode {
+            name: String::from(ENTRY_POINT),
+            return_type,
+            args: Vec::new(),
+            statements,
+            contracts: Vec::new(),
+            native: false,
+            generics: Vec::new(),
+            template: None,
+            docs: None,
+            position: Position::default(),
+            span,
+        };
 
-                let proc_def_node = ProcDefNode {
-                    name: ident.value,
-                    return_type,
-                    args,
-                    statements,
-                };
+        self.procedures.push(proc_def_node.clone());
+        self.program.push(Expression::ProcDef(proc_def_node));
+    }
 
-                self.procedures.push(proc_def_node.clone());
+    fn parse_expr(&mut self, token: &Token) -> Option<Expression> {
+        type TT = TokenType;
 
-                return Some(Expression::ProcDef(proc_def_node));
+        match token.kind {
+            TT::If => self.visit_if_statement(token),
+            TT::While => self.visit_while_statement(token),
+            TT::For => self.visit_for_loop(token),
+            TT::Match => self.visit_match(token),
+            TT::Let => self.visit_let_statement(token),
+            TT::Impl => self.visit_impl_block(token),
+            TT::Trait => self.visit_trait_def(),
+            TT::Return => self.visit_return_statement(token),
+            TT::Break | TT::Continue => self.visit_loop_control(token),
+            TT::Proc => match self.lexer.peek_token().map(|t| t.kind) {
+                Some(TT::Oparen) => self.visit_closure(token, ClosureSyntax::Proc),
+                _ if self.proc_depth > 0 => self.visit_closure(token, ClosureSyntax::Named),
+                _ => self.visit_procedure_def(Attributes::default()),
+            },
+            TT::BitOr => self.visit_closure(token, ClosureSyntax::Pipe),
+            TT::At => self.visit_attributes(token),
+            TT::Ident => {
+                let expr = self.visit_identifier(token);
+                self.visit_binary_op(expr)
+            }
+            TT::Struct => self.visit_struct_def(Attributes::default()),
+            TT::Enum => self.visit_enum_def(Attributes::default()),
+            TT::Oparen => {
+                let expr = self.visit_group();
+                self.visit_binary_op(expr)
+            }
+            TT::Literal(lt) => {
+                let literal = self.visit_literal(token.clone(), lt);
+                self.visit_binary_op(literal)
+            }
+            TT::Sub => {
+                let expr = self.visit_negation(token);
+                self.visit_binary_op(expr)
+            }
+            TT::Interpolation => {
+                let expr = self.visit_interpolation(token);
+                self.visit_binary_op(expr)
+            }
+            _ => {
+                self.error(&token.position, format!("unexpected '{}'", token.value));
+                None
+            }
+        }
+    }
+
+    fn is_trailing(&mut self, expr: &Expression) -> bool {
+        if let Some(TokenType::Ccurly) = self.lexer.peek_token().map(|t| t.kind) {
+            return !matches!(expr, Expression::Error(..)) && expr.type_name() != "None";
+        }
+
+        false
+    }
+
+    fn skip_semicolon(&mut self) {
+        if let Some(TokenType::Semicolon) = self.lexer.peek_token().map(|t| t.kind) {
+            let _semicolon = self.lexer.next();
+        }
+    }
+
+    fn synchronize(&mut self) {
+        type TT = TokenType;
+
+        let mut depth = 0;
+        while let Some(token) = self.lexer.peek_token() {
+            match token.kind {
+                TT::Semicolon if depth == 0 => {
+                    let _semicolon = self.lexer.next();
+                    return;
+                }
+                TT::Ccurly if depth == 0 => return,
+                TT::Proc | TT::Struct | TT::Impl | TT::Trait | TT::At if depth == 0 => return,
+                TT::Ocurly => depth += 1,
+                TT::Ccurly => {
+                    depth -= 1;
+
+                    if depth == 0 {
+                        let _ccurly = self.lexer.next();
+                        return;
+                    }
+                }
+                _ => {}
+            }
+
+            let _skipped = self.lexer.next();
+        }
+    }
+
+    fn recover(&mut self, token: Token, diagnostics: usize) -> Expression {
+        // a sub-parse that gave up without saying why still needs a diagnostic at the placeholder
+        if self.diagnostics.len() == diagnostics {
+            if self.lexer.valid() {
+                self.error(
+                    &token.position,
+                    format!("unexpected token '{}'", token.value),
+                );
+            } else {
+                let position = self.lexer.get_cursor_pos();
+                self.error(&position, String::from("unexpected end of file"));
+            }
+        }
+
+        self.synchronize();
+        Expression::Error(token.position)
+    }
+
+    fn parse_operand(&mut self, token: &Token) -> Option<Expression> {
+        match token.kind {
+            TokenType::Ident => self.visit_identifier(token),
+            TokenType::Oparen => self.visit_group(),
+            TokenType::Literal(lt) => self.visit_literal(token.clone(), lt),
+            TokenType::Interpolation => self.visit_interpolation(token),
+            TokenType::Sub => self.visit_negation(token),
+            TokenType::If => self.visit_if_statement(token),
+            _ => None,
+        }
+    }
+
+    fn visit_literal(&mut self, token: Token, lt: LiteralType) -> Option<Expression> {
+        let valid = match lt {
+            LiteralType::Number => token.value.parse::<i32>().is_ok(),
+            LiteralType::Float => token.value.parse::<f32>().is_ok_and(|f| f.is_finite()),
+            _ => true,
+        };
+
+        if !valid {
+            self.error(
+                &token.position,
+                format!("invalid {} literal '{}'", lt.type_name(), token.value),
+            );
+            return None;
+        }
+
+        Some(Expression::Literal(token.into_owned(), lt))
+    }
+
+    fn visit_negation(&mut self, token: &Token) -> Option<Expression> {
+        let next = self.lexer.next()?;
+
+        if let TokenType::Literal(lt @ (LiteralType::Number | LiteralType::Float)) = next.kind {
+            let value = format!("-{}", next.value);
+            let mut literal = Token::from(next.kind, value, token.position.clone());
+            literal.span = self.span_since(&token.position);
+            return self.visit_literal(literal, lt);
+        }
+
+        let operand = if let Some(operand) = self.parse_operand(&next) {
+            operand
+        } else {
+            self.error(
+                &next.position,
+                format!("expected operand found '{}'", next.value),
+            );
+            return None;
+        };
+
+        let zero = Token::from(
+            TokenType::Literal(LiteralType::Number),
+            String::from("0"),
+            token.position.clone(),
+        );
+
+        Some(Expression::BinaryOp(BinaryOpNode {
+            lhs: Box::new(Expression::Literal(zero, LiteralType::Number)),
+            op: BinaryOp::Sub,
+            rhs: Box::new(operand),
+            position: token.position.clone(),
+            span: self.span_since(&token.position),
+        }))
+    }
+
+    fn visit_interpolation(&mut self, token: &Token) -> Option<Expression> {
+        let raw: Vec<char> = token.value.chars().collect();
+
+        let mut text = String::new();
+        let mut parts = Vec::new();
+
+        let mut i = 0;
+        while i < raw.len() {
+            match raw[i] {
+                '\\' => {
+                    text.push(raw[i]);
+                    if let Some(&c) = raw.get(i + 1) {
+                        text.push(c);
+                    }
+                    i += 2;
+                }
+                '{' => {
+                    let start = i + 1;
+                    let end = (start..raw.len()).find(|&j| raw[j] == '}');
+
+                    let end = if let Some(end) = end {
+                        end
+                    } else {
+                        self.error(&token.position, String::from("unterminated '{' in string"));
+                        return None;
+                    };
+
+                    parts.push(self.string_literal(&text, &token.position));
+                    text.clear();
+
+                    let source: String = raw[start..end].iter().collect();
+                    let position = Parser::offset_position(&token.position, &raw[..start]);
+
+                    parts.push(self.parse_embedded_expr(
+                        source,
+                        &position,
+                        "string interpolation",
+                    )?);
+
+                    i = end + 1;
+                }
+                c => {
+                    text.push(c);
+                    i += 1;
+                }
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(self.string_literal(&text, &token.position));
+        }
+
+        let mut parts = parts.into_iter();
+        let mut expr = parts.next()?;
+        for part in parts {
+            expr = Expression::BinaryOp(BinaryOpNode {
+                lhs: Box::new(expr),
+                op: BinaryOp::Add,
+                rhs: Box::new(part),
+                position: token.position.clone(),
+                span: self.span_since(&token.position),
+            });
+        }
+
+        Some(expr)
+    }
+
+    fn string_literal(&self, raw: &str, position: &Position) -> Expression {
+        let token = Token::from(
+            TokenType::Literal(LiteralType::String),
+            lexer::unescape(raw),
+            position.clone(),
+        );
+
+        Expression::Literal(token, LiteralType::String)
+    }
+
+    fn offset_position(position: &Position, preceding: &[char]) -> Position {
+        let mut row = position.row;
+        let mut column = position.column + 1;
+
+        for &c in preceding.iter() {
+            if c == '\n' {
+                row += 1;
+                column = 0;
+            } else {
+                column += 1;
+            }
+        }
+
+        Position::from(position.filename.clone(), row, column)
+    }
+
+    fn parse_embedded_expr(
+        &mut self,
+        source: String,
+        position: &Position,
+        context: &str,
+    ) -> Option<Expression> {
+        let padded = format!(
+            "{}{}{source}",
+            "\n".repeat(position.row as usize),
+            " ".repeat(position.column as usize)
+        );
+
+        let lexer = Lexer::new(padded, position.filename.clone());
+        let outer = std::mem::replace(&mut self.lexer, lexer);
+
+        let expr = match self.lexer.next() {
+            Some(first) => {
+                let mut expr = self.parse_expr(&first);
+                // cover everything that was parsed, not only the leading operand
+                if let Some(span) = expr.as_mut().and_then(Expression::span_mut) {
+                    *span = self.span_since(&first.position);
+                }
+                expr
+            }
+            None => {
+                self.error(position, format!("expected expression in {context}"));
+                None
+            }
+        };
+
+        if let Some(extra) = self.lexer.next() {
+            self.error(&extra.position, format!("unexpected '{}'", extra.value));
+        }
+
+        self.lexer = outer;
+
+        expr
+    }
+
+    fn visit_group(&mut self) -> Option<Expression> {
+        let first = self.lexer.next()?;
+        let expr = self.parse_expr(&first);
+
+        if let Some(cparen) = self.lexer.next() {
+            if cparen.kind != TokenType::Cparen {
+                self.error(
+                    &cparen.position,
+                    format!("expected ')' found '{}'", cparen.value),
+                );
+            }
+        }
+
+        expr
+    }
+
+    fn visit_condition(&mut self) -> Option<Expression> {
+        let first = self.lexer.next()?;
+        let expr = self.parse_expr(&first)?;
+
+        let next = self.lexer.peek_token()?;
+        if next.kind != TokenType::Ocurly {
+            self.error(
+                &next.position,
+                format!("expected '{{' found '{}'", next.value),
+            );
+            return None;
+        }
+
+        let span = expr.span();
+        let found = expr.type_name();
+        let condition = match self.visit_boolean_expr(expr.clone()) {
+            Some(condition) if matches!(found.as_str(), "bool" | "any" | UNKNOWN_TYPE) => condition,
+            condition => {
+                // keep parsing the body so the error doesn't cascade into the enclosing block
+                self.error_spanning(
+                    &first.position,
+                    span,
+                    format!("expected 'bool' condition found '{found}'"),
+                );
+                condition.unwrap_or(expr)
+            }
+        };
+
+        let _ocurly = self.lexer.next()?;
+        Some(condition)
+    }
+
+    fn visit_if_statement(&mut self, token: &Token) -> Option<Expression> {
+        let boolean_expr = self.visit_condition()?;
+        let statements = self.visit_branch();
+
+        let mut else_branch = None;
+        if let Some(TokenType::Else) = self.lexer.peek_token().map(|t| t.kind) {
+            let else_token = self.lexer.next()?;
+            let next = self.lexer.next()?;
+
+            let statements = match next.kind {
+                TokenType::If => vec![self.visit_if_statement(&next)?],
+                TokenType::Ocurly => self.visit_branch(),
+                _ => {
+                    self.error(
+                        &next.position,
+                        format!("expected '{{' or 'if' found '{}'", next.value),
+                    );
+                    return None;
+                }
+            };
+
+            else_branch = Some(ElseNode {
+                statements,
+                position: else_token.position,
+            });
+        }
+
+        let if_node = IfNode {
+            value: Box::new(boolean_expr),
+            statements,
+            else_branch,
+            position: token.position.clone(),
+            span: self.span_since(&token.position),
+        };
+
+        Some(Expression::IfStatement(if_node))
+    }
+
+    fn check_if_value(&mut self, expr: &Expression) {
+        let Expression::IfStatement(first) = expr else {
+            return;
+        };
+
+        let expected = expr.type_name();
+        let mut if_node = first;
+
+        loop {
+            self.check_branch_type(&if_node.statements, &expected, &if_node.position);
+
+            let Some(else_node) = &if_node.else_branch else {
+                let mut diagnostic = Diagnostic::error(
+                    Some(if_node.position.clone()),
+                    String::from("'if' used as a value must have an 'else' branch"),
+                );
+                diagnostic.note = Some(String::from(
+                    "without 'else' there is no value when the condition is false",
+                ));
+                self.diagnostics.push(diagnostic);
+                return;
+            };
+
+            match else_node.statements.as_slice() {
+                [Expression::IfStatement(nested)] => if_node = nested,
+                statements => {
+                    self.check_branch_type(statements, &expected, &else_node.position);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn check_branch_type(
+        &mut self,
+        statements: &[Expression],
+        expected: &str,
+        fallback: &Position,
+    ) {
+        let Some(last) = statements.last() else {
+            return;
+        };
+
+        let found = last.type_name();
+        let known = |t: &str| t != UNKNOWN_TYPE && t != "any" && t != "None";
+        if known(expected) && known(&found) && expected != found {
+            let position = last.position().unwrap_or(fallback).clone();
+            let mut diagnostic = Diagnostic::error(
+                Some(position),
+                format!("'if' and 'else' have incompatible types, expected '{expected}' found '{found}'"),
+            )
+            .with_span(last.span());
+            diagnostic.note = Some(String::from(
+                "every branch of an 'if' used as a value must produce the same type",
+            ));
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn visit_branch(&mut self) -> Vec<Expression> {
+        let scope_start = self.variables.len();
+        let mut statements = Vec::new();
+
+        while let Some(next) = self.lexer.next() {
+            if let TokenType::Ccurly = next.kind {
+                break;
+            } else if let TokenType::Semicolon = next.kind {
+                continue;
+            }
+
+            let diagnostics = self.diagnostics.len();
+            if let Some(expr) = self.parse_expr(&next) {
+                statements.push(expr);
+            } else {
+                statements.push(self.recover(next, diagnostics));
+            }
+        }
+
+        self.variables.truncate(scope_start);
+
+        statements
+    }
+
+    fn visit_while_statement(&mut self, token: &Token) -> Option<Expression> {
+        let boolean_expr = self.visit_condition()?;
+        let scope_start = self.variables.len();
+        let mut statements = Vec::new();
+
+        self.loop_depth += 1;
+
+        while let Some(next) = self.lexer.next() {
+            if let TokenType::Ccurly = next.kind {
+                break;
+            } else if let TokenType::Semicolon = next.kind {
+                continue;
+            }
+
+            let diagnostics = self.diagnostics.len();
+            if let Some(expr) = self.parse_expr(&next) {
+                statements.push(expr.clone());
+            } else {
+                statements.push(self.recover(next, diagnostics));
+            }
+        }
+
+        self.loop_depth -= 1;
+        self.variables.truncate(scope_start);
+
+        let while_node = WhileNode {
+            value: Box::new(boolean_expr),
+            statements,
+            position: token.position.clone(),
+            span: self.span_since(&token.position),
+        };
+
+        Some(Expression::WhileStatement(while_node))
+    }
+
+    fn visit_for_loop(&mut self, token: &Token) -> Option<Expression> {
+        if let Some(counter_token) = self.lexer.next() {
+            let in_token = self.lexer.next()?;
+            if in_token.kind != TokenType::In {
+                self.error(
+                    &in_token.position,
+                    format!("expected 'in' found '{}'", in_token.value),
+                );
+                return None;
+            }
+
+            let start_token = self.lexer.next()?;
+
+            if let Some(start) = self.parse_expr(&start_token) {
+                let (range, counter) = match self.lexer.peek_token().map(|t| t.kind) {
+                    Some(TokenType::Range | TokenType::RangeInclusive) => {
+                        let range_op = self.lexer.next()?;
+                        let start = Box::new(start);
+
+                        let counter = self.make_variable(
+                            counter_token.value.into_owned(),
+                            "i32".to_string(),
+                            start.clone(),
+                        );
+
+                        let end_token = self.lexer.next()?;
+                        let end = Box::new(self.parse_expr(&end_token)?);
+
+                        let span = self.span_since(&start_token.position);
+                        let range_node = RangeNode {
+                            start,
+                            end,
+                            inclusive: range_op.kind == TokenType::RangeInclusive,
+                            span,
+                        };
+
+                        (Expression::RangeStatement(range_node), counter)
+                    }
+                    _ => {
+                        let type_name = start.type_name();
+                        let Some(item_type) = element_type(&type_name) else {
+                            let mut diagnostic = Diagnostic::error(
+                                Some(start_token.position.clone()),
+                                format!("values of type '{type_name}' cannot be iterated"),
+                            );
+                            diagnostic.note =
+                                Some(String::from("loop over a range such as '0..n' instead"));
+                            self.diagnostics.push(diagnostic);
+                            return None;
+                        };
+
+                        let value = self.default_initialize_value(item_type.clone());
+                        let counter = self.make_variable(
+                            counter_token.value.into_owned(),
+                            item_type,
+                            Box::new(value),
+                        );
+
+                        (start, counter)
+                    }
+                };
+
+                let scope_start = self.variables.len();
+                self.variables.push(counter.clone());
+
+                if let Some(ocurly) = self.lexer.next() {
+                    if ocurly.kind != TokenType::Ocurly {
+                        self.error(
+                            &ocurly.position,
+                            format!("expected '{{' found '{}'", ocurly.value),
+                        );
+                        self.variables.truncate(scope_start);
+                        return None;
+                    }
+
+                    let mut statements = Vec::new();
+
+                    self.loop_depth += 1;
+
+                    while let Some(next) = self.lexer.next() {
+                        if let TokenType::Ccurly = next.kind {
+                            break;
+                        } else if let TokenType::Semicolon = next.kind {
+                            continue;
+                        }
+
+                        let diagnostics = self.diagnostics.len();
+                        if let Some(statement) = self.parse_expr(&next) {
+                            statements.push(statement);
+                        } else {
+                            statements.push(self.recover(next, diagnostics));
+                        }
+                    }
+
+                    self.loop_depth -= 1;
+
+                    let for_node = ForNode {
+                        counter,
+                        range: Box::new(range),
+                        statements,
+                        position: token.position.clone(),
+                        span: self.span_since(&token.position),
+                    };
+
+                    self.variables.truncate(scope_start);
+
+                    return Some(Expression::ForLoop(for_node));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn visit_boolean_expr(&mut self, expr: Expression) -> Option<Expression> {
+        match expr.clone() {
+            Expression::FunCall(fun_call_node) => {
+                if let Some(return_type) = fun_call_node.proc_def.return_type {
+                    if return_type == "bool" || return_type == "any" {
+                        return self.visit_binary_op(Some(expr));
+                    }
+                }
+
+                None
+            }
+            Expression::ClosureCall(ref closure_call_node) => {
+                if let Some("bool" | "any") = closure_call_node.return_type.as_deref() {
+                    return self.visit_binary_op(Some(expr));
+                }
+
+                None
+            }
+            Expression::Variable(variable_node) => {
+                if matches!(
+                    variable_node.metadata.type_name.as_str(),
+                    "bool" | "any" | UNKNOWN_TYPE
+                ) {
+                    return self.visit_binary_op(Some(expr));
+                }
+
+                None
+            }
+            Expression::StructFieldAccess(..)
+            | Expression::Contains(..)
+            | Expression::MapCall(..)
+            | Expression::VecCall(..) => self.visit_binary_op(Some(expr)),
+            Expression::BinaryOp(..)
+            | Expression::Pipe(..)
+            | Expression::Coalesce(..)
+            | Expression::CompareChain(..) => Some(expr),
+            Expression::Literal(..) => self.visit_binary_op(Some(expr)),
+            _ => None,
+        }
+    }
+
+    fn visit_let_statement(&mut self, token: &Token) -> Option<Expression> {
+        let mut mutable = false;
+        if let Some(TokenType::Mut) = self.lexer.peek_token().map(|t| t.kind) {
+            let _mut = self.lexer.next();
+            mutable = true;
+        }
+
+        if let Some(ident) = self.lexer.next() {
+            if let Some(next) = self.lexer.next() {
+                let mut type_hint = None;
+
+                if let TokenType::Colon = next.kind {
+                    if let Some(TokenType::Ident | TokenType::Proc) =
+                        self.lexer.peek_token().map(|t| t.kind)
+                    {
+                        type_hint = self.visit_type();
+                    } else {
+                        let _type_name = self.lexer.next()?;
+                    }
+
+                    let _equal_op = self.lexer.next()?;
+                }
+
+                let first = self.lexer.next()?;
+
+                if let Some(value) = self.parse_expr(&first) {
+                    self.check_if_value(&value);
+
+                    let name = ident.value.into_owned();
+                    let mut value = Box::new(value);
+
+                    let mut kind_str = match first.kind {
+                        TokenType::Interpolation => String::from("String"),
+                        _ => value.type_name(),
+                    };
+
+                    if kind_str == UNKNOWN_TYPE && type_hint.is_none() && !self.uses_unknown(&value)
+                    {
+                        let mut diagnostic = Diagnostic::warning(
+                            Some(first.position.clone()),
+                            format!("cannot infer the type of '{name}'"),
+                        );
+                        diagnostic.note = Some(format!(
+                            "add a type annotation, e.g. 'let {name}: i32 = ...'"
+                        ));
+                        self.diagnostics.push(diagnostic);
+                    }
+
+                    let annotated = type_hint.is_some();
+
+                    if let (Expression::MapNew(map_new_node), Some(hint)) =
+                        (value.as_mut(), &type_hint)
+                    {
+                        if map_new_node.type_name == UNKNOWN_TYPE && map_types(hint).is_some() {
+                            map_new_node.type_name = hint.clone();
+                            kind_str = hint.clone();
+                        } else if map_new_node.type_name == UNKNOWN_TYPE && hint != "any" {
+                            self.error(&first.position, format!("expected '{hint}' found 'Map'"));
+                        }
+                    }
+
+                    if let (Expression::VecNew(vec_new_node), Some(hint)) =
+                        (value.as_mut(), &type_hint)
+                    {
+                        if !vec_new_node.explicit && vec_type(hint).is_some() {
+                            vec_new_node.type_name = hint.clone();
+                            kind_str = hint.clone();
+                        } else if !vec_new_node.explicit && hint != "any" {
+                            self.error(&first.position, format!("expected '{hint}' found 'Vec'"));
+                            kind_str = hint.clone();
+                        }
+                    }
+
+                    if let Some(hint) = type_hint {
+                        if kind_str == UNKNOWN_TYPE {
+                            kind_str = hint;
+                        } else if kind_str == "any" {
+                            self.check_any_flow(&hint, &first.position);
+                            kind_str = hint;
+                        } else if hint == "any" || self.implements(&hint, &kind_str) {
+                            kind_str = hint;
+                        } else if kind_str != hint {
+                            self.error_spanning(
+                                &first.position,
+                                value.span(),
+                                format!("expected '{hint}' found '{kind_str}'"),
+                            );
+                        }
+                    }
+
+                    let mut variable =
+                        self.make_variable(name.clone(), kind_str.clone(), value.clone());
+                    variable.metadata.mutable = mutable;
+                    self.variables.push(variable);
+
+                    let let_node = LetNode {
+                        name,
+                        type_name: kind_str,
+                        mutable,
+                        annotated,
+                        value,
+                        position: token.position.clone(),
+                        span: self.span_since(&token.position),
+                    };
+
+                    return Some(Expression::LetStatement(let_node));
+                }
+
+                // the initializer reported its own error, bind the name so later uses stay quiet
+                let type_name = type_hint.unwrap_or_else(|| String::from(UNKNOWN_TYPE));
+                let value = Box::new(Expression::Error(first.position));
+                let mut variable = self.make_variable(ident.value.into_owned(), type_name, value);
+                variable.metadata.mutable = mutable;
+                self.variables.push(variable);
+            }
+        }
+
+        None
+    }
+
+    fn visit_impl_block(&mut self, token: &Token) -> Option<Expression> {
+        if let Some(mut type_name) = self.lexer.next() {
+            let mut trait_def = None;
+            if let Some(TokenType::For) = self.lexer.peek_token().map(|t| t.kind) {
+                let _for = self.lexer.next()?;
+
+                trait_def = self.find_trait(&type_name.value).cloned();
+                if trait_def.is_none() {
+                    self.error(
+                        &type_name.position,
+                        format!("cannot implement unknown trait '{}'", type_name.value),
+                    );
+                }
+
+                type_name = self.lexer.next()?;
+            }
+
+            if let Some(struct_def) = self
+                .structs
+                .iter()
+                .find(|&s| s.type_name == type_name.value)
+                .cloned()
+            {
+                let ocurly = self.lexer.next()?;
+                if ocurly.kind != TokenType::Ocurly {
+                    self.error(
+                        &ocurly.position,
+                        format!("expected '{{' found '{}'", ocurly.value),
+                    );
+                    return None;
+                }
+
+                if let Some(trait_def) = &trait_def {
+                    if self.implements(&trait_def.name, &struct_def.type_name) {
+                        self.error(
+                            &type_name.position,
+                            format!(
+                                "'{}' is already implemented for '{}'",
+                                trait_def.name, struct_def.type_name
+                            ),
+                        );
+                    }
+                }
+
+                let index = self.impl_blocks.len();
+                self.impl_blocks.push(ImplNode {
+                    procedures: Vec::new(),
+                    struct_def: struct_def.clone(),
+                    trait_name: trait_def.as_ref().map(|t| t.name.clone()),
+                    derived: false,
+                    position: token.position.clone(),
+                    span: self.span_since(&token.position),
+                });
+
+                self.impl_type = Some(struct_def.type_name.clone());
+
+                while let Some(next) = self.lexer.next() {
+                    if let TokenType::Ccurly = next.kind {
+                        break;
+                    } else if let TokenType::Semicolon = next.kind {
+                        continue;
+                    }
+
+                    if let TokenType::Proc = next.kind {
+                        if let Some(proc_def_node) = self.parse_expr(&next) {
+                            if let Expression::ProcDef(proc_def) = &proc_def_node {
+                                self.check_duplicate_method(&struct_def, proc_def);
+                            }
+
+                            self.impl_blocks[index].procedures.push(proc_def_node);
+                        }
+                    } else {
+                        self.error(
+                            &next.position,
+                            format!("expected 'proc' found '{}'", next.value),
+                        );
+                        self.synchronize();
+                    }
+                }
+
+                self.impl_type = None;
+
+                let impl_node = self.impl_blocks[index].clone();
+                if let Some(trait_def) = &trait_def {
+                    self.check_trait_impl(trait_def, &impl_node);
+                }
+
+                return Some(Expression::ImplStatement(impl_node));
+            } else if self.enums.iter().any(|e| e.type_name == type_name.value) {
+                self.error(
+                    &type_name.position,
+                    format!(
+                        "impl blocks are only supported for structs, '{}' is an enum",
+                        type_name.value
+                    ),
+                );
+            } else {
+                self.error(
+                    &type_name.position,
+                    format!("cannot implement unknown type '{}'", type_name.value),
+                );
+            }
+        }
+
+        None
+    }
+
+    fn check_trait_impl(&mut self, trait_def: &TraitDefNode, impl_node: &ImplNode) {
+        let type_name = &impl_node.struct_def.type_name;
+        let procedures: Vec<&ProcDefNode> = impl_node
+            .procedures
+            .iter()
+            .filter_map(|p| match p {
+                Expression::ProcDef(proc_def) => Some(proc_def),
+                _ => None,
+            })
+            .collect();
+
+        let mut missing = Vec::new();
+        for method in trait_def.methods.iter() {
+            let Some(proc_def) = procedures.iter().find(|p| p.name == method.name) else {
+                missing.push(format!("'{}'", method.name));
+                continue;
+            };
+
+            let expected = ProcType {
+                args: method
+                    .args
+                    .iter()
+                    .skip(1)
+                    .map(|a| a.type_name.clone())
+                    .collect(),
+                return_type: method.return_type.clone(),
+            };
+            let found = ProcType {
+                args: proc_def
+                    .args
+                    .iter()
+                    .skip(1)
+                    .map(|a| a.type_name.clone())
+                    .collect(),
+                return_type: proc_def.return_type.clone(),
+            };
+
+            let receiver = |p: &ProcDefNode| match p.args.first() {
+                Some(arg) if arg.name == "self" && arg.mutable => "mut self",
+                Some(arg) if arg.name == "self" => "self",
+                _ => "no 'self'",
+            };
+
+            if receiver(method) != receiver(proc_def) {
+                self.error(
+                    &proc_def.position,
+                    format!(
+                        "'{type_name}::{}' must take '{}' to implement '{}'",
+                        proc_def.name,
+                        receiver(method),
+                        trait_def.name
+                    ),
+                );
+            } else if expected.to_string() != found.to_string() {
+                let mut diagnostic = Diagnostic::error(
+                    Some(proc_def.position.clone()),
+                    format!(
+                        "'{type_name}::{}' does not match its declaration in '{}'",
+                        proc_def.name, trait_def.name
+                    ),
+                );
+                diagnostic.note = Some(format!(
+                    "expected '{expected}' found '{found}', not counting 'self'"
+                ));
+                self.diagnostics.push(diagnostic);
+            } else if let Some(trait_def) = self.find_trait(&trait_def.name) {
+                trait_def.dispatch.insert(type_name, (*proc_def).clone());
+            }
+        }
+
+        for proc_def in procedures.iter() {
+            if !trait_def.methods.iter().any(|m| m.name == proc_def.name) {
+                let mut diagnostic = Diagnostic::error(
+                    Some(proc_def.position.clone()),
+                    format!(
+                        "'{}' is not a method of trait '{}'",
+                        proc_def.name, trait_def.name
+                    ),
+                );
+                diagnostic.note = Some(format!("move it into a separate 'impl {type_name}' block"));
+                self.diagnostics.push(diagnostic);
+            }
+        }
+
+        if !missing.is_empty() {
+            let mut diagnostic = Diagnostic::error(
+                Some(impl_node.position.clone()),
+                format!(
+                    "impl of '{}' for '{type_name}' is missing {}",
+                    trait_def.name,
+                    missing.join(", ")
+                ),
+            );
+            diagnostic.note = Some(String::from(
+                "every method declared by the trait must be implemented",
+            ));
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn implements(&self, trait_name: &str, type_name: &str) -> bool {
+        self.impl_blocks.iter().any(|i| {
+            i.trait_name.as_deref() == Some(trait_name) && i.struct_def.type_name == type_name
+        })
+    }
+
+    fn find_trait(&self, name: &str) -> Option<&TraitDefNode> {
+        self.traits.iter().rev().find(|t| t.name == name)
+    }
+
+    fn visit_trait_def(&mut self) -> Option<Expression> {
+        let docs = self.lexer.doc_comment();
+        let ident = self.lexer.next()?;
+
+        let ocurly = self.lexer.next()?;
+        if ocurly.kind != TokenType::Ocurly {
+            self.error(
+                &ocurly.position,
+                format!("expected '{{' found '{}'", ocurly.value),
+            );
+            return None;
+        }
+
+        let impl_type = self.impl_type.replace(ident.value.to_string());
+
+        let mut methods: Vec<ProcDefNode> = Vec::new();
+        while let Some(next) = self.lexer.next() {
+            if let TokenType::Ccurly = next.kind {
+                break;
+            } else if let TokenType::Semicolon = next.kind {
+                continue;
+            }
+
+            if next.kind != TokenType::Proc {
+                self.error(
+                    &next.position,
+                    format!("expected 'proc' found '{}'", next.value),
+                );
+                self.synchronize();
+                continue;
+            }
+
+            if let Some(method) = self.visit_trait_method(&ident.value) {
+                if methods.iter().any(|m| m.name == method.name) {
+                    self.error(
+                        &method.position,
+                        format!(
+                            "'{}' already has a method named '{}'",
+                            ident.value, method.name
+                        ),
+                    );
+                } else {
+                    methods.push(method);
+                }
+            }
+        }
+
+        self.impl_type = impl_type;
+
+        let trait_def = TraitDefNode {
+            name: ident.value.into_owned(),
+            methods,
+            dispatch: DispatchTable::default(),
+            docs,
+            span: self.span_since(&ident.position),
+            position: ident.position,
+        };
+
+        self.traits.push(trait_def.clone());
+
+        Some(Expression::TraitDef(trait_def))
+    }
+
+    fn visit_trait_method(&mut self, trait_name: &str) -> Option<ProcDefNode> {
+        let docs = self.lexer.doc_comment();
+        let ident = self.lexer.next()?;
+
+        let oparen = self.lexer.next()?;
+        if oparen.kind != TokenType::Oparen {
+            self.error(
+                &oparen.position,
+                format!("expected '(' found '{}'", oparen.value),
+            );
+            self.synchronize();
+            return None;
+        }
+
+        let scope_start = self.variables.len();
+        let mut args = Vec::new();
+        self.visit_args(&mut args, TokenType::Cparen);
+        self.variables.truncate(scope_start);
+
+        let mut return_type = None;
+        if let Some(TokenType::Colon) = self.lexer.peek_token().map(|t| t.kind) {
+            let _colon = self.lexer.next()?;
+            return_type = Some(self.visit_type()?);
+        }
+
+        if let Some(ocurly) = self
+            .lexer
+            .peek_token()
+            .filter(|t| t.kind == TokenType::Ocurly)
+        {
+            let mut diagnostic = Diagnostic::error(
+                Some(ocurly.position.clone()),
+                String::from("trait methods cannot have a body"),
+            );
+            diagnostic.note = Some(format!(
+                "move the body into an 'impl {trait_name} for ...' block"
+            ));
+            self.diagnostics.push(diagnostic);
+            self.synchronize();
+        }
+
+        if args.first().map(|a| a.name.as_str()) != Some("self") {
+            self.error(
+                &ident.position,
+                format!(
+                    "trait method '{}' must take 'self' as its first parameter",
+                    ident.value
+                ),
+            );
+            return None;
+        }
+
+        Some(ProcDefNode {
+            name: ident.value.into_owned(),
+            return_type,
+            args,
+            statements: Vec::new(),
+            contracts: Vec::new(),
+            native: false,
+            generics: Vec::new(),
+            template: None,
+            docs,
+            span: self.span_since(&ident.position),
+            position: ident.position,
+        })
+    }
+
+    fn check_duplicate_method(&mut self, struct_def: &StructDefNode, proc_def: &ProcDefNode) {
+        let exists = self
+            .impl_blocks
+            .iter()
+            .filter(|i| i.struct_def.type_name == struct_def.type_name)
+            .flat_map(|i| i.procedures.iter())
+            .any(|p| matches!(p, Expression::ProcDef(p) if p.name == proc_def.name));
+
+        if !exists {
+            return;
+        }
+
+        let mut diagnostic = Diagnostic::error(
+            Some(proc_def.position.clone()),
+            format!(
+                "'{}' already has a method named '{}'",
+                struct_def.type_name, proc_def.name
+            ),
+        );
+        if let Some(derive) = struct_def
+            .derives
+            .iter()
+            .find(|d| derived_method(d) == proc_def.name)
+        {
+            diagnostic.note = Some(format!("it is generated by '@derive({derive})'"));
+        }
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn visit_return_statement(&mut self, token: &Token) -> Option<Expression> {
+        if let Some(first) = self.lexer.next() {
+            if let Some(return_value) = self.parse_expr(&first) {
+                self.check_if_value(&return_value);
+
+                let return_node = ReturnNode {
+                    value: Box::new(return_value),
+                    implicit: false,
+                    position: token.position.clone(),
+                    span: self.span_since(&token.position),
+                };
+
+                return Some(Expression::ReturnStatement(return_node));
+            }
+        }
+
+        None
+    }
+
+    fn visit_loop_control(&mut self, token: &Token) -> Option<Expression> {
+        if self.loop_depth == 0 {
+            self.error(
+                &token.position,
+                format!("'{}' outside of a loop", token.value),
+            );
+        }
+
+        if let TokenType::Break = token.kind {
+            return Some(Expression::BreakStatement(token.span));
+        }
+
+        Some(Expression::ContinueStatement(token.span))
+    }
+
+    fn visit_attributes(&mut self, token: &Token) -> Option<Expression> {
+        let mut attributes = Attributes {
+            docs: self.lexer.doc_comment(),
+            flags: false,
+            derives: Vec::new(),
+            contracts: Vec::new(),
+        };
+
+        let mut next = token.clone();
+        while next.kind == TokenType::At {
+            let name = self.lexer.next()?;
+            let kind = match name.value.as_ref() {
+                "requires" => ContractKind::Requires,
+                "ensures" => ContractKind::Ensures,
+                "flags" => {
+                    attributes.flags = true;
+                    next = self.lexer.next()?;
+                    continue;
+                }
+                "derive" => {
+                    attributes.derives.extend(self.visit_derive_list(&name)?);
+                    next = self.lexer.next()?;
+                    continue;
+                }
+                _ => {
+                    self.error(
+                        &name.position,
+                        format!("unknown attribute '@{}'", name.value),
+                    );
+                    return None;
+                }
+            };
+
+            let oparen = self.lexer.next()?;
+            if oparen.kind != TokenType::Oparen {
+                self.error(
+                    &oparen.position,
+                    format!(
+                        "expected '(' after '@{}' found '{}'",
+                        name.value, oparen.value
+                    ),
+                );
+                return None;
+            }
+
+            let Some((source, position)) = self.lexer.group_source() else {
+                self.error(&oparen.position, String::from("unclosed '(' in attribute"));
+                return None;
+            };
+
+            attributes.contracts.push((kind, source, position));
+            next = self.lexer.next()?;
+        }
+
+        if let Some(derive) = attributes.derives.first() {
+            if next.kind != TokenType::Struct {
+                self.error(
+                    &derive.position,
+                    String::from("'@derive' can only be applied to a struct"),
+                );
+            }
+        }
+
+        if next.kind == TokenType::Struct {
+            if let Some((_, _, position)) = attributes.contracts.first() {
+                self.error(
+                    position,
+                    String::from("contracts can only be attached to procedures"),
+                );
+            }
+
+            if attributes.flags {
+                self.error(
+                    &next.position,
+                    String::from("'@flags' can only be applied to an enum"),
+                );
+            }
+
+            return self.visit_struct_def(attributes);
+        }
+
+        if next.kind == TokenType::Enum {
+            if let Some((_, _, position)) = attributes.contracts.first() {
+                self.error(
+                    position,
+                    String::from("contracts can only be attached to procedures"),
+                );
+            }
+
+            return self.visit_enum_def(attributes);
+        }
+
+        if next.kind != TokenType::Proc {
+            self.error(
+                &next.position,
+                format!("expected 'proc' after attributes found '{}'", next.value),
+            );
+            return None;
+        }
+
+        if attributes.flags {
+            self.error(
+                &next.position,
+                String::from("'@flags' can only be applied to an enum"),
+            );
+        }
+
+        self.visit_procedure_def(attributes)
+    }
+
+    fn visit_derive_list(&mut self, name: &Token) -> Option<Vec<Token<'src>>> {
+        let oparen = self.lexer.next()?;
+        if oparen.kind != TokenType::Oparen {
+            self.error(
+                &oparen.position,
+                format!(
+                    "expected '(' after '@{}' found '{}'",
+                    name.value, oparen.value
+                ),
+            );
+            return None;
+        }
+
+        let mut derives = Vec::new();
+        while let Some(next) = self.lexer.next() {
+            match next.kind {
+                TokenType::Cparen => return Some(derives),
+                TokenType::Comma => {}
+                TokenType::Ident if DERIVES.contains(&next.value.as_ref()) => derives.push(next),
+                _ => self.error(
+                    &next.position,
+                    format!(
+                        "cannot derive '{}', expected one of: {}",
+                        next.value,
+                        DERIVES.join(", ")
+                    ),
+                ),
+            }
+        }
+
+        self.error(&oparen.position, String::from("unclosed '(' in attribute"));
+        None
+    }
+
+    fn visit_contracts(
+        &mut self,
+        attributes: &Attributes<'src>,
+        kind: ContractKind,
+        contracts: &mut Vec<ContractNode>,
+    ) {
+        for (_, source, position) in attributes.contracts.iter().filter(|c| c.0 == kind) {
+            let condition = self.parse_embedded_expr(source.clone(), position, "contract");
+
+            if let Some(condition) = condition {
+                contracts.push(ContractNode {
+                    kind,
+                    condition,
+                    source: String::from(source.trim()),
+                    position: position.clone(),
+                });
+            }
+        }
+    }
+
+    fn visit_procedure_def(&mut self, attributes: Attributes<'src>) -> Option<Expression> {
+        type TT = TokenType;
+
+        let docs = self.lexer.doc_comment().or(attributes.docs.clone());
+
+        if let Some(ident) = self.lexer.next() {
+            let mut args = Vec::new();
+            let mut statements = Vec::new();
+
+            let instance_name = self.instance_name.take();
+            let mut generics = self.visit_generics()?;
+            if instance_name.is_some() {
+                generics.clear();
+            }
+
+            let generic_params = self.generic_params.len();
+            self.generic_params.extend(generics.iter().cloned());
+
+            if let Some(_oparen) = self.lexer.next() {
+                let scope_start = self.variables.len();
+                let loop_depth = std::mem::take(&mut self.loop_depth);
+
+                // args
+                self.visit_args(&mut args, TT::Cparen);
+
+                let mut contracts = Vec::new();
+                self.visit_contracts(&attributes, ContractKind::Requires, &mut contracts);
+
+                let mut return_type = None;
+
+                // statements
+                if let Some(n) = self.lexer.next() {
+                    match n.kind {
+                        TT::Colon => {
+                            return_type = Some(self.visit_type()?);
+
+                            let _ocurly = self.lexer.next()?;
+                            statements = self.visit_proc_body(true);
+                        }
+                        TT::Ocurly => statements = self.visit_proc_body(false),
+                        _ => {
+                            // parsing a body here would nest every following definition inside this one
+                            self.error(
+                                &n.position,
+                                format!("expected ':' or '{{' found '{}'", n.value),
+                            );
+                            self.synchronize();
+                        }
+                    }
+                }
+
+                if attributes
+                    .contracts
+                    .iter()
+                    .any(|c| c.0 == ContractKind::Ensures)
+                {
+                    let metadata = VarMetadataNode {
+                        name: String::from("result"),
+                        type_name: return_type.clone().unwrap_or(String::from("None")),
+                        mutable: false,
+                    };
+
+                    let value = self.default_initialize_value(metadata.type_name.clone());
+                    self.variables.push(VariableNode {
+                        metadata,
+                        value: Box::new(value),
+                        span: Span::default(),
+                    });
+
+                    self.visit_contracts(&attributes, ContractKind::Ensures, &mut contracts);
+                }
+
+                if self.options.release {
+                    contracts.clear();
+                }
+
+                self.loop_depth = loop_depth;
+                self.variables.truncate(scope_start);
+                self.generic_params.truncate(generic_params);
+
+                let template = if generics.is_empty() {
+                    None
+                } else {
+                    Some(self.lexer.source_since(&ident.position))
+                };
+
+                let proc_def_node = ProcDefNode {
+                    name: instance_name.unwrap_or(ident.value.into_owned()),
+                    return_type,
+                    args,
+                    statements,
+                    contracts,
+                    native: false,
+                    generics,
+                    template,
+                    docs,
+                    position: ident.position.clone(),
+                    span: self.span_since(&ident.position),
+                };
+
+                self.procedures.push(proc_def_node.clone());
+
+                return Some(Expression::ProcDef(proc_def_node));
+            }
+        }
+
+        None
+    }
+
+    fn visit_generics(&mut self) -> Option<Vec<String>> {
+        let mut generics = Vec::new();
+
+        if let Some(TokenType::Lt) = self.lexer.peek_token().map(|t| t.kind) {
+            let _lt = self.lexer.next()?;
+
+            while let Some(param) = self.lexer.next() {
+                match param.kind {
+                    TokenType::Gt => break,
+                    TokenType::Comma => continue,
+                    TokenType::Ident if !generics.iter().any(|g| *g == param.value) => {
+                        generics.push(param.value.into_owned())
+                    }
+                    TokenType::Ident => self.error(
+                        &param.position,
+                        format!("type parameter '{}' is declared twice", param.value),
+                    ),
+                    _ => {
+                        self.error(
+                            &param.position,
+                            format!("expected type parameter found '{}'", param.value),
+                        );
+                        return None;
+                    }
+                }
+            }
+        }
+
+        Some(generics)
+    }
+
+    fn visit_type_args(&mut self) -> Option<Vec<String>> {
+        let _lt = self.lexer.next()?;
+
+        let mut args = Vec::new();
+        loop {
+            match self.lexer.peek_token()?.kind {
+                TokenType::Gt => {
+                    let _gt = self.lexer.next()?;
+                    break;
+                }
+                TokenType::Comma => {
+                    let _comma = self.lexer.next()?;
+                }
+                _ => args.push(self.visit_type()?),
+            }
+        }
+
+        Some(args)
+    }
+
+    fn visit_proc_body(&mut self, implicit_return: bool) -> Vec<Expression> {
+        type TT = TokenType;
+
+        let mut statements = Vec::new();
+        self.proc_depth += 1;
+
+        while let Some(next) = self.lexer.next() {
+            if let TT::Ccurly = next.kind {
+                break;
+            } else if let TT::Semicolon = next.kind {
+                continue;
+            }
+
+            let diagnostics = self.diagnostics.len();
+            if let Some(expr) = self.parse_expr(&next) {
+                if implicit_return && self.is_trailing(&expr) {
+                    statements.push(Expression::ReturnStatement(ReturnNode {
+                        value: Box::new(expr),
+                        implicit: true,
+                        span: self.span_since(&next.position),
+                        position: next.position,
+                    }));
+                } else {
+                    statements.push(expr);
+                }
+            } else {
+                statements.push(self.recover(next, diagnostics));
+            }
+        }
+
+        self.proc_depth -= 1;
+
+        statements
+    }
+
+    fn visit_closure(&mut self, token: &Token, syntax: ClosureSyntax) -> Option<Expression> {
+        let name = match syntax {
+            ClosureSyntax::Named => self.lexer.next()?,
+            _ => token.clone(),
+        };
+
+        if let Some(lt) = self.lexer.peek_token().filter(|t| t.kind == TokenType::Lt) {
+            // a nested procedure is a closure value, which has a single concrete type
+            let _generics = self.visit_generics()?;
+            let mut diagnostic = Diagnostic::error(
+                Some(lt.position),
+                format!(
+                    "generic procedure '{}' must be defined at the top level",
+                    name.value
+                ),
+            );
+            diagnostic.note = Some(String::from("define it outside of any other procedure"));
+            self.diagnostics.push(diagnostic);
+            return None;
+        }
+
+        let close = match syntax {
+            ClosureSyntax::Pipe => TokenType::BitOr,
+            _ => {
+                let _oparen = self.lexer.next()?;
+                TokenType::Cparen
+            }
+        };
+
+        let scope_start = self.variables.len();
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        self.closures.push(ClosureScope {
+            scope_start,
+            captures: Vec::new(),
+        });
+
+        let mut args = Vec::new();
+        self.visit_args(&mut args, close);
+
+        let body = self.visit_closure_body(syntax);
+
+        self.loop_depth = loop_depth;
+        self.variables.truncate(scope_start);
+        let captures = self
+            .closures
+            .pop()
+            .map(|scope| scope.captures)
+            .unwrap_or_default();
+
+        let (return_type, statements) = body?;
+
+        let proc_def = ProcDefNode {
+            name: match syntax {
+                ClosureSyntax::Named => name.value.to_string(),
+                _ => String::from("closure"),
+            },
+            return_type,
+            args,
+            statements,
+            contracts: Vec::new(),
+            native: false,
+            generics: Vec::new(),
+            template: None,
+            docs: None,
+            position: name.position.clone(),
+            span: self.span_since(&name.position),
+        };
+
+        let closure_node = ClosureNode {
+            proc_def,
+            captures,
+            syntax,
+        };
+
+        if syntax != ClosureSyntax::Named {
+            return Some(Expression::Closure(closure_node));
+        }
+
+        let type_name = ProcType::of(&closure_node.proc_def).to_string();
+        let value = Box::new(Expression::Closure(closure_node));
+
+        let variable = self.make_variable(name.value.to_string(), type_name.clone(), value.clone());
+        self.variables.push(variable);
+
+        Some(Expression::LetStatement(LetNode {
+            name: name.value.into_owned(),
+            type_name,
+            mutable: false,
+            annotated: true,
+            value,
+            position: token.position.clone(),
+            span: self.span_since(&token.position),
+        }))
+    }
+
+    fn visit_closure_body(
+        &mut self,
+        syntax: ClosureSyntax,
+    ) -> Option<(Option<String>, Vec<Expression>)> {
+        type TT = TokenType;
+
+        if syntax != ClosureSyntax::Pipe {
+            let mut return_type = None;
+            if let TT::Colon = self.lexer.next()?.kind {
+                return_type = Some(self.visit_type()?);
+                let _ocurly = self.lexer.next()?;
+            }
+
+            let statements = self.visit_proc_body(return_type.is_some());
+            return Some((return_type, statements));
+        }
+
+        if let Some(TT::Ocurly) = self.lexer.peek_token().map(|t| t.kind) {
+            let _ocurly = self.lexer.next()?;
+            let statements = self.visit_proc_body(true);
+
+            let return_type = match statements.last() {
+                Some(Expression::ReturnStatement(return_node)) if return_node.implicit => {
+                    Some(return_node.value.type_name())
+                }
+                _ => None,
+            };
+
+            return Some((return_type, statements));
+        }
+
+        let next = self.lexer.next()?;
+        let expr = self.parse_expr(&next)?;
+
+        let type_name = expr.type_name();
+        if type_name == "None" {
+            return Some((None, vec![expr]));
+        }
+
+        let return_node = ReturnNode {
+            value: Box::new(expr),
+            implicit: true,
+            span: self.span_since(&next.position),
+            position: next.position,
+        };
+
+        Some((
+            Some(type_name),
+            vec![Expression::ReturnStatement(return_node)],
+        ))
+    }
+
+    fn visit_closure_call(
+        &mut self,
+        variable: VariableNode,
+        piped: Option<Expression>,
+        token: &Token,
+    ) -> Option<Expression> {
+        let proc_type = ProcType::parse(&variable.metadata.type_name);
+
+        let mut args: Vec<Expression> = piped.into_iter().collect();
+        let mut end = token.position.clone();
+
+        if let Some(TokenType::Oparen) = self.lexer.peek_token().map(|t| t.kind) {
+            let _oparen = self.lexer.next()?;
+
+            while let Some(potential_arg) = self.lexer.next() {
+                if potential_arg.kind == TokenType::Cparen {
+                    end = potential_arg.position;
+                    break;
+                } else if potential_arg.kind == TokenType::Comma {
+                    continue;
+                }
+
+                if let Some(value) = self.parse_expr(&potential_arg) {
+                    let expected = proc_type.as_ref().and_then(|p| p.args.get(args.len()));
+                    if let Some(expected) = expected {
+                        self.check_call_arg(
+                            &expected.clone(),
+                            &(args.len() + 1).to_string(),
+                            &variable.metadata.name,
+                            &value,
+                            &potential_arg.position,
+                        );
+                    }
+
+                    args.push(value);
+                }
+            }
+        }
+
+        if let Some(proc_type) = &proc_type {
+            if args.len() != proc_type.args.len() {
+                self.error(
+                    &end,
+                    format!(
+                        "'{}' expects {} argument(s) found {}",
+                        variable.metadata.name,
+                        proc_type.args.len(),
+                        args.len()
+                    ),
+                );
+            }
+        }
+
+        let return_type = match proc_type {
+            Some(proc_type) => proc_type.return_type,
+            None => Some(String::from("any")),
+        };
+
+        Some(Expression::ClosureCall(ClosureCallNode {
+            callee: Box::new(Expression::Variable(variable)),
+            args,
+            return_type,
+            position: token.position.clone(),
+            span: self.span_since(&token.position),
+        }))
+    }
+
+    fn visit_args(&mut self, args: &mut Vec<VarMetadataNode>, close: TokenType) {
+        while let Some(mut ident) = self.lexer.next() {
+            if ident.kind == close {
+                break;
+            } else if let TokenType::Comma = ident.kind {
+                continue;
+            }
+
+            let mutable = ident.kind == TokenType::Mut;
+            if mutable {
+                let Some(next) = self.lexer.next() else {
+                    break;
+                };
+                ident = next;
+            }
+
+            let type_name = if let Some(impl_type) = self.self_param_type(&ident, args) {
+                impl_type
+            } else if let Some(TokenType::Colon) = self.lexer.peek_token().map(|t| t.kind) {
+                let _colon = self.lexer.next();
+                let Some(type_name) = self.visit_type() else {
+                    break;
+                };
+                type_name
+            } else {
+                self.error(
+                    &ident.position,
+                    format!("parameter '{}' needs a type annotation", ident.value),
+                );
+                String::from(UNKNOWN_TYPE)
+            };
+
+            let arg = VarMetadataNode {
+                name: ident.value.into_owned(),
+                type_name: type_name.clone(),
+                mutable,
+            };
+
+            args.push(arg.clone());
+
+            let value = self.default_initialize_value(type_name);
+            let var = VariableNode {
+                metadata: arg,
+                value: Box::new(value),
+                span: self.span_since(&ident.position),
+            };
+
+            self.variables.push(var);
+        }
+    }
+
+    fn visit_type(&mut self) -> Option<String> {
+        let token = self.lexer.next()?;
+        if token.kind != TokenType::Proc {
+            if let Some((_, bound)) = self.type_bindings.iter().find(|(p, _)| *p == token.value) {
+                return Some(bound.clone());
+            }
+
+            if let Some(TokenType::Lt) = self.lexer.peek_token().map(|t| t.kind) {
+                let args = self.visit_type_args()?;
+                let type_name = GenericType {
+                    name: token.value.into_owned(),
+                    args,
+                }
+                .to_string();
+
+                self.instantiate_type(&type_name, &token.position);
+
+                return Some(type_name);
+            }
+
+            return Some(token.value.into_owned());
+        }
+
+        let _oparen = self.lexer.next()?;
+
+        let mut args = Vec::new();
+        loop {
+            match self.lexer.peek_token()?.kind {
+                TokenType::Cparen => {
+                    let _cparen = self.lexer.next()?;
+                    break;
+                }
+                TokenType::Comma => {
+                    let _comma = self.lexer.next()?;
+                }
+                _ => args.push(self.visit_type()?),
+            }
+        }
+
+        let mut return_type = None;
+        if let Some(TokenType::Colon) = self.lexer.peek_token().map(|t| t.kind) {
+            let _colon = self.lexer.next()?;
+            return_type = Some(self.visit_type()?);
+        }
+
+        Some(ProcType { args, return_type }.to_string())
+    }
+
+    fn self_param_type(&mut self, ident: &Token, args: &[VarMetadataNode]) -> Option<String> {
+        if ident.value != "self" {
+            return None;
+        }
+
+        if let Some(TokenType::Colon) = self.lexer.peek_token().map(|t| t.kind) {
+            return None;
+        }
+
+        let impl_type = if let Some(impl_type) = self.impl_type.clone() {
+            impl_type
+        } else {
+            self.error(
+                &ident.position,
+                String::from("'self' parameter is only allowed in impl procedures"),
+            );
+            String::from("None")
+        };
+
+        if !args.is_empty() {
+            self.error(
+                &ident.position,
+                String::from("'self' must be the first parameter"),
+            );
+        }
+
+        Some(impl_type)
+    }
+
+    fn visit_identifier(&mut self, token: &Token) -> Option<Expression> {
+        if let Some(index) = self
+            .variables
+            .iter()
+            .rposition(|v| v.metadata.name == token.value)
+        {
+            let mut variable = self.variables[index].clone();
+            variable.span = token.span;
+            self.capture(index);
+
+            if let Some(next) = self.lexer.peek_token() {
+                if let TokenType::Assign = next.kind {
+                    if let Some(_equal_op) = self.lexer.next() {
+                        self.check_mutable(&variable.metadata, &token.position);
+
+                        let next = self.lexer.next()?;
+
+                        if let Some(expr) = self.parse_expr(&next) {
+                            self.check_if_value(&expr);
+                            let new_value = Box::new(expr);
+
+                            let assign_node = AssignNode {
+                                value: variable.clone(),
+                                new_value,
+                                position: token.position.clone(),
+                                span: self.span_since(&token.position),
+                            };
+
+                            self.skip_semicolon();
+
+                            return Some(Expression::AssignStatement(assign_node));
+                        }
+                    }
+                }
+            }
+
+            let type_name = &variable.metadata.type_name;
+            if self.lexer.character() == '('
+                && (type_name == "any" || type_name.starts_with("proc("))
+            {
+                return self.visit_closure_call(variable, None, token);
+            }
+
+            if self.at_member_access() || self.at_index() {
+                return self.visit_struct_field(&variable);
+            } else {
+                return Some(Expression::Variable(variable));
+            }
+        } else if let Some(proc_def) = self
+            .procedures
+            .iter()
+            .rev()
+            .find(|&f| f.name == token.value)
+            .cloned()
+        {
+            match self.lexer.peek_token().map(|t| t.kind) {
+                Some(TokenType::Oparen) => return self.visit_procedure(&proc_def, token),
+                Some(TokenType::Lt) if !proc_def.generics.is_empty() => {
+                    return self.visit_procedure(&proc_def, token)
+                }
+                _ => {}
+            }
+
+            if !proc_def.generics.is_empty() {
+                self.error(
+                    &token.position,
+                    format!(
+                        "generic procedure '{}' cannot be used as a value",
+                        proc_def.name
+                    ),
+                );
+                return None;
+            }
+
+            let closure_node = ClosureNode {
+                proc_def: ProcDefNode {
+                    position: token.position.clone(),
+                    ..proc_def
+                },
+                captures: Vec::new(),
+                syntax: ClosureSyntax::Reference,
+            };
+
+            return Some(Expression::Closure(closure_node));
+        } else if let Some(struct_def) = self
+            .structs
+            .iter()
+            .find(|&s| s.type_name == token.value)
+            .cloned()
+        {
+            if self.lexer.character() == ':' {
+                if let Some(n) = self.lexer.peek_char() {
+                    if n == ':' {
+                        return self.visit_struct_impl(&struct_def);
+                    }
+                }
+            } else {
+                return self.make_struct_instance(&struct_def, token);
+            }
+        } else if let Some(enum_def) = self
+            .enums
+            .iter()
+            .find(|&e| e.type_name == token.value)
+            .cloned()
+        {
+            return self.visit_enum_variant(&enum_def);
+        } else if (token.value == "Map" || token.value == "Vec")
+            && matches!(
+                self.lexer.peek_token().map(|t| t.kind),
+                Some(TokenType::Lt | TokenType::Oparen)
+            )
+        {
+            return self.visit_collection_new(token);
+        }
+
+        let message = match self.lexer.peek_token().map(|t| t.kind) {
+            Some(TokenType::Oparen) => format!("cannot find procedure '{}'", token.value),
+            _ => format!("expected identifier found '{}'", token.value),
+        };
+        self.error_spanning(&token.position, Some(token.span), message);
+
+        None
+    }
+
+    fn visit_struct_field(&mut self, variable: &VariableNode) -> Option<Expression> {
+        let mut base = Expression::Variable(variable.clone());
+        let mut fields = Vec::new();
+        let mut assignable = true;
+
+        loop {
+            if self.at_index() {
+                if !matches!(
+                    base,
+                    Expression::Variable(..) | Expression::StructFieldAccess(..)
+                ) {
+                    assignable = false;
+                }
+
+                base = self.visit_index(base)?;
+                if !self.at_member_access() && !self.at_index() {
+                    break;
+                }
+
+                continue;
+            }
+
+            let _period = self.lexer.next()?;
+            let member = self.lexer.next()?;
+            let base_type = base.type_name();
+
+            if let Some(TokenType::Oparen) = self.lexer.peek_token().map(|t| t.kind) {
+                base = if member.value == "contains" && self.is_flags(&base_type) {
+                    self.visit_contains(base, &base_type, &member)?
+                } else if member.value == "to_string" && self.is_enum(&base_type) {
+                    self.visit_variant_name(base, &member)?
+                } else if let Some((key, value)) = map_types(&base_type) {
+                    self.visit_map_call(base, &key, &value, &member)?
+                } else if let Some(element) = vec_type(&base_type) {
+                    self.visit_vec_call(base, &element, &member)?
+                } else if let Some(trait_def) = self.find_trait(&base_type).cloned() {
+                    self.visit_dyn_call(base, &trait_def, &member)?
+                } else if self.poisoned(&base_type) {
+                    self.skip_poisoned_call(&member)?
+                } else {
+                    self.visit_method_call(base, &base_type, &member)?
+                };
+                assignable = false;
+            } else {
+                if let Expression::VecCall(..) = base {
+                    assignable = false;
+                }
+
+                let field = self.visit_field_name(&base_type, &member)?;
+                fields.push(field.clone());
+
+                let span = self.span_from(base.span());
+                let field_access_node = FieldAccessNode {
+                    base: Box::new(base),
+                    field,
+                    position: member.position.clone(),
+                    span,
+                };
+
+                base = Expression::StructFieldAccess(field_access_node);
+            }
+
+            if !self.at_member_access() && !self.at_index() {
+                break;
+            }
+        }
+
+        if let Some(next) = self.lexer.peek_token() {
+            if let TokenType::Assign = next.kind {
+                let equal_op = self.lexer.next()?;
+
+                if !assignable {
+                    self.error(
+                        &equal_op.position,
+                        String::from("cannot assign to the result of a method call"),
+                    );
+                    return None;
+                }
+
+                self.check_mutable(&variable.metadata, &equal_op.position);
+
+                let next = self.lexer.next()?;
+                let new_value = Box::new(self.parse_expr(&next)?);
+
+                if let Expression::VecCall(mut vec_call_node) = base {
+                    let expected = vec_call_node.type_name.clone();
+                    self.check_arg_type(&expected, &new_value, &next.position);
+
+                    vec_call_node.method = VecMethod::Set;
+                    vec_call_node.args.push(*new_value);
+                    vec_call_node.type_name = String::from("None");
+
+                    return Some(Expression::VecCall(vec_call_node));
+                }
+
+                let field_assign_node = FieldAssignNode {
+                    struct_instance: variable.clone(),
+                    fields,
+                    new_value,
+                    position: equal_op.position.clone(),
+                    span: self.span_from(Some(variable.span)),
+                };
+
+                return Some(Expression::StructFieldAssign(field_assign_node));
+            }
+        }
+
+        Some(base)
+    }
+
+    fn at_member_access(&self) -> bool {
+        self.lexer.character() == '.' && self.lexer.peek_char() != Some('.')
+    }
+
+    fn at_index(&self) -> bool {
+        self.lexer.character() == '['
+    }
+
+    fn visit_index(&mut self, base: Expression) -> Option<Expression> {
+        let obracket = self.lexer.next()?;
+        let base_type = base.type_name();
+
+        let Some(element) = vec_type(&base_type) else {
+            if self.poisoned(&base_type) {
+                let next = self.lexer.next()?;
+                let _index = self.parse_expr(&next);
+                let _cbracket = self.lexer.next()?;
+                return Some(Expression::Error(obracket.position));
+            }
+
+            self.error(
+                &obracket.position,
+                format!("values of type '{base_type}' cannot be indexed"),
+            );
+            return None;
+        };
+
+        let next = self.lexer.next()?;
+        let index = self.parse_expr(&next)?;
+        self.check_arg_type("i32", &index, &next.position);
+
+        let cbracket = self.lexer.next()?;
+        if cbracket.kind != TokenType::Cbracket {
+            self.error(
+                &cbracket.position,
+                format!("expected ']' found '{}'", cbracket.value),
+            );
+            return None;
+        }
+
+        let span = self.span_from(base.span());
+        Some(Expression::VecCall(VecCallNode {
+            vec: Box::new(base),
+            method: VecMethod::Get,
+            args: vec![index],
+            indexed: true,
+            type_name: element,
+            position: obracket.position.clone(),
+            span,
+        }))
+    }
+
+    fn visit_field_name(&mut self, type_name: &str, member: &Token) -> Option<VariableNode> {
+        let field = self
+            .structs
+            .iter()
+            .find(|&s| s.type_name == type_name)
+            .and_then(|s| s.fields.iter().find(|&f| f.name == member.value))
+            .cloned();
+
+        if let Some(field) = field {
+            let value = self.default_initialize_value(field.type_name.clone());
+            return Some(self.make_variable(field.name, field.type_name, Box::new(value)));
+        }
+
+        if type_name == UNKNOWN_TYPE {
+            let value = self.default_initialize_value(String::from(UNKNOWN_TYPE));
+            let type_name = String::from(UNKNOWN_TYPE);
+            return Some(self.make_variable(member.value.to_string(), type_name, Box::new(value)));
+        }
+
+        self.error(
+            &member.position,
+            format!("'{type_name}' has no field '{}'", member.value),
+        );
+
+        None
+    }
+
+    // an unknown type only comes from an error that was already reported at its definition
+    fn poisoned(&self, type_name: &str) -> bool {
+        type_name == UNKNOWN_TYPE && self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+
+    fn skip_poisoned_call(&mut self, method: &Token) -> Option<Expression> {
+        let _oparen = self.lexer.next()?;
+        while let Some(arg) = self.lexer.next() {
+            match arg.kind {
+                TokenType::Cparen => break,
+                TokenType::Comma => continue,
+                _ => {
+                    let _arg = self.parse_expr(&arg);
+                }
+            }
+        }
+
+        Some(Expression::Error(method.position.clone()))
+    }
+
+    fn visit_method_call(
+        &mut self,
+        receiver: Expression,
+        type_name: &str,
+        method: &Token,
+    ) -> Option<Expression> {
+        let (impl_node, proc_def) = self.find_method(type_name, method)?;
+
+        if proc_def.args.first().map(|a| a.name.as_str()) != Some("self") {
+            self.error(
+                &method.position,
+                format!(
+                    "'{type_name}::{}' has no 'self' parameter and must be called as '{type_name}::{}()'",
+                    method.value, method.value
+                ),
+            );
+            return None;
+        }
+
+        self.check_receiver(&receiver, &proc_def, method);
+
+        let fun_call_node =
+            self.visit_procedure_with_receiver(&proc_def, Some(receiver), method)?;
+
+        let impl_fun_call_node = ImplFunCallNode {
+            impl_node,
+            fun_call_node: Box::new(fun_call_node),
+            method: true,
+        };
+
+        Some(Expression::ImplFunCall(impl_fun_call_node))
+    }
+
+    fn visit_dyn_call(
+        &mut self,
+        receiver: Expression,
+        trait_def: &TraitDefNode,
+        method: &Token,
+    ) -> Option<Expression> {
+        let Some(proc_def) = trait_def
+            .methods
+            .iter()
+            .find(|m| m.name == method.value)
+            .cloned()
+        else {
+            self.error(
+                &method.position,
+                format!(
+                    "trait '{}' has no method '{}'",
+                    trait_def.name, method.value
+                ),
+            );
+            return None;
+        };
+
+        self.check_receiver(&receiver, &proc_def, method);
+
+        let Expression::FunCall(fun_call_node) =
+            self.visit_procedure_with_receiver(&proc_def, Some(receiver), method)?
+        else {
+            return None;
+        };
+
+        Some(Expression::DynCall(DynCallNode {
+            trait_name: trait_def.name.clone(),
+            fun_call_node,
+            dispatch: trait_def.dispatch.clone(),
+        }))
+    }
+
+    fn check_receiver(&mut self, receiver: &Expression, proc_def: &ProcDefNode, method: &Token) {
+        if proc_def.args.first().is_some_and(|a| a.mutable) {
+            self.check_mut_receiver(receiver, method);
+        }
+    }
+
+    fn check_mut_receiver(&mut self, receiver: &Expression, method: &Token) {
+        let mut root = receiver;
+        while let Expression::StructFieldAccess(field_access_node) = root {
+            root = &field_access_node.base;
+        }
+
+        if let Expression::Variable(variable) = root {
+            if !variable.metadata.mutable {
+                let mut diagnostic = Diagnostic::error(
+                    Some(method.position.clone()),
+                    format!(
+                        "cannot call '{}' on immutable variable '{}', it takes 'mut self'",
+                        method.value, variable.metadata.name
+                    ),
+                );
+                diagnostic.note = Some(self.mutability_note(&variable.metadata.name));
+
+                self.diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    fn visit_collection_new(&mut self, token: &Token) -> Option<Expression> {
+        let mut type_name = String::from(UNKNOWN_TYPE);
+        let explicit = self.lexer.peek_token()?.kind == TokenType::Lt;
+
+        if explicit {
+            let args = self.visit_type_args()?;
+            type_name = GenericType {
+                name: token.value.to_string(),
+                args,
+            }
+            .to_string();
+
+            self.instantiate_type(&type_name, &token.position);
+        }
+
+        let oparen = self.lexer.next()?;
+        if oparen.kind != TokenType::Oparen {
+            self.error(
+                &oparen.position,
+                format!("expected '(' found '{}'", oparen.value),
+            );
+            return None;
+        }
+
+        let cparen = self.lexer.next()?;
+        if cparen.kind != TokenType::Cparen {
+            self.error(
+                &cparen.position,
+                format!(
+                    "'{}' expects 0 argument(s) found '{}'",
+                    token.value, cparen.value
+                ),
+            );
+            return None;
+        }
+
+        if token.value == "Vec" {
+            if !explicit {
+                // the element type is filled in by the first push
+                type_name = GenericType {
+                    name: token.value.to_string(),
+                    args: vec![String::from(UNKNOWN_TYPE)],
+                }
+                .to_string();
+            }
+
+            return Some(Expression::VecNew(VecNewNode {
+                type_name,
+                explicit,
+                position: token.position.clone(),
+                span: self.span_since(&token.position),
+            }));
+        }
+
+        Some(Expression::MapNew(MapNewNode {
+            type_name,
+            explicit,
+            position: token.position.clone(),
+            span: self.span_since(&token.position),
+        }))
+    }
+
+    fn visit_map_call(
+        &mut self,
+        map: Expression,
+        key: &str,
+        value: &str,
+        method: &Token,
+    ) -> Option<Expression> {
+        let map_type = map.type_name();
+        let Some(map_method) = MapMethod::parse(&method.value) else {
+            self.error(
+                &method.position,
+                format!("'{map_type}' has no method '{}'", method.value),
+            );
+            return None;
+        };
+
+        let (params, type_name) = match map_method {
+            MapMethod::Insert => (vec![key, value], "None"),
+            MapMethod::Get | MapMethod::Remove => (vec![key], value),
+            MapMethod::ContainsKey => (vec![key], "bool"),
+            MapMethod::Len => (Vec::new(), "i32"),
+        };
+
+        if map_method.mutates() {
+            self.check_mut_receiver(&map, method);
+        }
+
+        let args = self.visit_builtin_args(&params, method);
+
+        let span = self.span_from(map.span());
+        Some(Expression::MapCall(MapCallNode {
+            map: Box::new(map),
+            method: map_method,
+            args,
+            type_name: String::from(type_name),
+            position: method.position.clone(),
+            span,
+        }))
+    }
+
+    fn visit_vec_call(
+        &mut self,
+        mut vec: Expression,
+        element: &str,
+        method: &Token,
+    ) -> Option<Expression> {
+        let vec_type = vec.type_name();
+        let Some(vec_method) = VecMethod::parse(&method.value) else {
+            self.error(
+                &method.position,
+                format!("'{vec_type}' has no method '{}'", method.value),
+            );
+            return None;
+        };
+
+        let (params, type_name) = match vec_method {
+            VecMethod::Push => (vec![element], "None"),
+            VecMethod::Pop => (Vec::new(), element),
+            VecMethod::Get => (vec!["i32"], element),
+            VecMethod::Set => (vec!["i32", element], "None"),
+            VecMethod::Len => (Vec::new(), "i32"),
+        };
+
+        if vec_method.mutates() {
+            self.check_mut_receiver(&vec, method);
+        }
+
+        let args = self.visit_builtin_args(&params, method);
+
+        if let (VecMethod::Push, UNKNOWN_TYPE, Some(pushed)) = (vec_method, element, args.first()) {
+            let found = pushed.type_name();
+            if found != UNKNOWN_TYPE && found != "any" {
+                let refined = GenericType {
+                    name: String::from("Vec"),
+                    args: vec![found],
+                }
+                .to_string();
+                self.refine_variable(&mut vec, refined);
+            }
+        }
+
+        let span = self.span_from(vec.span());
+        Some(Expression::VecCall(VecCallNode {
+            vec: Box::new(vec),
+            method: vec_method,
+            args,
+            indexed: false,
+            type_name: String::from(type_name),
+            position: method.position.clone(),
+            span,
+        }))
+    }
+
+    fn refine_variable(&mut self, expr: &mut Expression, type_name: String) {
+        let Expression::Variable(variable_node) = expr else {
+            return;
+        };
+
+        if let Some(variable) = self
+            .variables
+            .iter_mut()
+            .rev()
+            .find(|v| v.metadata.name == variable_node.metadata.name)
+        {
+            variable.metadata.type_name = type_name.clone();
+        }
+
+        variable_node.metadata.type_name = type_name;
+    }
+
+    fn check_arg_type(&mut self, expected: &str, arg: &Expression, position: &Position) {
+        let found = arg.type_name();
+        if found == "any" {
+            self.check_any_flow(expected, position);
+        } else if !assignable(expected, &found, &[]) {
+            self.error_spanning(
+                position,
+                arg.span(),
+                format!("expected '{expected}' found '{found}'"),
+            );
+        }
+    }
+
+    fn check_call_arg(
+        &mut self,
+        expected: &str,
+        param: &str,
+        callee: &str,
+        arg: &Expression,
+        position: &Position,
+    ) {
+        let found = arg.type_name();
+        if found == "any" {
+            self.check_any_flow(expected, position);
+        } else if !self.is_generic(&found)
+            && !assignable(expected, &found, &self.generic_params)
+            && !self.implements(expected, &found)
+        {
+            self.error_spanning(
+                position,
+                arg.span(),
+                format!("argument '{param}' of '{callee}' expected '{expected}' found '{found}'"),
+            );
+        }
+    }
+
+    fn visit_builtin_args(&mut self, params: &[&str], method: &Token) -> Vec<Expression> {
+        let _oparen = self.lexer.next();
+
+        let mut args = Vec::new();
+        while let Some(potential_arg) = self.lexer.next() {
+            if potential_arg.kind == TokenType::Cparen {
+                if args.len() != params.len() {
+                    self.error(
+                        &potential_arg.position,
+                        format!(
+                            "'{}' expects {} argument(s) found {}",
+                            method.value,
+                            params.len(),
+                            args.len()
+                        ),
+                    );
+                }
+
+                break;
+            } else if potential_arg.kind == TokenType::Comma {
+                continue;
+            }
+
+            if let Some(arg) = self.parse_expr(&potential_arg) {
+                if let Some(&expected) = params.get(args.len()) {
+                    self.check_arg_type(expected, &arg, &potential_arg.position);
+                }
+
+                args.push(arg);
+            }
+        }
+
+        args
+    }
+
+    fn find_method(&mut self, type_name: &str, method: &Token) -> Option<(ImplNode, ProcDefNode)> {
+        let method_def = self
+            .impl_blocks
+            .iter()
+            .filter(|&i| i.struct_def.type_name == type_name)
+            .find_map(|impl_node| {
+                impl_node.procedures.iter().find_map(|proc| match proc {
+                    Expression::ProcDef(proc_def_node) if proc_def_node.name == method.value => {
+                        Some((impl_node.clone(), proc_def_node.clone()))
+                    }
+                    _ => None,
+                })
+            });
+
+        if method_def.is_none() {
+            let mut diagnostic = Diagnostic::error(
+                Some(method.position.clone()),
+                format!("'{type_name}' has no method '{}'", method.value),
+            );
+            if let Some(derive) = DERIVES
+                .iter()
+                .find(|d| derived_method(d) == method.value)
+                .filter(|_| self.structs.iter().any(|s| s.type_name == type_name))
+            {
+                diagnostic.note = Some(format!(
+                    "add '@derive({derive})' to the definition of '{type_name}'"
+                ));
+            }
+
+            self.diagnostics.push(diagnostic);
+        }
+
+        method_def
+    }
+
+    fn visit_procedure(&mut self, proc_def: &ProcDefNode, token: &Token) -> Option<Expression> {
+        self.visit_procedure_with_receiver(proc_def, None, token)
+    }
+
+    fn visit_procedure_with_receiver(
+        &mut self,
+        proc_def: &ProcDefNode,
+        receiver: Option<Expression>,
+        token: &Token,
+    ) -> Option<Expression> {
+        let mut args = Vec::new();
+
+        let receiver_span = receiver.as_ref().and_then(|r| r.span());
+        let offset = usize::from(receiver.is_some());
+        if let (Some(receiver), Some(var)) = (receiver, proc_def.args.first().cloned()) {
+            let variable = self.make_variable(var.name, var.type_name, Box::new(receiver));
+            args.push(variable);
+        }
+
+        let type_args = match self.lexer.peek_token().map(|t| t.kind) {
+            Some(TokenType::Lt) if !proc_def.generics.is_empty() => self.visit_type_args()?,
+            _ => Vec::new(),
+        };
+
+        if let Some(TokenType::Oparen) = self.lexer.peek_token().map(|t| t.kind) {
+            let _oparen = self.lexer.next()?;
+
+            let mut i = offset;
+            while let Some(potential_arg) = self.lexer.next() {
+                if potential_arg.kind == TokenType::Cparen {
+                    if i != proc_def.args.len() {
+                        self.error(
+                            &potential_arg.position,
+                            format!(
+                                "'{}' expects {} argument(s) found {}",
+                                proc_def.name,
+                                proc_def.args.len() - offset,
+                                i - offset
+                            ),
+                        );
+                    }
+
+                    break;
+                } else if potential_arg.kind == TokenType::Comma {
+                    continue;
+                }
+
+                if let Some(value) = self.parse_expr(&potential_arg) {
+                    if let Some(var) = proc_def.args.get(i).cloned() {
+                        // generic parameters are unified against the arguments in monomorphize
+                        if proc_def.generics.is_empty() {
+                            self.check_call_arg(
+                                &var.type_name,
+                                &var.name,
+                                &proc_def.name,
+                                &value,
+                                &potential_arg.position,
+                            );
+                        }
+
+                        let variable = self.make_variable(var.name, var.type_name, Box::new(value));
+
+                        args.push(variable);
+                    }
+
+                    i += 1;
+                }
+            }
+        } else if offset != proc_def.args.len() {
+            self.error(
+                &token.position,
+                format!(
+                    "'{}' expects {} argument(s) found 0",
+                    proc_def.name,
+                    proc_def.args.len().saturating_sub(offset)
+                ),
+            );
+        }
+
+        let mut proc_def = proc_def.clone();
+        if !proc_def.generics.is_empty() {
+            if let Some(instance) = self.monomorphize(&proc_def, type_args.clone(), &args, token) {
+                proc_def = instance;
+            }
+
+            for (arg, param) in args.iter_mut().zip(proc_def.args.iter()) {
+                arg.metadata.type_name = param.type_name.clone();
+            }
+        }
+
+        let span = match receiver_span {
+            Some(span) => self.span_from(Some(span)),
+            None => self.span_since(&token.position),
+        };
+
+        let fun_call_node = FunCallNode {
+            proc_def,
+            args,
+            type_args,
+            position: token.position.clone(),
+            span,
+        };
+
+        Some(Expression::FunCall(fun_call_node))
+    }
+
+    fn monomorphize(
+        &mut self,
+        generic: &ProcDefNode,
+        type_args: Vec<String>,
+        args: &[VariableNode],
+        token: &Token,
+    ) -> Option<ProcDefNode> {
+        if !type_args.is_empty() && type_args.len() != generic.generics.len() {
+            self.error(
+                &token.position,
+                format!(
+                    "'{}' expects {} type argument(s) found {}",
+                    generic.name,
+                    generic.generics.len(),
+                    type_args.len()
+                ),
+            );
+            return None;
+        }
+
+        let mut bindings: Vec<(String, String)> =
+            generic.generics.iter().cloned().zip(type_args).collect();
+
+        for (param, arg) in generic.args.iter().zip(args.iter()) {
+            let arg_type = arg.value.type_name();
+            if !unify(
+                &param.type_name,
+                &arg_type,
+                &generic.generics,
+                &mut bindings,
+            ) {
+                self.error(
+                    &token.position,
+                    format!(
+                        "argument '{}' of '{}' expected '{}' found '{arg_type}'",
+                        param.name,
+                        generic.name,
+                        substitute_type(&param.type_name, &bindings)
+                    ),
+                );
+                return None;
+            }
+        }
+
+        let example = vec!["i32"; generic.generics.len()].join(", ");
+        let note = format!(
+            "specify it explicitly, e.g. '{}<{example}>(...)'",
+            generic.name
+        );
+        let type_args =
+            self.infer_type_args(&generic.generics, &bindings, &generic.name, note, token)?;
+        let bindings: Vec<(String, String)> = generic
+            .generics
+            .iter()
+            .cloned()
+            .zip(type_args.iter().cloned())
+            .collect();
+
+        if type_args.iter().any(|t| self.is_generic(t)) {
+            let args = generic
+                .args
+                .iter()
+                .map(|arg| VarMetadataNode {
+                    type_name: substitute_type(&arg.type_name, &bindings),
+                    ..arg.clone()
+                })
+                .collect();
+
+            return Some(ProcDefNode {
+                args,
+                return_type: generic
+                    .return_type
+                    .as_ref()
+                    .map(|t| substitute_type(t, &bindings)),
+                ..generic.clone()
+            });
+        }
+
+        self.instantiate_proc(generic, type_args, bindings)
+    }
+
+    fn infer_type_args(
+        &mut self,
+        generics: &[String],
+        bindings: &[(String, String)],
+        name: &str,
+        note: String,
+        token: &Token,
+    ) -> Option<Vec<String>> {
+        let mut type_args = Vec::new();
+
+        for param in generics.iter() {
+            if let Some((_, bound)) = bindings.iter().find(|(p, _)| p == param) {
+                type_args.push(bound.clone());
+            } else {
+                let mut diagnostic = Diagnostic::error(
+                    Some(token.position.clone()),
+                    format!("cannot infer type parameter '{param}' of '{name}'"),
+                );
+                diagnostic.note = Some(note);
+                self.diagnostics.push(diagnostic);
+                return None;
+            }
+        }
+
+        Some(type_args)
+    }
+
+    fn instantiate_proc(
+        &mut self,
+        generic: &ProcDefNode,
+        type_args: Vec<String>,
+        bindings: Vec<(String, String)>,
+    ) -> Option<ProcDefNode> {
+        let name = GenericType {
+            name: generic.name.clone(),
+            args: type_args,
+        }
+        .to_string();
+
+        if let Some(instance) = self.procedures.iter().rev().find(|p| p.name == name) {
+            return Some(instance.clone());
+        }
+
+        let template = format!("proc {}", generic.template.clone()?);
+        let mut position = generic.position.clone();
+        position.column = position.column.saturating_sub(5);
+
+        // instances are parsed with the same globals the generic definition saw
+        let scope_start = self.global_scope.min(self.variables.len());
+        let variables = self.variables.split_off(scope_start);
+        let closures = std::mem::take(&mut self.closures);
+        let proc_depth = std::mem::take(&mut self.proc_depth);
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        let impl_type = self.impl_type.take();
+        let generic_params = std::mem::take(&mut self.generic_params);
+        let type_bindings = std::mem::replace(&mut self.type_bindings, bindings);
+        let diagnostics = self.diagnostics.len();
+
+        self.instance_name = Some(name.clone());
+        let expr = self.parse_embedded_expr(template, &position, "generic instantiation");
+        self.instance_name = None;
+
+        self.variables.extend(variables);
+        self.closures = closures;
+        self.proc_depth = proc_depth;
+        self.loop_depth = loop_depth;
+        self.impl_type = impl_type;
+        self.generic_params = generic_params;
+        self.type_bindings = type_bindings;
+
+        for mut diagnostic in self.diagnostics.split_off(diagnostics) {
+            if diagnostic.is_error() {
+                diagnostic.note = diagnostic
+                    .note
+                    .or(Some(format!("while instantiating '{name}'")));
+                self.diagnostics.push(diagnostic);
+            }
+        }
+
+        let Some(Expression::ProcDef(mut instance)) = expr else {
+            return None;
+        };
+
+        instance.contracts = generic.contracts.clone();
+        if let Some(registered) = self.procedures.iter_mut().rev().find(|p| p.name == name) {
+            registered.contracts = instance.contracts.clone();
+        }
+
+        self.instances.push(Expression::ProcDef(instance.clone()));
+
+        Some(instance)
+    }
+
+    fn is_generic(&self, type_name: &str) -> bool {
+        type_words(type_name).any(|word| self.generic_params.iter().any(|p| p == word))
+    }
+
+    fn instantiate_type(&mut self, type_name: &str, position: &Position) {
+        if let Some(proc_type) = ProcType::parse(type_name) {
+            for arg in proc_type.args.iter().chain(proc_type.return_type.iter()) {
+                self.instantiate_type(arg, position);
+            }
+        } else if let Some(generic_type) = GenericType::parse(type_name) {
+            for arg in generic_type.args.iter() {
+                self.instantiate_type(arg, position);
+            }
+
+            if let Some(generic) = self
+                .structs
+                .iter()
+                .find(|s| s.type_name == generic_type.name && !s.generics.is_empty())
+                .cloned()
+            {
+                self.struct_instance(&generic, generic_type.args, position);
+            } else if generic_type.name == "Map" {
+                self.check_map_type(&generic_type, position);
+            } else if generic_type.name == "Vec" && generic_type.args.len() != 1 {
+                self.error(
+                    position,
+                    format!(
+                        "'Vec' expects 1 type argument(s) found {}",
+                        generic_type.args.len()
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_map_type(&mut self, generic_type: &GenericType, position: &Position) {
+        if generic_type.args.len() != 2 {
+            self.error(
+                position,
+                format!(
+                    "'Map' expects 2 type argument(s) found {}",
+                    generic_type.args.len()
+                ),
+            );
+            return;
+        }
+
+        let key = &generic_type.args[0];
+        if !MAP_KEY_TYPES.contains(&key.as_str()) && !self.is_generic(key) {
+            let mut diagnostic = Diagnostic::error(
+                Some(position.clone()),
+                format!("'{key}' cannot be used as a map key"),
+            );
+            diagnostic.note = Some(format!(
+                "map keys must be one of {}",
+                MAP_KEY_TYPES.join(", ")
+            ));
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn struct_instance(
+        &mut self,
+        generic: &StructDefNode,
+        type_args: Vec<String>,
+        position: &Position,
+    ) -> Option<StructDefNode> {
+        if type_args.len() != generic.generics.len() {
+            self.error(
+                position,
+                format!(
+                    "'{}' expects {} type argument(s) found {}",
+                    generic.type_name,
+                    generic.generics.len(),
+                    type_args.len()
+                ),
+            );
+            return None;
+        }
+
+        let type_name = GenericType {
+            name: generic.type_name.clone(),
+            args: type_args.clone(),
+        }
+        .to_string();
+
+        if let Some(instance) = self.structs.iter().find(|s| s.type_name == type_name) {
+            return Some(instance.clone());
+        }
+
+        let bindings: Vec<(String, String)> =
+            generic.generics.iter().cloned().zip(type_args).collect();
+        let fields = generic
+            .fields
+            .iter()
+            .map(|field| VarMetadataNode {
+                type_name: substitute_type(&field.type_name, &bindings),
+                ..field.clone()
+            })
+            .collect();
+
+        let instance = StructDefNode {
+            type_name,
+            fields,
+            derives: Vec::new(),
+            generics: Vec::new(),
+            docs: None,
+            position: generic.position.clone(),
+            span: generic.span,
+        };
+
+        self.structs.push(instance.clone());
+        for field in instance.fields.iter() {
+            self.instantiate_type(&field.type_name, position);
+        }
+
+        if !self.is_generic(&instance.type_name) {
+            self.instances.push(Expression::StructDef(instance.clone()));
+        }
+
+        Some(instance)
+    }
+
+    fn visit_struct_impl(&mut self, struct_def: &StructDefNode) -> Option<Expression> {
+        let _scope_resolution = self.lexer.next()?;
+        let proc_name = self.lexer.next()?;
+
+        let (impl_node, proc_def) = self.find_method(&struct_def.type_name, &proc_name)?;
+        let fun_call_node = self.visit_procedure_with_receiver(&proc_def, None, &proc_name)?;
+
+        let impl_fun_call_node = ImplFunCallNode {
+            impl_node,
+            fun_call_node: Box::new(fun_call_node),
+            method: false,
+        };
+
+        Some(Expression::ImplFunCall(impl_fun_call_node))
+    }
+
+    fn make_struct_instance(
+        &mut self,
+        struct_def: &StructDefNode,
+        token: &Token,
+    ) -> Option<Expression> {
+        if let Some(_ocurly) = self.lexer.next() {
+            let mut fields = Vec::new();
+            let mut i = 0;
+
+            while self.lexer.valid() {
+                if let Some(field) = self.lexer.next() {
+                    if let TokenType::Ccurly = field.kind {
+                        break;
+                    } else if field.kind != TokenType::Ident {
+                        self.error(
+                            &field.position,
+                            format!("expected identifier found '{:?}'", field.kind),
+                        );
+
+                        break;
+                    }
+
+                    let Some(def_field) = struct_def.fields.get(i).cloned() else {
+                        self.error(
+                            &field.position,
+                            format!(
+                                "unexpected field '{}', '{}' has {} field(s)",
+                                field.value,
+                                struct_def.type_name,
+                                struct_def.fields.len()
+                            ),
+                        );
+                        return None;
+                    };
+
+                    let _colon = self.lexer.next()?;
+
+                    let first = self.lexer.next()?;
+                    if let Some(value) = self.parse_expr(&first) {
+                        let name = def_field.name;
+                        let type_name = def_field.type_name;
+
+                        let field = self.make_variable(name, type_name, Box::new(value));
+
+                        fields.push(field);
+                        i += 1;
+                    }
+                }
+
+                if let Some(TokenType::Comma) = self.lexer.peek_token().map(|t| t.kind) {
+                    let _comma = self.lexer.next()?;
+                }
+
+                if let Some(TokenType::Ccurly) = self.lexer.peek_token().map(|t| t.kind) {
+                    let _ccurly = self.lexer.next()?;
+                    break;
+                }
+            }
+
+            let mut struct_def = struct_def.clone();
+            if !struct_def.generics.is_empty() {
+                let mut bindings = Vec::new();
+
+                for (def_field, field) in struct_def.fields.iter().zip(fields.iter()) {
+                    let value_type = field.value.type_name();
+                    if !unify(
+                        &def_field.type_name,
+                        &value_type,
+                        &struct_def.generics,
+                        &mut bindings,
+                    ) {
+                        self.error(
+                            &token.position,
+                            format!(
+                                "field '{}' of '{}' expected '{}' found '{value_type}'",
+                                def_field.name,
+                                struct_def.type_name,
+                                substitute_type(&def_field.type_name, &bindings)
+                            ),
+                        );
+                        return None;
+                    }
+                }
+
+                let note = String::from("give every field a value of a known type");
+                let type_args = self.infer_type_args(
+                    &struct_def.generics,
+                    &bindings,
+                    &struct_def.type_name,
+                    note,
+                    token,
+                )?;
+                struct_def = self.struct_instance(&struct_def, type_args, &token.position)?;
+
+                for (field, def_field) in fields.iter_mut().zip(struct_def.fields.iter()) {
+                    field.metadata.type_name = def_field.type_name.clone();
+                }
+            }
+
+            let struct_instance_node = StructInstanceNode {
+                struct_def,
+                fields,
+                position: token.position.clone(),
+                span: self.span_since(&token.position),
+            };
+
+            return Some(Expression::StructInstance(struct_instance_node));
+        }
+
+        None
+    }
+
+    fn visit_struct_def(&mut self, attributes: Attributes<'src>) -> Option<Expression> {
+        let docs = self.lexer.doc_comment().or(attributes.docs);
+
+        let mut derives: Vec<String> = Vec::new();
+        for derive in attributes.derives.iter() {
+            if !derives.iter().any(|d| *d == derive.value) {
+                derives.push(derive.value.to_string());
+            }
+        }
+
+        if let Some(ident) = self.lexer.next() {
+            let generics = self.visit_generics()?;
+            let generic_params = self.generic_params.len();
+            self.generic_params.extend(generics.iter().cloned());
+
+            if let (Some(derive), false) = (attributes.derives.first(), generics.is_empty()) {
+                self.error(
+                    &derive.position,
+                    format!("cannot derive traits for generic struct '{}'", ident.value),
+                );
+                derives.clear();
+            }
+
+            if let Some(_ocurly) = self.lexer.next() {
+                let mut fields = Vec::new();
+
+                while self.lexer.valid() {
+                    if let Some(field) = self.lexer.next() {
+                        if let TokenType::Ccurly = field.kind {
+                            break;
+                        } else if field.kind != TokenType::Ident {
+                            self.error(
+                                &field.position,
+                                format!("expected identifier found '{:?}'", field.kind),
+                            );
+
+                            break;
+                        }
+
+                        let _colon = self.lexer.next()?;
+
+                        if let Some(type_name) = self.visit_type() {
+                            let var = VarMetadataNode {
+                                name: field.value.into_owned(),
+                                type_name,
+                                mutable: false,
+                            };
+
+                            fields.push(var);
+                        }
+
+                        if self.lexer.character() == ',' {
+                            let _comma = self.lexer.next()?;
+                        }
+                    }
+                }
+
+                if let Some(c) = self.lexer.peek_char() {
+                    if c == '}' {
+                        let _ccurly = self.lexer.next()?;
+                    }
+                }
+
+                self.generic_params.truncate(generic_params);
+
+                let struct_def = StructDefNode {
+                    type_name: ident.value.into_owned(),
+                    fields,
+                    derives,
+                    generics,
+                    docs,
+                    position: ident.position.clone(),
+                    span: self.span_since(&ident.position),
+                };
+
+                if self.contains_struct(&struct_def, &struct_def.type_name) {
+                    self.error(
+                        &struct_def.position,
+                        format!(
+                            "recursive struct '{}' has infinite size",
+                            struct_def.type_name
+                        ),
+                    );
+                    return None;
+                }
+
+                self.structs.push(struct_def.clone());
+
+                let requested = match struct_def.generics.is_empty() {
+                    true => attributes.derives.as_slice(),
+                    false => &[],
+                };
+
+                let mut derived = struct_def.clone();
+                if let Some(ord) = requested.iter().find(|d| d.value == "ord") {
+                    if !self.check_orderable(&struct_def, &ord.position) {
+                        derived.derives.retain(|d| d != "ord");
+                    }
+                }
+
+                if let Some(derive) = requested.first() {
+                    self.derive_impl(&derived, &derive.position);
+                }
+
+                return Some(Expression::StructDef(struct_def));
+            }
+        }
+
+        None
+    }
+
+    fn contains_struct(&self, struct_def: &StructDefNode, target: &str) -> bool {
+        let mut pending: Vec<&str> = struct_def
+            .fields
+            .iter()
+            .map(|f| f.type_name.as_str())
+            .collect();
+        let mut seen = Vec::new();
+
+        while let Some(type_name) = pending.pop() {
+            if type_name == target {
+                return true;
+            }
+
+            if seen.contains(&type_name) {
+                continue;
+            }
+            seen.push(type_name);
+
+            if let Some(field_struct) = self.structs.iter().find(|s| s.type_name == type_name) {
+                pending.extend(field_struct.fields.iter().map(|f| f.type_name.as_str()));
+            }
+        }
+
+        false
+    }
+
+    fn check_orderable(&mut self, struct_def: &StructDefNode, position: &Position) -> bool {
+        let mut orderable_fields = true;
+        for field in struct_def.fields.iter() {
+            let type_name = field.type_name.as_str();
+
+            let orderable = ORDERED_TYPES.contains(&type_name)
+                || self
+                    .enums
+                    .iter()
+                    .any(|e| e.type_name == type_name && e.is_fieldless())
+                || self
+                    .structs
+                    .iter()
+                    .any(|s| s.type_name == type_name && s.derives.iter().any(|d| d == "ord"));
+
+            if orderable {
+                continue;
+            }
+
+            let mut diagnostic = Diagnostic::error(
+                Some(position.clone()),
+                format!(
+                    "cannot derive 'ord' for '{}', field '{}' of type '{type_name}' is not orderable",
+                    struct_def.type_name, field.name
+                ),
+            );
+
+            diagnostic.note = Some(if self.structs.iter().any(|s| s.type_name == type_name) {
+                format!("add '@derive(ord)' to the definition of '{type_name}'")
+            } else {
+                format!(
+                    "orderable fields are {}, fieldless enums and structs deriving 'ord'",
+                    ORDERED_TYPES.join(", ")
+                )
+            });
+
+            self.diagnostics.push(diagnostic);
+            orderable_fields = false;
+        }
+
+        orderable_fields
+    }
+
+    fn derive_impl(&mut self, struct_def: &StructDefNode, position: &Position) {
+        let name = &struct_def.type_name;
+        let fields: Vec<&str> = struct_def.fields.iter().map(|f| f.name.as_str()).collect();
+
+        let mut procedures = Vec::new();
+        for derive in struct_def.derives.iter() {
+            let procedure = match derive.as_str() {
+                "eq" => {
+                    let checks: String = fields
+                        .iter()
+                        .map(|f| format!("if self.{f} != other.{f} {{ return false; }} "))
+                        .collect();
+
+                    format!("proc eq(self, other: {name}): bool {{ {checks}return true; }}")
+                }
+                "clone" => {
+                    let fields: Vec<String> =
+                        fields.iter().map(|f| format!("{f}: self.{f}")).collect();
+
+                    format!(
+                        "proc clone(self): {name} {{ return {name} {{ {} }}; }}",
+                        fields.join(", ")
+                    )
+                }
+                "to_string" => {
+                    let parts: Vec<String> = fields
+                        .iter()
+                        .enumerate()
+                        .map(|(i, f)| {
+                            let separator = if i > 0 { "," } else { "" };
+                            format!("\"{separator} {f}: \" + self.{f}")
+                        })
+                        .collect();
+
+                    let value = if parts.is_empty() {
+                        format!("\"{name} \\{{ \\}}\"")
+                    } else {
+                        format!("\"{name} \\{{\" + {} + \" \\}}\"", parts.join(" + "))
+                    };
+
+                    format!("proc to_string(self): String {{ return {value}; }}")
+                }
+                "ord" => {
+                    let checks: String = struct_def
+                        .fields
+                        .iter()
+                        .map(|field| {
+                            let f = &field.name;
+                            if self.structs.iter().any(|s| s.type_name == field.type_name) {
+                                format!(
+                                    "let cmp_{f} = self.{f}.cmp(other.{f}); \
+                                     if cmp_{f} != 0 {{ return cmp_{f}; }} "
+                                )
+                            } else {
+                                let cast = if self.is_enum(&field.type_name) {
+                                    " as i32"
+                                } else {
+                                    ""
+                                };
+
+                                format!(
+                                    "if self.{f}{cast} < other.{f}{cast} {{ return -1; }} \
+                                     if self.{f}{cast} > other.{f}{cast} {{ return 1; }} "
+                                )
+                            }
+                        })
+                        .collect();
+
+                    format!("proc cmp(self, other: {name}): i32 {{ {checks}return 0; }}")
+                }
+                _ => continue,
+            };
+
+            procedures.push(procedure);
+        }
+
+        let source = format!("impl {name} {{ {} }}", procedures.join(" "));
+
+        if let Some(Expression::ImplStatement(mut impl_node)) =
+            self.parse_embedded_expr(source, position, "'@derive'")
+        {
+            impl_node.derived = true;
+            if let Some(registered) = self.impl_blocks.last_mut() {
+                registered.derived = true;
+            }
+
+            self.derived_impls
+                .push(Expression::ImplStatement(impl_node));
+        }
+    }
+
+    fn visit_match(&mut self, token: &Token) -> Option<Expression> {
+        let first = self.lexer.next()?;
+        let value = self.parse_expr(&first)?;
+        let value_type = value.type_name();
+
+        let ocurly = self.lexer.next()?;
+        if ocurly.kind != TokenType::Ocurly {
+            self.error(
+                &ocurly.position,
+                format!("expected '{{' found '{}'", ocurly.value),
+            );
+            return None;
+        }
+
+        let mut arms = Vec::new();
+        let mut close = None;
+
+        while let Some(next) = self.lexer.next() {
+            match next.kind {
+                TokenType::Ccurly => {
+                    close = Some(next.position);
+                    break;
+                }
+                TokenType::Comma | TokenType::Semicolon => continue,
+                _ => {}
+            }
+
+            let scope_start = self.variables.len();
+
+            if let Some(arm) = self.visit_match_arm(&next, &value_type) {
+                arms.push(arm);
+            } else {
+                self.synchronize();
+            }
+
+            self.variables.truncate(scope_start);
+        }
+
+        if let Some(enum_def) = self
+            .enums
+            .iter()
+            .find(|&e| e.type_name == value_type)
+            .cloned()
+        {
+            self.check_exhaustive(&enum_def, &arms, &token.position, close.as_ref());
+        }
+
+        let match_node = MatchNode {
+            value: Box::new(value),
+            arms,
+            position: token.position.clone(),
+            span: self.span_since(&token.position),
+        };
+
+        Some(Expression::MatchStatement(match_node))
+    }
+
+    fn check_exhaustive(
+        &mut self,
+        enum_def: &EnumDefNode,
+        arms: &[MatchArmNode],
+        position: &Position,
+        close: Option<&Position>,
+    ) {
+        let mut covered = Vec::new();
+        for arm in arms.iter().filter(|a| a.guard.is_none()) {
+            if arm.pattern.is_irrefutable() {
+                return;
+            }
+
+            covered.extend(arm.pattern.variants());
+        }
+
+        if enum_def.flags {
+            let mut diagnostic = Diagnostic::error(
+                Some(position.clone()),
+                format!(
+                    "non-exhaustive match on '@flags' enum '{}': combined flags are not covered",
+                    enum_def.type_name
+                ),
+            );
+            diagnostic.note = Some(String::from("add a '_' arm"));
+            self.diagnostics.push(diagnostic);
+            return;
+        }
+
+        let missing: Vec<&EnumVariantNode> = enum_def
+            .variants
+            .iter()
+            .filter(|v| !covered.contains(&v.name.as_str()))
+            .collect();
+
+        if missing.is_empty() {
+            return;
+        }
+
+        let names = missing
+            .iter()
+            .map(|v| format!("'{}::{}'", enum_def.type_name, v.name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut diagnostic = Diagnostic::error(
+            Some(position.clone()),
+            format!(
+                "non-exhaustive match on '{}': missing {names}",
+                enum_def.type_name
+            ),
+        );
+        diagnostic.note = Some(String::from(
+            "add an arm for each missing variant or a '_' arm",
+        ));
+
+        if let Some(close) = close {
+            // the stub arms produce a value of the type the other arms produce
+            let result_type = arms
+                .iter()
+                .filter_map(|arm| arm.statements.last())
+                .map(Expression::type_name)
+                .find(|type_name| type_name != "None" && type_name != UNKNOWN_TYPE);
+            let body = result_type
+                .and_then(|type_name| self.placeholder_source(&type_name, 0))
+                .unwrap_or_else(|| String::from("{}"));
+
+            let stubs = missing
+                .iter()
+                .map(|v| {
+                    let bindings = v.fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>();
+
+                    if bindings.is_empty() {
+                        format!("{}::{} => {body},", enum_def.type_name, v.name)
+                    } else {
+                        format!(
+                            "{}::{}({}) => {body},",
+                            enum_def.type_name,
+                            v.name,
+                            bindings.join(", ")
+                        )
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let line = self.lexer.source_line(close.row).unwrap_or_default();
+            let before: String = line.chars().take(close.column as usize).collect();
+
+            let edit = if before.trim().is_empty() {
+                Edit {
+                    position: Position::from(close.filename.clone(), close.row, 0),
+                    text: stubs.iter().map(|s| format!("{before}    {s}\n")).collect(),
+                }
+            } else {
+                let before = before.trim_end();
+                let separator = if before.ends_with(['{', ',']) {
+                    " "
+                } else {
+                    ", "
+                };
+
+                Edit {
+                    position: Position::from(
+                        close.filename.clone(),
+                        close.row,
+                        before.chars().count() as u32,
+                    ),
+                    text: format!("{separator}{}", stubs.join(" ")),
+                }
+            };
+
+            diagnostic.fix = Some(Box::new(Fix {
+                message: format!("insert stub arms for {names}"),
+                edits: vec![edit],
+            }));
+        }
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn visit_match_arm(&mut self, first: &Token, value_type: &str) -> Option<MatchArmNode> {
+        let pattern = self.visit_pattern(first, value_type)?;
+
+        for binding in pattern.bindings() {
+            let value = self.default_initialize_value(binding.type_name.clone());
+            let variable = self.make_variable(binding.name, binding.type_name, Box::new(value));
+            self.variables.push(variable);
+        }
+
+        let mut guard = None;
+        if let Some(TokenType::If) = self.lexer.peek_token().map(|t| t.kind) {
+            self.lexer.next();
+            let next = self.lexer.next()?;
+            guard = Some(Box::new(self.parse_expr(&next)?));
+        }
+
+        let arrow = self.lexer.next()?;
+        if arrow.kind != TokenType::FatArrow {
+            self.error(
+                &arrow.position,
+                format!("expected '=>' found '{}'", arrow.value),
+            );
+            return None;
+        }
+
+        let next = self.lexer.next()?;
+        let mut statements = Vec::new();
+
+        if let TokenType::Ocurly = next.kind {
+            while let Some(next) = self.lexer.next() {
+                if let TokenType::Ccurly = next.kind {
+                    break;
+                } else if let TokenType::Semicolon = next.kind {
+                    continue;
+                }
+
+                let diagnostics = self.diagnostics.len();
+                if let Some(expr) = self.parse_expr(&next) {
+                    statements.push(expr);
+                } else {
+                    statements.push(self.recover(next, diagnostics));
+                }
             }
+        } else {
+            statements.push(self.parse_expr(&next)?);
         }
 
-        None
+        Some(MatchArmNode {
+            pattern,
+            guard,
+            statements,
+        })
     }
 
-    fn visit_args(&mut self, args: &mut Vec<VarMetadataNode>) {
-        while let Some(ident) = self.lexer.next() {
-            if let TokenType::Cparen = ident.kind {
-                break;
-            } else if let TokenType::Comma = ident.kind {
+    fn visit_pattern(&mut self, token: &Token, value_type: &str) -> Option<PatternNode> {
+        let first = self.visit_pattern_alternative(token, value_type)?;
+
+        if self.lexer.peek_token().map(|t| t.kind) != Some(TokenType::BitOr) {
+            return Some(first);
+        }
+
+        let mut alternatives = vec![first];
+        while let Some(TokenType::BitOr) = self.lexer.peek_token().map(|t| t.kind) {
+            self.lexer.next();
+            let next = self.lexer.next()?;
+            alternatives.push(self.visit_pattern_alternative(&next, value_type)?);
+        }
+
+        self.check_or_bindings(&alternatives, &token.position);
+
+        Some(PatternNode::Or(alternatives))
+    }
+
+    fn check_or_bindings(&mut self, alternatives: &[PatternNode], position: &Position) {
+        let bindings: Vec<Vec<VarMetadataNode>> =
+            alternatives.iter().map(PatternNode::bindings).collect();
+
+        let mut reported: Vec<&str> = Vec::new();
+        for binding in bindings.iter().flatten() {
+            if reported.contains(&binding.name.as_str()) {
                 continue;
             }
 
-            let _colon = self.lexer.next().unwrap();
-            let type_name = self.lexer.next().unwrap();
+            let types: Vec<Option<&str>> = bindings
+                .iter()
+                .map(|alternative| {
+                    alternative
+                        .iter()
+                        .find(|b| b.name == binding.name)
+                        .map(|b| b.type_name.as_str())
+                })
+                .collect();
+
+            if types.contains(&None) {
+                self.error(
+                    position,
+                    format!(
+                        "'{}' is not bound in every alternative of the or-pattern",
+                        binding.name
+                    ),
+                );
+                reported.push(&binding.name);
+            } else if let Some(other) = types.iter().flatten().find(|&&t| t != binding.type_name) {
+                self.error(
+                    position,
+                    format!(
+                        "'{}' has type '{}' in one alternative of the or-pattern and '{}' in another",
+                        binding.name, binding.type_name, other
+                    ),
+                );
+                reported.push(&binding.name);
+            }
+        }
+    }
 
-            let arg = VarMetadataNode {
-                name: ident.value,
-                type_name: type_name.value.clone(),
-            };
+    fn visit_pattern_alternative(
+        &mut self,
+        token: &Token,
+        value_type: &str,
+    ) -> Option<PatternNode> {
+        if token.kind == TokenType::Ident
+            && token.value != "_"
+            && self.lexer.peek_token().map(|t| t.kind) == Some(TokenType::At)
+        {
+            self.lexer.next();
+            let next = self.lexer.next()?;
+            let pattern = self.visit_primary_pattern(&next, value_type)?;
+
+            return Some(PatternNode::Bind {
+                binding: VarMetadataNode {
+                    name: token.value.to_string(),
+                    type_name: String::from(value_type),
+                    mutable: false,
+                },
+                pattern: Box::new(pattern),
+            });
+        }
 
-            args.push(arg.clone());
+        self.visit_primary_pattern(token, value_type)
+    }
 
-            let value = self.default_initialize_value(type_name.value);
-            let var = VariableNode {
-                metadata: arg,
-                value: Box::new(value),
-            };
+    fn visit_literal_pattern(&mut self, token: &Token) -> Option<(Token<'static>, LiteralType)> {
+        match token.kind {
+            TokenType::Literal(lt) => {
+                if let Some(Expression::Literal(token, lt)) = self.visit_literal(token.clone(), lt)
+                {
+                    return Some((token, lt));
+                }
 
-            self.variables.push(var);
+                None
+            }
+            TokenType::Sub => {
+                let next = self.lexer.next()?;
+                if let TokenType::Literal(lt @ (LiteralType::Number | LiteralType::Float)) =
+                    next.kind
+                {
+                    let value = format!("-{}", next.value);
+                    let token = Token::from(next.kind, value, token.position.clone());
+
+                    if let Some(Expression::Literal(token, lt)) = self.visit_literal(token, lt) {
+                        return Some((token, lt));
+                    }
+
+                    return None;
+                }
+
+                self.error(
+                    &next.position,
+                    format!("expected numeric literal found '{}'", next.value),
+                );
+                None
+            }
+            _ => {
+                self.error(
+                    &token.position,
+                    format!("expected literal found '{}'", token.value),
+                );
+                None
+            }
         }
     }
 
-    fn visit_identifier(&mut self, token: &Token) -> Option<Expression> {
-        if let Some(variable) = self
-            .variables
-            .clone()
-            .iter()
-            .find(|&v| v.metadata.name == token.value)
-        {
-            if let Some(c) = self.lexer.peek_char() {
-                if c == '=' {
-                    if let Some(_equal_op) = self.lexer.next() {
-                        let next = self.lexer.next().unwrap();
+    fn visit_range_pattern(
+        &mut self,
+        start: Token<'static>,
+        kind: LiteralType,
+    ) -> Option<PatternNode> {
+        self.lexer.next();
+        let next = self.lexer.next()?;
+        let (end, end_kind) = self.visit_literal_pattern(&next)?;
+
+        let empty = match kind {
+            LiteralType::Number => start.value.parse::<i32>().ok() >= end.value.parse::<i32>().ok(),
+            LiteralType::Float => start.value.parse::<f32>().ok() >= end.value.parse::<f32>().ok(),
+            _ => start.value.chars().next() >= end.value.chars().next(),
+        };
 
-                        if let Some(expr) = self.parse_expr(&next) {
-                            let new_value = Box::new(expr);
+        if !matches!(
+            kind,
+            LiteralType::Number | LiteralType::Float | LiteralType::Char
+        ) {
+            self.error(
+                &start.position,
+                format!(
+                    "range patterns need 'i32', 'f32' or 'char' bounds, found '{}'",
+                    kind.type_name()
+                ),
+            );
+        } else if kind != end_kind {
+            self.error(
+                &end.position,
+                format!(
+                    "range pattern bounds must have the same type, found '{}' and '{}'",
+                    kind.type_name(),
+                    end_kind.type_name()
+                ),
+            );
+        } else if empty {
+            self.error(
+                &start.position,
+                format!(
+                    "range pattern '{}..{}' is empty, the end is exclusive",
+                    start.value, end.value
+                ),
+            );
+        }
 
-                            let assign_node = AssignNode {
-                                value: variable.clone(),
-                                new_value,
-                            };
+        Some(PatternNode::Range { start, end, kind })
+    }
 
-                            let _semicolon = self.lexer.next().unwrap();
+    fn visit_primary_pattern(&mut self, token: &Token, value_type: &str) -> Option<PatternNode> {
+        match token.kind {
+            TokenType::Literal(..) | TokenType::Sub => {
+                let (token, lt) = self.visit_literal_pattern(token)?;
 
-                            return Some(Expression::AssignStatement(assign_node));
-                        }
-                    }
+                if let Some(TokenType::Range) = self.lexer.peek_token().map(|t| t.kind) {
+                    return self.visit_range_pattern(token, lt);
                 }
+
+                Some(PatternNode::Literal(token, lt))
             }
+            TokenType::Oparen => {
+                let next = self.lexer.next()?;
+                let pattern = self.visit_pattern(&next, value_type)?;
+
+                let cparen = self.lexer.next()?;
+                if cparen.kind != TokenType::Cparen {
+                    self.error(
+                        &cparen.position,
+                        format!("expected ')' found '{}'", cparen.value),
+                    );
+                    return None;
+                }
 
-            if self.lexer.character() == '.' {
-                let _period = self.lexer.next().unwrap();
-                let expr = self.visit_struct_field(variable);
-                return self.visit_binary_op(expr);
-            } else {
-                return self.visit_binary_op(Some(Expression::Variable(variable.clone())));
+                Some(pattern)
             }
-        } else if let Some(proc_def) = self
-            .procedures
-            .clone()
-            .iter()
-            .find(|&f| f.name == token.value)
-        {
-            let expr = self.visit_procedure(proc_def);
-            return self.visit_binary_op(expr);
-        } else if let Some(struct_def) = self
-            .structs
-            .clone()
-            .iter()
-            .find(|&s| s.type_name == token.value)
+            TokenType::Ident if token.value == "_" => Some(PatternNode::Wildcard),
+            TokenType::Ident => {
+                let enum_def = self
+                    .enums
+                    .iter()
+                    .find(|&e| e.type_name == token.value)
+                    .cloned();
+
+                if let Some(enum_def) = enum_def {
+                    return self.visit_variant_pattern(&enum_def);
+                }
+
+                Some(PatternNode::Binding(VarMetadataNode {
+                    name: token.value.to_string(),
+                    type_name: String::from(value_type),
+                    mutable: false,
+                }))
+            }
+            _ => {
+                self.error(
+                    &token.position,
+                    format!("expected pattern found '{}'", token.value),
+                );
+                None
+            }
+        }
+    }
+
+    fn visit_variant_pattern(&mut self, enum_def: &EnumDefNode) -> Option<PatternNode> {
+        let scope_resolution = self.lexer.next()?;
+        if scope_resolution.kind != TokenType::ScopeResolution {
+            self.error(
+                &scope_resolution.position,
+                format!("expected '::' found '{}'", scope_resolution.value),
+            );
+            return None;
+        }
+
+        let name = self.lexer.next()?;
+        let variant = if let Some(variant) = enum_def.variants.iter().find(|v| v.name == name.value)
         {
-            if self.lexer.character() == ':' {
-                if let Some(n) = self.lexer.peek_char() {
-                    if n == ':' {
-                        if let Some(impl_node) = self
-                            .impl_blocks
-                            .clone()
-                            .iter()
-                            .find(|&i| i.struct_def.type_name == token.value)
-                        {
-                            let expr = self.visit_struct_impl(impl_node);
-                            return self.visit_binary_op(expr);
-                        }
+            variant
+        } else {
+            self.error(
+                &name.position,
+                format!("'{}' has no variant '{}'", enum_def.type_name, name.value),
+            );
+            return None;
+        };
+
+        let mut bindings = Vec::new();
+
+        if let Some(TokenType::Oparen) = self.lexer.peek_token().map(|t| t.kind) {
+            self.lexer.next();
+
+            while let Some(binding) = self.lexer.next() {
+                match binding.kind {
+                    TokenType::Cparen => break,
+                    TokenType::Comma => continue,
+                    TokenType::Ident => {}
+                    _ => {
+                        self.error(
+                            &binding.position,
+                            format!("expected binding name found '{}'", binding.value),
+                        );
+                        return None;
                     }
                 }
-            } else {
-                let expr = self.make_struct_instance(struct_def);
-                return self.visit_binary_op(expr);
+
+                let type_name = variant
+                    .fields
+                    .get(bindings.len())
+                    .map_or_else(|| String::from("None"), |f| f.type_name.clone());
+
+                bindings.push(VarMetadataNode {
+                    name: binding.value.into_owned(),
+                    type_name,
+                    mutable: false,
+                });
             }
         }
 
-        println!(
-            "<{}> Error: expected identifier found '{}'",
-            token.position, token.value
-        );
+        if bindings.len() != variant.fields.len() {
+            self.error(
+                &name.position,
+                format!(
+                    "'{}::{}' has {} field(s) but the pattern binds {}",
+                    enum_def.type_name,
+                    variant.name,
+                    variant.fields.len(),
+                    bindings.len()
+                ),
+            );
+        }
 
-        None
+        Some(PatternNode::Variant {
+            type_name: enum_def.type_name.clone(),
+            variant: variant.name.clone(),
+            bindings,
+        })
     }
 
-    fn visit_struct_field(&mut self, variable: &VariableNode) -> Option<Expression> {
-        if let Some(struct_field) = self.lexer.next() {
-            if let Expression::StructInstance(struct_instance) = variable.value.as_ref() {
-                for field in struct_instance.fields.iter() {
-                    if field.metadata.name != struct_field.value {
+    fn visit_enum_def(&mut self, attributes: Attributes<'src>) -> Option<Expression> {
+        let docs = self.lexer.doc_comment().or(attributes.docs);
+
+        let ident = self.lexer.next()?;
+        let ocurly = self.lexer.next()?;
+        if ocurly.kind != TokenType::Ocurly {
+            self.error(
+                &ocurly.position,
+                format!("expected '{{' found '{}'", ocurly.value),
+            );
+            return None;
+        }
+
+        let mut variants: Vec<EnumVariantNode> = Vec::new();
+
+        while let Some(next) = self.lexer.next() {
+            match next.kind {
+                TokenType::Ccurly => break,
+                TokenType::Comma => continue,
+                TokenType::Ident => {}
+                _ => {
+                    self.error(
+                        &next.position,
+                        format!("expected variant name found '{}'", next.value),
+                    );
+                    return None;
+                }
+            }
+
+            if variants.iter().any(|v| v.name == next.value) {
+                self.error(
+                    &next.position,
+                    format!("duplicate variant '{}::{}'", ident.value, next.value),
+                );
+            }
+
+            let mut fields = Vec::new();
+
+            if let Some(TokenType::Oparen) = self.lexer.peek_token().map(|t| t.kind) {
+                let _oparen = self.lexer.next();
+
+                while let Some(field) = self.lexer.next() {
+                    if let TokenType::Cparen = field.kind {
+                        break;
+                    } else if let TokenType::Comma = field.kind {
                         continue;
                     }
 
-                    if let Some(c) = self.lexer.peek_char() {
-                        let mut is_eq_node = false;
+                    let _colon = self.lexer.next()?;
+                    let type_name = self.lexer.next()?;
 
-                        if let Some(n) = self.lexer.peek_char_by_amount(2) {
-                            is_eq_node = n == '=';
-                        }
-
-                        if c == '=' && !is_eq_node {
-                            let _equal_op = self.lexer.next().unwrap();
+                    fields.push(VarMetadataNode {
+                        name: field.value.into_owned(),
+                        type_name: type_name.value.into_owned(),
+                        mutable: false,
+                    });
+                }
+            }
 
-                            let next = self.lexer.next().unwrap();
-                            if let Some(value) = self.parse_expr(&next) {
-                                let new_value = Box::new(value);
+            let mut discriminant = None;
 
-                                let field_assign_node = FieldAssignNode {
-                                    struct_instance: variable.clone(),
-                                    field: field.clone(),
-                                    new_value: new_value.clone(),
-                                };
+            if let Some(TokenType::Assign) = self.lexer.peek_token().map(|t| t.kind) {
+                let _equal = self.lexer.next();
 
-                                if let Expression::StructInstance(struct_instance_node) =
-                                    variable.value.as_ref()
-                                {
-                                    for (i, field) in
-                                        struct_instance_node.fields.clone().iter().enumerate()
-                                    {
-                                        if field.metadata.name
-                                            != field_assign_node.field.metadata.name
-                                        {
-                                            continue;
-                                        }
-
-                                        let index = self
-                                            .variables
-                                            .iter()
-                                            .position(|v| v.metadata.name == variable.metadata.name)
-                                            .unwrap();
-                                        let var = self.variables[index].value.as_mut();
-                                        if let Expression::StructInstance(instance) = var {
-                                            instance.fields[i].value = new_value.clone();
-                                        }
-                                    }
-                                }
-
-                                return Some(Expression::StructFieldAssign(field_assign_node));
-                            }
-                        } else {
-                            let field_access_node = FieldAccessNode {
-                                struct_instance: variable.clone(),
-                                field: field.clone(),
-                            };
+                discriminant = self.visit_discriminant()?;
 
-                            return Some(Expression::StructFieldAccess(field_access_node));
-                        }
-                    }
+                if !fields.is_empty() {
+                    self.error(
+                        &next.position,
+                        format!(
+                            "'{}::{}' has fields and cannot have a discriminant",
+                            ident.value, next.value
+                        ),
+                    );
                 }
             }
+
+            variants.push(EnumVariantNode {
+                name: next.value.into_owned(),
+                fields,
+                discriminant,
+            });
         }
 
-        None
+        let enum_def = EnumDefNode {
+            type_name: ident.value.into_owned(),
+            variants,
+            flags: attributes.flags,
+            docs,
+            position: ident.position.clone(),
+            span: self.span_since(&ident.position),
+        };
+
+        if enum_def.flags && !enum_def.is_fieldless() {
+            self.error(
+                &enum_def.position,
+                format!(
+                    "'@flags' enum '{}' cannot have variants with fields",
+                    enum_def.type_name
+                ),
+            );
+        }
+
+        let discriminants = enum_def.discriminants();
+        for (i, (name, discriminant)) in discriminants.iter().enumerate() {
+            if enum_def.flags && *discriminant == 0 && enum_def.variants[i].discriminant.is_none() {
+                self.error(
+                    &enum_def.position,
+                    format!(
+                        "'{0}::{name}' needs an explicit value: '{0}' has no bit left",
+                        enum_def.type_name
+                    ),
+                );
+                continue;
+            }
+
+            if let Some((other, _)) = discriminants[..i].iter().find(|(_, d)| d == discriminant) {
+                self.error(
+                    &enum_def.position,
+                    format!(
+                        "discriminant {discriminant} of '{0}::{name}' is already used by '{0}::{other}'",
+                        enum_def.type_name
+                    ),
+                );
+            }
+        }
+
+        self.enums.push(enum_def.clone());
+
+        Some(Expression::EnumDef(enum_def))
     }
 
-    fn visit_procedure(&mut self, proc_def: &ProcDefNode) -> Option<Expression> {
-        let mut args = Vec::new();
+    fn visit_discriminant(&mut self) -> Option<Option<i32>> {
+        let mut token = self.lexer.next()?;
+
+        let negative = token.kind == TokenType::Sub;
+        if negative {
+            token = self.lexer.next()?;
+        }
+
+        let value = if let TokenType::Literal(LiteralType::Number) = token.kind {
+            if negative {
+                format!("-{}", token.value).parse::<i32>().ok()
+            } else {
+                token.value.parse::<i32>().ok()
+            }
+        } else {
+            None
+        };
+
+        if value.is_none() {
+            self.error(
+                &token.position,
+                format!("expected an i32 discriminant found '{}'", token.value),
+            );
+        }
+
+        Some(value)
+    }
+
+    fn visit_enum_variant(&mut self, enum_def: &EnumDefNode) -> Option<Expression> {
+        let scope_resolution = self.lexer.next()?;
+        if scope_resolution.kind != TokenType::ScopeResolution {
+            self.error(
+                &scope_resolution.position,
+                format!("expected '::' found '{}'", scope_resolution.value),
+            );
+            return None;
+        }
+
+        let name = self.lexer.next()?;
+        let variant = if let Some(variant) = enum_def.variants.iter().find(|v| v.name == name.value)
+        {
+            variant
+        } else if name.value == "from" {
+            return self.visit_enum_from(enum_def, &name);
+        } else if name.value == "parse" {
+            return self.visit_enum_parse(enum_def, &name);
+        } else {
+            self.error(
+                &name.position,
+                format!("'{}' has no variant '{}'", enum_def.type_name, name.value),
+            );
+            return None;
+        };
+
+        let mut fields = Vec::new();
+
+        if !variant.fields.is_empty() {
+            let _oparen = self.lexer.next()?;
 
-        if let Some(_oparen) = self.lexer.next() {
             let mut i = 0;
             while let Some(potential_arg) = self.lexer.next() {
                 if potential_arg.kind == TokenType::Cparen {
+                    if i != variant.fields.len() {
+                        self.error(
+                            &potential_arg.position,
+                            format!(
+                                "'{}::{}' expects {} field(s) found {i}",
+                                enum_def.type_name,
+                                variant.name,
+                                variant.fields.len()
+                            ),
+                        );
+                    }
+
                     break;
                 } else if potential_arg.kind == TokenType::Comma {
                     continue;
                 }
 
                 if let Some(value) = self.parse_expr(&potential_arg) {
-                    let var = proc_def.args[i].clone();
-                    let variable = self.make_variable(var.name, var.type_name, Box::new(value));
+                    if let Some(field) = variant.fields.get(i).cloned() {
+                        let variable =
+                            self.make_variable(field.name, field.type_name, Box::new(value));
 
-                    args.push(variable);
+                        fields.push(variable);
+                    }
 
                     i += 1;
                 }
             }
         }
 
-        let fun_call_node = FunCallNode {
-            proc_def: proc_def.clone(),
-            args,
+        let enum_instance_node = EnumInstanceNode {
+            enum_def: enum_def.clone(),
+            variant: variant.name.clone(),
+            fields,
+            position: name.position.clone(),
+            span: self.span_since(&name.position),
         };
 
-        Some(Expression::FunCall(fun_call_node))
+        Some(Expression::EnumInstance(enum_instance_node))
     }
 
-    fn visit_struct_impl(&mut self, impl_node: &ImplNode) -> Option<Expression> {
-        if let Some(_scope_resolution) = self.lexer.next() {
-            if let Some(proc_name) = self.lexer.next() {
-                let mut proc_def = None;
+    fn visit_single_arg(&mut self) -> Option<(Expression, Token<'src>)> {
+        let oparen = self.lexer.next()?;
+        if oparen.kind != TokenType::Oparen {
+            self.error(
+                &oparen.position,
+                format!("expected '(' found '{}'", oparen.value),
+            );
+            return None;
+        }
 
-                for proc in impl_node.procedures.iter() {
-                    if let Expression::ProcDef(proc_def_node) = proc {
-                        if proc_def_node.name == proc_name.value {
-                            proc_def = Some(proc_def_node.clone());
-                            break;
-                        }
-                    }
-                }
+        let next = self.lexer.next()?;
+        let value = self.parse_expr(&next)?;
+
+        let cparen = self.lexer.next()?;
+        if cparen.kind != TokenType::Cparen {
+            self.error(
+                &cparen.position,
+                format!("expected ')' found '{}'", cparen.value),
+            );
+            return None;
+        }
+
+        Some((value, next))
+    }
+
+    fn visit_enum_from(&mut self, enum_def: &EnumDefNode, token: &Token) -> Option<Expression> {
+        let (value, next) = self.visit_single_arg()?;
+
+        if !enum_def.is_fieldless() {
+            self.error(
+                &token.position,
+                format!(
+                    "'{}' has variants with fields and cannot be built from an i32",
+                    enum_def.type_name
+                ),
+            );
+        }
+
+        match value.type_name().as_str() {
+            "i32" | UNKNOWN_TYPE => {}
+            "any" => self.check_any_flow("i32", &next.position),
+            type_name => self.error(
+                &next.position,
+                format!("expected 'i32' found '{type_name}'"),
+            ),
+        }
+
+        Some(Expression::EnumFrom(EnumFromNode {
+            enum_def: enum_def.clone(),
+            value: Box::new(value),
+            position: token.position.clone(),
+            span: self.span_since(&token.position),
+        }))
+    }
+
+    fn visit_enum_parse(&mut self, enum_def: &EnumDefNode, token: &Token) -> Option<Expression> {
+        let (value, next) = self.visit_single_arg()?;
+
+        if !enum_def.is_fieldless() {
+            self.error(
+                &token.position,
+                format!(
+                    "'{}' has variants with fields and cannot be parsed from a string",
+                    enum_def.type_name
+                ),
+            );
+        }
+
+        match value.type_name().as_str() {
+            "String" | UNKNOWN_TYPE => {}
+            "any" => self.check_any_flow("String", &next.position),
+            type_name => self.error(
+                &next.position,
+                format!("expected 'String' found '{type_name}'"),
+            ),
+        }
+
+        Some(Expression::EnumParse(EnumParseNode {
+            enum_def: enum_def.clone(),
+            value: Box::new(value),
+            position: token.position.clone(),
+            span: self.span_since(&token.position),
+        }))
+    }
+
+    fn visit_variant_name(&mut self, value: Expression, token: &Token) -> Option<Expression> {
+        let _oparen = self.lexer.next()?;
+
+        let cparen = self.lexer.next()?;
+        if cparen.kind != TokenType::Cparen {
+            self.error(
+                &cparen.position,
+                format!("'to_string' takes no arguments, found '{}'", cparen.value),
+            );
+            return None;
+        }
+
+        let span = self.span_from(value.span());
+        Some(Expression::VariantName(VariantNameNode {
+            value: Box::new(value),
+            position: token.position.clone(),
+            span,
+        }))
+    }
+
+    fn visit_contains(
+        &mut self,
+        value: Expression,
+        type_name: &str,
+        token: &Token,
+    ) -> Option<Expression> {
+        let _oparen = self.lexer.next()?;
+
+        let next = self.lexer.next()?;
+        let flag = self.parse_expr(&next)?;
+
+        let cparen = self.lexer.next()?;
+        if cparen.kind != TokenType::Cparen {
+            self.error(
+                &cparen.position,
+                format!("expected ')' found '{}'", cparen.value),
+            );
+            return None;
+        }
+
+        let flag_type = flag.type_name();
+        if flag_type != type_name && flag_type != UNKNOWN_TYPE {
+            self.error(
+                &next.position,
+                format!("expected '{type_name}' found '{flag_type}'"),
+            );
+        }
 
-                proc_def.as_ref()?;
+        let span = self.span_from(value.span());
+        Some(Expression::Contains(ContainsNode {
+            value: Box::new(value),
+            flag: Box::new(flag),
+            position: token.position.clone(),
+            span,
+        }))
+    }
+
+    fn is_enum(&self, type_name: &str) -> bool {
+        self.enums.iter().any(|e| e.type_name == type_name)
+    }
+
+    fn is_flags(&self, type_name: &str) -> bool {
+        self.enums
+            .iter()
+            .any(|e| e.flags && e.type_name == type_name)
+    }
+
+    fn check_bitwise(&mut self, binary_op_node: &BinaryOpNode) {
+        if !matches!(
+            binary_op_node.op,
+            BinaryOp::BitOr | BinaryOp::BitAnd | BinaryOp::BitXor
+        ) {
+            return;
+        }
+
+        let lhs = binary_op_node.lhs.type_name();
+        let rhs = binary_op_node.rhs.type_name();
+
+        if [lhs.as_str(), rhs.as_str()]
+            .iter()
+            .any(|&t| t == UNKNOWN_TYPE || t == "any")
+        {
+            return;
+        }
+
+        if lhs == rhs && (lhs == "i32" || self.is_flags(&lhs)) {
+            return;
+        }
+
+        self.error(
+            &binary_op_node.position,
+            format!(
+                "cannot apply '{}' to '{lhs}' and '{rhs}'",
+                binary_op_node.op
+            ),
+        );
+    }
+
+    fn visit_cast(&mut self, mut value: Expression) -> Expression {
+        while let Some(TokenType::As) = self.lexer.peek_token().map(|t| t.kind) {
+            let Some(as_token) = self.lexer.next() else {
+                break;
+            };
+
+            let Some(target) = self.lexer.next() else {
+                break;
+            };
 
-                let mut args = Vec::new();
-                let mut arg_index = 0;
+            let type_name = value.type_name();
+            let enum_def = self
+                .enums
+                .iter()
+                .find(|e| e.type_name == type_name)
+                .cloned();
+
+            let Some(enum_def) = enum_def.filter(|_| target.value == "i32") else {
+                self.error(
+                    &as_token.position,
+                    format!("cannot cast '{type_name}' as '{}'", target.value),
+                );
+                continue;
+            };
+
+            if !enum_def.is_fieldless() {
+                self.error(
+                    &as_token.position,
+                    format!("'{type_name}' has variants with fields and cannot be cast as 'i32'"),
+                );
+            }
 
-                if let Some(_oparen) = self.lexer.next() {
-                    while let Some(potential_arg) = self.lexer.next() {
-                        if let TokenType::Cparen = potential_arg.kind {
-                            break;
-                        } else if let TokenType::Semicolon | TokenType::Comma = potential_arg.kind {
-                            continue;
-                        }
+            let span = self.span_from(value.span());
+            value = Expression::Cast(CastNode {
+                value: Box::new(value),
+                type_name: target.value.into_owned(),
+                enum_def,
+                position: as_token.position,
+                span,
+            });
+        }
 
-                        if let Some(proc) = proc_def.clone() {
-                            let name = proc.args[arg_index].name.clone();
-                            let type_name = proc.args[arg_index].type_name.clone();
+        value
+    }
 
-                            if let Some(value) = self.parse_expr(&potential_arg) {
-                                let variable = self.make_variable(name, type_name, Box::new(value));
+    fn visit_binary_op(&mut self, expr: Option<Expression>) -> Option<Expression> {
+        let lhs = self.visit_cast(expr?);
+        Some(self.visit_binary_op_rhs(lhs, 1))
+    }
 
-                                args.push(variable);
+    fn visit_binary_op_rhs(&mut self, mut lhs: Expression, min_precedence: u8) -> Expression {
+        // only comparisons built by this loop chain, a parenthesized one stays a single operand
+        let mut chainable = false;
 
-                                arg_index += 1;
-                            }
-                        }
-                    }
+        while let Some(op_token) = self.lexer.peek_token() {
+            let op = self.token_type_to_binary_op(op_token.kind);
+            let precedence = self.precedence(op_token.kind);
 
-                    let fun_call_node = FunCallNode {
-                        proc_def: proc_def.unwrap(),
-                        args,
-                    };
+            if precedence == 0 || precedence < min_precedence {
+                break;
+            }
 
-                    let impl_fun_call_node = ImplFunCallNode {
-                        impl_node: impl_node.clone(),
-                        fun_call_node: Box::new(Expression::FunCall(fun_call_node)),
-                    };
+            let Some(op_token) = self.lexer.next() else {
+                break;
+            };
 
-                    let _semicolon = self.lexer.next().unwrap();
+            if op_token.kind == TokenType::Pipe {
+                let position = op_token.position.clone();
+                lhs = match self.visit_pipe(lhs, &op_token) {
+                    Some(pipe) => pipe,
+                    None => Expression::Error(position),
+                };
+                chainable = false;
+                continue;
+            }
 
-                    return Some(Expression::ImplFunCall(impl_fun_call_node));
-                }
+            if let (
+                BinaryOp::Inc
+                | BinaryOp::Dec
+                | BinaryOp::AddAssign
+                | BinaryOp::SubAssign
+                | BinaryOp::MulAssign
+                | BinaryOp::DivAssign,
+                Expression::Variable(variable_node),
+            ) = (&op, &lhs)
+            {
+                let metadata = variable_node.metadata.clone();
+                self.check_mutable(&metadata, &op_token.position);
             }
-        }
 
-        None
-    }
+            let rhs = if let BinaryOp::Inc | BinaryOp::Dec = op {
+                Expression::Literal(
+                    Token::from(
+                        TokenType::Literal(LiteralType::Number),
+                        String::from("1"),
+                        op_token.position.clone(),
+                    ),
+                    LiteralType::Number,
+                )
+            } else {
+                let next = if let Some(next) = self.lexer.next() {
+                    next
+                } else {
+                    break;
+                };
 
-    fn make_struct_instance(&mut self, struct_def: &StructDefNode) -> Option<Expression> {
-        if let Some(_ocurly) = self.lexer.next() {
-            let mut fields = Vec::new();
-            let mut i = 0;
+                let mut rhs = if let Some(rhs) = self.parse_operand(&next) {
+                    self.visit_cast(rhs)
+                } else {
+                    self.error(
+                        &next.position,
+                        format!("expected operand found '{}'", next.value),
+                    );
+                    break;
+                };
 
-            while self.lexer.valid() {
-                if let Some(field) = self.lexer.next() {
-                    if let TokenType::Ccurly = field.kind {
-                        break;
-                    } else if field.kind != TokenType::Ident {
-                        println!(
-                            "<{}> Error: expected identifier found '{:?}'",
-                            field.position, field.kind
-                        );
+                while let Some(next_op) = self.lexer.peek_token() {
+                    let next_precedence = self.precedence(next_op.kind);
 
+                    // '??' groups to the right so a failed fallback lookup falls through
+                    let right = next_op.kind == TokenType::Coalesce;
+                    if next_precedence < precedence || (next_precedence == precedence && !right) {
                         break;
                     }
 
-                    let _colon = self.lexer.next().unwrap();
-
-                    let first = self.lexer.next().unwrap();
-                    if let Some(value) = self.parse_expr(&first) {
-                        let name = struct_def.fields[i].name.clone();
-                        let type_name = struct_def.fields[i].type_name.clone();
-
-                        let field = self.make_variable(name, type_name, Box::new(value));
-
-                        fields.push(field);
-                        i += 1;
-                    }
+                    rhs = self.visit_binary_op_rhs(rhs, next_precedence);
                 }
 
-                if self.lexer.character() == ',' {
-                    let _comma = self.lexer.next().unwrap();
-                }
+                rhs
+            };
 
-                if let Some(c) = self.lexer.peek_char() {
-                    if c == '}' {
-                        let _ccurly = self.lexer.next().unwrap();
-                        break;
-                    }
-                }
+            if op_token.kind == TokenType::Coalesce {
+                lhs = self.visit_coalesce(lhs, rhs, &op_token);
+                chainable = false;
+                continue;
+            }
+
+            if chainable && op.is_ordering() {
+                lhs = self.visit_compare_chain(lhs, op, rhs, &op_token.position);
+                continue;
             }
 
-            let _semicolon = self.lexer.next().unwrap();
+            chainable = op.is_ordering();
 
-            let struct_instance_node = StructInstanceNode {
-                struct_def: struct_def.clone(),
-                fields,
+            let span = self.span_from(lhs.span());
+            let binary_op_node = BinaryOpNode {
+                lhs: Box::new(lhs),
+                op,
+                rhs: Box::new(rhs),
+                position: op_token.position.clone(),
+                span,
             };
 
-            self.struct_instances.push(struct_instance_node.clone());
+            self.check_bitwise(&binary_op_node);
 
-            return Some(Expression::StructInstance(struct_instance_node));
+            lhs = Expression::BinaryOp(binary_op_node);
         }
 
-        None
+        lhs
     }
 
-    fn visit_struct_def(&mut self) -> Option<Expression> {
-        if let Some(ident) = self.lexer.next() {
-            if let Some(_ocurly) = self.lexer.next() {
-                let mut fields = Vec::new();
-
-                while self.lexer.valid() {
-                    if let Some(field) = self.lexer.next() {
-                        if let TokenType::Ccurly = field.kind {
-                            break;
-                        } else if field.kind != TokenType::Ident {
-                            println!(
-                                "<{}> Error: expected identifier found '{:?}'",
-                                field.position, field.kind
-                            );
-
-                            break;
-                        }
-
-                        let _colon = self.lexer.next().unwrap();
-
-                        if let Some(type_name) = self.lexer.next() {
-                            let var = VarMetadataNode {
-                                name: field.value,
-                                type_name: type_name.value,
-                            };
-
-                            fields.push(var);
-                        }
-
-                        if self.lexer.character() == ',' {
-                            let _comma = self.lexer.next().unwrap();
-                        }
-                    }
-                }
-
-                if let Some(c) = self.lexer.peek_char() {
-                    if c == '}' {
-                        let _ccurly = self.lexer.next().unwrap();
-                    }
-                }
-
-                let struct_def = StructDefNode {
-                    type_name: ident.value,
-                    fields,
-                };
+    fn visit_coalesce(
+        &mut self,
+        value: Expression,
+        fallback: Expression,
+        op_token: &Token,
+    ) -> Expression {
+        let type_name = value.type_name();
+        let lookup = match &value {
+            Expression::MapCall(map_call_node) => map_call_node.method.is_lookup(),
+            Expression::VecCall(vec_call_node) => vec_call_node.method.is_lookup(),
+            _ => false,
+        };
 
-                self.structs.push(struct_def.clone());
+        if type_name != "None" {
+            self.check_arg_type(&type_name, &fallback, &op_token.position);
+        }
 
-                return Some(Expression::StructDef(struct_def));
-            }
+        if !lookup && !["None", "any", UNKNOWN_TYPE].contains(&type_name.as_str()) {
+            self.diagnostics.push(Diagnostic::warning(
+                Some(op_token.position.clone()),
+                String::from("the left side of '??' is never None, so the fallback is unused"),
+            ));
         }
 
-        None
+        let span = self.span_from(value.span());
+        Expression::Coalesce(CoalesceNode {
+            value: Box::new(value),
+            fallback: Box::new(fallback),
+            position: op_token.position.clone(),
+            span,
+        })
     }
 
-    fn visit_binary_op(&mut self, expr: Option<Expression>) -> Option<Expression> {
-        let mut ex = expr.clone();
+    fn visit_compare_chain(
+        &mut self,
+        lhs: Expression,
+        op: BinaryOp,
+        rhs: Expression,
+        position: &Position,
+    ) -> Expression {
+        let mut compare_chain_node = match lhs {
+            Expression::CompareChain(compare_chain_node) => compare_chain_node,
+            Expression::BinaryOp(binary_op_node) => CompareChainNode {
+                operands: vec![*binary_op_node.lhs, *binary_op_node.rhs],
+                ops: vec![binary_op_node.op],
+                position: binary_op_node.position,
+                span: binary_op_node.span,
+            },
+            lhs => return lhs,
+        };
 
-        let ops = "+-*/=<>!";
-        while let Some(potential_op) = self.lexer.peek_char() {
-            if !ops.contains(potential_op) {
-                break;
+        if let Some(first) = compare_chain_node.ops.first() {
+            if first.is_ascending() != op.is_ascending() {
+                self.error(
+                    position,
+                    format!(
+                        "comparison chain mixes '{first}' and '{op}', split it into separate comparisons"
+                    ),
+                );
             }
+        }
 
-            let op_token = self.lexer.next().unwrap();
-            let op = self.token_type_to_binary_op(op_token.kind);
-
-            if let BinaryOp::Inc | BinaryOp::Dec = op {
-                if let Some(Expression::Variable(..)) = expr.clone() {
-                    let rhs = Box::new(Expression::Literal(
-                        Token::from(
-                            TokenType::Literal(LiteralType::Number),
-                            String::from("1"),
-                            self.lexer.get_cursor_pos(),
-                        ),
-                        LiteralType::Number,
-                    ));
-
-                    if let Some(lhs) = ex {
-                        let binary_op_node = BinaryOpNode {
-                            lhs: Box::new(lhs),
-                            op,
-                            rhs,
-                        };
-
-                        ex = Some(Expression::BinaryOp(binary_op_node));
-                    }
-                } else {
-                    let rhs = Box::new(Expression::Literal(
-                        Token::from(
-                            TokenType::Literal(LiteralType::Number),
-                            String::from("1"),
-                            self.lexer.get_cursor_pos(),
-                        ),
-                        LiteralType::Number,
-                    ));
-
-                    if let Some(lhs) = ex {
-                        let binary_op_node = BinaryOpNode {
-                            lhs: Box::new(lhs),
-                            op,
-                            rhs,
-                        };
+        compare_chain_node.operands.push(rhs);
+        compare_chain_node.ops.push(op);
+        compare_chain_node.span = self.span_from(Some(compare_chain_node.span));
 
-                        ex = Some(Expression::BinaryOp(binary_op_node));
-                    }
-                }
-            } else {
-                let next = self.lexer.next().unwrap();
+        Expression::CompareChain(compare_chain_node)
+    }
 
-                if let TokenType::Literal(lt) = next.kind {
-                    let rhs = Box::new(Expression::Literal(next, lt));
+    fn placeholder_source(&self, type_name: &str, depth: usize) -> Option<String> {
+        match type_name {
+            "i32" => return Some(String::from("0")),
+            "f32" => return Some(String::from("0.0")),
+            "bool" => return Some(String::from("false")),
+            "char" => return Some(String::from("' '")),
+            "String" => return Some(String::from("\"\"")),
+            _ if depth > 2 => return None,
+            _ => {}
+        }
 
-                    if let Some(lhs) = ex {
-                        let binary_op_node = BinaryOpNode {
-                            lhs: Box::new(lhs),
-                            op,
-                            rhs,
-                        };
+        if vec_type(type_name).is_some() || map_types(type_name).is_some() {
+            return Some(format!("{type_name}()"));
+        }
 
-                        ex = Some(Expression::BinaryOp(binary_op_node));
-                    }
-                } else if let TokenType::Ident = next.kind {
-                    if let Some(var) = self
-                        .variables
-                        .iter()
-                        .find(|&v| v.metadata.name == next.value)
-                    {
-                        let rhs = Box::new(Expression::Variable(var.clone()));
+        if let Some(struct_def) = self.structs.iter().find(|s| s.type_name == type_name) {
+            let fields = struct_def
+                .fields
+                .iter()
+                .map(|f| {
+                    let value = self.placeholder_source(&f.type_name, depth + 1)?;
+                    Some(format!("{}: {value}", f.name))
+                })
+                .collect::<Option<Vec<_>>>()?;
 
-                        if let Some(lhs) = ex {
-                            let binary_op_node = BinaryOpNode {
-                                lhs: Box::new(lhs),
-                                op,
-                                rhs,
-                            };
+            return Some(format!("{type_name} {{ {} }}", fields.join(", ")));
+        }
 
-                            ex = Some(Expression::BinaryOp(binary_op_node));
-                        }
-                    }
-                }
-            }
+        let enum_def = self.enums.iter().find(|e| e.type_name == type_name)?;
+        let variant = enum_def.variants.first()?;
+        if variant.fields.is_empty() {
+            return Some(format!("{type_name}::{}", variant.name));
         }
 
-        ex
+        let values = variant
+            .fields
+            .iter()
+            .map(|f| self.placeholder_source(&f.type_name, depth + 1))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(format!(
+            "{type_name}::{}({})",
+            variant.name,
+            values.join(", ")
+        ))
     }
 
     fn default_initialize_value(&mut self, type_name: String) -> Expression {
         if let Some(struct_def_node) = self
             .structs
-            .clone()
             .iter()
             .find(|&s| s.type_name == type_name)
+            .cloned()
         {
-            return self.default_initialize_struct(struct_def_node);
+            return self.default_initialize_struct(&struct_def_node);
         }
 
         let kind;
@@ -959,7 +5260,14 @@ impl Parser {
                     self.lexer.get_cursor_pos(),
                 )
             }
-            _ => panic!("unimplemented literal type"),
+            _ => {
+                kind = LiteralType::None;
+                Token::from(
+                    TokenType::Literal(kind),
+                    String::from("None"),
+                    self.lexer.get_cursor_pos(),
+                )
+            }
         };
 
         Expression::Literal(token, kind)
@@ -981,34 +5289,309 @@ impl Parser {
         let struct_instance_node = StructInstanceNode {
             struct_def: struct_def_node.clone(),
             fields,
+            position: struct_def_node.position.clone(),
+            span: Span::default(),
         };
 
         Expression::StructInstance(struct_instance_node)
     }
 
+    fn expression_from_value(&self, value: &Value) -> Expression {
+        if let Value::Struct(struct_value) = value {
+            let fields: Vec<VariableNode> = struct_value
+                .fields
+                .iter()
+                .map(|(name, field)| {
+                    let expr = self.expression_from_value(field);
+                    self.make_variable(name.clone(), field.type_name(), Box::new(expr))
+                })
+                .collect();
+
+            let struct_def = StructDefNode {
+                type_name: struct_value.type_name.clone(),
+                fields: fields.iter().map(|f| f.metadata.clone()).collect(),
+                derives: Vec::new(),
+                generics: Vec::new(),
+                docs: None,
+                position: Position::default(),
+                span: Span::default(),
+            };
+
+            return Expression::StructInstance(StructInstanceNode {
+                struct_def,
+                fields,
+                position: Position::default(),
+                span: Span::default(),
+            });
+        }
+
+        let kind = match value {
+            Value::Char(..) => LiteralType::Char,
+            Value::Bool(..) => LiteralType::Bool,
+            Value::Number(..) => LiteralType::Number,
+            Value::Float(..) => LiteralType::Float,
+            Value::String(..) => LiteralType::String,
+            _ => LiteralType::None,
+        };
+
+        let token = Token::from(
+            TokenType::Literal(kind),
+            value.to_string(),
+            self.lexer.get_cursor_pos(),
+        );
+
+        Expression::Literal(token, kind)
+    }
+
+    fn span_since(&self, start: &Position) -> Span {
+        Span::new(Location::from(start), self.lexer.last_end())
+    }
+
+    // a node built around an operand spans from the operand's start
+    fn span_from(&self, operand: Option<Span>) -> Span {
+        match operand {
+            Some(span) => Span::new(span.start, self.lexer.last_end()),
+            None => Span::default(),
+        }
+    }
+
+    fn error(&mut self, position: &Position, message: String) {
+        self.diagnostics
+            .push(Diagnostic::error(Some(position.clone()), message));
+    }
+
+    fn error_spanning(&mut self, position: &Position, span: Option<Span>, message: String) {
+        self.diagnostics
+            .push(Diagnostic::error(Some(position.clone()), message).with_span(span));
+    }
+
+    fn check_any_flow(&mut self, type_name: &str, position: &Position) {
+        if self.options.strict && type_name != "any" {
+            self.diagnostics.push(Diagnostic::warning(
+                Some(position.clone()),
+                format!("'any' value flows into a position of type '{type_name}'"),
+            ));
+        }
+    }
+
+    fn capture(&mut self, index: usize) {
+        let metadata = &self.variables[index].metadata;
+
+        for closure in self.closures.iter_mut() {
+            if index < closure.scope_start
+                && !closure.captures.iter().any(|c| c.name == metadata.name)
+            {
+                closure.captures.push(metadata.clone());
+            }
+        }
+    }
+
+    fn is_captured(&self, name: &str) -> bool {
+        let Some(closure) = self.closures.last() else {
+            return false;
+        };
+
+        self.variables
+            .iter()
+            .rposition(|v| v.metadata.name == name)
+            .is_some_and(|index| index < closure.scope_start)
+    }
+
+    fn check_mutable(&mut self, metadata: &VarMetadataNode, position: &Position) {
+        if self.is_captured(&metadata.name) {
+            let mut diagnostic = Diagnostic::error(
+                Some(position.clone()),
+                format!("cannot assign to captured variable '{}'", metadata.name),
+            );
+            diagnostic.note = Some(String::from(
+                "closures capture variables by value, so the assignment would be lost",
+            ));
+
+            self.diagnostics.push(diagnostic);
+            return;
+        }
+
+        if metadata.mutable {
+            return;
+        }
+
+        let mut diagnostic = Diagnostic::error(
+            Some(position.clone()),
+            format!("cannot assign to immutable variable '{}'", metadata.name),
+        );
+        diagnostic.note = Some(self.mutability_note(&metadata.name));
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    // host globals sit below every script binding and cannot be redeclared as 'mut'
+    fn mutability_note(&self, name: &str) -> String {
+        let host = self
+            .variables
+            .iter()
+            .rposition(|v| v.metadata.name == name)
+            .is_some_and(|index| index < self.host_globals);
+
+        if host {
+            format!("'{name}' is registered by the host as immutable")
+        } else {
+            format!("consider declaring it as 'mut {name}'")
+        }
+    }
+
     fn make_variable(
         &self,
         name: String,
         type_name: String,
         value: Box<Expression>,
     ) -> VariableNode {
+        let span = value.span().unwrap_or_default();
         VariableNode {
-            metadata: VarMetadataNode { name, type_name },
+            metadata: VarMetadataNode {
+                name,
+                type_name,
+                mutable: false,
+            },
             value,
+            span,
+        }
+    }
+
+    fn uses_unknown(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Variable(variable_node) => variable_node.metadata.type_name == UNKNOWN_TYPE,
+            Expression::StructFieldAccess(field_access_node) => {
+                self.uses_unknown(&field_access_node.base)
+            }
+            Expression::BinaryOp(binary_op_node) => {
+                self.uses_unknown(&binary_op_node.lhs) || self.uses_unknown(&binary_op_node.rhs)
+            }
+            Expression::Coalesce(coalesce_node) => {
+                self.uses_unknown(&coalesce_node.value)
+                    || self.uses_unknown(&coalesce_node.fallback)
+            }
+            Expression::Error(..) => true,
+            _ => false,
+        }
+    }
+
+    fn precedence(&self, kind: TokenType) -> u8 {
+        match kind {
+            TokenType::Pipe => PIPE_PRECEDENCE,
+            TokenType::Coalesce => COALESCE_PRECEDENCE,
+            kind => self.token_type_to_binary_op(kind).precedence(),
+        }
+    }
+
+    fn visit_pipe(&mut self, value: Expression, op_token: &Token) -> Option<Expression> {
+        let value_span = value.span();
+        let callee = self.lexer.next()?;
+        let next = self.lexer.peek_token().map(|t| t.kind);
+        let mut bare = next != Some(TokenType::Oparen);
+
+        if callee.kind != TokenType::Ident {
+            self.error(
+                &callee.position,
+                format!("expected a procedure after '|>' found '{}'", callee.value),
+            );
+            return None;
         }
+
+        let call = if let Some(index) = self
+            .variables
+            .iter()
+            .rposition(|v| v.metadata.name == callee.value)
+        {
+            let variable = self.variables[index].clone();
+            let type_name = variable.metadata.type_name.clone();
+            if type_name != "any" && !type_name.starts_with("proc(") {
+                self.error(
+                    &callee.position,
+                    format!("cannot pipe into '{}' of type '{type_name}'", callee.value),
+                );
+                return None;
+            }
+
+            let params = ProcType::parse(&type_name).map(|p| p.args);
+            if !self.check_piped(&value, &callee, params.as_deref(), false) {
+                return None;
+            }
+            self.capture(index);
+
+            self.visit_closure_call(variable, Some(value), &callee)?
+        } else if let Some(proc_def) = self
+            .procedures
+            .iter()
+            .rev()
+            .find(|&f| f.name == callee.value)
+            .cloned()
+        {
+            let params: Vec<String> = proc_def.args.iter().map(|a| a.type_name.clone()).collect();
+            let generic = !proc_def.generics.is_empty();
+            if !self.check_piped(&value, &callee, Some(&params), generic) {
+                return None;
+            }
+
+            bare &= !(generic && next == Some(TokenType::Lt));
+
+            self.visit_procedure_with_receiver(&proc_def, Some(value), &callee)?
+        } else {
+            self.error(
+                &callee.position,
+                format!("cannot find procedure '{}'", callee.value),
+            );
+            return None;
+        };
+
+        Some(Expression::Pipe(PipeNode {
+            call: Box::new(call),
+            bare,
+            position: op_token.position.clone(),
+            span: self.span_from(value_span),
+        }))
     }
 
-    fn string_from_literal_type(&self, kind: LiteralType) -> String {
-        let kind = format!("{kind:?}");
-        let s = match &kind[..] {
-            "Char" => "char",
-            "Bool" => "bool",
-            "Number" => "i32",
-            "Float" => "f32",
-            kind => kind,
+    fn check_piped(
+        &mut self,
+        value: &Expression,
+        callee: &Token,
+        params: Option<&[String]>,
+        generic: bool,
+    ) -> bool {
+        let Some(params) = params else {
+            return true;
         };
 
-        String::from(s)
+        let Some(expected) = params.first() else {
+            self.error(
+                &callee.position,
+                format!(
+                    "'{}' takes no arguments and cannot be piped into",
+                    callee.value
+                ),
+            );
+            return false;
+        };
+
+        let found = value.type_name();
+        if found == "any" {
+            self.check_any_flow(expected, &callee.position);
+        } else if !generic
+            && found != *expected
+            && found != UNKNOWN_TYPE
+            && expected != "any"
+            && !self.implements(expected, &found)
+        {
+            self.error(
+                &callee.position,
+                format!(
+                    "cannot pipe '{found}' into '{}', which expects '{expected}'",
+                    callee.value
+                ),
+            );
+        }
+
+        true
     }
 
     fn token_type_to_binary_op(&self, kind: TokenType) -> BinaryOp {
@@ -1021,9 +5604,9 @@ impl Parser {
             TT::Sub => BinaryOp::Sub,
             TT::SubAssign => BinaryOp::SubAssign,
             TT::Mul => BinaryOp::Mul,
-            TT::MulAssign => BinaryOp::SubAssign,
+            TT::MulAssign => BinaryOp::MulAssign,
             TT::Div => BinaryOp::Div,
-            TT::DivAssign => BinaryOp::SubAssign,
+            TT::DivAssign => BinaryOp::DivAssign,
             TT::Eq => BinaryOp::Eq,
             TT::Ne => BinaryOp::Ne,
             TT::Lt => BinaryOp::Lt,
@@ -1031,26 +5614,100 @@ impl Parser {
             TT::Gt => BinaryOp::Gt,
             TT::Gte => BinaryOp::Gte,
             TT::Neg => BinaryOp::Neg,
+            TT::BitOr => BinaryOp::BitOr,
+            TT::BitAnd => BinaryOp::BitAnd,
+            TT::BitXor => BinaryOp::BitXor,
             _ => BinaryOp::None,
         }
     }
 
-    fn write_to_file<P: AsRef<Path>>(&self, path: P) {
+    fn cache_path(&self) -> Option<PathBuf> {
+        if !self.options.cache_ast {
+            return None;
+        }
+
+        self.source_path
+            .as_ref()
+            .map(|path| path.with_extension("astc"))
+    }
+
+    fn cache_hash(&self) -> u64 {
+        // every option that changes the AST or the diagnostics has to be part of the key
+        let key = format!(
+            "{VERSION} {} {} {} {}",
+            self.options.release, self.options.strict, self.options.hardened, self.dependency_hash
+        );
+        astc::mix(self.source_hash, key.as_bytes())
+    }
+
+    fn read_cache(&mut self) -> Option<Program> {
+        let path = self.cache_path()?;
+        let bytes = std::fs::read(&path).ok()?;
+
+        let cached = astc::decode(&bytes, self.cache_hash())?;
+        log::info(
+            "parser",
+            format_args!("loaded AST cache '{}'", path.display()),
+        );
+
+        self.diagnostics.extend(cached.diagnostics);
+        self.lexer.restore_comments(cached.comments);
+
+        Some(cached.program)
+    }
+
+    fn write_cache(&mut self) {
+        if self.diagnostics.iter().any(|d| d.is_error()) {
+            return;
+        }
+
+        if let Some(path) = self.cache_path() {
+            let bytes = astc::encode(
+                &self.program,
+                &self.diagnostics,
+                self.lexer.comments(),
+                self.cache_hash(),
+            );
+            match std::fs::write(&path, bytes) {
+                Ok(()) => log::trace(
+                    "parser",
+                    format_args!("wrote AST cache '{}'", path.display()),
+                ),
+                Err(err) => self.diagnostics.push(Diagnostic::warning(
+                    None,
+                    format!("failed to write AST cache '{}': {err}", path.display()),
+                )),
+            }
+        }
+    }
+
+    fn register_definitions(&mut self) {
+        let program = std::mem::take(&mut self.program);
+        self.register(&program);
+        self.program = program;
+    }
+
+    fn write_to_file<P: AsRef<Path>>(&self, path: P, format: AstFormat) {
         let mut content = String::new();
 
         if let Ok(mut file) = File::create(path) {
             use std::fmt::Write;
             use std::io::Write as W;
 
-            /* for custom ast */
-            for expr in self.program.iter() {
-                content.write_fmt(format_args!("{}\n", expr)).unwrap();
+            match format {
+                AstFormat::Tree => {
+                    content = self.options.ast_printer.print(&self.program);
+                }
+                AstFormat::Dot => {
+                    content = self.options.ast_printer.print_dot(&self.program);
+                }
+                AstFormat::Debug => {
+                    content
+                        .write_fmt(format_args!("{program:#?}", program = self.program))
+                        .unwrap();
+                }
             }
 
-            // content
-            //     .write_fmt(format_args!("{program:#?}", program = self.program))
-            //     .unwrap();
-
             file.write_all(content.as_bytes()).unwrap();
         }
     }
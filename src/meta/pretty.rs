@@ -0,0 +1,196 @@
+use std::fmt::Write;
+
+use crate::value::Value;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PrettyPrinter {
+    max_depth: usize,
+    indent: usize,
+}
+
+impl Default for PrettyPrinter {
+    fn default() -> Self {
+        Self {
+            max_depth: 8,
+            indent: 4,
+        }
+    }
+}
+
+impl PrettyPrinter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn print(&self, value: &Value) -> String {
+        let mut out = String::new();
+        self.write_value(&mut out, value, 0);
+        out
+    }
+
+    fn write_value(&self, out: &mut String, value: &Value, depth: usize) {
+        match value {
+            Value::Struct(struct_value) => {
+                let open = format!("{} {{", struct_value.type_name);
+                self.write_fields(out, &open, "}", &struct_value.fields, true, depth);
+            }
+            Value::Enum(enum_value) if !enum_value.fields.is_empty() => {
+                let open = format!("{}::{}(", enum_value.type_name, enum_value.variant);
+                self.write_fields(out, &open, ")", &enum_value.fields, false, depth);
+            }
+            Value::Vec(vec_value) => {
+                let items: Vec<(Option<&Value>, &Value)> =
+                    vec_value.items.iter().map(|item| (None, item)).collect();
+                self.write_entries(out, "[", "]", &items, depth);
+            }
+            Value::Map(map_value) => {
+                let entries: Vec<(Option<&Value>, &Value)> =
+                    map_value.iter().map(|(k, v)| (Some(k), v)).collect();
+                self.write_entries(out, "{", "}", &entries, depth);
+            }
+            Value::String(s) if depth > 0 => {
+                let _ = write!(out, "{s:?}");
+            }
+            Value::Char(c) if depth > 0 => {
+                let _ = write!(out, "{c:?}");
+            }
+            value => {
+                let _ = write!(out, "{value}");
+            }
+        }
+    }
+
+    fn write_fields(
+        &self,
+        out: &mut String,
+        open: &str,
+        close: &str,
+        fields: &[(String, Value)],
+        named: bool,
+        depth: usize,
+    ) {
+        out.push_str(open);
+
+        let padding = if named { " " } else { "" };
+
+        if fields.is_empty() {
+            out.push_str(close);
+            return;
+        }
+
+        if depth >= self.max_depth {
+            out.push_str(padding);
+            out.push_str("...");
+            out.push_str(padding);
+            out.push_str(close);
+            return;
+        }
+
+        if !fields.iter().any(|(_, value)| is_composite(value)) {
+            for (i, (name, value)) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                } else {
+                    out.push_str(padding);
+                }
+
+                if named {
+                    let _ = write!(out, "{name}: ");
+                }
+
+                self.write_value(out, value, depth + 1);
+            }
+
+            out.push_str(padding);
+            out.push_str(close);
+            return;
+        }
+
+        for (name, value) in fields.iter() {
+            out.push('\n');
+            out.push_str(&" ".repeat(self.indent * (depth + 1)));
+
+            if named {
+                let _ = write!(out, "{name}: ");
+            }
+
+            self.write_value(out, value, depth + 1);
+            out.push(',');
+        }
+
+        out.push('\n');
+        out.push_str(&" ".repeat(self.indent * depth));
+        out.push_str(close);
+    }
+
+    fn write_entries(
+        &self,
+        out: &mut String,
+        open: &str,
+        close: &str,
+        entries: &[(Option<&Value>, &Value)],
+        depth: usize,
+    ) {
+        out.push_str(open);
+
+        if entries.is_empty() {
+            out.push_str(close);
+            return;
+        }
+
+        if depth >= self.max_depth {
+            out.push_str("...");
+            out.push_str(close);
+            return;
+        }
+
+        let multiline = entries.iter().any(|(_, value)| is_composite(value));
+
+        for (i, (key, value)) in entries.iter().enumerate() {
+            if multiline {
+                out.push('\n');
+                out.push_str(&" ".repeat(self.indent * (depth + 1)));
+            } else if i > 0 {
+                out.push_str(", ");
+            }
+
+            if let Some(key) = key {
+                self.write_value(out, key, depth + 1);
+                out.push_str(": ");
+            }
+
+            self.write_value(out, value, depth + 1);
+
+            if multiline {
+                out.push(',');
+            }
+        }
+
+        if multiline {
+            out.push('\n');
+            out.push_str(&" ".repeat(self.indent * depth));
+        }
+
+        out.push_str(close);
+    }
+}
+
+fn is_composite(value: &Value) -> bool {
+    match value {
+        Value::Struct(struct_value) => !struct_value.fields.is_empty(),
+        Value::Enum(enum_value) => !enum_value.fields.is_empty(),
+        Value::Vec(vec_value) => vec_value.items.iter().any(is_composite),
+        Value::Map(map_value) => map_value.iter().any(|(_, value)| is_composite(value)),
+        _ => false,
+    }
+}
@@ -1,3 +1,15 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    expression::Expression, hooks::ExecutorHooks, json::Json, log, token::Position, value::Value,
+};
+
+const HOT_STATEMENTS: usize = 10;
+
 pub struct Timer {
     name: &'static str,
     timer: std::time::Instant,
@@ -14,10 +26,235 @@ impl Timer {
 
 impl Drop for Timer {
     fn drop(&mut self) {
-        println!(
-            "{} took {} microseconds",
-            self.name,
-            self.timer.elapsed().as_micros()
+        log::info(
+            "timer",
+            format_args!(
+                "{} took {} microseconds",
+                self.name,
+                self.timer.elapsed().as_micros()
+            ),
+        );
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcedureProfile {
+    pub name: String,
+    pub calls: u64,
+    pub inclusive: Duration,
+    pub exclusive: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatementProfile {
+    pub position: Position,
+    pub count: u64,
+    pub time: Duration,
+}
+
+struct ActiveCall {
+    procedure: usize,
+    start: Instant,
+    children: Duration,
+}
+
+#[derive(Default)]
+pub struct Profiler {
+    procedures: Vec<ProcedureProfile>,
+    statements: Vec<StatementProfile>,
+    statement_ids: HashMap<(String, u32, u32), usize>,
+    stack: Vec<ActiveCall>,
+    current: Option<(usize, Instant)>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn finish(&mut self) {
+        while !self.stack.is_empty() {
+            self.on_return("", &Value::None);
+        }
+    }
+
+    pub fn procedures(&self) -> Vec<&ProcedureProfile> {
+        let mut procedures: Vec<&ProcedureProfile> = self.procedures.iter().collect();
+        procedures.sort_by(|a, b| b.exclusive.cmp(&a.exclusive).then(a.name.cmp(&b.name)));
+        procedures
+    }
+
+    pub fn hot_statements(&self, limit: usize) -> Vec<&StatementProfile> {
+        let mut statements: Vec<&StatementProfile> = self.statements.iter().collect();
+        statements.sort_by(|a, b| b.time.cmp(&a.time).then(b.count.cmp(&a.count)));
+        statements.truncate(limit);
+        statements
+    }
+
+    pub fn report(&self) -> String {
+        let mut out = String::new();
+
+        let width = self
+            .procedures
+            .iter()
+            .map(|p| p.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("procedure".len());
+
+        let _ = writeln!(
+            out,
+            "{:<width$}  {:>8}  {:>12}  {:>12}",
+            "procedure", "calls", "total (us)", "self (us)"
+        );
+        for procedure in self.procedures() {
+            let _ = writeln!(
+                out,
+                "{:<width$}  {:>8}  {:>12}  {:>12}",
+                procedure.name,
+                procedure.calls,
+                procedure.inclusive.as_micros(),
+                procedure.exclusive.as_micros()
+            );
+        }
+
+        let statements = self.hot_statements(HOT_STATEMENTS);
+        let width = statements
+            .iter()
+            .map(|s| s.position.to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max("statement".len());
+
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "{:<width$}  {:>8}  {:>12}",
+            "statement", "count", "self (us)"
         );
+        for statement in statements {
+            let _ = writeln!(
+                out,
+                "{:<width$}  {:>8}  {:>12}",
+                statement.position.to_string(),
+                statement.count,
+                statement.time.as_micros()
+            );
+        }
+
+        out
+    }
+
+    pub fn to_json(&self) -> Json {
+        let procedures = self
+            .procedures()
+            .into_iter()
+            .map(|p| {
+                Json::object([
+                    ("name", Json::from(p.name.as_str())),
+                    ("calls", Json::Number(p.calls as f64)),
+                    ("total_us", Json::Number(p.inclusive.as_micros() as f64)),
+                    ("self_us", Json::Number(p.exclusive.as_micros() as f64)),
+                ])
+            })
+            .collect::<Vec<Json>>();
+
+        let statements = self
+            .hot_statements(HOT_STATEMENTS)
+            .into_iter()
+            .map(|s| {
+                Json::object([
+                    ("file", Json::from(s.position.filename.as_str())),
+                    ("line", Json::from(s.position.row + 1)),
+                    ("column", Json::from(s.position.column + 1)),
+                    ("count", Json::Number(s.count as f64)),
+                    ("self_us", Json::Number(s.time.as_micros() as f64)),
+                ])
+            })
+            .collect::<Vec<Json>>();
+
+        Json::object([
+            ("procedures", Json::from(procedures)),
+            ("statements", Json::from(statements)),
+        ])
+    }
+
+    fn settle(&mut self, now: Instant) {
+        if let Some((statement, start)) = self.current.take() {
+            self.statements[statement].time += now - start;
+        }
+    }
+
+    fn procedure_id(&mut self, name: &str) -> usize {
+        if let Some(i) = self.procedures.iter().position(|p| p.name == name) {
+            return i;
+        }
+
+        self.procedures.push(ProcedureProfile {
+            name: String::from(name),
+            calls: 0,
+            inclusive: Duration::ZERO,
+            exclusive: Duration::ZERO,
+        });
+
+        self.procedures.len() - 1
+    }
+}
+
+impl ExecutorHooks for Profiler {
+    fn on_statement(&mut self, _statement: &Expression, position: &Position) {
+        let now = Instant::now();
+        self.settle(now);
+
+        let key = (position.filename.clone(), position.row, position.column);
+        let next = self.statements.len();
+        let statement = *self.statement_ids.entry(key).or_insert(next);
+
+        if statement == next {
+            self.statements.push(StatementProfile {
+                position: position.clone(),
+                count: 0,
+                time: Duration::ZERO,
+            });
+        }
+
+        self.statements[statement].count += 1;
+        self.current = Some((statement, now));
+    }
+
+    fn on_call(&mut self, name: &str, _args: &[Value]) {
+        let now = Instant::now();
+        self.settle(now);
+
+        let procedure = self.procedure_id(name);
+        self.procedures[procedure].calls += 1;
+
+        self.stack.push(ActiveCall {
+            procedure,
+            start: now,
+            children: Duration::ZERO,
+        });
+    }
+
+    fn on_return(&mut self, _name: &str, _value: &Value) {
+        let now = Instant::now();
+        self.settle(now);
+
+        let Some(call) = self.stack.pop() else {
+            return;
+        };
+
+        let elapsed = now - call.start;
+        let recursive = self.stack.iter().any(|c| c.procedure == call.procedure);
+
+        let procedure = &mut self.procedures[call.procedure];
+        procedure.exclusive += elapsed.saturating_sub(call.children);
+        if !recursive {
+            procedure.inclusive += elapsed;
+        }
+
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children += elapsed;
+        }
     }
 }
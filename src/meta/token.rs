@@ -1,4 +1,4 @@
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display};
 
 #[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
 pub enum LiteralType {
@@ -11,30 +11,57 @@ pub enum LiteralType {
     String,
 }
 
+impl LiteralType {
+    pub fn type_name(&self) -> String {
+        let kind = format!("{self:?}");
+        let s = match &kind[..] {
+            "Char" => "char",
+            "Bool" => "bool",
+            "Number" => "i32",
+            "Float" => "f32",
+            kind => kind,
+        };
+
+        String::from(s)
+    }
+}
+
 #[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
 pub enum TokenType {
     #[default]
     None,
     If,
+    Else,
     While,
     For,
+    Match,
     In,
     Range,
+    RangeInclusive,
     Let,
+    Mut,
     Impl,
+    Trait,
     ScopeResolution,
     Proc,
     Ident,
     Struct,
+    Enum,
     Return,
+    Break,
+    Continue,
+    As,
     Oparen,
     Cparen,
     Colon,
     Semicolon,
     Comma,
     Period,
+    At,
     Ocurly,
     Ccurly,
+    Obracket,
+    Cbracket,
     Inc,
     Dec,
     Add,
@@ -46,6 +73,7 @@ pub enum TokenType {
     Div,
     DivAssign,
     Assign,
+    FatArrow,
     Eq,
     Ne,
     Lt,
@@ -53,31 +81,165 @@ pub enum TokenType {
     Gt,
     Gte,
     Neg,
+    BitOr,
+    BitAnd,
+    BitXor,
+    Pipe,
+    Coalesce,
     Literal(LiteralType),
+    Interpolation,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum TokenClass {
+    #[default]
+    Unknown,
+    Keyword,
+    Identifier,
+    Function,
+    Type,
+    Attribute,
+    Number,
+    String,
+    Char,
+    Boolean,
+    Operator,
+    Punctuation,
+    Comment,
+    DocComment,
+}
+
+impl TokenClass {
+    pub fn of(kind: TokenType) -> Self {
+        match kind {
+            TokenType::None => TokenClass::Unknown,
+            TokenType::If
+            | TokenType::Else
+            | TokenType::While
+            | TokenType::For
+            | TokenType::Match
+            | TokenType::In
+            | TokenType::Let
+            | TokenType::Mut
+            | TokenType::Impl
+            | TokenType::Trait
+            | TokenType::Proc
+            | TokenType::Struct
+            | TokenType::Enum
+            | TokenType::Return
+            | TokenType::Break
+            | TokenType::Continue
+            | TokenType::As => TokenClass::Keyword,
+            TokenType::Ident => TokenClass::Identifier,
+            TokenType::At => TokenClass::Attribute,
+            TokenType::Literal(LiteralType::Number | LiteralType::Float) => TokenClass::Number,
+            TokenType::Literal(LiteralType::Char) => TokenClass::Char,
+            TokenType::Literal(LiteralType::Bool) => TokenClass::Boolean,
+            TokenType::Literal(_) | TokenType::Interpolation => TokenClass::String,
+            TokenType::Oparen
+            | TokenType::Cparen
+            | TokenType::Ocurly
+            | TokenType::Ccurly
+            | TokenType::Obracket
+            | TokenType::Cbracket
+            | TokenType::Colon
+            | TokenType::Semicolon
+            | TokenType::Comma
+            | TokenType::Period
+            | TokenType::ScopeResolution
+            | TokenType::FatArrow => TokenClass::Punctuation,
+            _ => TokenClass::Operator,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenClass::Unknown => "unknown",
+            TokenClass::Keyword => "keyword",
+            TokenClass::Identifier => "identifier",
+            TokenClass::Function => "function",
+            TokenClass::Type => "type",
+            TokenClass::Attribute => "attribute",
+            TokenClass::Number => "number",
+            TokenClass::String => "string",
+            TokenClass::Char => "char",
+            TokenClass::Boolean => "boolean",
+            TokenClass::Operator => "operator",
+            TokenClass::Punctuation => "punctuation",
+            TokenClass::Comment => "comment",
+            TokenClass::DocComment => "doc-comment",
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Location {
+    pub row: u32,
+    pub column: u32,
+}
+
+impl From<&Position> for Location {
+    fn from(position: &Position) -> Self {
+        Self {
+            row: position.row,
+            column: position.column,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Span {
+    pub start: Location,
+    pub end: Location,
+}
+
+impl Span {
+    pub fn new(start: Location, end: Location) -> Self {
+        Self { start, end }
+    }
+
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start, other.end.max(self.end))
+    }
+
+    pub fn end_position(&self, filename: &str) -> Position {
+        Position::from(String::from(filename), self.end.row, self.end.column)
+    }
 }
 
 #[derive(Debug, Default, Clone)]
-pub struct Token {
+pub struct Token<'src> {
     pub kind: TokenType,
-    pub value: String,
+    pub value: Cow<'src, str>,
     pub position: Position,
+    pub span: Span,
 }
 
-impl Token {
+impl<'src> Token<'src> {
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn from(_type: TokenType, value: String, position: Position) -> Self {
+    pub fn from(_type: TokenType, value: impl Into<Cow<'src, str>>, position: Position) -> Self {
         Self {
             kind: _type,
-            value,
+            value: value.into(),
+            span: Span::new(Location::from(&position), Location::from(&position)),
             position,
         }
     }
+
+    pub fn into_owned(self) -> Token<'static> {
+        Token {
+            kind: self.kind,
+            value: Cow::Owned(self.value.into_owned()),
+            position: self.position,
+            span: self.span,
+        }
+    }
 }
 
-impl Display for Token {
+impl Display for Token<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!(
             "<{} {:?}> {}",
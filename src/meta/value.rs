@@ -0,0 +1,480 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::File,
+    hash::{Hash, Hasher},
+    io::{self, BufRead, BufReader},
+    sync::Arc,
+};
+
+use crate::{
+    nodes::{EnumDefNode, ProcDefNode, ProcType},
+    token::{LiteralType, Token},
+};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum Value {
+    #[default]
+    None,
+    Char(char),
+    Bool(bool),
+    Number(i32),
+    Float(f32),
+    String(String),
+    Struct(StructValue),
+    Enum(EnumValue),
+    Flags(FlagsValue),
+    Closure(ClosureValue),
+    Lines(LinesValue),
+    Map(MapValue),
+    Vec(VecValue),
+    Range(RangeValue),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructValue {
+    pub type_name: String,
+    pub fields: Vec<(String, Value)>,
+}
+
+impl StructValue {
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.fields
+            .iter_mut()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumValue {
+    pub type_name: String,
+    pub variant: String,
+    pub fields: Vec<(String, Value)>,
+}
+
+impl EnumValue {
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        self.fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlagsValue {
+    pub type_name: String,
+    pub bits: i32,
+    pub variants: Vec<(String, i32)>,
+}
+
+impl FlagsValue {
+    pub fn new(enum_def: &EnumDefNode, bits: i32) -> Self {
+        Self {
+            type_name: enum_def.type_name.clone(),
+            bits,
+            variants: enum_def
+                .discriminants()
+                .into_iter()
+                .map(|(name, d)| (String::from(name), d))
+                .collect(),
+        }
+    }
+
+    pub fn discriminant(&self, variant: &str) -> Option<i32> {
+        self.variants
+            .iter()
+            .find(|(name, _)| name == variant)
+            .map(|(_, d)| *d)
+    }
+
+    pub fn names(&self) -> (Vec<&str>, i32) {
+        if let Some((name, _)) = self.variants.iter().find(|(_, d)| *d == self.bits) {
+            return (vec![name.as_str()], 0);
+        }
+
+        let mut names = Vec::new();
+        let mut remaining = self.bits;
+        for (name, d) in self.variants.iter() {
+            if *d != 0 && self.bits & d == *d && remaining & d != 0 {
+                names.push(name.as_str());
+                remaining &= !d;
+            }
+        }
+
+        (names, remaining)
+    }
+}
+
+impl Display for FlagsValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (names, remaining) = self.names();
+        let mut parts: Vec<String> = names
+            .iter()
+            .map(|name| format!("{}::{name}", self.type_name))
+            .collect();
+
+        if remaining != 0 || parts.is_empty() {
+            parts.push(format!("{}({remaining})", self.type_name));
+        }
+
+        f.write_str(&parts.join(" | "))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClosureValue {
+    pub proc_def: Arc<ProcDefNode>,
+    pub captures: Vec<(String, Value)>,
+}
+
+impl ClosureValue {
+    pub fn proc_type(&self) -> ProcType {
+        ProcType::of(&self.proc_def)
+    }
+}
+
+impl PartialEq for ClosureValue {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.proc_def, &other.proc_def) && self.captures == other.captures
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinesValue {
+    pub path: String,
+}
+
+impl LinesValue {
+    pub fn open(&self) -> io::Result<io::Lines<BufReader<File>>> {
+        Ok(BufReader::new(File::open(&self.path)?).lines())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MapKey(pub Value);
+
+impl PartialEq for MapKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for MapKey {}
+
+impl Hash for MapKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(&self.0).hash(state);
+
+        // values that are not valid keys only hash by kind, which keeps equal values equal
+        match &self.0 {
+            Value::Char(c) => c.hash(state),
+            Value::Bool(b) => b.hash(state),
+            Value::Number(n) => n.hash(state),
+            Value::String(s) => s.hash(state),
+            Value::Enum(enum_value) => {
+                enum_value.type_name.hash(state);
+                enum_value.variant.hash(state);
+            }
+            _ => {}
+        }
+    }
+}
+
+// entries keep insertion order, removed ones leave a hole until the next compaction
+#[derive(Debug, Clone)]
+pub struct MapValue {
+    pub type_name: String,
+    entries: Vec<Option<(Value, Value)>>,
+    index: HashMap<MapKey, usize>,
+}
+
+impl MapValue {
+    pub fn new(type_name: &str) -> Self {
+        Self {
+            type_name: String::from(type_name),
+            entries: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        let slot = *self.index.get(&MapKey(key.clone()))?;
+        self.entries[slot].as_ref().map(|(_, v)| v)
+    }
+
+    pub fn insert(&mut self, key: Value, value: Value) {
+        if let Some(&slot) = self.index.get(&MapKey(key.clone())) {
+            self.entries[slot] = Some((key, value));
+            return;
+        }
+
+        self.index.insert(MapKey(key.clone()), self.entries.len());
+        self.entries.push(Some((key, value)));
+    }
+
+    pub fn remove(&mut self, key: &Value) -> Option<Value> {
+        let slot = self.index.remove(&MapKey(key.clone()))?;
+        let (_, value) = self.entries[slot].take()?;
+
+        if self.entries.len() > 2 * self.index.len() {
+            self.compact();
+        }
+
+        Some(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.entries.iter().flatten().map(|(k, v)| (k, v))
+    }
+
+    pub fn into_keys(self) -> impl Iterator<Item = Value> {
+        self.entries.into_iter().flatten().map(|(k, _)| k)
+    }
+
+    fn compact(&mut self) {
+        self.entries.retain(Option::is_some);
+        for (slot, (key, _)) in self.entries.iter().flatten().enumerate() {
+            self.index.insert(MapKey(key.clone()), slot);
+        }
+    }
+}
+
+impl PartialEq for MapValue {
+    fn eq(&self, other: &Self) -> bool {
+        // insertion order is only kept for iteration, it doesn't make two maps different
+        self.type_name == other.type_name
+            && self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl Display for MapValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("{")?;
+        for (i, (key, value)) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write_element(f, key)?;
+            f.write_str(": ")?;
+            write_element(f, value)?;
+        }
+        f.write_str("}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeValue {
+    pub start: i32,
+    pub end: i32,
+    pub inclusive: bool,
+}
+
+impl Display for RangeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = if self.inclusive { "..=" } else { ".." };
+        f.write_fmt(format_args!("{}{op}{}", self.start, self.end))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VecValue {
+    pub type_name: String,
+    pub items: Vec<Value>,
+}
+
+impl VecValue {
+    pub fn new(type_name: &str) -> Self {
+        Self {
+            type_name: String::from(type_name),
+            items: Vec::new(),
+        }
+    }
+
+    pub fn slot(&self, index: i32) -> Option<usize> {
+        usize::try_from(index)
+            .ok()
+            .filter(|&i| i < self.items.len())
+    }
+}
+
+impl Display for VecValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[")?;
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write_element(f, item)?;
+        }
+        f.write_str("]")
+    }
+}
+
+// strings inside a collection are quoted so their commas can't be mistaken for separators
+fn write_element(f: &mut std::fmt::Formatter<'_>, value: &Value) -> std::fmt::Result {
+    match value {
+        Value::String(s) => f.write_fmt(format_args!("{s:?}")),
+        Value::Char(c) => f.write_fmt(format_args!("{c:?}")),
+        value => f.write_fmt(format_args!("{value}")),
+    }
+}
+
+impl Value {
+    pub fn from_literal(token: &Token, kind: LiteralType) -> Self {
+        match kind {
+            LiteralType::None => Value::None,
+            LiteralType::Char => Value::Char(token.value.chars().next().unwrap_or('\0')),
+            LiteralType::Bool => Value::Bool(token.value == "true"),
+            LiteralType::Number => token.value.parse().map_or(Value::None, Value::Number),
+            LiteralType::Float => token.value.parse().map_or(Value::None, Value::Float),
+            LiteralType::String => Value::String(token.value.to_string()),
+        }
+    }
+
+    pub fn type_name(&self) -> String {
+        let name = match self {
+            Value::None => "None",
+            Value::Char(..) => "char",
+            Value::Bool(..) => "bool",
+            Value::Number(..) => "i32",
+            Value::Float(..) => "f32",
+            Value::String(..) => "String",
+            Value::Struct(struct_value) => &struct_value.type_name,
+            Value::Enum(enum_value) => &enum_value.type_name,
+            Value::Flags(flags_value) => &flags_value.type_name,
+            Value::Closure(closure_value) => return closure_value.proc_type().to_string(),
+            Value::Lines(..) => "Lines",
+            Value::Map(map_value) => &map_value.type_name,
+            Value::Vec(vec_value) => &vec_value.type_name,
+            Value::Range(..) => "Range",
+        };
+
+        String::from(name)
+    }
+
+    pub fn is_none(&self) -> bool {
+        matches!(self, Value::None)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::None => f.write_str("None"),
+            Value::Char(c) => f.write_fmt(format_args!("{c}")),
+            Value::Bool(b) => f.write_fmt(format_args!("{b}")),
+            Value::Number(n) => f.write_fmt(format_args!("{n}")),
+            Value::Float(n) => f.write_fmt(format_args!("{n:?}")),
+            Value::String(s) => f.write_str(s),
+            Value::Struct(struct_value) => {
+                f.write_fmt(format_args!("{} {{", struct_value.type_name))?;
+                for (i, (name, value)) in struct_value.fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    f.write_fmt(format_args!(" {name}: {value}"))?;
+                }
+                f.write_str(" }")
+            }
+            Value::Enum(enum_value) => {
+                f.write_fmt(format_args!(
+                    "{}::{}",
+                    enum_value.type_name, enum_value.variant
+                ))?;
+
+                if enum_value.fields.is_empty() {
+                    return Ok(());
+                }
+
+                f.write_str("(")?;
+                for (i, (_, value)) in enum_value.fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    f.write_fmt(format_args!("{value}"))?;
+                }
+                f.write_str(")")
+            }
+            Value::Flags(flags_value) => f.write_fmt(format_args!("{flags_value}")),
+            Value::Closure(closure_value) => f.write_fmt(format_args!(
+                "<{} {}>",
+                closure_value.proc_def.name,
+                closure_value.proc_type()
+            )),
+            Value::Lines(lines_value) => {
+                f.write_fmt(format_args!("<lines of '{}'>", lines_value.path))
+            }
+            Value::Map(map_value) => f.write_fmt(format_args!("{map_value}")),
+            Value::Vec(vec_value) => f.write_fmt(format_args!("{vec_value}")),
+            Value::Range(range_value) => f.write_fmt(format_args!("{range_value}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueTypeError {
+    pub expected: &'static str,
+    pub found: String,
+}
+
+impl Display for ValueTypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "expected '{}' found '{}'",
+            self.expected, self.found
+        ))
+    }
+}
+
+impl std::error::Error for ValueTypeError {}
+
+macro_rules! impl_value_conversion {
+    ($type:ty, $variant:ident, $name:literal) => {
+        impl From<$type> for Value {
+            fn from(value: $type) -> Self {
+                Value::$variant(value)
+            }
+        }
+
+        impl TryFrom<Value> for $type {
+            type Error = ValueTypeError;
+
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                if let Value::$variant(inner) = value {
+                    return Ok(inner);
+                }
+
+                Err(ValueTypeError {
+                    expected: $name,
+                    found: value.type_name(),
+                })
+            }
+        }
+    };
+}
+
+impl_value_conversion!(char, Char, "char");
+impl_value_conversion!(bool, Bool, "bool");
+impl_value_conversion!(i32, Number, "i32");
+impl_value_conversion!(f32, Float, "f32");
+impl_value_conversion!(String, String, "String");
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(String::from(value))
+    }
+}
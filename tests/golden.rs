@@ -0,0 +1,13 @@
+use std::path::Path;
+
+use meta::golden::{self, GoldenOptions};
+
+#[test]
+fn golden_fixtures() {
+    // fixtures like read_lines.mt open files relative to the repository root
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    std::env::set_current_dir(root).unwrap();
+
+    let report = golden::run(&root.join("tests/golden"), GoldenOptions::new()).unwrap();
+    assert!(report.passed(), "{report}");
+}